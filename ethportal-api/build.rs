@@ -12,6 +12,16 @@ fn hook(mut file: &File) -> SdResult<()> {
     let env_var_git_hash = std::env::var("GIT_HASH").unwrap_or_default();
     writeln!(file, "const ENV_GIT_HASH: &str = \"{}\";", env_var_git_hash)?;
 
+    let mut enabled_features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .collect();
+    enabled_features.sort();
+    writeln!(
+        file,
+        "pub const ENABLED_FEATURES: &[&str] = &{:?};",
+        enabled_features
+    )?;
+
     hook_method(file)?;
 
     Ok(())
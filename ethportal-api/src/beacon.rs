@@ -77,9 +77,14 @@ pub trait BeaconNetworkApi {
     #[method(name = "beaconFindNodes")]
     async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo>;
 
-    /// Lookup a target node within in the network
+    /// Lookup a target node within in the network. Returns up to `limit` of the discovered nodes,
+    /// closest to the target first, or 16 if no limit is given.
     #[method(name = "beaconRecursiveFindNodes")]
-    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>>;
+    async fn recursive_find_nodes(
+        &self,
+        node_id: NodeId,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<Enr>>;
 
     /// Get the optimistic root of the optimistic header.
     #[method(name = "beaconOptimisticStateRoot")]
@@ -1,17 +1,33 @@
+use std::path::PathBuf;
+
+use alloy::primitives::B256;
 use discv5::enr::NodeId;
-use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use jsonrpsee::{
+    core::{RpcResult, SubscriptionResult},
+    proc_macros::rpc,
+};
 
 use crate::{
     types::{
-        content_key::history::HistoryContentKey,
+        content_key::history::{HistoryBlockId, HistoryContentKey, HistoryContentType},
         enr::Enr,
         portal::{
-            AcceptInfo, DataRadius, FindContentInfo, FindNodesInfo, GetContentInfo,
-            PaginateLocalContentInfo, PongInfo, TraceContentInfo, TraceGossipInfo,
+            AcceptInfo, AuditPeerRadiusInfo, BackfillInfo, CircuitBreakerInfo, ClientInfo,
+            ConfirmedOfferInfo, ContentDistanceDistributionInfo, ContentRangeInfo, DataRadius,
+            DebugPingInfo, EncodedContentKeyInfo, ExportStoreInfo, FindAndStoreInfo,
+            FindContentInfo, FindContentRangeInfo, FindNodesInfo, GetContentInfo, GossipBatchInfo,
+            InFlightRequestInfo, InboundReachabilityInfo, LastErrorInfo, NetworkCrawlInfo,
+            NodeDistanceInfo, OfferThroughputInfo, PaginateLocalContentInfo, PeerTransfersInfo,
+            PongInfo, RadiusChangeInfo, RawStoredBytesInfo, ReadinessInfo,
+            RejectionExplanationInfo, ReplayTraceInfo, SampleAvailabilityInfo, ServedContentInfo,
+            SetRadiusInfo, SimulatedHopInfo, StorageInfo, StoreBenchmarkInfo, TraceContentInfo,
+            TraceGetContentEvent, TraceGossipInfo,
         },
-        portal_wire::OfferTrace,
+        portal_wire::{Accept, OfferTrace},
+        query_trace::{QueryTraceSummary, RecordedQueryTrace},
     },
-    RawContentValue, RoutingTableInfo,
+    NeighborhoodInfo, RawContentKey, RawContentValue, RefreshInfo, RoutingTableInfo,
+    SubnetDistributionInfo,
 };
 
 /// Portal History JSON-RPC endpoints
@@ -21,6 +37,21 @@ pub trait HistoryNetworkApi {
     #[method(name = "historyRoutingTableInfo")]
     async fn routing_table_info(&self) -> RpcResult<RoutingTableInfo>;
 
+    /// Returns the number of peers within gossip range across the whole key space, the radius
+    /// used, and a per-bucket breakdown.
+    #[method(name = "historyNeighborhoodInfo")]
+    async fn neighborhood_info(&self) -> RpcResult<NeighborhoodInfo>;
+
+    /// Returns the number of peers in the routing table per IP subnet of their advertised
+    /// address, and the max number of peers per subnet, if limited.
+    #[method(name = "historySubnetDistribution")]
+    async fn subnet_distribution(&self) -> RpcResult<SubnetDistributionInfo>;
+
+    /// Returns, per bucket, the number of nodes in it and the time of the last node lookup that
+    /// refreshed it.
+    #[method(name = "historyBucketRefreshInfo")]
+    async fn bucket_refresh_info(&self) -> RpcResult<RefreshInfo>;
+
     /// Returns the node data radios
     #[method(name = "historyRadius")]
     async fn radius(&self) -> RpcResult<DataRadius>;
@@ -37,6 +68,15 @@ pub trait HistoryNetworkApi {
     #[method(name = "historyDeleteEnr")]
     async fn delete_enr(&self, node_id: NodeId) -> RpcResult<bool>;
 
+    /// Remove Node ID from the overlay routing table and, if `deny_ttl_secs` is provided, prevent
+    /// it from being re-added for that many seconds. Returns whether the node was present.
+    #[method(name = "historyRemoveEnr")]
+    async fn remove_enr(&self, node_id: NodeId, deny_ttl_secs: Option<u64>) -> RpcResult<bool>;
+
+    /// Reload the denylist from the file it was loaded from at startup.
+    #[method(name = "historyReloadDenylist")]
+    async fn reload_denylist(&self) -> RpcResult<bool>;
+
     /// Fetch the ENR representation associated with the given Node ID.
     #[method(name = "historyLookupEnr")]
     async fn lookup_enr(&self, node_id: NodeId) -> RpcResult<Enr>;
@@ -45,14 +85,78 @@ pub trait HistoryNetworkApi {
     #[method(name = "historyPing")]
     async fn ping(&self, enr: Enr) -> RpcResult<PongInfo>;
 
+    /// Send a PING message to the designated node and return the raw request and response
+    /// payloads along with their decoded fields. This is a diagnostic tool, not meant for
+    /// production use. Requires `--enable-debug-ping`.
+    #[method(name = "historyDebugPing")]
+    async fn debug_ping(&self, enr: Enr) -> RpcResult<DebugPingInfo>;
+
+    /// Check whether the designated node holds content consistent with its advertised radius, by
+    /// probing it for locally stored content that falls within that radius
+    #[method(name = "historyAuditPeerRadius")]
+    async fn audit_peer_radius(&self, enr: Enr) -> RpcResult<AuditPeerRadiusInfo>;
+
+    /// Check whether peers can reach the local node at the address advertised in its ENR, by
+    /// pinging the designated peer and comparing the address it observed the local node at
+    #[method(name = "historyTestInboundReachability")]
+    async fn test_inbound_reachability(&self, enr: Enr) -> RpcResult<InboundReachabilityInfo>;
+
     /// Send a FINDNODES request for nodes that fall within the given set of distances, to the
     /// designated peer and wait for a response
     #[method(name = "historyFindNodes")]
     async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo>;
 
-    /// Lookup a target node within in the network
+    /// Returns the XOR distance, and its log2, between two arbitrary node ids.
+    #[method(name = "historyNodeDistance")]
+    async fn node_distance(
+        &self,
+        node_id_a: NodeId,
+        node_id_b: NodeId,
+    ) -> RpcResult<NodeDistanceInfo>;
+
+    /// Returns the content key of the given content type for a block, identified by its hash or
+    /// number, and the content id of that key.
+    #[method(name = "historyEncodeContentKey")]
+    async fn encode_content_key(
+        &self,
+        content_type: HistoryContentType,
+        block_id: HistoryBlockId,
+    ) -> RpcResult<EncodedContentKeyInfo>;
+
+    /// Returns the number of the block that the content key refers to, without looking up the
+    /// content. Fails for content keys that don't encode a block number, i.e. those keyed by block
+    /// hash.
+    #[method(name = "historyContentKeyBlockNumber")]
+    async fn content_key_block_number(&self, content_key: HistoryContentKey) -> RpcResult<u64>;
+
+    /// Simulates a lookup of the content id against the given synthetic peers, without any network
+    /// traffic. Each peer is assumed to respond with the peers closest to the content id among the
+    /// other synthetic peers. Returns the peers in the order they would be contacted.
+    #[method(name = "historySimulateLookup")]
+    async fn simulate_lookup(
+        &self,
+        enrs: Vec<Enr>,
+        content_id: B256,
+    ) -> RpcResult<Vec<SimulatedHopInfo>>;
+
+    /// Lookup a target node within in the network. Returns up to `limit` of the discovered nodes,
+    /// closest to the target first, or 16 if no limit is given.
     #[method(name = "historyRecursiveFindNodes")]
-    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>>;
+    async fn recursive_find_nodes(
+        &self,
+        node_id: NodeId,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<Enr>>;
+
+    /// Crawl the network by looking up random node ids across the key space, and return the
+    /// distinct peers discovered, until `max_peers` are discovered or `timeout_secs` elapse. A
+    /// measurement tool for researchers, not meant for production use.
+    #[method(name = "historyNetworkCrawl")]
+    async fn network_crawl(
+        &self,
+        max_peers: Option<usize>,
+        timeout_secs: Option<u64>,
+    ) -> RpcResult<NetworkCrawlInfo>;
 
     /// Send FINDCONTENT message to get the content with a content key.
     #[method(name = "historyFindContent")]
@@ -63,18 +167,103 @@ pub trait HistoryNetworkApi {
     ) -> RpcResult<FindContentInfo>;
 
     /// First checks local storage if content is not found lookup a target content key in the
-    /// network
+    /// network. If `hint_peer` is provided, it is asked for the content before the network lookup.
     #[method(name = "historyGetContent")]
-    async fn get_content(&self, content_key: HistoryContentKey) -> RpcResult<GetContentInfo>;
+    async fn get_content(
+        &self,
+        content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
+    ) -> RpcResult<GetContentInfo>;
+
+    /// Looks up the content key in local storage only, without ever starting a network lookup.
+    /// Returns null if the content isn't stored locally.
+    #[method(name = "historyLocalLookup")]
+    async fn local_lookup(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<Option<GetContentInfo>>;
 
     /// First checks local storage if content is not found lookup a target content key in the
-    /// network. Return tracing info.
+    /// network. If `hint_peer` is provided, it is asked for the content before the network lookup.
+    /// Return tracing info.
     #[method(name = "historyTraceGetContent")]
     async fn trace_get_content(
         &self,
         content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
     ) -> RpcResult<TraceContentInfo>;
 
+    /// Same as `historyGetContent`, but content that is found on the network is also stored if
+    /// it's within the radius of the node, and whether it was stored is returned. Requires the
+    /// `--enable-find-and-store` flag.
+    #[method(name = "historyFindAndStore")]
+    async fn find_and_store(&self, content_key: HistoryContentKey) -> RpcResult<FindAndStoreInfo>;
+
+    /// Fetch the given content from a single peer and store the content that is within the radius
+    /// of the node, running at most `concurrency` find content transfers at a time. Returns the
+    /// outcome counts and throughput of the backfill.
+    #[method(name = "historyBackfillFromPeer")]
+    async fn backfill_from_peer(
+        &self,
+        enr: Enr,
+        content_keys: Vec<HistoryContentKey>,
+        concurrency: Option<usize>,
+    ) -> RpcResult<BackfillInfo>;
+
+    /// Looks up the content of the given type of each block from `start_block` to `end_block`,
+    /// inclusive, first in local storage and then in the network. Only content types keyed by
+    /// block number are supported. Returns the content of each block, or null for blocks whose
+    /// content wasn't found.
+    #[method(name = "historyFindContentRange")]
+    async fn find_content_range(
+        &self,
+        content_type: HistoryContentType,
+        start_block: u64,
+        end_block: u64,
+    ) -> RpcResult<FindContentRangeInfo>;
+
+    /// Estimates the share of the content of the given type of the blocks from `start_block` to
+    /// `end_block`, inclusive, that is retrievable from the network. A random sample of up to
+    /// `sample_size` blocks is drawn from the range, and the peers closest to the content of each
+    /// are asked for it with a FINDCONTENT request, without a recursive lookup. Only content types
+    /// keyed by block number are supported.
+    #[method(name = "historySampleAvailability")]
+    async fn sample_availability(
+        &self,
+        content_type: HistoryContentType,
+        start_block: u64,
+        end_block: u64,
+        sample_size: u64,
+    ) -> RpcResult<SampleAvailabilityInfo>;
+
+    /// Same as `historyTraceGetContent`, but streams an event for each peer contacted during the
+    /// network lookup as it responds, followed by a final event with the content or the reason it
+    /// wasn't found. Requires a WebSocket connection.
+    #[subscription(
+        name = "historySubscribeTraceGetContent" => "historyTraceGetContentEvent",
+        unsubscribe = "historyUnsubscribeTraceGetContent",
+        item = TraceGetContentEvent
+    )]
+    async fn subscribe_trace_get_content(
+        &self,
+        content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
+    ) -> SubscriptionResult;
+
+    /// Returns the trace of a recent content lookup, by the id listed by `historyListTraces`.
+    /// Traces of the most recent lookups are retained, whether or not tracing was requested.
+    #[method(name = "historyGetTrace")]
+    async fn get_trace(&self, query_trace_id: u64) -> RpcResult<RecordedQueryTrace>;
+
+    /// Returns a summary of each retained content lookup trace, the most recent first.
+    #[method(name = "historyListTraces")]
+    async fn list_traces(&self) -> RpcResult<Vec<QueryTraceSummary>>;
+
+    /// Looks up the content of a trace returned by `historyGetTrace` again, and compares the
+    /// peers that responded to those of the recorded lookup.
+    #[method(name = "historyReplayTrace")]
+    async fn replay_trace(&self, recorded_trace: RecordedQueryTrace) -> RpcResult<ReplayTraceInfo>;
+
     /// Pagination of local content keys
     #[method(name = "historyPaginateLocalContentKeys")]
     async fn paginate_local_content_keys(
@@ -83,6 +272,25 @@ pub trait HistoryNetworkApi {
         limit: u64,
     ) -> RpcResult<PaginateLocalContentInfo<HistoryContentKey>>;
 
+    /// Returns the minimum and maximum block number of locally stored content, for each content
+    /// type whose content key encodes a block number.
+    #[method(name = "historyContentRange")]
+    async fn content_range(&self) -> RpcResult<ContentRangeInfo>;
+
+    /// Returns the number of locally stored content items per log2 distance from the local node
+    /// id, to show how well the stored content matches the radius.
+    #[method(name = "historyContentDistanceDistribution")]
+    async fn content_distance_distribution(&self) -> RpcResult<ContentDistanceDistributionInfo>;
+
+    /// Returns the keys of up to `count` locally stored content items whose content ids are
+    /// closest to the given node id, sorted by distance.
+    #[method(name = "historyClosestContent")]
+    async fn closest_content(
+        &self,
+        node_id: NodeId,
+        count: u64,
+    ) -> RpcResult<Vec<HistoryContentKey>>;
+
     /// Send the provided content value to interested peers. Clients may choose to send to some or
     /// all peers. Return the number of peers that the content was gossiped to.
     #[method(name = "historyGossip")]
@@ -101,6 +309,15 @@ pub trait HistoryNetworkApi {
         content_value: RawContentValue,
     ) -> RpcResult<TraceGossipInfo>;
 
+    /// Send the provided content items to interested peers. Clients may choose to send to some or
+    /// all peers. Return, for each content key, the number of peers that were offered and
+    /// accepted the content.
+    #[method(name = "historyGossipBatch")]
+    async fn gossip_batch(
+        &self,
+        content_items: Vec<(HistoryContentKey, RawContentValue)>,
+    ) -> RpcResult<GossipBatchInfo>;
+
     /// Send an OFFER request with given ContentItems, to the designated peer and wait for a
     /// response. Does not store the content locally.
     /// Returns the content keys bitlist upon successful content transmission or empty bitlist
@@ -123,6 +340,39 @@ pub trait HistoryNetworkApi {
         content_value: RawContentValue,
     ) -> RpcResult<OfferTrace>;
 
+    /// Send an OFFER request with given ContentItem, to the designated peer, and once the content
+    /// is transferred, send a FINDCONTENT request for it to the same peer, to confirm that the
+    /// peer stored it. Does not store the content locally.
+    /// Returns trace info for the offer, and whether the peer confirmed storing the content.
+    #[method(name = "historyOfferWithConfirmation")]
+    async fn offer_with_confirmation(
+        &self,
+        enr: Enr,
+        content_key: HistoryContentKey,
+        content_value: RawContentValue,
+    ) -> RpcResult<ConfirmedOfferInfo>;
+
+    /// Send an OFFER request with given ContentItems, to the designated peer, and measure the
+    /// uTP transfer of the accepted content. Does not store the content locally.
+    /// Returns the content keys bitlist, and the throughput of the transfer and of each item.
+    #[method(name = "historyOfferWithThroughput")]
+    async fn offer_with_throughput(
+        &self,
+        enr: Enr,
+        content_items: Vec<(HistoryContentKey, RawContentValue)>,
+    ) -> RpcResult<OfferThroughputInfo>;
+
+    /// Send an OFFER request with the given raw content keys and values to the designated peer,
+    /// without checking that they're well-formed or that the values match the keys, to test how
+    /// peers handle malformed offers. Does not store the content locally.
+    /// Returns the raw ACCEPT response of the peer. Requires `--enable-raw-offer`.
+    #[method(name = "historyRawOffer")]
+    async fn raw_offer(
+        &self,
+        enr: Enr,
+        content_items: Vec<(RawContentKey, RawContentValue)>,
+    ) -> RpcResult<Accept>;
+
     /// Store content key with a content data to the local database.
     #[method(name = "historyStore")]
     async fn store(
@@ -131,7 +381,107 @@ pub trait HistoryNetworkApi {
         content_value: RawContentValue,
     ) -> RpcResult<bool>;
 
+    /// Reports whether the content key would be accepted if it were offered or stored now, and
+    /// every reason why it would be rejected otherwise. Nothing is stored.
+    #[method(name = "historyExplainRejection")]
+    async fn explain_rejection(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<RejectionExplanationInfo>;
+
+    /// Force all content stored in the local database to be durably written to disk. Returns once
+    /// complete.
+    #[method(name = "historySyncStore")]
+    async fn sync_store(&self) -> RpcResult<bool>;
+
+    /// Write all content stored in the local database to a new file at `path`, in content id
+    /// order, so that nodes that store the same content produce identical files.
+    #[method(name = "historyExportStore")]
+    async fn export_store(&self, path: PathBuf) -> RpcResult<ExportStoreInfo>;
+
+    /// Returns the radius of the local node, the number and size of stored entries, in total and
+    /// per content type, and the ratio by which deduplication of identical values saves space.
+    #[method(name = "historyStorageInfo")]
+    async fn storage_info(&self) -> RpcResult<StorageInfo>;
+
+    /// Returns the latest changes of the radius of the local node, with the time of each change,
+    /// the oldest first.
+    #[method(name = "historyRadiusHistory")]
+    async fn radius_history(&self) -> RpcResult<Vec<RadiusChangeInfo>>;
+
+    /// Returns the version, git commit, build time and enabled cargo features of trin, and the
+    /// protocol identifiers and versions of the enabled subnetworks.
+    #[method(name = "historyClientInfo")]
+    async fn client_info(&self) -> RpcResult<ClientInfo>;
+
+    /// Returns whether the node is ready to serve traffic, i.e. its routing table is populated,
+    /// its store is initialized and it completed enough successful content lookups, together with
+    /// the status of each criterion.
+    #[method(name = "historyReadiness")]
+    async fn readiness(&self) -> RpcResult<ReadinessInfo>;
+
+    /// Returns the node's effective configuration, keyed by field name, with secrets redacted.
+    #[method(name = "historyGetConfig")]
+    async fn get_config(&self) -> RpcResult<serde_json::Value>;
+
+    /// Returns the last error encountered by each subsystem of the local node (store, utp,
+    /// discovery and gossip), with the time at which it was encountered. Subsystems that didn't
+    /// encounter any error since the node started are omitted.
+    #[method(name = "historyLastErrors")]
+    async fn last_errors(&self) -> RpcResult<Vec<LastErrorInfo>>;
+
+    /// Sets the data radius of the local node, overriding the radius derived from the stored
+    /// content, e.g. to test or recover a node, or clears the override if `radius` is null. The
+    /// sequence number of the local ENR is bumped, and the new radius is advertised to the
+    /// connected peers by pinging them. The radius doesn't exceed the override until it's
+    /// cleared or the node restarts, but pruning may still shrink it. Content stored outside the
+    /// radius is evicted in the background, following the `--on-radius-shrink` policy.
+    #[method(name = "historySetRadius")]
+    async fn set_radius(&self, radius: Option<DataRadius>) -> RpcResult<SetRadiusInfo>;
+
+    /// Writes the given number of synthetic entries with values of the given size to the local
+    /// database, reads them back and deletes them. Returns the write and read throughput.
+    #[method(name = "historyBenchmarkStore")]
+    async fn benchmark_store(
+        &self,
+        entry_count: u64,
+        value_size: u64,
+    ) -> RpcResult<StoreBenchmarkInfo>;
+
+    /// Returns the limit of concurrent uTP transfers requested by a single peer, and the number of
+    /// active transfers requested by each peer.
+    #[method(name = "historyPeerTransfers")]
+    async fn peer_transfers(&self) -> RpcResult<PeerTransfersInfo>;
+
+    /// Returns the circuit breakers of peers that failed requests. Peers whose circuit is open
+    /// aren't selected for lookups and offers until their cooldown ends.
+    #[method(name = "historyCircuitBreakers")]
+    async fn circuit_breakers(&self) -> RpcResult<Vec<CircuitBreakerInfo>>;
+
+    /// Returns up to `count` content keys that the local node recently served to peers, with the
+    /// number of times each was served within the last hour, the most served first.
+    #[method(name = "historyRecentlyServed")]
+    async fn recently_served(&self, count: u64) -> RpcResult<Vec<ServedContentInfo>>;
+
+    /// Returns the JSON-RPC requests that are currently being executed, the oldest first.
+    #[method(name = "historyListInFlight")]
+    async fn list_in_flight(&self) -> RpcResult<Vec<InFlightRequestInfo>>;
+
+    /// Cancel the execution of a request, by the id listed by `historyListInFlight`. Any uTP
+    /// transfers and network lookups of the request are abandoned. Returns whether the request
+    /// was still in flight.
+    #[method(name = "historyCancelRequest")]
+    async fn cancel_request(&self, request_id: u64) -> RpcResult<bool>;
+
     /// Get a content value from the local database
     #[method(name = "historyLocalContent")]
     async fn local_content(&self, content_key: HistoryContentKey) -> RpcResult<RawContentValue>;
+
+    /// Returns the content value as it's stored in the local database, before it's decoded or its
+    /// checksum is verified, to debug store-level issues.
+    #[method(name = "historyRawStoredBytes")]
+    async fn raw_stored_bytes(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<RawStoredBytesInfo>;
 }
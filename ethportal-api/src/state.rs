@@ -50,9 +50,14 @@ pub trait StateNetworkApi {
     #[method(name = "stateFindNodes")]
     async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo>;
 
-    /// Lookup a target node within in the network
+    /// Lookup a target node within in the network. Returns up to `limit` of the discovered nodes,
+    /// closest to the target first, or 16 if no limit is given.
     #[method(name = "stateRecursiveFindNodes")]
-    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>>;
+    async fn recursive_find_nodes(
+        &self,
+        node_id: NodeId,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<Enr>>;
 
     /// Send FINDCONTENT message to get the content with a content key.
     #[method(name = "stateFindContent")]
@@ -13,6 +13,12 @@ pub enum ContentKeyError {
 
     #[error("Input Vec has length {received}, expected {expected})")]
     InvalidLength { received: usize, expected: usize },
+
+    #[error("A {content_type} content key can't be built from block {block_id}")]
+    UnsupportedBlockId {
+        content_type: String,
+        block_id: String,
+    },
 }
 
 impl ContentKeyError {
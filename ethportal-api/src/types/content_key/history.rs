@@ -1,5 +1,6 @@
 use std::{fmt, hash::Hash};
 
+use alloy::primitives::B256;
 use bytes::{BufMut, BytesMut};
 use rand::{seq::SliceRandom, RngCore};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -19,6 +20,45 @@ pub const HISTORY_BLOCK_BODY_KEY_PREFIX: u8 = 0x01;
 pub const HISTORY_BLOCK_RECEIPTS_KEY_PREFIX: u8 = 0x02;
 pub const HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX: u8 = 0x03;
 
+/// The type of content in the history overlay network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryContentType {
+    BlockHeaderByHash,
+    BlockHeaderByNumber,
+    BlockBody,
+    BlockReceipts,
+}
+
+impl HistoryContentType {
+    /// Returns the selector byte that content keys of this type start with.
+    pub fn key_prefix(&self) -> u8 {
+        match self {
+            Self::BlockHeaderByHash => HISTORY_BLOCK_HEADER_BY_HASH_KEY_PREFIX,
+            Self::BlockHeaderByNumber => HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX,
+            Self::BlockBody => HISTORY_BLOCK_BODY_KEY_PREFIX,
+            Self::BlockReceipts => HISTORY_BLOCK_RECEIPTS_KEY_PREFIX,
+        }
+    }
+}
+
+/// Identifies a block by its hash or number.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HistoryBlockId {
+    Hash(B256),
+    Number(u64),
+}
+
+impl fmt::Display for HistoryBlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hash(block_hash) => write!(f, "hash {block_hash}"),
+            Self::Number(block_number) => write!(f, "number {block_number}"),
+        }
+    }
+}
+
 /// A content key in the history overlay network.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum HistoryContentKey {
@@ -74,6 +114,55 @@ impl HistoryContentKey {
             block_hash: block_hash.into(),
         })
     }
+
+    /// Returns the type of content that the key refers to.
+    pub fn content_type(&self) -> HistoryContentType {
+        match self {
+            Self::BlockHeaderByHash(_) => HistoryContentType::BlockHeaderByHash,
+            Self::BlockHeaderByNumber(_) => HistoryContentType::BlockHeaderByNumber,
+            Self::BlockBody(_) => HistoryContentType::BlockBody,
+            Self::BlockReceipts(_) => HistoryContentType::BlockReceipts,
+        }
+    }
+
+    /// Returns the number of the block that the key refers to, if the key encodes it.
+    ///
+    /// Only block headers by number are keyed by block number. The other content types are keyed
+    /// by block hash, which can't be mapped to a block number without the header.
+    pub fn block_number(&self) -> Option<u64> {
+        match self {
+            Self::BlockHeaderByNumber(key) => Some(key.block_number),
+            Self::BlockHeaderByHash(_) | Self::BlockBody(_) | Self::BlockReceipts(_) => None,
+        }
+    }
+
+    /// Builds the content key of the given type for a block.
+    ///
+    /// Block bodies and receipts are only keyed by block hash, and each header content type only
+    /// by its own kind of block id.
+    pub fn from_block_id(
+        content_type: HistoryContentType,
+        block_id: HistoryBlockId,
+    ) -> Result<Self, ContentKeyError> {
+        match (content_type, block_id) {
+            (HistoryContentType::BlockHeaderByHash, HistoryBlockId::Hash(block_hash)) => {
+                Ok(Self::new_block_header_by_hash(block_hash))
+            }
+            (HistoryContentType::BlockHeaderByNumber, HistoryBlockId::Number(block_number)) => {
+                Ok(Self::new_block_header_by_number(block_number))
+            }
+            (HistoryContentType::BlockBody, HistoryBlockId::Hash(block_hash)) => {
+                Ok(Self::new_block_body(block_hash))
+            }
+            (HistoryContentType::BlockReceipts, HistoryBlockId::Hash(block_hash)) => {
+                Ok(Self::new_block_receipts(block_hash))
+            }
+            _ => Err(ContentKeyError::UnsupportedBlockId {
+                content_type: format!("{content_type:?}"),
+                block_id: block_id.to_string(),
+            }),
+        }
+    }
 }
 
 impl Hash for HistoryContentKey {
@@ -188,6 +277,13 @@ impl OverlayContentKey for HistoryContentKey {
         RawContentKey::from(bytes.freeze())
     }
 
+    fn block_number(&self) -> Option<u64> {
+        match self {
+            HistoryContentKey::BlockHeaderByNumber(key) => Some(key.block_number),
+            _ => None,
+        }
+    }
+
     fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, ContentKeyError> {
         let bytes = bytes.as_ref();
         let Some((&selector, key)) = bytes.split_first() else {
@@ -413,4 +509,77 @@ mod test {
             content_key_json
         );
     }
+
+    #[test]
+    fn from_block_id() {
+        let block_hash = B256::from(BLOCK_HASH);
+        assert_eq!(
+            HistoryContentKey::from_block_id(
+                HistoryContentType::BlockHeaderByHash,
+                HistoryBlockId::Hash(block_hash)
+            )
+            .unwrap(),
+            HistoryContentKey::new_block_header_by_hash(block_hash)
+        );
+        assert_eq!(
+            HistoryContentKey::from_block_id(
+                HistoryContentType::BlockHeaderByNumber,
+                HistoryBlockId::Number(12_345_678)
+            )
+            .unwrap(),
+            HistoryContentKey::new_block_header_by_number(12_345_678u64)
+        );
+        assert_eq!(
+            HistoryContentKey::from_block_id(
+                HistoryContentType::BlockReceipts,
+                HistoryBlockId::Hash(block_hash)
+            )
+            .unwrap(),
+            HistoryContentKey::new_block_receipts(block_hash)
+        );
+
+        for (content_type, block_id) in [
+            (
+                HistoryContentType::BlockHeaderByNumber,
+                HistoryBlockId::Hash(block_hash),
+            ),
+            (HistoryContentType::BlockBody, HistoryBlockId::Number(1)),
+        ] {
+            assert!(matches!(
+                HistoryContentKey::from_block_id(content_type, block_id),
+                Err(ContentKeyError::UnsupportedBlockId { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn block_number_is_only_encoded_by_header_by_number_keys() {
+        assert_eq!(
+            HistoryContentKey::new_block_header_by_number(12_345_678u64).block_number(),
+            Some(12_345_678)
+        );
+        assert_eq!(
+            HistoryContentKey::new_block_header_by_hash(BLOCK_HASH).block_number(),
+            None
+        );
+        assert_eq!(
+            HistoryContentKey::new_block_body(BLOCK_HASH).block_number(),
+            None
+        );
+        assert_eq!(
+            HistoryContentKey::new_block_receipts(BLOCK_HASH).block_number(),
+            None
+        );
+    }
+
+    #[test]
+    fn block_id_deserializes_from_hash_or_number() {
+        let block_id: HistoryBlockId = serde_json::from_str(
+            "\"0xd1c390624d3bd4e409a61a858e5dcc5517729a9170d014a6c96530d64dd8621d\"",
+        )
+        .unwrap();
+        assert_eq!(block_id, HistoryBlockId::Hash(B256::from(BLOCK_HASH)));
+        let block_id: HistoryBlockId = serde_json::from_str("12345678").unwrap();
+        assert_eq!(block_id, HistoryBlockId::Number(12_345_678));
+    }
 }
@@ -28,6 +28,11 @@ pub trait OverlayContentKey:
     /// obtain it with: `key.to_bytes().to_vec()`.
     fn to_bytes(&self) -> RawContentKey;
 
+    /// Returns the number of the block that the content belongs to, if the key encodes it.
+    fn block_number(&self) -> Option<u64> {
+        None
+    }
+
     /// Decodes bytes as content key.
     fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, ContentKeyError>;
 
@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
+
+use alloy::primitives::U256;
 use discv5::enr::NodeId;
 use serde::{Deserialize, Serialize};
 
-use super::enr::Enr;
+use super::{cli::ValidationLevel, enr::Enr};
 
 /// Discv5 bucket
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +28,41 @@ pub struct NodeInfo {
     pub enr: Enr,
     pub node_id: NodeId,
     pub ip: Option<String>,
+    /// Whether each overlay network is ready to serve content lookups, keyed by subnetwork.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lookup_readiness: BTreeMap<String, LookupReadinessInfo>,
+    /// Whether the data store of each overlay network is usable, keyed by subnetwork.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub store_health: BTreeMap<String, StoreHealthInfo>,
+    /// How thoroughly content is validated before it's stored, served or propagated.
+    #[serde(default)]
+    pub validation_level: ValidationLevel,
+    /// The name of the validator of each content type, keyed by subnetwork and then by content
+    /// type, i.e. the first byte of the content key, hex encoded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub validators: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Whether an overlay network has enough connected peers to serve content lookups.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupReadinessInfo {
+    /// The number of connected peers in the routing table.
+    pub peers: usize,
+    /// The number of connected peers required to serve content lookups.
+    pub min_peers: usize,
+    pub ready: bool,
+}
+
+/// Whether the data store of an overlay network is usable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreHealthInfo {
+    /// Whether the store is considered unavailable, in which case offered content is declined
+    /// and lookups are only routed to peers.
+    pub degraded: bool,
+    /// The number of consecutive failed store operations.
+    pub consecutive_failures: u32,
 }
 
 /// Information about a discv5/overlay network's routing table.
@@ -35,6 +73,76 @@ pub struct RoutingTableInfo {
     pub buckets: KBucketsTable,
 }
 
+/// Gossip reach of a single bucket of an overlay network's routing table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketNeighborhoodInfo {
+    /// The log2 distance from the local node covered by the bucket.
+    pub log2_distance: u16,
+    /// The number of connected peers in the bucket.
+    pub peers: usize,
+    /// The number of connected peers in the bucket that are within gossip range.
+    pub peers_in_range: usize,
+}
+
+/// Information about the gossip neighborhood of an overlay network's routing table.
+///
+/// A peer is within gossip range if it is connected and the distance between it and the local
+/// node doesn't exceed the sum of their radii, meaning that the peer may be interested in some of
+/// the content the local node stores.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NeighborhoodInfo {
+    pub local_node_id: NodeId,
+    /// The data radius of the local node at the time of the query.
+    pub radius: U256,
+    /// The total number of connected peers within gossip range.
+    pub peers_in_range: usize,
+    /// Breakdown per non-empty bucket, ordered by log2 distance.
+    pub buckets: Vec<BucketNeighborhoodInfo>,
+}
+
+/// The number of peers in an overlay network's routing table that advertise an address in an IP
+/// subnet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetPeersInfo {
+    /// The subnet, a /24 for IPv4 addresses and a /64 for IPv6 addresses.
+    pub subnet: String,
+    pub peers: usize,
+}
+
+/// The distribution of the peers in an overlay network's routing table over IP subnets.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetDistributionInfo {
+    /// The max number of peers per subnet, or none if it isn't limited.
+    pub max_peers_per_subnet: Option<usize>,
+    /// Breakdown per subnet that peers advertise an address in, the most crowded first.
+    pub subnets: Vec<SubnetPeersInfo>,
+}
+
+/// Refresh state of a single bucket of an overlay network's routing table.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketRefreshInfo {
+    /// The log2 distance from the local node covered by the bucket.
+    pub log2_distance: u16,
+    /// The number of nodes in the bucket.
+    pub entries: usize,
+    /// The unix timestamp, in milliseconds, of the last node lookup targeting the bucket, if any.
+    pub last_refresh_ms: Option<u64>,
+}
+
+/// Information about when the buckets of an overlay network's routing table were last refreshed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshInfo {
+    pub local_node_id: NodeId,
+    /// Breakdown per bucket that is non-empty or was ever refreshed, ordered by log2 distance.
+    pub buckets: Vec<BucketRefreshInfo>,
+}
+
 impl<TVal: Eq> From<discv5::kbucket::KBucketsTable<NodeId, TVal>> for KBucketsTable {
     fn from(table: discv5::kbucket::KBucketsTable<NodeId, TVal>) -> Self {
         let buckets = table
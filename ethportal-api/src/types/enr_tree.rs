@@ -0,0 +1,293 @@
+//! Types of an EIP-1459 DNS node list: a tree of ENRs published in DNS TXT records, whose root is
+//! signed by the key of the list.
+//!
+//! See <https://eips.ethereum.org/EIPS/eip-1459>.
+
+use std::{fmt, str::FromStr};
+
+use alloy::primitives::keccak256;
+use anyhow::{anyhow, bail, ensure};
+use secp256k1::{ecdsa::Signature, Message, PublicKey, SECP256K1};
+
+use crate::types::enr::Enr;
+
+const URL_PREFIX: &str = "enrtree://";
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+const LINK_PREFIX: &str = "enrtree://";
+const ENR_PREFIX: &str = "enr:";
+
+/// The alphabet of the base32 encoding (RFC 4648) that DNS node lists use.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The URL of a DNS node list: `enrtree://<public key>@<domain>`, where the public key is the
+/// base32 encoding of the compressed secp256k1 key that signs the list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnrTreeUrl {
+    pub public_key: PublicKey,
+    pub domain: String,
+}
+
+impl FromStr for EnrTreeUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (public_key, domain) = s
+            .strip_prefix(URL_PREFIX)
+            .and_then(|rest| rest.split_once('@'))
+            .ok_or_else(|| {
+                anyhow!("DNS node list URL must be of the form {URL_PREFIX}<key>@<domain>")
+            })?;
+        ensure!(!domain.is_empty(), "DNS node list URL has an empty domain");
+        let public_key = PublicKey::from_slice(&base32_decode(public_key)?)
+            .map_err(|err| anyhow!("Invalid DNS node list public key: {err}"))?;
+        Ok(Self {
+            public_key,
+            domain: domain.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for EnrTreeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{URL_PREFIX}{}@{}",
+            base32_encode(&self.public_key.serialize()),
+            self.domain
+        )
+    }
+}
+
+/// The root of a DNS node list, held by the TXT record of its domain:
+/// `enrtree-root:v1 e=<enr root> l=<link root> seq=<sequence number> sig=<signature>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnrTreeRoot {
+    /// The hash of the root of the subtree that holds ENRs.
+    pub enr_root: String,
+    /// The hash of the root of the subtree that holds links to other lists.
+    pub link_root: String,
+    /// The sequence number, which is increased with every update of the list.
+    pub seq: u64,
+    /// The signed part of the record, everything before the signature.
+    signed: String,
+    /// The signature, in the 65 bytes `[r || s || v]` format.
+    signature: Vec<u8>,
+}
+
+impl EnrTreeRoot {
+    /// Verifies that the root is signed by the key of the list.
+    pub fn verify(&self, public_key: &PublicKey) -> anyhow::Result<()> {
+        ensure!(
+            self.signature.len() == 65,
+            "Invalid DNS node list signature length: {}",
+            self.signature.len()
+        );
+        let mut signature = Signature::from_compact(&self.signature[..64])
+            .map_err(|err| anyhow!("Invalid DNS node list signature: {err}"))?;
+        signature.normalize_s();
+        let message = Message::from_digest(keccak256(self.signed.as_bytes()).0);
+        SECP256K1
+            .verify_ecdsa(&message, &signature, public_key)
+            .map_err(|_| anyhow!("DNS node list root isn't signed by the key of the list"))
+    }
+}
+
+impl FromStr for EnrTreeRoot {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (signed, signature) = s
+            .rsplit_once(" sig=")
+            .ok_or_else(|| anyhow!("DNS node list root has no signature"))?;
+        let mut fields = signed.split(' ');
+        ensure!(
+            fields.next() == Some(ROOT_PREFIX),
+            "DNS node list root must start with {ROOT_PREFIX}"
+        );
+        let mut field = |name: &str| {
+            fields
+                .next()
+                .and_then(|field| field.strip_prefix(name))
+                .ok_or_else(|| anyhow!("DNS node list root has no {name} field"))
+        };
+        let enr_root = field("e=")?.to_string();
+        let link_root = field("l=")?.to_string();
+        let seq = field("seq=")?
+            .parse()
+            .map_err(|err| anyhow!("Invalid DNS node list sequence number: {err}"))?;
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+            .map_err(|err| anyhow!("Invalid DNS node list signature encoding: {err}"))?;
+        Ok(Self {
+            enr_root,
+            link_root,
+            seq,
+            signed: signed.to_string(),
+            signature,
+        })
+    }
+}
+
+/// An entry of a DNS node list, held by the TXT record of `<hash>.<domain>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EnrTreeEntry {
+    /// The hashes of the child entries.
+    Branch(Vec<String>),
+    /// A node of the list.
+    Enr(Box<Enr>),
+    /// A link to another list.
+    Link(String),
+}
+
+impl FromStr for EnrTreeEntry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(children) = s.strip_prefix(BRANCH_PREFIX) {
+            let children = children
+                .split(',')
+                .filter(|child| !child.is_empty())
+                .map(str::to_string)
+                .collect();
+            Ok(Self::Branch(children))
+        } else if s.starts_with(ENR_PREFIX) {
+            let enr = Enr::from_str(s).map_err(|err| anyhow!("Invalid ENR: {err}"))?;
+            Ok(Self::Enr(Box::new(enr)))
+        } else if s.starts_with(LINK_PREFIX) {
+            Ok(Self::Link(s.to_string()))
+        } else {
+            bail!("Unknown DNS node list entry: {s}")
+        }
+    }
+}
+
+/// Returns the hash of an entry, under which it's published: the base32 encoding of the first 16
+/// bytes of the keccak256 hash of its text.
+pub fn entry_hash(text: &str) -> String {
+    base32_encode(&keccak256(text.as_bytes())[..16])
+}
+
+/// Encodes the bytes in unpadded base32.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decodes unpadded base32.
+fn base32_decode(encoded: &str) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| anyhow!("Invalid base32 character: {}", c as char))?;
+        buffer = (buffer << 5) | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use secp256k1::SecretKey;
+
+    use super::*;
+
+    fn signed_root(secret_key: &SecretKey, signed: &str) -> String {
+        let message = Message::from_digest(keccak256(signed.as_bytes()).0);
+        let (recovery_id, signature) = SECP256K1
+            .sign_ecdsa_recoverable(&message, secret_key)
+            .serialize_compact();
+        let mut signature = signature.to_vec();
+        signature.push(recovery_id.to_i32() as u8);
+        format!(
+            "{signed} sig={}",
+            base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        for len in 0..40 {
+            let bytes: Vec<u8> = (0..len).map(|_| rand::random()).collect();
+            assert_eq!(base32_decode(&base32_encode(&bytes)).unwrap(), bytes);
+        }
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn url_round_trip() {
+        let (_, public_key) = SECP256K1.generate_keypair(&mut rand::thread_rng());
+        let url = EnrTreeUrl {
+            public_key,
+            domain: "nodes.example.org".to_string(),
+        };
+        assert_eq!(url.to_string().parse::<EnrTreeUrl>().unwrap(), url);
+        assert!("enrtree://nodes.example.org".parse::<EnrTreeUrl>().is_err());
+        assert!("https://key@nodes.example.org"
+            .parse::<EnrTreeUrl>()
+            .is_err());
+    }
+
+    #[test]
+    fn root_signature_is_verified() {
+        let (secret_key, public_key) = SECP256K1.generate_keypair(&mut rand::thread_rng());
+        let signed =
+            "enrtree-root:v1 e=JWXYDBPXYWG6FX3GMDIBFA6CJ4 l=C7HRFPF3BLGF3YR4DY5KX3SMBE seq=1";
+        let root: EnrTreeRoot = signed_root(&secret_key, signed).parse().unwrap();
+        assert_eq!(root.enr_root, "JWXYDBPXYWG6FX3GMDIBFA6CJ4");
+        assert_eq!(root.link_root, "C7HRFPF3BLGF3YR4DY5KX3SMBE");
+        assert_eq!(root.seq, 1);
+        root.verify(&public_key).unwrap();
+
+        // Signed by another key.
+        let (_, other_public_key) = SECP256K1.generate_keypair(&mut rand::thread_rng());
+        root.verify(&other_public_key).unwrap_err();
+
+        // Tampered with after signing.
+        let tampered = signed_root(&secret_key, signed).replace("seq=1", "seq=2");
+        let root: EnrTreeRoot = tampered.parse().unwrap();
+        root.verify(&public_key).unwrap_err();
+    }
+
+    #[test]
+    fn entries_are_parsed() {
+        assert_eq!(
+            "enrtree-branch:2XS2367YHAXJFGLZHVAWLQD4ZY,H4FHT4B454P6UXFD7JCYQ5PWDY"
+                .parse::<EnrTreeEntry>()
+                .unwrap(),
+            EnrTreeEntry::Branch(vec![
+                "2XS2367YHAXJFGLZHVAWLQD4ZY".to_string(),
+                "H4FHT4B454P6UXFD7JCYQ5PWDY".to_string()
+            ])
+        );
+        assert!(matches!(
+            "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@morenodes.example.org"
+                .parse::<EnrTreeEntry>()
+                .unwrap(),
+            EnrTreeEntry::Link(_)
+        ));
+        "unknown".parse::<EnrTreeEntry>().unwrap_err();
+        assert_eq!(entry_hash("enrtree-branch:").len(), 26);
+    }
+}
@@ -1,8 +1,18 @@
+use std::path::PathBuf;
+
+use alloy::primitives::B256;
 use discv5::enr::NodeId;
+use strum::AsRefStr;
 
 use crate::{
-    types::enr::Enr, BeaconContentKey, BeaconContentValue, HistoryContentKey, HistoryContentValue,
-    StateContentKey, StateContentValue,
+    types::{
+        content_key::history::{HistoryBlockId, HistoryContentType},
+        enr::Enr,
+        portal::DataRadius,
+        query_trace::RecordedQueryTrace,
+    },
+    BeaconContentKey, BeaconContentValue, HistoryContentKey, HistoryContentValue, RawContentKey,
+    RawContentValue, StateContentKey, StateContentValue,
 };
 
 /// Discv5 JSON-RPC endpoints. Start with "discv5_" prefix
@@ -29,8 +39,8 @@ pub enum StateEndpoint {
     LookupEnr(NodeId),
     /// params: [enr, distances]
     FindNodes(Enr, Vec<u16>),
-    /// params: [node_id]
-    RecursiveFindNodes(NodeId),
+    /// params: [node_id, limit]
+    RecursiveFindNodes(NodeId, usize),
     /// params: None
     DataRadius,
     /// params: content_key
@@ -56,7 +66,7 @@ pub enum StateEndpoint {
 }
 
 /// History network JSON-RPC endpoints. Start with "portal_history" prefix
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, AsRefStr)]
 pub enum HistoryEndpoint {
     /// params: [enr]
     AddEnr(Enr),
@@ -64,6 +74,8 @@ pub enum HistoryEndpoint {
     DataRadius,
     /// params: [node_id]
     DeleteEnr(NodeId),
+    /// params: [node_id, deny_ttl_secs]
+    RemoveEnr(NodeId, Option<u64>),
     /// params: [enr, content_key]
     FindContent(Enr, HistoryContentKey),
     /// params: [enr, distances]
@@ -72,31 +84,144 @@ pub enum HistoryEndpoint {
     GetEnr(NodeId),
     /// params: content_key
     LocalContent(HistoryContentKey),
+    /// params: content_key
+    LocalLookup(HistoryContentKey),
+    /// params: content_key
+    RawStoredBytes(HistoryContentKey),
     /// params: [node_id]
     LookupEnr(NodeId),
     /// params: [content_key, content_value]
     Gossip(HistoryContentKey, HistoryContentValue),
     /// params: [content_key, content_value]
     TraceGossip(HistoryContentKey, HistoryContentValue),
+    /// params: [Vec<(content_key, content_value)>]
+    GossipBatch(Vec<(HistoryContentKey, HistoryContentValue)>),
     /// params: [enr, Vec<(content_key, content_value)>]
     Offer(Enr, Vec<(HistoryContentKey, HistoryContentValue)>),
     /// params: [enr, content_key, content_value]
     TraceOffer(Enr, HistoryContentKey, HistoryContentValue),
+    /// params: [enr, content_key, content_value]
+    OfferWithConfirmation(Enr, HistoryContentKey, HistoryContentValue),
+    /// params: [enr, Vec<(content_key, content_value)>]
+    OfferWithThroughput(Enr, Vec<(HistoryContentKey, HistoryContentValue)>),
+    /// params: [enr, Vec<(raw_content_key, raw_content_value)>]
+    RawOffer(Enr, Vec<(RawContentKey, RawContentValue)>),
     /// params: [enr]
     Ping(Enr),
+    /// params: [enr]
+    DebugPing(Enr),
+    /// params: [enr]
+    AuditPeerRadius(Enr),
+    /// params: [enr]
+    TestInboundReachability(Enr),
+    /// params: [content_key, hint_peer]
+    GetContent(HistoryContentKey, Option<Enr>),
+    /// params: [content_key, hint_peer]
+    TraceGetContent(HistoryContentKey, Option<Enr>),
     /// params: content_key
-    GetContent(HistoryContentKey),
-    /// params: content_key
-    TraceGetContent(HistoryContentKey),
+    FindAndStore(HistoryContentKey),
+    /// params: [enr, content_keys, concurrency]
+    BackfillFromPeer(Enr, Vec<HistoryContentKey>, usize),
+    /// params: [content_type, start_block, end_block]
+    FindContentRange(HistoryContentType, u64, u64),
+    /// params: [content_type, start_block, end_block, sample_size]
+    SampleAvailability(HistoryContentType, u64, u64, u64),
+    /// params: [content_key, hint_peer]
+    StreamTraceGetContent(HistoryContentKey, Option<Enr>),
+    /// params: [query_trace_id]
+    GetTrace(u64),
+    /// params: None
+    ListTraces,
+    /// params: [recorded_trace]
+    ReplayTrace(RecordedQueryTrace),
     /// params: [content_key, content_value]
     Store(HistoryContentKey, HistoryContentValue),
+    /// params: content_key
+    ExplainRejection(HistoryContentKey),
     /// params: None
     RoutingTableInfo,
+    /// params: None
+    NeighborhoodInfo,
+    /// params: None
+    SubnetDistribution,
+    /// params: None
+    BucketRefreshInfo,
+    /// params: None
+    ReloadDenylist,
+    /// params: None
+    SyncStore,
+    /// params: [path]
+    ExportStore(PathBuf),
+    /// params: None
+    StorageInfo,
+    /// params: None
+    RadiusHistory,
+    /// params: None
+    ClientInfo,
+    /// params: None
+    Readiness,
+    /// params: None
+    GetConfig,
+    /// params: None
+    LastErrors,
+    /// params: [radius]
+    SetRadius(Option<DataRadius>),
+    /// params: [entry_count, value_size]
+    BenchmarkStore(u64, u64),
+    /// params: None
+    PeerTransfers,
+    /// params: None
+    CircuitBreakers,
+    /// params: [count]
+    RecentlyServed(u64),
+    /// params: None
+    ListInFlight,
+    /// params: [request_id]
+    CancelRequest(u64),
     // This endpoint is not History network specific
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
-    /// params: [node_id]
-    RecursiveFindNodes(NodeId),
+    /// params: None
+    ContentRange,
+    /// params: None
+    ContentDistanceDistribution,
+    /// params: [node_id, count]
+    ClosestContent(NodeId, u64),
+    /// params: [node_id, node_id]
+    NodeDistance(NodeId, NodeId),
+    /// params: [content_type, block_id]
+    EncodeContentKey(HistoryContentType, HistoryBlockId),
+    /// params: [content_key]
+    ContentKeyBlockNumber(HistoryContentKey),
+    /// params: [enrs, content_id]
+    SimulateLookup(Vec<Enr>, B256),
+    /// params: [node_id, limit]
+    RecursiveFindNodes(NodeId, usize),
+    /// params: [max_peers, timeout_secs]
+    NetworkCrawl(usize, u64),
+}
+
+impl HistoryEndpoint {
+    /// The endpoints that look up content on the network, which are queued by priority when the
+    /// number of concurrent lookups is limited.
+    pub const LOOKUP_ENDPOINTS: [&'static str; 4] = [
+        "GetContent",
+        "TraceGetContent",
+        "FindAndStore",
+        "StreamTraceGetContent",
+    ];
+
+    /// Returns the key of the content that the endpoint looks up on the network, if it's one of
+    /// the `LOOKUP_ENDPOINTS`.
+    pub fn lookup_content_key(&self) -> Option<&HistoryContentKey> {
+        match self {
+            Self::GetContent(content_key, _)
+            | Self::TraceGetContent(content_key, _)
+            | Self::FindAndStore(content_key)
+            | Self::StreamTraceGetContent(content_key, _) => Some(content_key),
+            _ => None,
+        }
+    }
 }
 
 /// Beacon network JSON-RPC endpoints. Start with "portal_beacon" prefix
@@ -150,8 +275,8 @@ pub enum BeaconEndpoint {
     RoutingTableInfo,
     /// params: [offset, limit]
     PaginateLocalContentKeys(u64, u64),
-    /// params: [node_id]
-    RecursiveFindNodes(NodeId),
+    /// params: [node_id, limit]
+    RecursiveFindNodes(NodeId, usize),
 }
 
 /// The common functionality of subnetwork endpoints.
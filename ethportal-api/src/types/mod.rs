@@ -7,6 +7,7 @@ pub mod content_value;
 pub mod discv5;
 pub mod distance;
 pub mod enr;
+pub mod enr_tree;
 pub mod execution;
 pub mod jsonrpc;
 pub mod network;
@@ -1,8 +1,18 @@
-use alloy::primitives::{Bytes, U256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use alloy::primitives::{Bytes, B256, U256};
+use discv5::enr::NodeId;
 use serde::{Deserialize, Serialize};
 use ssz_types::{typenum, BitList};
 
-use super::query_trace::QueryTrace;
+use super::{
+    portal_wire::OfferTrace,
+    query_trace::{QueryHopEvent, QueryTrace, QueryTraceDiff},
+};
 use crate::{types::enr::Enr, OverlayContentKey};
 
 /// The SSZ encoded representation of content key.
@@ -56,10 +66,183 @@ pub struct PongInfo {
     pub data_radius: DataRadius,
 }
 
+/// Response for DebugPing endpoint
+///
+/// `request` and `response` are the raw payloads of the TALKREQ and TALKRESP discv5 messages of
+/// the exchange.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugPingInfo {
+    pub request: Bytes,
+    pub response: Bytes,
+    /// The ENR sequence number sent in the PING.
+    pub local_enr_seq: u64,
+    /// The data radius sent in the PING.
+    pub local_data_radius: DataRadius,
+    /// The decoded PONG, if the response could be decoded as one.
+    pub pong: Option<PongInfo>,
+    /// Why the response couldn't be decoded as a PONG, if it couldn't.
+    pub decode_error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Response for AuditPeerRadius endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditPeerRadiusInfo {
+    /// The radius advertised by the peer
+    pub data_radius: DataRadius,
+    /// Number of locally stored content items within the peer's radius that were probed
+    pub sampled: u32,
+    /// Number of probed content items that the peer returned
+    pub held: u32,
+    /// Fraction of probed content items that the peer returned, if any were probed
+    pub hit_rate: Option<f64>,
+}
+
+/// Response for TestInboundReachability endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundReachabilityInfo {
+    /// The UDP address advertised in the local ENR, if any
+    pub advertised_address: Option<SocketAddr>,
+    /// The address from which the peer observed the local node's ping, if it responded
+    pub observed_address: Option<SocketAddr>,
+    /// Whether peers can reach the local node at its advertised address, i.e. the peer responded
+    /// and observed the local node at that address
+    pub reachable: bool,
+    /// Why the peer didn't respond, if it didn't
+    pub error: Option<String>,
+}
+
+/// Response for Readiness endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessInfo {
+    /// Whether all the readiness criteria are met
+    pub ready: bool,
+    /// The number of connected peers in the routing table
+    pub peers: usize,
+    /// The min number of connected peers required for readiness
+    pub min_peers: usize,
+    /// Whether the content store is initialized and usable
+    pub store_initialized: bool,
+    /// The number of content lookups that found the content since the node started
+    pub successful_lookups: u64,
+    /// The min number of successful content lookups required for readiness
+    pub min_successful_lookups: u64,
+}
+
 pub type FindNodesInfo = Vec<Enr>;
 
+/// The number of nodes that a recursive find nodes returns, if no limit is given.
+pub const DEFAULT_RECURSIVE_FIND_NODES_LIMIT: usize = 16;
+
+/// The max number of nodes that a recursive find nodes can return.
+pub const MAX_RECURSIVE_FIND_NODES_LIMIT: usize = 256;
+
+/// The number of peers that a network crawl discovers, if no limit is given.
+pub const DEFAULT_NETWORK_CRAWL_MAX_PEERS: usize = 1000;
+
+/// The max number of peers that a network crawl can discover.
+pub const MAX_NETWORK_CRAWL_MAX_PEERS: usize = 10_000;
+
+/// The time (in seconds) after which a network crawl stops, if no timeout is given.
+pub const DEFAULT_NETWORK_CRAWL_TIMEOUT_SECS: u64 = 60;
+
+/// The max time (in seconds) that a network crawl can run for.
+pub const MAX_NETWORK_CRAWL_TIMEOUT_SECS: u64 = 600;
+
+/// Why a network crawl stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkCrawlStopReason {
+    /// The max number of peers was discovered.
+    MaxPeers,
+    /// The timeout elapsed.
+    Timeout,
+    /// Consecutive lookups stopped discovering new peers.
+    Exhausted,
+}
+
+/// A peer discovered by a network crawl.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawledPeerInfo {
+    pub enr: Enr,
+    /// The client advertised in the ENR of the peer, if any.
+    pub client: Option<String>,
+    /// The radius advertised by the peer, if it's in the routing table.
+    pub data_radius: Option<DataRadius>,
+}
+
+/// The number of peers that a network crawl discovered, after one of its lookups.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkCrawlProgressInfo {
+    pub elapsed_ms: u64,
+    pub lookups: u64,
+    pub discovered_peers: usize,
+}
+
+/// Response for NetworkCrawl endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkCrawlInfo {
+    /// The discovered peers, closest to the local node first.
+    pub peers: Vec<CrawledPeerInfo>,
+    pub stop_reason: NetworkCrawlStopReason,
+    pub lookups: u64,
+    pub duration_ms: u64,
+    /// The average number of peers discovered per second.
+    pub peers_per_second: f64,
+    /// The number of discovered peers after each lookup, which shows how the discovery rate
+    /// changes over time.
+    pub progress: Vec<NetworkCrawlProgressInfo>,
+}
+
 pub const MAX_CONTENT_KEYS_PER_OFFER: usize = 64;
 
+/// The max number of blocks whose content a content range lookup finds.
+pub const MAX_FIND_CONTENT_RANGE_BLOCKS: u64 = 1024;
+
+/// Response for FindContentRange endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindContentRangeInfo {
+    /// The content of each block in the range, keyed by block number, or None if it wasn't found.
+    pub content: BTreeMap<u64, Option<RawContentValue>>,
+}
+
+/// The max number of content items that an availability sample probes the network for.
+pub const MAX_SAMPLE_AVAILABILITY_SIZE: u64 = 256;
+
+/// Response for SampleAvailability endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleAvailabilityInfo {
+    /// Number of content items that were probed
+    pub sampled: u32,
+    /// Number of probed content items that a peer returned
+    pub found: u32,
+    /// Percentage of probed content items that a peer returned, if any were probed
+    pub availability_percent: Option<f64>,
+    /// The result of probing each content item, ordered by block number
+    pub samples: Vec<AvailabilitySample>,
+}
+
+/// The result of probing the network for a single content item of an availability sample
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailabilitySample {
+    pub block_number: u64,
+    pub content_key: RawContentKey,
+    /// Whether a probed peer returned the content
+    pub found: bool,
+    /// Number of peers that were probed, until one returned the content
+    pub peers_probed: u32,
+}
+
 /// Response for Offer endpoint
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -67,6 +250,51 @@ pub struct AcceptInfo {
     pub content_keys: BitList<typenum::U64>,
 }
 
+/// Response for OfferWithConfirmation endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmedOfferInfo {
+    /// The trace of the offer.
+    pub trace: OfferTrace,
+    /// Whether the peer served the content back after it was transferred.
+    pub confirmed: bool,
+}
+
+/// Response for OfferWithThroughput endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferThroughputInfo {
+    pub content_keys: BitList<typenum::U64>,
+    /// The transfer of the accepted content, if any content was accepted.
+    pub transfer: Option<OfferTransferInfo>,
+    /// The offered items, in the order they were offered.
+    pub items: Vec<OfferedItemInfo>,
+}
+
+/// The uTP transfer of the content accepted in response to an offer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferTransferInfo {
+    /// Whether the accepted content was transferred.
+    pub success: bool,
+    /// The size of the transferred payload, in bytes.
+    pub bytes: usize,
+    /// The time the transfer took, in milliseconds.
+    pub duration_ms: u64,
+    /// The transfer rate in MB/s, if the transfer succeeded. The accepted items of an offer are
+    /// transferred in a single uTP stream, so the rate is only measured for all of them together.
+    pub throughput_mb_per_sec: Option<f64>,
+}
+
+/// An offered content item, as reported by the OfferWithThroughput endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferedItemInfo {
+    pub accepted: bool,
+    /// The size of the content value, in bytes.
+    pub bytes: usize,
+}
+
 /// Response for TraceGossip endpoint
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -77,8 +305,25 @@ pub struct TraceGossipInfo {
     pub accepted: Vec<String>,
     // List of all ENRs to whom the content was successfully transferred
     pub transferred: Vec<String>,
+    /// The number of times the offer to each ENR was retried after it failed. ENRs whose first
+    /// offer didn't fail are omitted.
+    #[serde(default)]
+    pub retries: BTreeMap<String, u32>,
 }
 
+/// Per content item outcome of the GossipBatch endpoint
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentGossipInfo {
+    /// Number of peers that were offered the content
+    pub offered: u32,
+    /// Number of peers that accepted the offer
+    pub accepted: u32,
+}
+
+/// Response for GossipBatch endpoint, keyed by content key
+pub type GossipBatchInfo = HashMap<RawContentKey, ContentGossipInfo>;
+
 /// Response for the FindContent endpoint
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -100,6 +345,61 @@ pub struct GetContentInfo {
     pub utp_transfer: bool,
 }
 
+/// What happened to content found by the FindAndStore endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FindAndStoreOutcome {
+    /// The content was found on the network and stored.
+    Stored,
+    /// The content was already stored, and no lookup was made.
+    AlreadyStored,
+    /// The content was found on the network, but isn't within the radius of the node.
+    OutOfRadius,
+}
+
+/// Response for the FindAndStore endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindAndStoreInfo {
+    pub content: RawContentValue,
+    pub utp_transfer: bool,
+    pub outcome: FindAndStoreOutcome,
+}
+
+/// The max number of content keys that a single backfill from a peer can fetch.
+pub const MAX_BACKFILL_CONTENT_KEYS: usize = 1024;
+
+/// The number of simultaneous find content transfers of a backfill, if no concurrency is given.
+pub const DEFAULT_BACKFILL_CONCURRENCY: usize = 4;
+
+/// The max number of simultaneous find content transfers of a backfill.
+pub const MAX_BACKFILL_CONCURRENCY: usize = 32;
+
+/// Response for the BackfillFromPeer endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillInfo {
+    /// The max number of simultaneous find content transfers that were run
+    pub concurrency: usize,
+    /// Number of content items that were fetched from the peer, validated and stored
+    pub stored: u32,
+    /// Number of content items that were already stored, and weren't fetched
+    pub already_stored: u32,
+    /// Number of content items that weren't fetched, since they aren't within the radius of the
+    /// node
+    pub out_of_radius: u32,
+    /// Number of content items that the peer didn't return, or that failed validation
+    pub failed: u32,
+    /// Total size in bytes of the content fetched from the peer
+    pub bytes: u64,
+    /// Time taken by the backfill, in milliseconds
+    pub duration_ms: u64,
+    /// Content items fetched from the peer per second
+    pub items_per_sec: f64,
+    /// Bytes fetched from the peer per second
+    pub bytes_per_sec: f64,
+}
+
 /// Parsed response for TraceGetContent endpoint
 /// This struct represents the content info, and is only used
 /// when the content is found locally or on the network.
@@ -111,6 +411,30 @@ pub struct TraceContentInfo {
     pub trace: QueryTrace,
 }
 
+/// Response for ReplayTrace endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayTraceInfo {
+    /// Whether the content was found by the replayed lookup.
+    pub found: bool,
+    /// The trace of the replayed lookup.
+    pub trace: QueryTrace,
+    /// How the peers that responded changed since the recorded lookup.
+    pub diff: QueryTraceDiff,
+}
+
+/// An event of the TraceGetContent subscription
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TraceGetContentEvent {
+    /// A peer contacted during the network lookup responded.
+    Hop(QueryHopEvent),
+    /// The content was found locally or on the network. This is the final event.
+    Found(TraceContentInfo),
+    /// The content couldn't be found. This is the final event.
+    Failed { error: String },
+}
+
 /// Response for PaginateLocalContentKeys endpoint
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -118,3 +442,329 @@ pub struct PaginateLocalContentInfo<TContentKey: OverlayContentKey> {
     pub content_keys: Vec<TContentKey>,
     pub total_entries: u64,
 }
+
+/// Response for NodeDistance endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDistanceInfo {
+    /// The XOR distance between the two node ids.
+    pub distance: Distance,
+    /// The index of the routing table bucket one node would occupy in the other's routing table.
+    /// `None` if the node ids are equal.
+    pub log2_distance: Option<usize>,
+}
+
+/// Response for EncodeContentKey endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedContentKeyInfo {
+    pub content_key: RawContentKey,
+    pub content_id: B256,
+}
+
+/// An inclusive range of block numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockNumberRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Response for ContentRange endpoint
+///
+/// Only content types whose content keys encode a block number are reported. A content type is
+/// `None` if no content of that type is stored.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRangeInfo {
+    pub block_header_by_number: Option<BlockNumberRange>,
+}
+
+/// The number of stored entries at a log2 distance from the local node, as listed by the
+/// ContentDistanceDistribution endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceBucketInfo {
+    pub log2_distance: u16,
+    pub count: u64,
+}
+
+/// Response for ContentDistanceDistribution endpoint
+///
+/// Only buckets that hold stored entries are listed, the closest first. Only the 32 most
+/// significant bits of the distances of stored entries are known, so entries whose log2 distance
+/// is at most 224 are all counted under 224.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentDistanceDistributionInfo {
+    pub radius: DataRadius,
+    pub buckets: Vec<DistanceBucketInfo>,
+}
+
+/// Response for RawStoredBytes endpoint
+///
+/// The content value as it's stored in the database, before it's decoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawStoredBytesInfo {
+    pub raw_value: Bytes,
+    /// The version of the encoding of the stored bytes.
+    pub value_encoding: u8,
+    /// The keccak256 hash of the decoded value, if it was stored with one.
+    pub checksum: Option<Bytes>,
+}
+
+/// The number and total size of stored entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsageInfo {
+    pub entry_count: u64,
+    pub total_entry_size_bytes: u64,
+}
+
+/// The configured storage capacity, compared to the actual use of disk space.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCapacityInfo {
+    /// The configured storage capacity.
+    pub capacity_bytes: u64,
+    /// The estimated number of additional entries that fit within the capacity, given the average
+    /// size of stored entries. It is `None` if nothing is stored.
+    pub estimated_remaining_entries: Option<u64>,
+    /// The size of the database file on disk, which is shared by all subnetworks and includes
+    /// indexes and other metadata.
+    pub database_size_bytes: u64,
+    /// The size of the free pages of the database file, which are reused before the file grows.
+    pub database_free_bytes: u64,
+}
+
+/// Response for StorageInfo endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageInfo {
+    pub radius: DataRadius,
+    pub total: StorageUsageInfo,
+    pub capacity: StorageCapacityInfo,
+    /// The usage of each stored content type, keyed by content type name.
+    pub content_types: BTreeMap<String, StorageUsageInfo>,
+    /// The capacity reserved for each content type with a storage quota, in bytes, keyed by
+    /// content type name.
+    pub content_type_quotas: BTreeMap<String, u64>,
+    /// The size of content values referenced by content keys, divided by the size of content
+    /// values stored after identical values are deduplicated.
+    pub dedup_ratio: f64,
+}
+
+/// The number of active uTP transfers with a single peer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerTransferInfo {
+    pub node_id: NodeId,
+    pub active_transfers: usize,
+}
+
+/// Response for PeerTransfers endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerTransfersInfo {
+    /// The max number of concurrent uTP transfers with a single peer, if limited.
+    pub limit: Option<usize>,
+    /// The peers with active uTP transfers, the busiest first.
+    pub peers: Vec<PeerTransferInfo>,
+}
+
+/// A JSON-RPC request that is currently being executed, as listed by the ListInFlight endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlightRequestInfo {
+    /// The id by which the request can be cancelled.
+    pub id: u64,
+    /// The name of the endpoint that was requested.
+    pub endpoint: String,
+    /// The time since the request started executing.
+    pub elapsed_ms: u64,
+}
+
+/// The throughput of a store operation that was repeated for every benchmark entry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreThroughputInfo {
+    pub duration_ms: u64,
+    pub ops_per_sec: f64,
+    pub mb_per_sec: f64,
+}
+
+impl StoreThroughputInfo {
+    /// Computes the throughput of `op_count` operations that transferred `bytes` in `duration`.
+    pub fn new(op_count: u64, bytes: u64, duration: Duration) -> Self {
+        // Avoid dividing by zero when the operations were faster than the timer resolution.
+        let secs = duration.as_secs_f64().max(f64::EPSILON);
+        Self {
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            ops_per_sec: op_count as f64 / secs,
+            mb_per_sec: bytes as f64 / 1_000_000.0 / secs,
+        }
+    }
+}
+
+/// Response for BenchmarkStore endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreBenchmarkInfo {
+    pub entry_count: u64,
+    pub value_size: u64,
+    pub write: StoreThroughputInfo,
+    pub read: StoreThroughputInfo,
+}
+
+/// Response for ExportStore endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportStoreInfo {
+    /// Number of exported entries
+    pub entries: u64,
+    /// Size in bytes of the export file
+    pub bytes: u64,
+}
+
+/// A change of the local radius, as listed by the RadiusHistory endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RadiusChangeInfo {
+    /// The unix timestamp of the change, in milliseconds.
+    pub timestamp_ms: u64,
+    /// The radius after the change.
+    pub radius: DataRadius,
+}
+
+/// The outcome of setting the radius of the local node with the SetRadius endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRadiusInfo {
+    /// The radius after the change.
+    pub radius: DataRadius,
+    /// The sequence number of the local ENR, bumped by the change.
+    pub enr_seq: u64,
+    /// Whether content farther than the new radius is stored. It's evicted in the background,
+    /// unless stored content is kept.
+    pub content_outside_radius: bool,
+}
+
+/// The last error encountered by a subsystem of the local node, as listed by the LastErrors
+/// endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastErrorInfo {
+    /// The subsystem that encountered the error: store, utp, discovery or gossip.
+    pub subsystem: String,
+    pub error: String,
+    /// The unix timestamp of the error, in milliseconds.
+    pub timestamp_ms: u64,
+}
+
+/// The reason why the local node would refuse to store content, as reported by the
+/// ExplainRejection endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectionReason {
+    /// The data store is unavailable, so no content is accepted.
+    StoreUnavailable,
+    /// The content id is on the denylist.
+    Denied,
+    /// The content is not within the radius of the local node.
+    NotWithinRadius,
+    /// The content is already stored.
+    AlreadyStored,
+    /// The storage capacity is exhausted by recent content that is exempt from eviction.
+    CapacityExhausted,
+}
+
+/// Response for ExplainRejection endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectionExplanationInfo {
+    pub content_id: B256,
+    /// The distance from the local node to the content id.
+    pub distance: Distance,
+    pub radius: DataRadius,
+    /// Whether the content would be accepted, i.e. there is no reason to reject it.
+    pub accepted: bool,
+    /// Every reason why the content would be rejected.
+    pub reasons: Vec<RejectionReason>,
+}
+
+/// Content that the local node served to peers, as listed by the RecentlyServed endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServedContentInfo {
+    pub content_key: RawContentKey,
+    /// The number of times the content was served within the current window.
+    pub hits: u64,
+    /// The time since the content was last served, in milliseconds.
+    pub last_served_ms_ago: u64,
+}
+
+/// A peer contacted by a simulated lookup, as listed by the SimulateLookup endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedHopInfo {
+    pub node_id: NodeId,
+    /// The number of hops from the local node to the peer. The peers known to the local node are
+    /// the first hop.
+    pub hop: usize,
+    /// The distance from the peer to the target content id.
+    pub distance: Distance,
+    /// The peers that the peer responded with.
+    pub returned_node_ids: Vec<NodeId>,
+}
+
+/// The protocol of an enabled subnetwork, as listed by the ClientInfo endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubnetworkProtocolInfo {
+    /// The hex encoded protocol identifier of the subnetwork.
+    pub protocol_id: String,
+    /// The version of the Portal wire protocol spoken on the subnetwork.
+    pub version: u8,
+}
+
+/// Response for ClientInfo endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    /// The version of the trin crates.
+    pub version: String,
+    /// The short hash of the git commit that trin was built from.
+    pub git_commit: String,
+    /// The time at which trin was built, in RFC 3339 format.
+    pub build_timestamp: String,
+    /// The protocols of the enabled subnetworks, keyed by subnetwork.
+    pub subnetworks: BTreeMap<String, SubnetworkProtocolInfo>,
+    /// The cargo features that were enabled in the build.
+    pub features: Vec<String>,
+}
+
+/// The state of the circuit breaker of a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    /// The peer is selected for lookups and offers.
+    Closed,
+    /// The peer failed too many consecutive requests, and isn't selected until its cooldown ends.
+    Open,
+    /// The cooldown of the peer ended, and the next request to it decides whether it's selected
+    /// again.
+    HalfOpen,
+}
+
+/// The circuit breaker of a peer, as listed by the CircuitBreakers endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitBreakerInfo {
+    pub node_id: NodeId,
+    pub state: CircuitState,
+    /// The number of consecutive requests to the peer that failed.
+    pub consecutive_failures: u32,
+    /// The time until the cooldown of an open circuit ends, in milliseconds.
+    pub cooldown_remaining_ms: Option<u64>,
+}
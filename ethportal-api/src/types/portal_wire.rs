@@ -3,6 +3,7 @@ use std::{
     fmt,
     ops::Deref,
     sync::Arc,
+    time::Duration,
 };
 
 use alloy::primitives::U256;
@@ -190,6 +191,9 @@ impl NetworkSpec {
     }
 }
 
+/// The version of the Portal wire protocol that is spoken on every subnetwork.
+pub const PORTAL_WIRE_PROTOCOL_VERSION: u8 = 0;
+
 pub static MAINNET: Lazy<Arc<NetworkSpec>> = Lazy::new(|| {
     let mut portal_subnetworks = BiHashMap::new();
     portal_subnetworks.insert(Subnetwork::State, "0x500A".to_string());
@@ -261,6 +265,7 @@ impl From<Request> for Message {
             Request::Offer(offer) => Message::Offer(offer),
             Request::PopulatedOffer(offer) => Request::Offer(offer.into()).into(),
             Request::PopulatedOfferWithResult(offer) => Request::Offer(offer.into()).into(),
+            Request::PopulatedOfferWithTransfer(offer) => Request::Offer(offer.into()).into(),
         }
     }
 }
@@ -295,6 +300,8 @@ pub enum Request {
     PopulatedOffer(PopulatedOffer),
     /// Equivalent to PopulatedOffer, but with a return channel for the result
     PopulatedOfferWithResult(PopulatedOfferWithResult),
+    /// Equivalent to PopulatedOffer, but with a return channel for the outcome of the transfer
+    PopulatedOfferWithTransfer(PopulatedOfferWithTransfer),
 }
 
 impl TryFrom<Message> for Request {
@@ -571,6 +578,54 @@ impl From<PopulatedOfferWithResult> for Offer {
     }
 }
 
+/// The content necessary to make an offer message and return the outcome of the transfer of the
+/// accepted content
+#[derive(Debug, Clone)]
+pub struct PopulatedOfferWithTransfer {
+    /// All the offered content, pairing the keys and values
+    pub content_items: Vec<(RawContentKey, RawContentValue)>,
+    /// The channel to send the outcome of the transfer to, if any content is accepted
+    pub result_tx: tokio::sync::mpsc::UnboundedSender<OfferTransfer>,
+}
+
+/// The outcome of the uTP transfer of the content accepted in response to an offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OfferTransfer {
+    /// Whether the accepted content was transferred
+    pub success: bool,
+    /// The number of bytes of the transferred payload
+    pub bytes: usize,
+    /// The time the transfer took
+    pub duration: Duration,
+}
+
+impl OfferTransfer {
+    /// The outcome of a transfer that failed before any data was sent
+    pub const FAILED: Self = Self {
+        success: false,
+        bytes: 0,
+        duration: Duration::ZERO,
+    };
+
+    /// Returns the throughput of a successful transfer, in megabytes (10^6 bytes) per second
+    pub fn throughput_mb_per_sec(&self) -> Option<f64> {
+        let secs = self.duration.as_secs_f64();
+        (self.success && secs > 0.0).then(|| self.bytes as f64 / 1_000_000.0 / secs)
+    }
+}
+
+impl From<PopulatedOfferWithTransfer> for Offer {
+    fn from(val: PopulatedOfferWithTransfer) -> Self {
+        Self {
+            content_keys: val
+                .content_items
+                .into_iter()
+                .map(|(key, _val)| key)
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Encode, Decode, Serialize, Deserialize)]
 pub struct Accept {
     pub connection_id: u16,
@@ -857,4 +912,21 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn offer_transfer_throughput() {
+        let transfer = OfferTransfer {
+            success: true,
+            bytes: 2_000_000,
+            duration: Duration::from_millis(500),
+        };
+        assert_eq!(transfer.throughput_mb_per_sec(), Some(4.0));
+
+        let failed = OfferTransfer {
+            success: false,
+            ..transfer
+        };
+        assert_eq!(failed.throughput_mb_per_sec(), None);
+        assert_eq!(OfferTransfer::FAILED.throughput_mb_per_sec(), None);
+    }
 }
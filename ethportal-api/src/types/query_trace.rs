@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
@@ -34,6 +34,12 @@ pub struct QueryTrace {
     pub target_id: ContentId,
     /// List of pending requests that were unresolved when the content was found.
     pub cancelled: Vec<NodeId>,
+    /// Node ID of the peer that was contacted before any other, if one was provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint_peer: Option<NodeId>,
+    /// Whether the query gave up on peers that are beyond the max number of lookup hops.
+    #[serde(default)]
+    pub max_hops_reached: bool,
 }
 
 impl QueryTrace {
@@ -55,9 +61,17 @@ impl QueryTrace {
             started_at_ms,
             cancelled: Vec::new(),
             target_id,
+            hint_peer: None,
+            max_hops_reached: false,
         }
     }
 
+    /// Records the peer that is contacted before any other peer in the query.
+    pub fn hint_peer_contacted(&mut self, enr: &Enr) {
+        self.hint_peer = Some(enr.into());
+        self.add_metadata(enr, false);
+    }
+
     /// Records the receipt of a node as part of a query in the trace.
     /// Adds a new `QueryResponse` object for `node` if it does not exist, updates if it does.
     /// Timestamp for the object is only set on the first call for a given `node`.
@@ -115,6 +129,11 @@ impl QueryTrace {
         );
     }
 
+    /// Returns milliseconds since the query started.
+    pub fn elapsed_ms(&self) -> u64 {
+        QueryTrace::timestamp_millis_u64(self.started_at_ms)
+    }
+
     /// Mark the node that sent the content that was finally verified.
     pub fn content_validated(&mut self, node_id: NodeId) {
         if self.received_from.is_none() {
@@ -122,6 +141,45 @@ impl QueryTrace {
         }
     }
 
+    /// Compares the peers that responded in this trace to those that responded in the `later`
+    /// trace of a lookup of the same content.
+    ///
+    /// A peer responded the same if it returned the same nodes both times, in any order. Peers
+    /// that returned the content have no nodes in a trace, like peers that returned no nodes.
+    pub fn diff(&self, later: &QueryTrace) -> QueryTraceDiff {
+        let mut diff = QueryTraceDiff::default();
+        for (node_id, response) in &self.responses {
+            match later.responses.get(node_id) {
+                Some(later_response) => {
+                    let responded_with = response.responded_with.iter().collect::<HashSet<_>>();
+                    let later_responded_with =
+                        later_response.responded_with.iter().collect::<HashSet<_>>();
+                    if responded_with == later_responded_with {
+                        diff.unchanged.push(*node_id);
+                    } else {
+                        diff.changed.push(*node_id);
+                    }
+                }
+                None => diff.disappeared.push(*node_id),
+            }
+        }
+        diff.appeared = later
+            .responses
+            .keys()
+            .filter(|node_id| !self.responses.contains_key(node_id))
+            .copied()
+            .collect();
+        for node_ids in [
+            &mut diff.unchanged,
+            &mut diff.changed,
+            &mut diff.appeared,
+            &mut diff.disappeared,
+        ] {
+            node_ids.sort();
+        }
+        diff
+    }
+
     /// Returns milliseconds since the time provided.
     fn timestamp_millis_u64(since: u64) -> u64 {
         // Convert `since` (milliseconds) to a `SystemTime`
@@ -170,6 +228,20 @@ impl QueryTrace {
     }
 }
 
+/// How the peers that responded to a content lookup changed in a later lookup of the same content.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryTraceDiff {
+    /// Peers that responded the same in both lookups.
+    pub unchanged: Vec<NodeId>,
+    /// Peers that responded in both lookups, but with different nodes.
+    pub changed: Vec<NodeId>,
+    /// Peers that only responded in the later lookup.
+    pub appeared: Vec<NodeId>,
+    /// Peers that only responded in the earlier lookup.
+    pub disappeared: Vec<NodeId>,
+}
+
 /// Represents the response from a single node.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -206,6 +278,72 @@ pub enum QueryFailureKind {
     InvalidContent,
 }
 
+/// A response of a single peer contacted during a content lookup, streamed while the lookup is in
+/// progress.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryHopEvent {
+    /// Milliseconds since query started.
+    pub duration_ms: u64,
+    /// The peer that was contacted.
+    pub peer: NodeId,
+    pub response: QueryHopResponse,
+    /// The peers known to the query that are closest to the target, ordered by distance.
+    pub closest: Vec<NodeId>,
+}
+
+/// The way in which a peer contacted during a content lookup responded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum QueryHopResponse {
+    /// The peer responded with the content, or with a connection id to transfer it.
+    Content,
+    /// The peer responded with the ENRs of nodes closer to the target.
+    Nodes { enrs: Vec<NodeId> },
+    /// The request to the peer failed or timed out, or the content it sent was unusable.
+    Failure { reason: String },
+}
+
+/// The trace of a finished content lookup, retained by the node so it can be inspected later.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedQueryTrace {
+    /// Id of the trace, unique for the lifetime of the node.
+    pub id: u64,
+    /// Hex encoded key of the content that was looked up.
+    pub content_key: String,
+    /// Milliseconds from the start to the end of the lookup.
+    pub duration_ms: u64,
+    pub trace: QueryTrace,
+}
+
+/// Summary of a retained content lookup trace.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryTraceSummary {
+    pub id: u64,
+    /// Hex encoded key of the content that was looked up.
+    pub content_key: String,
+    /// Timestamp when the lookup was started.
+    pub started_at_ms: u64,
+    /// Milliseconds from the start to the end of the lookup.
+    pub duration_ms: u64,
+    /// Whether the content was found and validated.
+    pub found: bool,
+}
+
+impl From<&RecordedQueryTrace> for QueryTraceSummary {
+    fn from(recorded: &RecordedQueryTrace) -> Self {
+        Self {
+            id: recorded.id,
+            content_key: recorded.content_key.clone(),
+            started_at_ms: recorded.trace.started_at_ms,
+            duration_ms: recorded.duration_ms,
+            found: recorded.trace.received_from.is_some(),
+        }
+    }
+}
+
 /// Represents additional info for a given node.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -280,6 +418,32 @@ mod tests {
         assert_eq!(local_data.enr, local_enr);
     }
 
+    #[test]
+    fn test_query_trace_diff() {
+        let (_, local_enr) = new_node();
+        let target_id = B256::from(local_enr.node_id().raw());
+        let (node_id_a, enr_a) = new_node();
+        let (node_id_b, enr_b) = new_node();
+        let (node_id_c, enr_c) = new_node();
+        let (node_id_d, enr_d) = new_node();
+
+        let mut earlier = QueryTrace::new(&local_enr, target_id);
+        earlier.node_responded_with(&enr_a, vec![&enr_c, &enr_d]);
+        earlier.node_responded_with(&enr_b, vec![&enr_c]);
+        earlier.node_responded_with(&enr_c, vec![]);
+
+        let mut later = QueryTrace::new(&local_enr, target_id);
+        later.node_responded_with(&enr_a, vec![&enr_d, &enr_c]);
+        later.node_responded_with(&enr_b, vec![&enr_d]);
+        later.node_responded_with_content(&enr_d);
+
+        let diff = earlier.diff(&later);
+        assert_eq!(diff.unchanged, vec![node_id_a]);
+        assert_eq!(diff.changed, vec![node_id_b]);
+        assert_eq!(diff.appeared, vec![node_id_d]);
+        assert_eq!(diff.disappeared, vec![node_id_c]);
+    }
+
     #[test]
     fn test_query_trace_multiple_peers() {
         let (local_node_id, local_enr) = new_node();
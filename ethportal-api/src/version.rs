@@ -2,3 +2,13 @@
 pub const fn get_trin_version() -> &'static str {
     crate::build_info::short_commit()
 }
+
+/// Returns the time at which trin was built, in RFC 3339 format.
+pub const fn get_build_timestamp() -> &'static str {
+    crate::build_info::BUILD_TIME_3339
+}
+
+/// Returns the cargo features that were enabled in the build.
+pub const fn get_enabled_features() -> &'static [&'static str] {
+    crate::build_info::ENABLED_FEATURES
+}
@@ -18,6 +18,10 @@ use futures::future;
 use jsonrpsee::async_client::Client;
 use rpc::RpcServerHandle;
 
+/// The upper bound of the random delay before a buddy node joins the network. It must be well
+/// below the time `launch_node` waits for nodes to connect.
+const BUDDY_NODE_JOIN_DELAY_JITTER_MS: &str = "500";
+
 pub struct PeertestNode {
     pub enr: Enr,
     pub ipc_client: Client,
@@ -89,7 +93,7 @@ fn generate_trin_config(
         .join(",");
     let network = network.to_string();
 
-    let trin_config_args = [
+    let mut trin_config_args = vec![
         "trin",
         "--network",
         &network,
@@ -107,6 +111,10 @@ fn generate_trin_config(
         private_key.as_str(),
         "--ephemeral",
     ];
+    // Stagger the joins of buddy nodes, so they don't all bond with the bootnode at once.
+    if bootnode_enr.is_some() {
+        trin_config_args.extend(["--join-delay-jitter", BUDDY_NODE_JOIN_DELAY_JITTER_MS]);
+    }
     TrinConfig::new_from(trin_config_args).unwrap()
 }
 
@@ -102,6 +102,7 @@ pub async fn test_trace_get_content(peertest: &Peertest) {
     let trace_content_info = HistoryNetworkApiClient::trace_get_content(
         &peertest.nodes[0].ipc_client,
         content_key.clone(),
+        None,
     )
     .await
     .unwrap();
@@ -144,7 +145,7 @@ pub async fn test_trace_get_content_for_absent_content(peertest: &Peertest) {
     let client = &peertest.nodes[0].ipc_client;
     let (content_key, _) = fixture_header_by_hash();
 
-    let error = HistoryNetworkApiClient::trace_get_content(client, content_key)
+    let error = HistoryNetworkApiClient::trace_get_content(client, content_key, None)
         .await
         .unwrap_err()
         .to_string();
@@ -167,10 +168,13 @@ pub async fn test_trace_get_content_local_db(peertest: &Peertest) {
 
     assert!(store_result);
 
-    let trace_content_info =
-        HistoryNetworkApiClient::trace_get_content(&peertest.bootnode.ipc_client, content_key)
-            .await
-            .unwrap();
+    let trace_content_info = HistoryNetworkApiClient::trace_get_content(
+        &peertest.bootnode.ipc_client,
+        content_key,
+        None,
+    )
+    .await
+    .unwrap();
     assert!(!trace_content_info.utp_transfer);
     assert_eq!(trace_content_info.content, content_value.encode());
 
@@ -187,9 +191,9 @@ async fn call_recursive_find_nodes(
     node_id: NodeId,
 ) -> Vec<Enr> {
     match subnetwork {
-        Subnetwork::Beacon => BeaconNetworkApiClient::recursive_find_nodes(client, node_id),
-        Subnetwork::History => HistoryNetworkApiClient::recursive_find_nodes(client, node_id),
-        Subnetwork::State => StateNetworkApiClient::recursive_find_nodes(client, node_id),
+        Subnetwork::Beacon => BeaconNetworkApiClient::recursive_find_nodes(client, node_id, None),
+        Subnetwork::History => HistoryNetworkApiClient::recursive_find_nodes(client, node_id, None),
+        Subnetwork::State => StateNetworkApiClient::recursive_find_nodes(client, node_id, None),
         _ => panic!("Unexpected subnetwork: {subnetwork}"),
     }
     .await
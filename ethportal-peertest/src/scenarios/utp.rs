@@ -38,7 +38,7 @@ pub async fn test_recursive_utp(peertest: &Peertest) {
         utp_transfer,
     } = peertest.nodes[0]
         .ipc_client
-        .get_content(content_key)
+        .get_content(content_key, None)
         .await
         .unwrap();
 
@@ -72,7 +72,7 @@ pub async fn test_trace_recursive_utp(peertest: &Peertest) {
 
     let trace_content_info: TraceContentInfo = peertest.nodes[0]
         .ipc_client
-        .trace_get_content(content_key)
+        .trace_get_content(content_key, None)
         .await
         .unwrap();
 
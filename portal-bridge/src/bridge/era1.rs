@@ -174,7 +174,10 @@ impl Era1Bridge {
             let mut found = 0;
             let hunter_threshold = (content_keys_to_sample.len() as u64 * threshold / 100) as usize;
             for content_key in content_keys_to_sample {
-                let result = self.portal_client.get_content(content_key.clone()).await;
+                let result = self
+                    .portal_client
+                    .get_content(content_key.clone(), None)
+                    .await;
                 if result.is_ok() {
                     found += 1;
                     if found == hunter_threshold {
@@ -366,7 +369,9 @@ impl Era1Bridge {
         if hunt {
             let header_hash = block_tuple.header.header.hash();
             let header_content_key = HistoryContentKey::new_block_header_by_hash(header_hash);
-            let header_content_info = portal_client.get_content(header_content_key.clone()).await;
+            let header_content_info = portal_client
+                .get_content(header_content_key.clone(), None)
+                .await;
             if header_content_info.is_ok() {
                 info!(
                     "Skipping header by hash at height: {} as header already found",
@@ -417,7 +422,9 @@ impl Era1Bridge {
         if hunt {
             let header_content_key =
                 HistoryContentKey::new_block_header_by_number(block_tuple.header.header.number);
-            let header_content_info = portal_client.get_content(header_content_key.clone()).await;
+            let header_content_info = portal_client
+                .get_content(header_content_key.clone(), None)
+                .await;
             if header_content_info.is_ok() {
                 info!(
                     "Skipping header by number at height: {} as header already found",
@@ -458,7 +465,9 @@ impl Era1Bridge {
         if hunt {
             let body_hash = block_tuple.header.header.hash();
             let body_content_key = HistoryContentKey::new_block_body(body_hash);
-            let body_content_info = portal_client.get_content(body_content_key.clone()).await;
+            let body_content_info = portal_client
+                .get_content(body_content_key.clone(), None)
+                .await;
             if body_content_info.is_ok() {
                 info!(
                     "Skipping body at height: {} as body already found",
@@ -498,7 +507,7 @@ impl Era1Bridge {
             let receipts_hash = block_tuple.header.header.hash();
             let receipts_content_key = HistoryContentKey::new_block_receipts(receipts_hash);
             let receipts_content_info = portal_client
-                .get_content(receipts_content_key.clone())
+                .get_content(receipts_content_key.clone(), None)
                 .await;
             if receipts_content_info.is_ok() {
                 info!(
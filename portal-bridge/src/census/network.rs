@@ -327,13 +327,13 @@ impl Network {
     async fn recursive_find_nodes(&self, node_id: NodeId) -> anyhow::Result<Vec<Enr>> {
         let enrs = match self.subnetwork {
             Subnetwork::History => {
-                HistoryNetworkApiClient::recursive_find_nodes(&self.client, node_id).await?
+                HistoryNetworkApiClient::recursive_find_nodes(&self.client, node_id, None).await?
             }
             Subnetwork::State => {
-                StateNetworkApiClient::recursive_find_nodes(&self.client, node_id).await?
+                StateNetworkApiClient::recursive_find_nodes(&self.client, node_id, None).await?
             }
             Subnetwork::Beacon => {
-                BeaconNetworkApiClient::recursive_find_nodes(&self.client, node_id).await?
+                BeaconNetworkApiClient::recursive_find_nodes(&self.client, node_id, None).await?
             }
             _ => unreachable!(
                 "recursive_find_nodes: unsupported subnetwork: {}",
@@ -132,7 +132,7 @@ async fn history_trace_gossip(
             }
         }
         // if not, make rfc request to see if data is available on network
-        let result = HistoryNetworkApiClient::get_content(&client, content_key.clone()).await;
+        let result = HistoryNetworkApiClient::get_content(&client, content_key.clone(), None).await;
         if result.is_ok() {
             debug!("Found content on network, after failing to gossip, aborting gossip. content key={:?}", content_key.to_hex());
             found = true;
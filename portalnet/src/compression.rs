@@ -0,0 +1,145 @@
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, bail};
+use ethportal_api::types::enr::Enr;
+
+use crate::discovery::ENR_PORTAL_CAPABILITIES_KEY;
+
+/// The capability bit of nodes that support snappy compressed uTP transfers of found content.
+pub const CAPABILITY_COMPRESSION: u8 = 0b0000_0001;
+
+/// The flag byte that precedes content transferred between nodes that both support compression,
+/// when the content follows as is.
+const UNCOMPRESSED_FLAG: u8 = 0x00;
+
+/// The flag byte that precedes content transferred between nodes that both support compression,
+/// when the content follows as a snappy framed stream.
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// Returns whether the node advertises support for compressed transfers in its ENR.
+pub fn supports_compression(enr: &Enr) -> bool {
+    enr.get(ENR_PORTAL_CAPABILITIES_KEY)
+        .and_then(|capabilities| capabilities.first().copied())
+        .is_some_and(|capabilities| capabilities & CAPABILITY_COMPRESSION != 0)
+}
+
+/// Compresses the data into a snappy framed stream.
+pub fn compress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = snap::write::FrameEncoder::new(vec![]);
+    encoder.write_all(data)?;
+    encoder
+        .into_inner()
+        .map_err(|err| anyhow!("Unable to compress data: {err}"))
+}
+
+/// Encodes the data for a transfer between nodes that both support compression: a flag byte,
+/// followed by the data compressed if that makes it smaller, or as is otherwise. Returns the
+/// encoded data, and whether it's compressed.
+pub fn encode_transfer(data: &[u8]) -> (Vec<u8>, bool) {
+    // Compressing into a vec doesn't fail, but the data is sent as is if it did.
+    match compress(data) {
+        Ok(compressed) if compressed.len() < data.len() => {
+            ([&[COMPRESSED_FLAG], compressed.as_slice()].concat(), true)
+        }
+        _ => ([&[UNCOMPRESSED_FLAG], data].concat(), false),
+    }
+}
+
+/// Decodes data encoded with [encode_transfer], failing if it decodes to more than `max_size`
+/// bytes. Returns the decoded data, and whether it was compressed.
+pub fn decode_transfer(data: &[u8], max_size: Option<usize>) -> anyhow::Result<(Vec<u8>, bool)> {
+    let Some((flag, data)) = data.split_first() else {
+        bail!("Transfer is missing its compression flag");
+    };
+    match *flag {
+        UNCOMPRESSED_FLAG => {
+            if let Some(max_size) = max_size.filter(|max_size| data.len() > *max_size) {
+                bail!("Data exceeds size limit of {max_size} bytes");
+            }
+            Ok((data.to_vec(), false))
+        }
+        COMPRESSED_FLAG => Ok((decompress(data, max_size)?, true)),
+        flag => bail!("Transfer has unknown compression flag {flag:#04x}"),
+    }
+}
+
+/// Decompresses a snappy framed stream, failing if it decompresses to more than `max_size` bytes.
+pub fn decompress(data: &[u8], max_size: Option<usize>) -> anyhow::Result<Vec<u8>> {
+    let mut decoder = snap::read::FrameDecoder::new(data);
+    let mut decompressed = vec![];
+    match max_size {
+        Some(max_size) => {
+            decoder
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() > max_size {
+                bail!("Decompressed data exceeds size limit of {max_size} bytes");
+            }
+        }
+        None => {
+            decoder.read_to_end(&mut decompressed)?;
+        }
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use discv5::enr::CombinedKey;
+
+    use super::*;
+
+    #[test]
+    fn compressed_data_roundtrips() {
+        let data = [0x01, 0x02, 0x03].repeat(1000);
+        let compressed = compress(&data).unwrap();
+
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed, None).unwrap(), data);
+        assert_eq!(decompress(&compressed, Some(data.len())).unwrap(), data);
+        assert!(decompress(&compressed, Some(data.len() - 1)).is_err());
+    }
+
+    #[test]
+    fn encoded_transfers_roundtrip() {
+        let compressible = [0x01, 0x02, 0x03].repeat(1000);
+        let (encoded, compressed) = encode_transfer(&compressible);
+        assert!(compressed);
+        assert!(encoded.len() < compressible.len());
+        assert_eq!(
+            decode_transfer(&encoded, Some(compressible.len())).unwrap(),
+            (compressible.clone(), true)
+        );
+        assert!(decode_transfer(&encoded, Some(compressible.len() - 1)).is_err());
+
+        // Data that looks like a compressed stream isn't mistaken for one.
+        let incompressible = compress(&[0x01, 0x02, 0x03]).unwrap();
+        let (encoded, compressed) = encode_transfer(&incompressible);
+        assert!(!compressed);
+        assert_eq!(
+            decode_transfer(&encoded, Some(incompressible.len())).unwrap(),
+            (incompressible.clone(), false)
+        );
+        assert!(decode_transfer(&encoded, Some(incompressible.len() - 1)).is_err());
+
+        assert!(decode_transfer(&[], None).is_err());
+        assert!(decode_transfer(&[0x02, 0x00], None).is_err());
+    }
+
+    #[test]
+    fn compression_support_is_read_from_enr() {
+        let key = CombinedKey::generate_secp256k1();
+        let enr = Enr::builder().build(&key).unwrap();
+        assert!(!supports_compression(&enr));
+
+        let enr = Enr::builder()
+            .add_value(
+                ENR_PORTAL_CAPABILITIES_KEY,
+                &[CAPABILITY_COMPRESSION].as_slice(),
+            )
+            .build(&key)
+            .unwrap();
+        assert!(supports_compression(&enr));
+    }
+}
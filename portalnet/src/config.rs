@@ -1,17 +1,29 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use alloy::primitives::B256;
 use ethportal_api::types::{
     bootnodes::Bootnodes,
-    cli::{TrinConfig, DEFAULT_UTP_TRANSFER_LIMIT},
+    cli::{
+        EnrAttribute, LookupPriority, RadiusShrinkPolicy, TrinConfig, ValidationLevel,
+        DEFAULT_EL_CROSSCHECK_SAMPLE_RATIO, DEFAULT_ENR_CACHE_SIZE, DEFAULT_FINDCONTENT_TIMEOUT_MS,
+        DEFAULT_FINDNODES_TIMEOUT_MS, DEFAULT_LOOKUP_MAX_HOPS, DEFAULT_LOOKUP_PARALLELISM,
+        DEFAULT_MAX_FIND_CONTENT_SIZE, DEFAULT_PING_TIMEOUT_MS, DEFAULT_QUERY_TRACE_BUFFER_SIZE,
+        DEFAULT_READINESS_MIN_LOOKUPS, DEFAULT_READINESS_MIN_PEERS, DEFAULT_UTP_TRANSFER_LIMIT,
+    },
     enr::Enr,
     network::Network,
 };
+use rand::Rng;
+use url::Url;
 
-/// Capacity of the cache for observed `NodeAddress` values.
-/// Provides capacity for 32 full k-buckets. This capacity will be shared among all active portal
-/// subnetworks.
-const NODE_ADDR_CACHE_CAPACITY: usize = discv5::kbucket::MAX_NODES_PER_BUCKET * 32;
+use crate::types::{
+    circuit_breaker::CircuitBreakerConfig,
+    denylist::SharedDenylist,
+    gossip_history::GossipDecayConfig,
+    offer_log::{OfferLog, SharedOfferLog},
+    readiness::ReadinessConfig,
+    serve_bandwidth::{ServeBandwidthLimiter, SharedServeBandwidthLimiter},
+};
 
 #[derive(Clone)]
 pub struct PortalnetConfig {
@@ -21,11 +33,101 @@ pub struct PortalnetConfig {
     pub bootnodes: Vec<Enr>,
     pub no_stun: bool,
     pub no_upnp: bool,
+    // custom attributes included in the local enr
+    pub enr_attributes: Vec<EnrAttribute>,
+    // the file the local enr sequence number is persisted in, so that it keeps increasing across
+    // restarts, not persisted if not set
+    pub enr_seq_file: Option<PathBuf>,
+    // the max number of cached node addresses, shared among all active portal subnetworks
     pub node_addr_cache_capacity: usize,
     pub disable_poke: bool,
     pub trusted_block_root: Option<B256>,
     // the max number of concurrent utp transfers
     pub utp_transfer_limit: usize,
+    // the target delay of utp congestion control, the utp library default is used if not set
+    pub utp_target_delay: Option<Duration>,
+    // the max number of concurrent utp transfers requested by a single peer, unlimited if not set
+    pub utp_peer_transfer_limit: Option<usize>,
+    // shared between all subnetworks, so that their combined serve bandwidth is limited
+    pub serve_bandwidth_limiter: Option<SharedServeBandwidthLimiter>,
+    // whether compressed utp transfers are advertised in the enr, and used with peers that
+    // advertise them too
+    pub enable_compression: bool,
+    // the max size of content received over utp in response to a find content request
+    pub max_find_content_size: usize,
+    // the min number of connected peers before content lookups are served
+    pub min_lookup_peers: usize,
+    // the number of times a content lookup that doesn't find the content is run again
+    pub lookup_retries: u32,
+    // the number of peers contacted in parallel by recursive lookups
+    pub lookup_parallelism: usize,
+    // the max number of hops of a content lookup, before it gives up
+    pub lookup_max_hops: usize,
+    pub gossip_on_new_peer: bool,
+    // whether content is only served to peers whose known radius contains it
+    pub serve_only_in_requester_radius: bool,
+    // shared between all subnetworks, so that a node denied on one is denied on all of them
+    pub denylist: SharedDenylist,
+    // shared between all subnetworks, so that they append to the same file
+    pub offer_log: Option<SharedOfferLog>,
+    // the node that newly stored content is offered to, to keep it in sync, not mirrored if not set
+    pub mirror_peer: Option<Enr>,
+    // the delay before bonding with bootnodes and discovering peers, including any jitter
+    pub join_delay: Duration,
+    // the number of most recent content lookup traces retained by each subnetwork
+    pub query_trace_buffer_size: usize,
+    // whether content stored through the json-rpc api is validated like content from peers
+    pub validate_stored_headers: bool,
+    // the interval at which expired content is deleted, only set if content expires
+    pub content_ttl_sweep_interval: Option<Duration>,
+    // how the re-gossip of the same content decays, content is always re-gossiped if not set
+    pub gossip_decay: Option<GossipDecayConfig>,
+    // the window within which the same content arriving again through gossip is dropped, not
+    // deduplicated if not set
+    pub gossip_dedup_window: Option<Duration>,
+    // whether offered content that is already stored is accepted instead of declined
+    pub accept_duplicate_content: bool,
+    // the number of times a failed offer of gossiped content to a peer is retried
+    pub gossip_peer_retries: u32,
+    // how long peers that accepted offered content are listed first in find content responses
+    pub content_holders_ttl: Option<Duration>,
+    // how thoroughly history content is validated before it's stored, served or propagated
+    pub validation_level: ValidationLevel,
+    // whether history content stored without validation is propagated to other peers
+    pub gossip_unvalidated: bool,
+    // the trusted execution client that validated history content is cross-checked against, not
+    // cross-checked if not set
+    pub el_rpc_url: Option<Url>,
+    // the share of validated history content that is cross-checked against the execution client
+    pub el_crosscheck_sample_ratio: f64,
+    // when peers that consistently fail requests stop being selected for lookups and offers
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    // the max number of routing table peers that share an ip subnet, not limited if not set
+    pub max_peers_per_subnet: Option<usize>,
+    // whether corrupted history content read by a content lookup is deleted and refetched
+    pub self_heal_corrupt_content: bool,
+    // what happens to content outside the radius when it shrinks, the network's default if not set
+    pub on_radius_shrink: Option<RadiusShrinkPolicy>,
+    // whether content found through the json-rpc api can be stored on request
+    pub enable_find_and_store: bool,
+    // whether raw, unchecked content can be offered through the json-rpc api, for testing
+    pub enable_raw_offer: bool,
+    // whether the raw ping exchange with a peer can be inspected through the json-rpc api
+    pub enable_debug_ping: bool,
+    // the max number of json-rpc content lookups executed at the same time, not limited if not set
+    pub rpc_lookup_concurrency: Option<usize>,
+    // the priorities of json-rpc content lookups waiting for a free slot
+    pub rpc_lookup_priorities: Vec<LookupPriority>,
+    // the duration after which json-rpc requests are logged as slow, not logged if not set
+    pub slow_request_threshold: Option<Duration>,
+    // the criteria that the history network must meet before it's reported as ready
+    pub readiness: ReadinessConfig,
+    // the times after which overlay requests of each message type to a peer are abandoned
+    pub ping_timeout: Duration,
+    pub findnodes_timeout: Duration,
+    pub findcontent_timeout: Duration,
+    // the node's configuration with secrets redacted, as reported by the json-rpc api
+    pub redacted_config: serde_json::Value,
 }
 
 // to be used inside test code only
@@ -38,10 +140,57 @@ impl Default for PortalnetConfig {
             bootnodes: Bootnodes::default().to_enrs(Network::Mainnet),
             no_stun: false,
             no_upnp: false,
-            node_addr_cache_capacity: NODE_ADDR_CACHE_CAPACITY,
+            enr_attributes: vec![],
+            enr_seq_file: None,
+            node_addr_cache_capacity: DEFAULT_ENR_CACHE_SIZE,
             disable_poke: false,
             trusted_block_root: None,
             utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT,
+            utp_target_delay: None,
+            utp_peer_transfer_limit: None,
+            serve_bandwidth_limiter: None,
+            enable_compression: false,
+            max_find_content_size: DEFAULT_MAX_FIND_CONTENT_SIZE,
+            min_lookup_peers: 0,
+            lookup_retries: 0,
+            lookup_parallelism: DEFAULT_LOOKUP_PARALLELISM,
+            lookup_max_hops: DEFAULT_LOOKUP_MAX_HOPS,
+            gossip_on_new_peer: false,
+            serve_only_in_requester_radius: false,
+            denylist: SharedDenylist::default(),
+            offer_log: None,
+            mirror_peer: None,
+            join_delay: Duration::ZERO,
+            query_trace_buffer_size: DEFAULT_QUERY_TRACE_BUFFER_SIZE,
+            validate_stored_headers: false,
+            content_ttl_sweep_interval: None,
+            gossip_decay: None,
+            gossip_dedup_window: None,
+            accept_duplicate_content: false,
+            gossip_peer_retries: 0,
+            content_holders_ttl: None,
+            validation_level: ValidationLevel::Full,
+            gossip_unvalidated: false,
+            el_rpc_url: None,
+            el_crosscheck_sample_ratio: DEFAULT_EL_CROSSCHECK_SAMPLE_RATIO,
+            circuit_breaker: None,
+            max_peers_per_subnet: None,
+            self_heal_corrupt_content: false,
+            on_radius_shrink: None,
+            enable_find_and_store: false,
+            enable_raw_offer: false,
+            enable_debug_ping: false,
+            rpc_lookup_concurrency: None,
+            rpc_lookup_priorities: vec![],
+            slow_request_threshold: None,
+            readiness: ReadinessConfig {
+                min_peers: DEFAULT_READINESS_MIN_PEERS,
+                min_successful_lookups: DEFAULT_READINESS_MIN_LOOKUPS,
+            },
+            ping_timeout: Duration::from_millis(DEFAULT_PING_TIMEOUT_MS),
+            findnodes_timeout: Duration::from_millis(DEFAULT_FINDNODES_TIMEOUT_MS),
+            findcontent_timeout: Duration::from_millis(DEFAULT_FINDCONTENT_TIMEOUT_MS),
+            redacted_config: TrinConfig::default().to_redacted_json(),
         }
     }
 }
@@ -55,10 +204,79 @@ impl PortalnetConfig {
             bootnodes: trin_config.bootnodes.to_enrs(trin_config.network.network()),
             no_stun: trin_config.no_stun,
             no_upnp: trin_config.no_upnp,
-            node_addr_cache_capacity: NODE_ADDR_CACHE_CAPACITY,
+            enr_attributes: trin_config.enr_attributes.clone(),
+            enr_seq_file: None,
+            node_addr_cache_capacity: trin_config.enr_cache_size,
             disable_poke: trin_config.disable_poke,
             trusted_block_root: trin_config.trusted_block_root,
             utp_transfer_limit: trin_config.utp_transfer_limit,
+            utp_target_delay: trin_config.utp_target_delay.map(Duration::from_millis),
+            utp_peer_transfer_limit: trin_config.utp_peer_transfer_limit,
+            serve_bandwidth_limiter: trin_config
+                .serve_bandwidth_limit
+                .map(ServeBandwidthLimiter::shared),
+            enable_compression: trin_config.enable_compression,
+            max_find_content_size: trin_config.max_find_content_size,
+            min_lookup_peers: trin_config.min_lookup_peers,
+            lookup_retries: trin_config.lookup_retries,
+            lookup_parallelism: trin_config.lookup_parallelism,
+            lookup_max_hops: trin_config.lookup_max_hops,
+            gossip_on_new_peer: trin_config.gossip_on_new_peer,
+            serve_only_in_requester_radius: trin_config.serve_only_in_requester_radius,
+            // Loaded by the caller, as reading the denylist file can fail.
+            denylist: SharedDenylist::default(),
+            offer_log: OfferLog::shared_from_file(trin_config.offer_log_file.clone()),
+            mirror_peer: trin_config.mirror_peer.clone(),
+            // The jitter is sampled once, so that all subnetworks join at the same time.
+            join_delay: Duration::from_millis(
+                trin_config.join_delay
+                    + rand::thread_rng().gen_range(0..=trin_config.join_delay_jitter),
+            ),
+            query_trace_buffer_size: trin_config.query_trace_buffer_size,
+            validate_stored_headers: trin_config.header_accumulator_path.is_some(),
+            content_ttl_sweep_interval: trin_config
+                .content_ttl
+                .map(|_| Duration::from_secs(trin_config.content_ttl_sweep_interval)),
+            gossip_decay: trin_config
+                .regossip_interval
+                .map(|interval| GossipDecayConfig {
+                    interval: Duration::from_secs(interval),
+                    max_rounds: trin_config.regossip_max_rounds,
+                    target_acceptance_ratio: trin_config.regossip_target_acceptance_ratio,
+                }),
+            gossip_dedup_window: trin_config.gossip_dedup_window.map(Duration::from_secs),
+            accept_duplicate_content: trin_config.accept_duplicate_content,
+            gossip_peer_retries: trin_config.gossip_peer_retries,
+            content_holders_ttl: trin_config.content_holders_ttl.map(Duration::from_secs),
+            validation_level: trin_config.validation_level,
+            gossip_unvalidated: trin_config.gossip_unvalidated,
+            el_rpc_url: trin_config.el_rpc_url.clone(),
+            el_crosscheck_sample_ratio: trin_config.el_crosscheck_sample_ratio,
+            circuit_breaker: trin_config
+                .circuit_breaker_threshold
+                .map(|failure_threshold| CircuitBreakerConfig {
+                    failure_threshold,
+                    cooldown: Duration::from_secs(trin_config.circuit_breaker_cooldown),
+                }),
+            max_peers_per_subnet: trin_config.max_peers_per_subnet,
+            self_heal_corrupt_content: trin_config.self_heal_corrupt_content,
+            on_radius_shrink: trin_config.on_radius_shrink,
+            enable_find_and_store: trin_config.enable_find_and_store,
+            enable_raw_offer: trin_config.enable_raw_offer,
+            enable_debug_ping: trin_config.enable_debug_ping,
+            rpc_lookup_concurrency: trin_config.rpc_lookup_concurrency,
+            rpc_lookup_priorities: trin_config.rpc_lookup_priorities.clone(),
+            slow_request_threshold: trin_config
+                .slow_request_threshold_ms
+                .map(Duration::from_millis),
+            readiness: ReadinessConfig {
+                min_peers: trin_config.readiness_min_peers,
+                min_successful_lookups: trin_config.readiness_min_lookups,
+            },
+            ping_timeout: Duration::from_millis(trin_config.ping_timeout),
+            findnodes_timeout: Duration::from_millis(trin_config.findnodes_timeout),
+            findcontent_timeout: Duration::from_millis(trin_config.findcontent_timeout),
+            redacted_config: trin_config.to_redacted_json(),
         }
     }
 }
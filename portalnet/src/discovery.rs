@@ -1,8 +1,10 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     fmt,
     hash::{Hash, Hasher},
     io,
     net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
     time::Duration,
@@ -16,27 +18,49 @@ use discv5::{
     ConfigBuilder, Discv5, Event, ListenConfig, RequestError, TalkRequest,
 };
 use ethportal_api::{
-    types::{discv5::RoutingTableInfo, enr::Enr, network::Subnetwork, portal_wire::NetworkSpec},
+    types::{
+        cli::ValidationLevel,
+        discv5::{LookupReadinessInfo, RoutingTableInfo},
+        enr::Enr,
+        network::Subnetwork,
+        portal::{ClientInfo, SubnetworkProtocolInfo},
+        portal_wire::{NetworkSpec, PORTAL_WIRE_PROTOCOL_VERSION},
+    },
     utils::bytes::hex_decode,
-    version::get_trin_version,
+    version::{get_build_timestamp, get_enabled_features, get_trin_version},
     NodeInfo,
 };
 use lru::LruCache;
 use parking_lot::RwLock;
 use tokio::sync::{mpsc, RwLock as TokioRwLock};
 use tracing::{debug, info, warn};
+use trin_metrics::portalnet::PORTALNET_METRICS;
 use trin_validation::oracle::HeaderOracle;
 use utp_rs::{cid::ConnectionPeer, udp::AsyncUdpSocket};
 
 use super::config::PortalnetConfig;
-use crate::socket;
+use crate::{
+    compression::CAPABILITY_COMPRESSION,
+    socket,
+    types::{
+        kbucket::SharedKBucketsTable, node_addr_cache::NodeAddrCache,
+        store_health::SharedStoreHealth, transfer_sizes::TransferSizeMeter,
+    },
+    utils::db::{load_enr_seq, store_enr_seq},
+};
 
 /// Size of the buffer of the Discv5 TALKREQ channel.
 const TALKREQ_CHANNEL_BUFFER: usize = 100;
 
+/// The min time after which Discv5 requests are abandoned.
+const DISCV5_REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// ENR key for portal network client version.
 pub const ENR_PORTAL_CLIENT_KEY: &str = "c";
 
+/// ENR key for the bitfield of optional capabilities of a portal network client.
+pub const ENR_PORTAL_CAPABILITIES_KEY: &str = "cap";
+
 pub type ProtocolRequest = Vec<u8>;
 
 /// The contact info for a remote node.
@@ -53,13 +77,27 @@ pub struct Discovery {
     /// The inner Discv5 service.
     discv5: Discv5,
     /// A cache of the latest observed `NodeAddress` for a node ID.
-    node_addr_cache: Arc<RwLock<LruCache<NodeId, NodeAddress>>>,
+    node_addr_cache: Arc<RwLock<NodeAddrCache>>,
     /// Indicates if the Discv5 service has been started.
     pub started: bool,
     /// The socket address that the Discv5 service listens on.
     pub listen_socket: SocketAddr,
     /// The Portal Network to Protocal Id Map etc MAINNET, ANGELFOOD
     network_spec: Arc<NetworkSpec>,
+    /// The routing table of each overlay network and the min number of connected peers it
+    /// requires to serve content lookups.
+    overlay_tables: Arc<RwLock<HashMap<Subnetwork, (SharedKBucketsTable, usize)>>>,
+    /// The health of the data store of each overlay network.
+    store_healths: RwLock<HashMap<Subnetwork, SharedStoreHealth>>,
+    /// How thoroughly content is validated before it's stored, served or propagated.
+    validation_level: ValidationLevel,
+    /// The name of the validator of each content type, keyed by content type, of each overlay
+    /// network that registered its validators.
+    validators: RwLock<HashMap<Subnetwork, BTreeMap<String, String>>>,
+    /// The file the local ENR sequence number is persisted in, if any.
+    enr_seq_file: Option<PathBuf>,
+    /// The data received by size limited inbound uTP transfers of all overlay networks.
+    transfer_sizes: TransferSizeMeter,
 }
 
 impl fmt::Debug for Discovery {
@@ -74,6 +112,32 @@ impl fmt::Debug for Discovery {
     }
 }
 
+/// Returns the client info advertised in the local ENR.
+fn enr_client_info() -> String {
+    // Use "t" as short-hand for "Trin" to save bytes in ENR.
+    format!("t {}", get_trin_version())
+}
+
+/// Persists the sequence number of the local ENR, so that it keeps increasing across restarts.
+/// Failures are logged, since they only matter after a restart.
+fn persist_enr_seq(enr_seq_file: &Path, seq: u64) {
+    if let Err(err) = store_enr_seq(enr_seq_file, seq) {
+        warn!(%err, path = %enr_seq_file.display(), "Failed to persist ENR sequence number");
+    }
+}
+
+/// Returns whether the node is in the routing table of any overlay network, in which case its
+/// cached address is never evicted.
+fn is_in_overlay_table(
+    overlay_tables: &RwLock<HashMap<Subnetwork, (SharedKBucketsTable, usize)>>,
+    node_id: &NodeId,
+) -> bool {
+    overlay_tables
+        .read()
+        .values()
+        .any(|(kbuckets, _)| kbuckets.entry(*node_id).present_or_pending().is_some())
+}
+
 impl Discovery {
     pub fn new(
         portal_config: PortalnetConfig,
@@ -132,18 +196,36 @@ impl Discovery {
             builder.udp4(enr_port);
 
             // Set the ENR sequence number to the current timestamp this prevents other nodes from
-            // storing outdated Trin Enr's
+            // storing outdated Trin Enr's. The persisted sequence number is continued instead if
+            // it's higher, e.g. because the ENR was updated many times or the clock went back.
             let epoch_timestamp = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .map_err(|e| format!("When getting current time: {e:?}"))?
                 .as_secs();
-            builder.seq(epoch_timestamp);
+            let persisted_seq = portal_config
+                .enr_seq_file
+                .as_deref()
+                .and_then(|enr_seq_file| match load_enr_seq(enr_seq_file) {
+                    Ok(seq) => seq,
+                    Err(err) => {
+                        warn!(%err, "Failed to load persisted ENR sequence number");
+                        None
+                    }
+                });
+            builder.seq(epoch_timestamp.max(persisted_seq.map_or(0, |seq| seq + 1)));
 
-            let trin_version = get_trin_version();
-            // Use "t" as short-hand for "Trin" to save bytes in ENR.
-            let client_info = format!("t {trin_version}");
             // Use "c" as short-hand for "client".
-            builder.add_value(ENR_PORTAL_CLIENT_KEY, &client_info.as_bytes());
+            builder.add_value(ENR_PORTAL_CLIENT_KEY, &enr_client_info().as_bytes());
+            if portal_config.enable_compression {
+                builder.add_value(
+                    ENR_PORTAL_CAPABILITIES_KEY,
+                    &[CAPABILITY_COMPRESSION].as_slice(),
+                );
+            }
+            // Custom attributes are validated against the reserved keys when parsing the config.
+            for attribute in &portal_config.enr_attributes {
+                builder.add_value(attribute.key.as_str(), &attribute.value.as_bytes());
+            }
             builder
                 .build(&enr_key)
                 .map_err(|e| format!("When adding key to servers ENR: {e:?}"))?
@@ -154,9 +236,23 @@ impl Discovery {
             port: portal_config.listen_port,
         };
 
+        // Overlay requests are timed out by the overlay, per message type, so that discv5 must not
+        // abandon them before.
+        let request_timeout = [
+            DISCV5_REQUEST_TIMEOUT,
+            portal_config.ping_timeout,
+            portal_config.findnodes_timeout,
+            portal_config.findcontent_timeout,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(DISCV5_REQUEST_TIMEOUT);
         let discv5_config = ConfigBuilder::new(listen_config)
-            .request_timeout(Duration::from_secs(3))
+            .request_timeout(request_timeout)
             .build();
+        if let Some(enr_seq_file) = &portal_config.enr_seq_file {
+            persist_enr_seq(enr_seq_file, enr.seq());
+        }
         let discv5 = Discv5::new(enr, enr_key, discv5_config)
             .map_err(|e| format!("Failed to create discv5 instance: {e}"))?;
 
@@ -170,7 +266,10 @@ impl Discovery {
                 .map_err(|e| format!("Failed to add bootnode enr: {e}"))?;
         }
 
-        let node_addr_cache = LruCache::new(portal_config.node_addr_cache_capacity);
+        let node_addr_cache = NodeAddrCache::new(
+            portal_config.node_addr_cache_capacity,
+            Some(PORTALNET_METRICS.overlay()),
+        );
         let node_addr_cache = Arc::new(RwLock::new(node_addr_cache));
 
         Ok(Self {
@@ -179,6 +278,12 @@ impl Discovery {
             started: false,
             listen_socket: listen_all_ips,
             network_spec,
+            overlay_tables: Arc::new(RwLock::new(HashMap::new())),
+            store_healths: RwLock::new(HashMap::new()),
+            validation_level: portal_config.validation_level,
+            validators: RwLock::new(HashMap::new()),
+            enr_seq_file: portal_config.enr_seq_file,
+            transfer_sizes: TransferSizeMeter::default(),
         })
     }
 
@@ -201,6 +306,9 @@ impl Discovery {
         let (talk_req_tx, talk_req_rx) = mpsc::channel(TALKREQ_CHANNEL_BUFFER);
 
         let node_addr_cache = Arc::clone(&self.node_addr_cache);
+        let overlay_tables = Arc::clone(&self.overlay_tables);
+        let local_enr = self.discv5.external_enr();
+        let enr_seq_file = self.enr_seq_file.clone();
 
         tokio::spawn(async move {
             while let Some(event) = event_rx.recv().await {
@@ -221,11 +329,11 @@ impl Discovery {
                             continue;
                         }
                         if let Some(old) = node_addr_cache.write().put(
-                            enr.node_id(),
                             NodeAddress {
                                 enr: enr.clone(),
                                 socket_addr,
                             },
+                            |node_id| is_in_overlay_table(&overlay_tables, node_id),
                         ) {
                             debug!(
                                 old = ?(old.enr, old.socket_addr),
@@ -236,6 +344,14 @@ impl Discovery {
                             debug!(addr = ?(enr, socket_addr), "node address cached");
                         }
                     }
+                    Event::SocketUpdated(socket_addr) => {
+                        // The sequence number of the local ENR was bumped.
+                        let seq = local_enr.read().seq();
+                        info!(%socket_addr, seq, "Local ENR socket updated");
+                        if let Some(enr_seq_file) = &enr_seq_file {
+                            persist_enr_seq(enr_seq_file, seq);
+                        }
+                    }
                     _ => continue,
                 }
             }
@@ -264,9 +380,95 @@ impl Discovery {
                 .local_enr()
                 .ip4()
                 .map_or(Some("None".to_owned()), |ip| Some(ip.to_string())),
+            lookup_readiness: self
+                .overlay_tables
+                .read()
+                .iter()
+                .map(|(subnetwork, (kbuckets, min_peers))| {
+                    let peers = kbuckets.connected_entries_count();
+                    let readiness = LookupReadinessInfo {
+                        peers,
+                        min_peers: *min_peers,
+                        ready: peers >= *min_peers,
+                    };
+                    (subnetwork.to_cli_arg(), readiness)
+                })
+                .collect(),
+            store_health: self
+                .store_healths
+                .read()
+                .iter()
+                .map(|(subnetwork, store_health)| (subnetwork.to_cli_arg(), store_health.info()))
+                .collect(),
+            validation_level: self.validation_level,
+            validators: self
+                .validators
+                .read()
+                .iter()
+                .map(|(subnetwork, validators)| (subnetwork.to_cli_arg(), validators.clone()))
+                .collect(),
         })
     }
 
+    /// Returns the version and build of trin, and the protocols of the enabled subnetworks.
+    pub fn client_info(&self) -> ClientInfo {
+        let subnetworks = self
+            .overlay_tables
+            .read()
+            .keys()
+            .filter_map(|subnetwork| {
+                let protocol_id = self
+                    .network_spec
+                    .get_protocol_identifier_from_subnetwork(subnetwork)
+                    .ok()?;
+                let protocol = SubnetworkProtocolInfo {
+                    protocol_id,
+                    version: PORTAL_WIRE_PROTOCOL_VERSION,
+                };
+                Some((subnetwork.to_cli_arg(), protocol))
+            })
+            .collect();
+        ClientInfo {
+            // The crates of the workspace share its version.
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: get_trin_version().to_string(),
+            build_timestamp: get_build_timestamp().to_string(),
+            subnetworks,
+            features: get_enabled_features()
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect(),
+        }
+    }
+
+    /// Registers the routing table of an overlay network, so that its readiness to serve content
+    /// lookups is reported in the node info.
+    pub fn register_overlay_table(
+        &self,
+        subnetwork: Subnetwork,
+        kbuckets: SharedKBucketsTable,
+        min_lookup_peers: usize,
+    ) {
+        self.overlay_tables
+            .write()
+            .insert(subnetwork, (kbuckets, min_lookup_peers));
+    }
+
+    /// Registers the store health of an overlay network, so that it is reported in the node info.
+    pub fn register_store_health(&self, subnetwork: Subnetwork, store_health: SharedStoreHealth) {
+        self.store_healths.write().insert(subnetwork, store_health);
+    }
+
+    /// Registers the names of the validators of an overlay network, keyed by content type, so
+    /// that they are reported in the node info.
+    pub fn register_validators(
+        &self,
+        subnetwork: Subnetwork,
+        validators: BTreeMap<String, String>,
+    ) {
+        self.validators.write().insert(subnetwork, validators);
+    }
+
     /// Returns the local node-id and a nested array of node-ids contained in each of this node's
     /// k-buckets.
     pub fn routing_table_info(&self) -> RoutingTableInfo {
@@ -286,6 +488,19 @@ impl Discovery {
         self.discv5.local_enr()
     }
 
+    /// Bumps the sequence number of the local ENR, by inserting its client info again, so that
+    /// peers refresh their copy of it. Returns the new sequence number.
+    pub fn bump_enr_seq(&self) -> anyhow::Result<u64> {
+        self.discv5
+            .enr_insert(ENR_PORTAL_CLIENT_KEY, &enr_client_info().as_bytes())
+            .map_err(|err| anyhow!("Unable to update the local ENR: {err:?}"))?;
+        let seq = self.discv5.local_enr().seq();
+        if let Some(enr_seq_file) = &self.enr_seq_file {
+            persist_enr_seq(enr_seq_file, seq);
+        }
+        Ok(seq)
+    }
+
     /// Looks up the ENR for `node_id`.
     pub fn find_enr(&self, node_id: &NodeId) -> Option<Enr> {
         self.discv5.find_enr(node_id)
@@ -296,6 +511,11 @@ impl Discovery {
         self.discv5.add_enr(enr)
     }
 
+    /// Returns the meter of the data received by size limited inbound uTP transfers.
+    pub fn transfer_sizes(&self) -> TransferSizeMeter {
+        self.transfer_sizes.clone()
+    }
+
     /// Returns the cached `NodeAddress` or `None` if not cached.
     pub fn cached_node_addr(&self, node_id: &NodeId) -> Option<NodeAddress> {
         self.node_addr_cache.write().get(node_id).cloned()
@@ -303,10 +523,13 @@ impl Discovery {
 
     /// Put a `NodeAddress` into cache. If the key already exists in the cache, then it updates the
     /// key's value and returns the old value. Otherwise, `None` is returned.
+    ///
+    /// The least recently used addresses of nodes that aren't in an overlay routing table are
+    /// evicted once the cache is full.
     pub fn put_cached_node_addr(&self, node_addr: NodeAddress) -> Option<NodeAddress> {
-        self.node_addr_cache
-            .write()
-            .put(node_addr.enr.node_id(), node_addr)
+        self.node_addr_cache.write().put(node_addr, |node_id| {
+            is_in_overlay_table(&self.overlay_tables, node_id)
+        })
     }
 
     /// Sends a TALKREQ message to `enr`.
@@ -332,6 +555,22 @@ impl Discovery {
         let response = self.discv5.talk_req(enr, protocol, request).await?;
         Ok(Bytes::from(response))
     }
+
+    /// Sends a discv5 PING message to `enr`, and returns the address from which it observed the
+    /// local node.
+    pub async fn send_ping(&self, enr: Enr) -> Result<SocketAddr, RequestError> {
+        let pong = self.discv5.send_ping(enr).await?;
+        Ok(SocketAddr::new(pong.ip, u16::from(pong.port)))
+    }
+
+    /// Returns the UDP address advertised in the local ENR, preferring IPv4.
+    pub fn advertised_address(&self) -> Option<SocketAddr> {
+        let local_enr = self.discv5.local_enr();
+        local_enr
+            .udp4_socket()
+            .map(SocketAddr::V4)
+            .or_else(|| local_enr.udp6_socket().map(SocketAddr::V6))
+    }
 }
 
 pub struct Discv5UdpSocket {
@@ -482,6 +721,7 @@ impl AsyncUdpSocket<UtpEnr> for Discv5UdpSocket {
                 let src_node_id = talk_req.node_id();
                 let enr = self.find_enr(src_node_id).await?;
                 let packet = talk_req.body();
+                self.discv5.transfer_sizes.record(*src_node_id, packet);
                 let n = std::cmp::min(buf.len(), packet.len());
                 buf[..n].copy_from_slice(&packet[..n]);
 
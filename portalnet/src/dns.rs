@@ -0,0 +1,160 @@
+//! Resolves bootnodes from an EIP-1459 DNS node list.
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, ensure};
+use ethportal_api::types::{
+    enr::Enr,
+    enr_tree::{entry_hash, EnrTreeEntry, EnrTreeRoot, EnrTreeUrl},
+};
+use futures::{stream, StreamExt};
+use hickory_resolver::{proto::rr::rdata::TXT, TokioAsyncResolver};
+use tokio::{sync::mpsc::UnboundedSender, time::timeout};
+use tracing::{debug, info, warn};
+
+use crate::{discovery::Discovery, events::OverlayRequest};
+
+/// The time after which resolving a node list fails, however many of its entries are left.
+const ENR_TREE_RESOLVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The max number of entries of a node list that are resolved concurrently.
+const MAX_CONCURRENT_DNS_LOOKUPS: usize = 16;
+
+/// The max number of entries of a node list that are resolved. This bounds the lookups made for a
+/// misconfigured or malicious list.
+const MAX_ENR_TREE_ENTRIES: usize = 2000;
+
+/// Resolves the nodes of the DNS node list at `url`, with the nameservers of the system.
+///
+/// The root of the list must be signed by the key of the list. Entries that fail to resolve, or
+/// that don't match their hash, are skipped. Links to other lists aren't followed.
+pub async fn resolve_enr_tree(url: &EnrTreeUrl) -> anyhow::Result<Vec<Enr>> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
+    timeout(
+        ENR_TREE_RESOLVE_TIMEOUT,
+        resolve_enr_tree_with(&resolver, url),
+    )
+    .await
+    .map_err(|_| anyhow!("Resolving DNS node list {url} timed out"))?
+}
+
+/// Resolves the nodes of the DNS node list at `url` with the `resolver`.
+async fn resolve_enr_tree_with(
+    resolver: &TokioAsyncResolver,
+    url: &EnrTreeUrl,
+) -> anyhow::Result<Vec<Enr>> {
+    let root: EnrTreeRoot = lookup_txt(resolver, &url.domain).await?.parse()?;
+    root.verify(&url.public_key)?;
+
+    let mut enrs = vec![];
+    let mut visited = HashSet::new();
+    let mut pending = vec![root.enr_root];
+    while !pending.is_empty() {
+        // The children of all branches of a level of the tree are resolved concurrently.
+        let level: Vec<String> = pending
+            .drain(..)
+            .filter(|hash| visited.insert(hash.clone()))
+            .collect();
+        ensure!(
+            visited.len() <= MAX_ENR_TREE_ENTRIES,
+            "DNS node list {url} has more than {MAX_ENR_TREE_ENTRIES} entries"
+        );
+        let mut lookups = stream::iter(level)
+            .map(|hash| async move {
+                let text = lookup_txt(resolver, &format!("{hash}.{}", url.domain)).await;
+                (hash, text)
+            })
+            .buffer_unordered(MAX_CONCURRENT_DNS_LOOKUPS);
+        while let Some((hash, text)) = lookups.next().await {
+            let text = match text {
+                Ok(text) => text,
+                Err(err) => {
+                    warn!(%err, entry = %hash, "Failed to resolve DNS node list entry");
+                    continue;
+                }
+            };
+            if entry_hash(&text) != hash {
+                warn!(entry = %hash, "DNS node list entry doesn't match its hash");
+                continue;
+            }
+            match text.parse() {
+                Ok(EnrTreeEntry::Branch(children)) => pending.extend(children),
+                Ok(EnrTreeEntry::Enr(enr)) => enrs.push(*enr),
+                Ok(EnrTreeEntry::Link(_)) => {}
+                Err(err) => warn!(%err, entry = %hash, "Invalid DNS node list entry"),
+            }
+        }
+    }
+    Ok(enrs)
+}
+
+/// Resolves the DNS node list at `url` right away and then every `interval`, and passes its nodes
+/// to the overlays, to be inserted into their routing tables like the bootnodes of the node.
+///
+/// Startup doesn't wait for the node list, the overlays join the network through the configured
+/// bootnodes and insert the nodes of the list once they're resolved.
+pub async fn refresh_bootnodes(
+    url: EnrTreeUrl,
+    interval: Duration,
+    discovery: Arc<Discovery>,
+    overlay_txs: Vec<UnboundedSender<OverlayRequest>>,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let enrs = match resolve_enr_tree(&url).await {
+            Ok(enrs) => enrs,
+            Err(err) => {
+                warn!(%err, %url, "Failed to resolve bootnodes from DNS node list");
+                continue;
+            }
+        };
+        info!(count = enrs.len(), %url, "Resolved bootnodes from DNS node list");
+        let local_node_id = discovery.local_enr().node_id();
+        let enrs: Vec<Enr> = enrs
+            .into_iter()
+            .filter(|enr| enr.node_id() != local_node_id)
+            .collect();
+        for enr in &enrs {
+            if let Err(err) = discovery.add_enr(enr.clone()) {
+                debug!(%err, node.id = %enr.node_id(), "Failed to add bootnode from DNS node list");
+            }
+        }
+        for overlay_tx in &overlay_txs {
+            let _ = overlay_tx.send(OverlayRequest::Bootnodes(enrs.clone()));
+        }
+    }
+}
+
+/// Returns the text of the first TXT record of `name`.
+async fn lookup_txt(resolver: &TokioAsyncResolver, name: &str) -> anyhow::Result<String> {
+    let lookup = resolver.txt_lookup(name).await?;
+    let txt = lookup
+        .iter()
+        .next()
+        .ok_or_else(|| anyhow!("No TXT record for {name}"))?;
+    txt_text(txt)
+}
+
+/// Returns the text of the TXT record, which is split into strings of up to 255 bytes.
+fn txt_text(txt: &TXT) -> anyhow::Result<String> {
+    Ok(String::from_utf8(txt.txt_data().concat())?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txt_record_strings_are_joined() {
+        let txt = TXT::new(vec![
+            "enrtree-branch:".to_string(),
+            "2XS2367YHAXJFGLZHVAWLQD4ZY".to_string(),
+        ]);
+        assert_eq!(
+            txt_text(&txt).unwrap(),
+            "enrtree-branch:2XS2367YHAXJFGLZHVAWLQD4ZY"
+        );
+    }
+}
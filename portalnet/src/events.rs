@@ -5,7 +5,7 @@ use std::{
 
 use discv5::TalkRequest;
 use ethportal_api::{
-    types::{network::Subnetwork, portal_wire::NetworkSpec},
+    types::{enr::Enr, network::Subnetwork, portal_wire::NetworkSpec},
     utils::bytes::{hex_encode, hex_encode_upper},
 };
 use futures::stream::{select_all, StreamExt};
@@ -38,6 +38,8 @@ pub enum OverlayRequest {
     Talk(TalkRequest),
     /// A forwarded event from another overlay.
     Event(EventEnvelope),
+    /// Nodes resolved from a DNS node list, to be inserted into the routing table as bootnodes.
+    Bootnodes(Vec<Enr>),
 }
 impl From<EventEnvelope> for OverlayRequest {
     fn from(event: EventEnvelope) -> Self {
@@ -127,6 +127,13 @@ pub struct FindContentQuery<TNodeId: std::fmt::Display + std::hash::Hash> {
     /// The number of peers for which the query is currently validating results.
     num_validating: usize,
 
+    /// The distance to the target of the hint peer, while it is being contacted. The hint peer is
+    /// contacted before, and not concurrently with, any other peer.
+    hint_peer: Option<Distance>,
+
+    /// Whether peers were skipped because they are beyond the max number of hops.
+    max_hops_reached: bool,
+
     /// The configuration of the query.
     config: QueryConfig,
 }
@@ -190,15 +197,21 @@ where
 
         // Incorporate the peer response into the query.
         match peer_response {
-            FindContentQueryResponse::ClosestNodes(closer_peers) => {
+            FindContentQueryResponse::ClosestNodes(mut closer_peers) => {
                 // Incorporate the reported closer peers into the query.
                 let mut progress = false;
                 let num_closest = self.closest_peers.len();
+                let hop = self.closest_peers[&distance].hop() + 1;
+                if hop > self.config.max_hops && !closer_peers.is_empty() {
+                    // The reported peers are too many hops away to be contacted.
+                    self.max_hops_reached = true;
+                    closer_peers.clear();
+                }
 
                 for peer in closer_peers {
                     let key: Key<TNodeId> = peer.into();
                     let distance = self.target_key.distance(&key);
-                    let peer = QueryPeer::new(key, QueryPeerState::NotContacted);
+                    let peer = QueryPeer::new(key, QueryPeerState::NotContacted).with_hop(hop);
                     self.closest_peers.entry(distance).or_insert(peer);
 
                     // The query makes progress if the new peer is either closer to the target
@@ -296,6 +309,38 @@ where
             return QueryState::Validating(peer);
         }
 
+        // Contact the hint peer on its own, and don't contact other peers until it is resolved.
+        if let Some(distance) = self.hint_peer {
+            match self.closest_peers.get_mut(&distance) {
+                Some(peer) => match peer.state() {
+                    QueryPeerState::NotContacted => {
+                        let timeout = now + self.config.peer_timeout;
+                        peer.set_state(QueryPeerState::Waiting(timeout));
+                        self.num_waiting += 1;
+                        let peer = peer.key().preimage().clone();
+                        return QueryState::Waiting(Some(peer));
+                    }
+                    QueryPeerState::Waiting(timeout) => {
+                        if now < *timeout {
+                            return QueryState::WaitingAtCapacity;
+                        }
+                        // Assert that the following subtraction will not overflow.
+                        assert!(
+                            self.num_waiting > 0,
+                            "Query (poll) reached invalid number of waiting peers"
+                        );
+                        self.num_waiting -= 1;
+                        peer.set_state(QueryPeerState::Unresponsive);
+                        self.hint_peer = None;
+                    }
+                    QueryPeerState::Succeeded
+                    | QueryPeerState::Failed
+                    | QueryPeerState::Unresponsive => self.hint_peer = None,
+                },
+                None => self.hint_peer = None,
+            }
+        }
+
         // Check if the query is at capacity w.r.t. the allowed parallelism.
         let at_capacity = self.at_capacity();
 
@@ -434,10 +479,25 @@ where
             validated_content: None,
             num_waiting: 0,
             num_validating: 0,
+            hint_peer: None,
+            max_hops_reached: false,
             config,
         }
     }
 
+    /// Sets a peer that is contacted before any other peer, even if it is not among the closest.
+    ///
+    /// Other peers are only contacted once the hint peer responds without content, fails, or times
+    /// out.
+    pub fn with_hint_peer(mut self, hint_peer: Key<TNodeId>) -> Self {
+        let distance = hint_peer.distance(&self.target_key);
+        self.closest_peers
+            .entry(distance)
+            .or_insert_with(|| QueryPeer::new(hint_peer, QueryPeerState::NotContacted));
+        self.hint_peer = Some(distance);
+        self
+    }
+
     /// Checks if the query is at capacity w.r.t. the permitted parallelism.
     ///
     /// While the query is stalled, up to `num_results` parallel requests
@@ -472,6 +532,22 @@ where
             .collect()
     }
 
+    /// Returns the peers closest to the target that haven't failed, ordered by distance, for use in
+    /// streamed query progress.
+    pub fn closest_known_peers(&self) -> Vec<TNodeId> {
+        self.closest_peers
+            .values()
+            .filter(|peer| !matches!(peer.state(), QueryPeerState::Failed))
+            .map(|peer| peer.key().clone().into_preimage())
+            .take(self.config.num_results)
+            .collect()
+    }
+
+    /// Returns `true` if peers were skipped because they are beyond the max number of hops.
+    pub fn max_hops_reached(&self) -> bool {
+        self.max_hops_reached
+    }
+
     /// Return a list of peers with whom we have unresolved queries, for use in trace result.
     /// Do not include the source who returned the content.
     pub fn pending_peers(&self, source: TNodeId) -> Vec<TNodeId> {
@@ -495,6 +571,7 @@ mod tests {
     use std::{cmp::min, time::Duration};
 
     use discv5::enr::NodeId;
+    use ethportal_api::types::cli::DEFAULT_LOOKUP_MAX_HOPS;
     use quickcheck::*;
     use rand::{thread_rng, Rng};
     use test_log::test;
@@ -516,6 +593,7 @@ mod tests {
         let config = QueryConfig {
             parallelism: rng.gen_range(1..10),
             num_results: rng.gen_range(1..25),
+            max_hops: DEFAULT_LOOKUP_MAX_HOPS,
             peer_timeout: Duration::from_secs(rng.gen_range(10..30)),
             overall_timeout: Duration::from_secs(rng.gen_range(30..120)),
         };
@@ -775,6 +853,77 @@ mod tests {
         QuickCheck::new().tests(300).quickcheck(prop as fn(_) -> _)
     }
 
+    #[test]
+    fn hint_peer_is_contacted_first() {
+        fn prop(query: TestQuery) -> bool {
+            let now = Instant::now();
+            let hint_peer = NodeId::random();
+            let mut query = query.with_hint_peer(Key::from(hint_peer));
+
+            // The hint peer is contacted alone, before any of the closest peers.
+            assert_eq!(query.poll(now), QueryState::Waiting(Some(hint_peer)));
+            assert_eq!(query.poll(now), QueryState::WaitingAtCapacity);
+
+            // Once the hint peer fails, the query proceeds with the closest peers.
+            query.on_failure(&hint_peer);
+            match query.poll(now) {
+                QueryState::Waiting(Some(peer)) => assert_ne!(peer, hint_peer),
+                state => panic!("Unexpected query state: {state:?}"),
+            }
+
+            true
+        }
+
+        QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _)
+    }
+
+    #[test]
+    fn hint_peer_content_short_circuits() {
+        let now = Instant::now();
+        let hint_peer = NodeId::random();
+        let mut query = random_query().with_hint_peer(Key::from(hint_peer));
+        let content = RawContentValue::from([0xef]);
+
+        assert_eq!(query.poll(now), QueryState::Waiting(Some(hint_peer)));
+        query.on_success(
+            &hint_peer,
+            FindContentQueryResponse::Content(content.clone()),
+        );
+        assert_eq!(query.poll(now), QueryState::Validating(hint_peer));
+
+        match query.pending_validation_result(hint_peer) {
+            FindContentQueryPending::PendingContent {
+                content,
+                peer,
+                valid_content_tx,
+                ..
+            } => valid_content_tx
+                .send(Some(ValidatedContent {
+                    content,
+                    was_utp_transfer: false,
+                    sending_peer: peer,
+                }))
+                .unwrap(),
+            result => panic!("Unexpected result: {result:?}"),
+        }
+        assert_eq!(query.poll(now), QueryState::Finished);
+
+        // No other peer was contacted.
+        assert!(query
+            .closest_peers
+            .values()
+            .filter(|peer| peer.key().preimage() != &hint_peer)
+            .all(|peer| matches!(peer.state(), QueryPeerState::NotContacted)));
+        match query.into_result() {
+            FindContentQueryResult::ValidContent(validated_content, cancelled_peers) => {
+                assert_eq!(validated_content.content, content);
+                assert_eq!(validated_content.sending_peer, hint_peer);
+                assert!(cancelled_peers.is_empty());
+            }
+            result => panic!("Unexpected result: {result:?}"),
+        }
+    }
+
     #[test]
     fn no_duplicates() {
         fn prop(mut query: TestQuery) -> bool {
@@ -874,4 +1023,45 @@ mod tests {
 
         QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _)
     }
+
+    #[test]
+    fn peers_beyond_max_hops_are_not_contacted() {
+        let target = NodeId::random();
+        let config = QueryConfig {
+            parallelism: 1,
+            max_hops: 2,
+            ..QueryConfig::default()
+        };
+        let first_hop = NodeId::random();
+        let mut query = TestQuery::with_config(config, target.into(), vec![first_hop.into()]);
+        let mut now = Instant::now();
+
+        // The peers returned by the first hop are the second hop, and are contacted.
+        assert!(matches!(query.poll(now), QueryState::Waiting(Some(peer)) if peer == first_hop));
+        let second_hop = NodeId::random();
+        query.on_success(
+            &first_hop,
+            FindContentQueryResponse::ClosestNodes(vec![second_hop]),
+        );
+        now += Duration::from_millis(1);
+        assert!(matches!(query.poll(now), QueryState::Waiting(Some(peer)) if peer == second_hop));
+        assert!(!query.max_hops_reached());
+
+        // The peers returned by the second hop are beyond the max number of hops.
+        let third_hop = NodeId::random();
+        query.on_success(
+            &second_hop,
+            FindContentQueryResponse::ClosestNodes(vec![third_hop]),
+        );
+        assert!(query.max_hops_reached());
+        assert!(query
+            .closest_peers
+            .values()
+            .all(|peer| peer.key().preimage() != &third_hop));
+        assert!(matches!(query.poll(now), QueryState::Finished));
+        assert!(matches!(
+            query.into_result(),
+            FindContentQueryResult::NoneFound
+        ));
+    }
 }
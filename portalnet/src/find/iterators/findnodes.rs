@@ -338,6 +338,7 @@ mod tests {
     use std::time::Duration;
 
     use discv5::enr::NodeId;
+    use ethportal_api::types::cli::DEFAULT_LOOKUP_MAX_HOPS;
     use quickcheck::*;
     use rand::{thread_rng, Rng};
     use test_log::test;
@@ -358,6 +359,7 @@ mod tests {
         let config = QueryConfig {
             parallelism: rng.gen_range(1..10),
             num_results: rng.gen_range(1..25),
+            max_hops: DEFAULT_LOOKUP_MAX_HOPS,
             peer_timeout: Duration::from_secs(rng.gen_range(10..30)),
             overall_timeout: Duration::from_secs(rng.gen_range(30..120)),
         };
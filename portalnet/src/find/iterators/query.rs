@@ -25,6 +25,8 @@ use std::time::{Duration, Instant};
 
 use discv5::kbucket::Key;
 
+use ethportal_api::types::cli::DEFAULT_LOOKUP_MAX_HOPS;
+
 use crate::{constants::DEFAULT_QUERY_TIMEOUT, find::query_pool::QueryState};
 
 // Configuration for a `Query`.
@@ -44,6 +46,13 @@ pub struct QueryConfig {
     /// to k, the max number of entries in a k-bucket. Currently defaults to `20`.
     pub num_results: usize,
 
+    /// The max number of hops towards the target.
+    ///
+    /// The peers known when the query starts are one hop away, and the peers they return are one
+    /// more hop away than them. Peers beyond the max number of hops are not contacted. Only
+    /// enforced by content queries. Defaults to `32`.
+    pub max_hops: usize,
+
     /// The timeout for a single peer.
     ///
     /// If a successful result is not reported for a peer within this timeout
@@ -64,6 +73,7 @@ impl Default for QueryConfig {
         Self {
             parallelism: 3,
             num_results: 20,
+            max_hops: DEFAULT_LOOKUP_MAX_HOPS,
             peer_timeout: Duration::from_secs(2),
             overall_timeout: DEFAULT_QUERY_TIMEOUT,
         }
@@ -175,21 +185,36 @@ pub struct QueryPeer<TNodeId> {
     /// The number of peers that have been returned by this peer.
     peers_returned: usize,
 
+    /// The number of hops from the local node to this peer.
+    hop: usize,
+
     /// The current query state of this peer.
     state: QueryPeerState,
 }
 
 impl<TNodeId> QueryPeer<TNodeId> {
     /// Constructs a new `QueryPeer<TNodeId>` whose `KBucket` key is `key` and whose initial state
-    /// is `state`.
+    /// is `state`. The peer is one hop away.
     pub fn new(key: Key<TNodeId>, state: QueryPeerState) -> Self {
         QueryPeer {
             key,
             peers_returned: 0,
+            hop: 1,
             state,
         }
     }
 
+    /// Sets the number of hops from the local node to the query peer.
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop;
+        self
+    }
+
+    /// Returns the number of hops from the local node to the query peer.
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
     /// Returns the `KBucket` key associated with the query peer.
     pub fn key(&self) -> &Key<TNodeId> {
         &self.key
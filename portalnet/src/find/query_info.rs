@@ -1,13 +1,16 @@
+use std::time::Instant;
+
 use discv5::{enr::NodeId, kbucket::Key, Enr};
 use ethportal_api::{
     types::{
         portal_wire::{Content, FindContent, FindNodes, Request},
-        query_trace::QueryTrace,
+        query_trace::{QueryHopEvent, QueryHopResponse, QueryTrace},
     },
     OverlayContentKey, RawContentValue,
 };
 use futures::channel::oneshot;
 use smallvec::SmallVec;
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{find::query_pool::TargetKey, overlay::errors::OverlayRequestError};
 
@@ -21,6 +24,40 @@ pub struct QueryInfo<TContentKey> {
     pub untrusted_enrs: SmallVec<[Enr; 16]>,
 
     pub trace: Option<QueryTrace>,
+
+    /// Whether the trace is returned to the initiator of the query. If not, the trace is only
+    /// retained in the query trace buffer.
+    pub return_trace: bool,
+
+    /// Streams the response of each contacted peer while the query is in progress, if set.
+    pub hop_stream: Option<QueryHopStream>,
+}
+
+/// Sends an event for every response of a peer contacted by a query, as it arrives.
+#[derive(Debug)]
+pub struct QueryHopStream {
+    tx: UnboundedSender<QueryHopEvent>,
+    started: Instant,
+}
+
+impl QueryHopStream {
+    pub fn new(tx: UnboundedSender<QueryHopEvent>) -> Self {
+        Self {
+            tx,
+            started: Instant::now(),
+        }
+    }
+
+    /// Sends an event for the response of `peer`. Events are dropped if the receiver is gone.
+    pub fn send(&self, peer: NodeId, response: QueryHopResponse, closest: Vec<NodeId>) {
+        let duration_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let _ = self.tx.send(QueryHopEvent {
+            duration_ms,
+            peer,
+            response,
+            closest,
+        });
+    }
 }
 
 // (content_value, utp_transfer, trace)
@@ -79,6 +116,21 @@ impl<TContentKey: OverlayContentKey> QueryInfo<TContentKey> {
 
         Ok(request)
     }
+
+    /// Returns `true` if the initiator of the query is no longer waiting for its result, for
+    /// example because the request that started it was cancelled.
+    ///
+    /// Queries without a callback, like those started by the overlay itself, are never abandoned.
+    pub(crate) fn is_abandoned(&self) -> bool {
+        match &self.query_type {
+            QueryType::FindNode { callback, .. } => {
+                callback.as_ref().is_some_and(oneshot::Sender::is_canceled)
+            }
+            QueryType::FindContent { callback, .. } => {
+                callback.as_ref().is_some_and(oneshot::Sender::is_canceled)
+            }
+        }
+    }
 }
 
 impl<TContentKey: OverlayContentKey> TargetKey<NodeId> for QueryInfo<TContentKey> {
@@ -134,6 +186,26 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn query_is_abandoned_when_callback_receiver_is_dropped() {
+        let (callback, receiver) = oneshot::channel();
+        let query_info: QueryInfo<ethportal_api::HistoryContentKey> = QueryInfo {
+            query_type: QueryType::FindNode {
+                target: NodeId::random(),
+                callback: Some(callback),
+                distances_to_request: 3,
+            },
+            untrusted_enrs: SmallVec::new(),
+            trace: None,
+            return_trace: false,
+            hop_stream: None,
+        };
+        assert!(!query_info.is_abandoned());
+
+        drop(receiver);
+        assert!(query_info.is_abandoned());
+    }
+
     #[test]
     fn test_log2distance() {
         let target = NodeId::new(&[0u8; 32]);
@@ -124,6 +124,11 @@ where
         let mut waiting = None;
         let mut timeout = None;
 
+        // Drop the queries whose initiator is gone, rather than contacting more peers and
+        // transferring content that nobody is waiting for.
+        self.queries
+            .retain(|_, (query_info, _)| !query_info.is_abandoned());
+
         for (&query_id, (_, query)) in self.queries.iter_mut() {
             if query.started().is_none() {
                 query.start(now);
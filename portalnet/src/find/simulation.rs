@@ -0,0 +1,179 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use discv5::{
+    enr::NodeId,
+    kbucket::{Key, MAX_NODES_PER_BUCKET},
+};
+use ethportal_api::types::{distance::Metric, portal::SimulatedHopInfo};
+use itertools::Itertools;
+
+use super::{
+    iterators::{
+        findcontent::{FindContentQuery, FindContentQueryResponse},
+        query::{Query, QueryConfig},
+    },
+    query_pool::QueryState,
+};
+use crate::overlay::service::FIND_CONTENT_MAX_NODES;
+
+/// The max number of synthetic peers that a lookup can be simulated against.
+pub const MAX_SIMULATED_PEERS: usize = 10_000;
+
+/// Simulates a content lookup from the local node for `content_id`, against a synthetic set of
+/// peers, without any network traffic.
+///
+/// The routing table of the local node and of each synthetic peer holds the other synthetic peers,
+/// up to `MAX_NODES_PER_BUCKET` per bucket, in the order they are given. Every peer responds
+/// right away to a FindContent request with the peers of its routing table that are closest to
+/// the content, and none of them has the content, so the lookup runs until it is exhausted.
+///
+/// Returns the peers in the order they are contacted.
+pub fn simulate_lookup<TMetric: Metric>(
+    local_node_id: NodeId,
+    peers: &[NodeId],
+    content_id: &[u8; 32],
+    config: QueryConfig,
+) -> Vec<SimulatedHopInfo> {
+    let initial_peers =
+        closest_peers::<TMetric>(&local_node_id, peers, content_id, config.num_results);
+    let mut peer_hops: HashMap<NodeId, usize> =
+        initial_peers.iter().map(|node_id| (*node_id, 1)).collect();
+    let mut query = FindContentQuery::with_config(
+        config,
+        Key::from(NodeId::new(content_id)),
+        initial_peers.into_iter().map(Key::from),
+    );
+
+    let now = Instant::now();
+    let mut hops = vec![];
+    // Every peer responds right away without content, so the query only ever waits for the next
+    // peer to contact, until it's finished.
+    while let QueryState::Waiting(Some(node_id)) = query.poll(now) {
+        let hop = peer_hops.get(&node_id).copied().unwrap_or(1);
+        let returned_node_ids =
+            closest_peers::<TMetric>(&node_id, peers, content_id, FIND_CONTENT_MAX_NODES);
+        for returned_node_id in &returned_node_ids {
+            peer_hops.entry(*returned_node_id).or_insert(hop + 1);
+        }
+        hops.push(SimulatedHopInfo {
+            node_id,
+            hop,
+            distance: *TMetric::distance(content_id, &node_id.raw()),
+            returned_node_ids: returned_node_ids.clone(),
+        });
+        query.on_success(
+            &node_id,
+            FindContentQueryResponse::ClosestNodes(returned_node_ids),
+        );
+    }
+    hops
+}
+
+/// Returns up to `limit` peers of the simulated routing table of `owner` that are closest to the
+/// content.
+fn closest_peers<TMetric: Metric>(
+    owner: &NodeId,
+    peers: &[NodeId],
+    content_id: &[u8; 32],
+    limit: usize,
+) -> Vec<NodeId> {
+    routing_table(owner, peers)
+        .into_iter()
+        .sorted_by_cached_key(|node_id| TMetric::distance(content_id, &node_id.raw()))
+        .take(limit)
+        .collect()
+}
+
+/// Returns the simulated routing table of `owner`: the other peers, up to `MAX_NODES_PER_BUCKET`
+/// per bucket, in the order they are given.
+fn routing_table(owner: &NodeId, peers: &[NodeId]) -> Vec<NodeId> {
+    let owner_key = Key::from(*owner);
+    let mut bucket_sizes: HashMap<u64, usize> = HashMap::new();
+    let mut seen = HashSet::new();
+    peers
+        .iter()
+        .filter(|node_id| seen.insert(**node_id))
+        .filter(|node_id| {
+            let Some(bucket) = owner_key.log2_distance(&Key::from(**node_id)) else {
+                // The owner isn't part of its own routing table.
+                return false;
+            };
+            let bucket_size = bucket_sizes.entry(bucket).or_default();
+            *bucket_size += 1;
+            *bucket_size <= MAX_NODES_PER_BUCKET
+        })
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ethportal_api::types::distance::XorMetric;
+
+    use super::*;
+
+    fn random_peers(count: usize) -> Vec<NodeId> {
+        (0..count).map(|_| NodeId::random()).collect()
+    }
+
+    #[test]
+    fn routing_table_is_bounded_per_bucket() {
+        let owner = NodeId::random();
+        let mut peers = random_peers(200);
+        peers.push(owner);
+        peers.push(peers[0]);
+
+        let routing_table = routing_table(&owner, &peers);
+        assert!(!routing_table.contains(&owner));
+        assert_eq!(routing_table.iter().unique().count(), routing_table.len());
+        let owner_key = Key::from(owner);
+        let bucket_sizes = routing_table
+            .iter()
+            .counts_by(|node_id| owner_key.log2_distance(&Key::from(*node_id)));
+        assert!(bucket_sizes
+            .values()
+            .all(|bucket_size| *bucket_size <= MAX_NODES_PER_BUCKET));
+    }
+
+    #[test]
+    fn lookup_converges_on_closest_peer() {
+        let local_node_id = NodeId::random();
+        let peers = random_peers(500);
+        let content_id = NodeId::random().raw();
+
+        let hops = simulate_lookup::<XorMetric>(
+            local_node_id,
+            &peers,
+            &content_id,
+            QueryConfig::default(),
+        );
+
+        // Each peer is contacted at most once, starting with the peers known to the local node.
+        assert_eq!(
+            hops.iter().map(|hop| hop.node_id).unique().count(),
+            hops.len()
+        );
+        assert_eq!(hops[0].hop, 1);
+        assert!(hops.iter().all(|hop| hop.hop >= 1));
+
+        // The lookup reaches the peer that is closest to the content.
+        let closest = peers
+            .iter()
+            .min_by_key(|node_id| XorMetric::distance(&content_id, &node_id.raw()))
+            .unwrap();
+        assert!(hops.iter().any(|hop| &hop.node_id == closest));
+
+        // The simulation is deterministic.
+        let repeated_hops = simulate_lookup::<XorMetric>(
+            local_node_id,
+            &peers,
+            &content_id,
+            QueryConfig::default(),
+        );
+        assert_eq!(hops, repeated_hops);
+    }
+}
@@ -1,19 +1,23 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use ethportal_api::{
     types::{
         distance::Metric,
         enr::Enr,
-        portal::MAX_CONTENT_KEYS_PER_OFFER,
+        portal::{ContentGossipInfo, GossipBatchInfo, MAX_CONTENT_KEYS_PER_OFFER},
         portal_wire::{OfferTrace, PopulatedOffer, PopulatedOfferWithResult, Request, Response},
     },
     utils::bytes::{hex_encode, hex_encode_compact},
-    OverlayContentKey, RawContentValue,
+    OverlayContentKey, RawContentKey, RawContentValue,
 };
-use futures::channel::oneshot;
+use futures::{channel::oneshot, future::join_all};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
 use tracing::{debug, error, trace, warn};
 
 use crate::{
@@ -21,7 +25,7 @@ use crate::{
         command::OverlayCommand,
         request::{OverlayRequest, RequestDirection},
     },
-    types::kbucket::SharedKBucketsTable,
+    types::{gossip_history::SharedGossipHistory, kbucket::SharedKBucketsTable},
     utp_controller::UtpController,
 };
 
@@ -34,61 +38,57 @@ pub struct GossipResult {
     pub accepted: Vec<Enr>,
     /// List of all ENRs to whom the content was successfully transferred
     pub transferred: Vec<Enr>,
+    /// The number of times the offer to each ENR was retried after it failed, keyed by ENR. ENRs
+    /// whose first offer didn't fail are omitted.
+    pub retries: BTreeMap<String, u32>,
+}
+
+/// The delay before the first retry of a failed offer. It doubles with every following retry, up
+/// to `GOSSIP_MAX_RETRY_BACKOFF`.
+const GOSSIP_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The max delay before a retry of a failed offer.
+const GOSSIP_MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Returns the delay before the given retry of a failed offer, counting from 1.
+fn retry_backoff(retry: u32) -> Duration {
+    GOSSIP_RETRY_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(GOSSIP_MAX_RETRY_BACKOFF)
 }
 
 /// Propagate gossip in a way that can be used across threads, without &self.
 /// Doesn't trace gossip results
+/// If a gossip history is given, content is skipped unless a new gossip round of it is due.
+/// Offers that fail, e.g. because the peer didn't respond, are retried up to `peer_retries` times
+/// with backoff.
 pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey, TMetric: Metric>(
     content: Vec<(TContentKey, RawContentValue)>,
     kbuckets: &SharedKBucketsTable,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
     utp_controller: Option<Arc<UtpController>>,
+    gossip_history: Option<&SharedGossipHistory>,
+    peer_retries: u32,
 ) -> usize {
-    // Precalculate content ids
-    let content = content
-        .into_iter()
-        .map(|(content_key, content_value)| {
-            (content_key.content_id(), (content_key, content_value))
-        })
-        .collect::<HashMap<_, _>>();
-
-    let content_ids = content.keys().collect::<Vec<_>>();
-    debug!(
-        ids = ?content_ids.iter().map(hex_encode_compact),
-        "propagating validated content",
-    );
-
-    // Map from content_ids to interested ENRs
-    let mut content_id_to_interested_enrs = kbuckets.batch_interested_enrs::<TMetric>(&content_ids);
-
-    // Map from ENRs to content they will gossip
-    let mut enrs_and_content: HashMap<Enr, Vec<&(TContentKey, RawContentValue)>> = HashMap::new();
-    for (content_id, content_key_value) in &content {
-        let interested_enrs = content_id_to_interested_enrs.remove(content_id).unwrap_or_else(|| {
-            error!("interested_enrs should contain all content ids, even if there are no interested ENRs");
-            vec![]
-        });
-        if interested_enrs.is_empty() {
-            debug!(
-                content.id = %hex_encode(content_id),
-                "No peers eligible for neighborhood gossip"
-            );
-            continue;
-        };
-
-        // Select gossip recipients
-        for enr in select_gossip_recipients::<TMetric>(content_id, interested_enrs) {
-            enrs_and_content
-                .entry(enr)
-                .or_default()
-                .push(content_key_value);
+    let content = match gossip_history {
+        Some(gossip_history) => {
+            let now = Instant::now();
+            let mut gossip_history = gossip_history.lock();
+            content
+                .into_iter()
+                .filter(|(content_key, _)| {
+                    gossip_history.start_round(&content_key.content_id(), now)
+                })
+                .collect()
         }
-    }
+        None => content,
+    };
+    let enrs_and_content = select_content_per_recipient::<_, TMetric>(content, kbuckets);
 
     let num_propagated_peers = enrs_and_content.len();
 
     // Create and send OFFER overlay request to the interested nodes
-    for (enr, mut interested_content) in enrs_and_content {
+    for (enr, interested_content) in enrs_and_content {
         let permit = match utp_controller {
             Some(ref utp_controller) => match utp_controller.get_outbound_semaphore() {
                 Some(permit) => Some(permit),
@@ -100,26 +100,18 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey, TMetric: Me
             None => None,
         };
 
-        // offer messages are limited to 64 content keys
-        if interested_content.len() > MAX_CONTENT_KEYS_PER_OFFER {
-            warn!(
-                enr = %enr,
-                content.len = interested_content.len(),
-                "Too many content items to offer to a single peer, dropping {}.",
-                interested_content.len() - MAX_CONTENT_KEYS_PER_OFFER
-            );
-            // sort content keys by distance to the node
-            interested_content.sort_by_cached_key(|(key, _)| {
-                TMetric::distance(&key.content_id(), &enr.node_id().raw())
-            });
-            // take 64 closest content keys
-            interested_content.truncate(MAX_CONTENT_KEYS_PER_OFFER);
+        if peer_retries > 0 {
+            tokio::spawn(offer_with_retries(
+                enr,
+                interested_content,
+                permit,
+                command_tx.clone(),
+                utp_controller.clone(),
+                peer_retries,
+            ));
+            continue;
         }
-        // change content keys to raw content keys
-        let interested_content = interested_content
-            .into_iter()
-            .map(|(key, value)| (key.to_bytes(), value.clone()))
-            .collect();
+
         let offer_request = Request::PopulatedOffer(PopulatedOffer {
             content_items: interested_content,
         });
@@ -140,10 +132,130 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey, TMetric: Me
     num_propagated_peers
 }
 
+/// Offers the content to the peer, retrying the offer up to `retries` times with backoff if it
+/// fails. Offers that the peer responded to aren't retried, whether it accepted any content or
+/// not.
+async fn offer_with_retries<TContentKey: OverlayContentKey>(
+    enr: Enr,
+    content_items: Vec<(RawContentKey, RawContentValue)>,
+    mut permit: Option<OwnedSemaphorePermit>,
+    command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    utp_controller: Option<Arc<UtpController>>,
+    retries: u32,
+) {
+    let mut retry = 0;
+    loop {
+        let offer_request = Request::PopulatedOffer(PopulatedOffer {
+            content_items: content_items.clone(),
+        });
+        let (tx, rx) = oneshot::channel();
+        let overlay_request = OverlayRequest::new(
+            offer_request,
+            RequestDirection::Outgoing {
+                destination: enr.clone(),
+            },
+            Some(tx),
+            None,
+            permit.take(),
+        );
+        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+            error!(error = %err, "Error sending OFFER message to service");
+            return;
+        }
+        match rx.await {
+            Ok(Err(err)) if retry < retries => {
+                debug!(error = %err, %enr, retry = retry + 1, "Retrying failed gossip offer");
+            }
+            _ => return,
+        }
+        retry += 1;
+        tokio::time::sleep(retry_backoff(retry)).await;
+        if let Some(utp_controller) = &utp_controller {
+            permit = match utp_controller.get_outbound_semaphore() {
+                Some(permit) => Some(permit),
+                None => {
+                    trace!(
+                        "Permit for gossip retry not acquired! Skipping gossiping to enr: {enr}"
+                    );
+                    return;
+                }
+            };
+        }
+    }
+}
+
+/// Propagate a batch of gossip in a way that can be used across threads, without &self.
+/// This function is designed to be used via the JSON-RPC API. Since it waits for every peer to
+/// respond to its offer, it should not be used internally in the offer/accept flow.
+/// Returns, for each content key, the number of peers that were offered and accepted the content.
+pub async fn batch_propagate_gossip_cross_thread<
+    TContentKey: OverlayContentKey,
+    TMetric: Metric,
+>(
+    content: Vec<(TContentKey, RawContentValue)>,
+    kbuckets: &SharedKBucketsTable,
+    command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+) -> GossipBatchInfo {
+    // Every content key is present in the result, even if it wasn't offered to anyone
+    let mut gossip_batch_info: GossipBatchInfo = content
+        .iter()
+        .map(|(content_key, _)| (content_key.to_bytes(), ContentGossipInfo::default()))
+        .collect();
+
+    let enrs_and_content = select_content_per_recipient::<_, TMetric>(content, kbuckets);
+
+    // Create and send OFFER overlay request to the interested nodes, keeping track of the order
+    // in which the content keys were offered, so that the ACCEPT bits can be matched to them
+    let mut pending_offers = vec![];
+    for (enr, interested_content) in enrs_and_content {
+        let content_keys: Vec<RawContentKey> = interested_content
+            .iter()
+            .map(|(content_key, _)| content_key.clone())
+            .collect();
+        let offer_request = Request::PopulatedOffer(PopulatedOffer {
+            content_items: interested_content,
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let overlay_request = OverlayRequest::new(
+            offer_request,
+            RequestDirection::Outgoing { destination: enr },
+            Some(tx),
+            None,
+            None,
+        );
+        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+            error!(error = %err, "Error sending OFFER message to service");
+            continue;
+        }
+        pending_offers.push(async move { (content_keys, rx.await) });
+    }
+
+    for (content_keys, response) in join_all(pending_offers).await {
+        let accept = match response {
+            Ok(Ok(Response::Accept(accept))) => Some(accept),
+            _ => None,
+        };
+        for (index, content_key) in content_keys.into_iter().enumerate() {
+            let content_gossip_info = gossip_batch_info.entry(content_key).or_default();
+            content_gossip_info.offered += 1;
+            if let Some(accept) = &accept {
+                if accept.content_keys.get(index).unwrap_or(false) {
+                    content_gossip_info.accepted += 1;
+                }
+            }
+        }
+    }
+
+    gossip_batch_info
+}
+
 /// Propagate gossip in a way that can be used across threads, without &self.
 /// This function is designed to be used via the JSON-RPC API. Since it is blocking, it should not
 /// be used internally in the offer/accept flow.
 /// Returns a trace detailing the outcome of the gossip.
+/// Offers that fail are retried up to `peer_retries` times with backoff, like
+/// [propagate_gossip_cross_thread].
 pub async fn trace_propagate_gossip_cross_thread<
     TContentKey: OverlayContentKey,
     TMetric: Metric,
@@ -152,6 +264,7 @@ pub async fn trace_propagate_gossip_cross_thread<
     data: RawContentValue,
     kbuckets: &SharedKBucketsTable,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    peer_retries: u32,
 ) -> GossipResult {
     let mut gossip_result = GossipResult::default();
 
@@ -164,31 +277,45 @@ pub async fn trace_propagate_gossip_cross_thread<
     };
 
     // Select ENRs to gossip to, create and send OFFER overlay request to the interested nodes
-    for enr in select_gossip_recipients::<TMetric>(&content_id, interested_enrs) {
-        let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
-        let offer_request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
-            content_item: (content_key.clone().to_bytes(), data.clone()),
-            result_tx,
-        });
+    'recipients: for enr in select_gossip_recipients::<TMetric>(&content_id, interested_enrs) {
+        let mut retry = 0;
+        let (response, mut result_rx) = loop {
+            let (result_tx, result_rx) = tokio::sync::mpsc::unbounded_channel();
+            let offer_request = Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
+                content_item: (content_key.clone().to_bytes(), data.clone()),
+                result_tx,
+            });
 
-        let (tx, rx) = oneshot::channel();
-        let responder = Some(tx);
-        let overlay_request = OverlayRequest::new(
-            offer_request,
-            RequestDirection::Outgoing {
-                destination: enr.clone(),
-            },
-            responder,
-            None,
-            None,
-        );
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
-            error!(error = %err, "Error sending OFFER message to service");
-            continue;
-        }
+            let (tx, rx) = oneshot::channel();
+            let responder = Some(tx);
+            let overlay_request = OverlayRequest::new(
+                offer_request,
+                RequestDirection::Outgoing {
+                    destination: enr.clone(),
+                },
+                responder,
+                None,
+                None,
+            );
+            if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+                error!(error = %err, "Error sending OFFER message to service");
+                continue 'recipients;
+            }
+            let response = rx.await;
+            if retry < peer_retries && matches!(response, Ok(Err(_))) {
+                retry += 1;
+                debug!(%enr, retry, "Retrying failed trace gossip offer");
+                tokio::time::sleep(retry_backoff(retry)).await;
+                continue;
+            }
+            break (response, result_rx);
+        };
         // update gossip result with peer marked as being offered the content
         gossip_result.offered.push(enr.clone());
-        match rx.await {
+        if retry > 0 {
+            gossip_result.retries.insert(enr.to_base64(), retry);
+        }
+        match response {
             Ok(res) => {
                 if let Ok(Response::Accept(accept)) = res {
                     if !accept.content_keys.is_zero() {
@@ -211,6 +338,84 @@ pub async fn trace_propagate_gossip_cross_thread<
     gossip_result
 }
 
+/// Groups the content by the peers it should be offered to.
+///
+/// Gossip recipients are selected for each content item separately. If more than
+/// `MAX_CONTENT_KEYS_PER_OFFER` items are selected for a single peer, only the ones closest to
+/// that peer are kept.
+fn select_content_per_recipient<TContentKey: OverlayContentKey, TMetric: Metric>(
+    content: Vec<(TContentKey, RawContentValue)>,
+    kbuckets: &SharedKBucketsTable,
+) -> HashMap<Enr, Vec<(RawContentKey, RawContentValue)>> {
+    // Precalculate content ids
+    let content = content
+        .into_iter()
+        .map(|(content_key, content_value)| {
+            (content_key.content_id(), (content_key, content_value))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let content_ids = content.keys().collect::<Vec<_>>();
+    debug!(
+        ids = ?content_ids.iter().map(hex_encode_compact),
+        "propagating validated content",
+    );
+
+    // Map from content_ids to interested ENRs
+    let mut content_id_to_interested_enrs = kbuckets.batch_interested_enrs::<TMetric>(&content_ids);
+
+    // Map from ENRs to content they will gossip
+    let mut enrs_and_content: HashMap<Enr, Vec<&(TContentKey, RawContentValue)>> = HashMap::new();
+    for (content_id, content_key_value) in &content {
+        let interested_enrs = content_id_to_interested_enrs.remove(content_id).unwrap_or_else(|| {
+            error!("interested_enrs should contain all content ids, even if there are no interested ENRs");
+            vec![]
+        });
+        if interested_enrs.is_empty() {
+            debug!(
+                content.id = %hex_encode(content_id),
+                "No peers eligible for neighborhood gossip"
+            );
+            continue;
+        };
+
+        // Select gossip recipients
+        for enr in select_gossip_recipients::<TMetric>(content_id, interested_enrs) {
+            enrs_and_content
+                .entry(enr)
+                .or_default()
+                .push(content_key_value);
+        }
+    }
+
+    enrs_and_content
+        .into_iter()
+        .map(|(enr, mut interested_content)| {
+            // offer messages are limited to 64 content keys
+            if interested_content.len() > MAX_CONTENT_KEYS_PER_OFFER {
+                warn!(
+                    enr = %enr,
+                    content.len = interested_content.len(),
+                    "Too many content items to offer to a single peer, dropping {}.",
+                    interested_content.len() - MAX_CONTENT_KEYS_PER_OFFER
+                );
+                // sort content keys by distance to the node
+                interested_content.sort_by_cached_key(|(key, _)| {
+                    TMetric::distance(&key.content_id(), &enr.node_id().raw())
+                });
+                // take 64 closest content keys
+                interested_content.truncate(MAX_CONTENT_KEYS_PER_OFFER);
+            }
+            // change content keys to raw content keys
+            let interested_content = interested_content
+                .into_iter()
+                .map(|(key, value)| (key.to_bytes(), value.clone()))
+                .collect();
+            (enr, interested_content)
+        })
+        .collect()
+}
+
 const NUM_CLOSEST_NODES: usize = 4;
 const NUM_FARTHER_NODES: usize = 4;
 
@@ -249,11 +454,117 @@ fn select_gossip_recipients<TMetric: Metric>(
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
-    use ethportal_api::types::{distance::XorMetric, enr::generate_random_remote_enr};
+    use discv5::{
+        kbucket::{InsertResult, KBucketsTable, NodeStatus},
+        ConnectionDirection, ConnectionState, Key,
+    };
+    use ethportal_api::{
+        types::{
+            distance::{Distance, XorMetric},
+            enr::generate_random_remote_enr,
+            portal_wire::Accept,
+        },
+        IdentityContentKey,
+    };
     use rand::random;
     use rstest::rstest;
+    use ssz_types::BitList;
 
     use super::*;
+    use crate::types::{denylist::SharedDenylist, node::Node};
+
+    #[test]
+    fn retry_backoff_doubles_up_to_max() {
+        assert_eq!(retry_backoff(1), GOSSIP_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(2), GOSSIP_RETRY_BACKOFF * 2);
+        assert_eq!(retry_backoff(4), GOSSIP_RETRY_BACKOFF * 8);
+        assert_eq!(retry_backoff(6), GOSSIP_RETRY_BACKOFF * 32);
+        assert_eq!(retry_backoff(7), GOSSIP_MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(u32::MAX), GOSSIP_MAX_RETRY_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn batch_gossip_counts_offers_and_accepts_per_content_key() {
+        let (_, local_enr) = generate_random_remote_enr();
+        let kbuckets = SharedKBucketsTable::new(
+            KBucketsTable::new(
+                Key::from(local_enr.node_id()),
+                Duration::from_secs(1),
+                /* max_incoming_per_bucket= */ 16,
+                /* table_filter= */ None,
+                /* bucket_filter= */ None,
+            ),
+            SharedDenylist::default(),
+            /* circuit_breaker= */ None,
+        );
+        let connected = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        // Three peers interested in all content: one accepts it, one declines it and one doesn't
+        // respond.
+        let peers: Vec<Enr> = (0..3).map(|_| generate_random_remote_enr().1).collect();
+        for peer in &peers {
+            assert!(matches!(
+                kbuckets.insert_or_update(Node::new(peer.clone(), Distance::MAX), connected),
+                InsertResult::Inserted
+            ));
+        }
+
+        let content_keys = [IdentityContentKey::random(), IdentityContentKey::random()];
+        let content = content_keys
+            .iter()
+            .map(|content_key| (content_key.clone(), RawContentValue::from([1, 2, 3])))
+            .collect();
+
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let accepting_peer = peers[0].clone();
+        let declining_peer = peers[1].clone();
+        let responder = tokio::spawn(async move {
+            while let Some(command) = command_rx.recv().await {
+                let OverlayCommand::Request(request) = command else {
+                    panic!("Expected an OFFER request");
+                };
+                let (Request::PopulatedOffer(offer), RequestDirection::Outgoing { destination }) =
+                    (request.request, request.direction)
+                else {
+                    panic!("Expected an outgoing OFFER request");
+                };
+                let accept = if destination == accepting_peer {
+                    true
+                } else if destination == declining_peer {
+                    false
+                } else {
+                    continue;
+                };
+                let mut content_keys = BitList::with_capacity(offer.content_items.len()).unwrap();
+                for index in 0..offer.content_items.len() {
+                    content_keys.set(index, accept).unwrap();
+                }
+                let response = Response::Accept(Accept {
+                    connection_id: 0,
+                    content_keys,
+                });
+                let _ = request.responder.unwrap().send(Ok(response));
+            }
+        });
+
+        let gossip_batch_info =
+            batch_propagate_gossip_cross_thread::<_, XorMetric>(content, &kbuckets, command_tx)
+                .await;
+        responder.await.unwrap();
+
+        assert_eq!(gossip_batch_info.len(), 2);
+        for content_key in content_keys {
+            assert_eq!(
+                gossip_batch_info[&content_key.to_bytes()],
+                ContentGossipInfo {
+                    offered: 3,
+                    accepted: 1,
+                }
+            );
+        }
+    }
 
     #[allow(clippy::zero_repeat_side_effects)]
     #[rstest]
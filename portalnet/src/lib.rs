@@ -2,12 +2,15 @@
 #![warn(clippy::uninlined_format_args)]
 
 pub mod accept_queue;
+pub mod compression;
 pub mod config;
 pub mod constants;
 pub mod discovery;
+pub mod dns;
 pub mod events;
 pub mod find;
 pub mod gossip;
+pub mod mirror;
 pub mod overlay;
 pub mod socket;
 pub mod types;
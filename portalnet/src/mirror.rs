@@ -0,0 +1,323 @@
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use ethportal_api::{
+    types::{
+        enr::Enr,
+        network::Subnetwork,
+        portal_wire::{OfferTrace, PopulatedOfferWithResult, Request},
+    },
+    utils::bytes::hex_encode_compact,
+    RawContentKey, RawContentValue,
+};
+use futures::channel::oneshot;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::overlay::{
+    command::OverlayCommand,
+    request::{OverlayRequest, RequestDirection},
+};
+
+/// The max number of stored content items waiting to be offered to the mirror peer. Once it's
+/// reached, the oldest waiting content is dropped.
+pub const MIRROR_QUEUE_CAPACITY: usize = 10_000;
+
+/// The delay before the first retry of an offer that failed, e.g. because the mirror peer was
+/// unreachable. It doubles after every consecutive failure, up to `MIRROR_MAX_RETRY_BACKOFF`.
+const MIRROR_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The max delay between retries of an offer to the mirror peer.
+const MIRROR_MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Offers every newly stored content to a mirror peer, to keep it in sync with the local node,
+/// e.g. as a hot standby.
+///
+/// Content is offered one item at a time, in the order it was stored. Content whose offer fails
+/// is retried with an exponential backoff, until the mirror peer accepts or declines it.
+#[derive(Clone, Debug)]
+pub struct Mirror {
+    stored_tx: mpsc::UnboundedSender<(RawContentKey, RawContentValue)>,
+}
+
+impl Mirror {
+    /// Spawns the task that offers stored content to the `peer`, through the overlay service of
+    /// the `command_tx`.
+    pub fn spawn<TContentKey: Send + 'static>(
+        peer: Enr,
+        command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+        protocol: Subnetwork,
+    ) -> Self {
+        let (stored_tx, stored_rx) = mpsc::unbounded_channel();
+        info!(%protocol, peer = %peer.node_id(), "Mirroring stored content");
+        tokio::spawn(mirror_stored_content(peer, command_tx, stored_rx, protocol));
+        Self { stored_tx }
+    }
+
+    /// Notifies the mirror that the content was stored, so that it's offered to the mirror peer.
+    pub fn notify_stored(&self, content_key: RawContentKey, content_value: RawContentValue) {
+        // The task only stops once the overlay service is gone, so the content has nowhere to go.
+        let _ = self.stored_tx.send((content_key, content_value));
+    }
+}
+
+/// The stored content that wasn't offered to the mirror peer yet, oldest first.
+#[derive(Debug, Default)]
+struct MirrorQueue {
+    content: VecDeque<(RawContentKey, RawContentValue)>,
+}
+
+impl MirrorQueue {
+    /// Queues the content, and returns the key of the oldest content if it was dropped to make
+    /// room for it.
+    fn push(
+        &mut self,
+        content_key: RawContentKey,
+        content_value: RawContentValue,
+    ) -> Option<RawContentKey> {
+        let dropped = if self.content.len() >= MIRROR_QUEUE_CAPACITY {
+            self.content.pop_front().map(|(content_key, _)| content_key)
+        } else {
+            None
+        };
+        self.content.push_back((content_key, content_value));
+        dropped
+    }
+
+    /// Queues the content ahead of all other content, unless the queue is full, in which case
+    /// the content is the oldest and returns its key.
+    fn push_front(
+        &mut self,
+        content_key: RawContentKey,
+        content_value: RawContentValue,
+    ) -> Option<RawContentKey> {
+        if self.content.len() >= MIRROR_QUEUE_CAPACITY {
+            return Some(content_key);
+        }
+        self.content.push_front((content_key, content_value));
+        None
+    }
+}
+
+/// Offers the content received on `stored_rx` to the `peer`, until the overlay service is gone.
+///
+/// Stored content is queued as it's received, also while an offer is in flight or waiting to be
+/// retried, so that it's the queue that bounds the content waiting to be offered.
+async fn mirror_stored_content<TContentKey>(
+    peer: Enr,
+    command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    mut stored_rx: mpsc::UnboundedReceiver<(RawContentKey, RawContentValue)>,
+    protocol: Subnetwork,
+) {
+    let mut queue = MirrorQueue::default();
+    let mut backoff = MIRROR_RETRY_BACKOFF;
+    loop {
+        let Some((content_key, content_value)) = queue.content.pop_front() else {
+            let Some((content_key, content_value)) = stored_rx.recv().await else {
+                return;
+            };
+            queue.push(content_key, content_value);
+            continue;
+        };
+        let attempt = offer(
+            &peer,
+            &command_tx,
+            content_key.clone(),
+            content_value.clone(),
+        );
+        let Some(Some(trace)) =
+            queue_stored_while(attempt, &mut queue, &mut stored_rx, protocol).await
+        else {
+            return;
+        };
+        match trace {
+            OfferTrace::Success(_) => backoff = MIRROR_RETRY_BACKOFF,
+            OfferTrace::Declined => {
+                debug!(
+                    %protocol,
+                    content.key = %hex_encode_compact(&content_key),
+                    "Mirror peer declined stored content"
+                );
+                backoff = MIRROR_RETRY_BACKOFF;
+            }
+            OfferTrace::Failed => {
+                warn!(
+                    %protocol,
+                    peer = %peer.node_id(),
+                    content.key = %hex_encode_compact(&content_key),
+                    queued = queue.content.len(),
+                    retry_in = ?backoff,
+                    "Failed to offer stored content to mirror peer"
+                );
+                // The content is retried before any other content, unless the queue filled up in
+                // the meantime, in which case it's the oldest content and dropped.
+                if let Some(dropped) = queue.push_front(content_key, content_value) {
+                    log_dropped(&dropped, protocol);
+                }
+                let sleep = tokio::time::sleep(backoff);
+                if queue_stored_while(sleep, &mut queue, &mut stored_rx, protocol)
+                    .await
+                    .is_none()
+                {
+                    return;
+                }
+                backoff = (backoff * 2).min(MIRROR_MAX_RETRY_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Queues the content received on `stored_rx` until the `future` completes, and returns its
+/// output.
+///
+/// It returns `None` if the overlay service is gone.
+async fn queue_stored_while<T>(
+    future: impl Future<Output = T>,
+    queue: &mut MirrorQueue,
+    stored_rx: &mut mpsc::UnboundedReceiver<(RawContentKey, RawContentValue)>,
+    protocol: Subnetwork,
+) -> Option<T> {
+    tokio::pin!(future);
+    loop {
+        tokio::select! {
+            output = &mut future => return Some(output),
+            stored = stored_rx.recv() => {
+                let (content_key, content_value) = stored?;
+                if let Some(dropped) = queue.push(content_key, content_value) {
+                    log_dropped(&dropped, protocol);
+                }
+            }
+        }
+    }
+}
+
+fn log_dropped(content_key: &RawContentKey, protocol: Subnetwork) {
+    warn!(
+        %protocol,
+        content.key = %hex_encode_compact(content_key),
+        "Mirror queue is full, dropping the oldest content"
+    );
+}
+
+/// Offers the content to the `peer`, and returns the result once the offer is complete.
+///
+/// It returns `None` if the overlay service is gone.
+async fn offer<TContentKey>(
+    peer: &Enr,
+    command_tx: &mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    content_key: RawContentKey,
+    content_value: RawContentValue,
+) -> Option<OfferTrace> {
+    let (result_tx, mut result_rx) = mpsc::unbounded_channel();
+    let (response_tx, response_rx) = oneshot::channel();
+    let request = OverlayRequest::new(
+        Request::PopulatedOfferWithResult(PopulatedOfferWithResult {
+            content_item: (content_key, content_value),
+            result_tx,
+        }),
+        RequestDirection::Outgoing {
+            destination: peer.clone(),
+        },
+        Some(response_tx),
+        None,
+        None,
+    );
+    command_tx.send(OverlayCommand::Request(request)).ok()?;
+
+    // The result isn't sent if the request itself failed, e.g. because the peer didn't respond.
+    if !matches!(response_rx.await, Ok(Ok(_))) {
+        return Some(OfferTrace::Failed);
+    }
+    Some(result_rx.recv().await.unwrap_or(OfferTrace::Failed))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ethportal_api::types::{
+        content_key::overlay::IdentityContentKey,
+        enr::generate_random_remote_enr,
+        portal_wire::{Accept, Response},
+    };
+    use ssz_types::BitList;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::overlay::errors::OverlayRequestError;
+
+    #[test]
+    fn mirror_queue_is_bounded() {
+        let mut queue = MirrorQueue::default();
+        for i in 0..MIRROR_QUEUE_CAPACITY as u32 {
+            let content_key = RawContentKey::from(i.to_be_bytes().to_vec());
+            assert!(queue
+                .push(content_key, RawContentValue::default())
+                .is_none());
+        }
+        let dropped = queue.push(RawContentKey::from(vec![]), RawContentValue::default());
+        assert_eq!(
+            dropped,
+            Some(RawContentKey::from(0u32.to_be_bytes().to_vec()))
+        );
+        assert_eq!(queue.content.len(), MIRROR_QUEUE_CAPACITY);
+
+        // Content that's retried once the queue is full is the oldest, and dropped.
+        let retried = RawContentKey::from(vec![1]);
+        assert_eq!(
+            queue.push_front(retried.clone(), RawContentValue::default()),
+            Some(retried)
+        );
+        assert_eq!(queue.content.len(), MIRROR_QUEUE_CAPACITY);
+    }
+
+    #[tokio::test]
+    async fn failed_offers_are_retried() {
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+        let (_, peer) = generate_random_remote_enr();
+        let mirror =
+            Mirror::spawn::<IdentityContentKey>(peer.clone(), command_tx, Subnetwork::History);
+
+        let content_key = RawContentKey::from(vec![1]);
+        let content_value = RawContentValue::from(vec![2]);
+        mirror.notify_stored(content_key.clone(), content_value.clone());
+
+        // The mirror peer is unreachable the first time, and accepts the content the second time.
+        for reachable in [false, true] {
+            let command = timeout(MIRROR_RETRY_BACKOFF * 2, command_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            let OverlayCommand::Request(request) = command else {
+                panic!("Unexpected overlay command variant");
+            };
+            assert_eq!(
+                request.direction,
+                RequestDirection::Outgoing {
+                    destination: peer.clone()
+                }
+            );
+            let Request::PopulatedOfferWithResult(offer) = request.request else {
+                panic!("Unexpected request variant");
+            };
+            assert_eq!(
+                offer.content_item,
+                (content_key.clone(), content_value.clone())
+            );
+            let responder = request.responder.unwrap();
+            if reachable {
+                let _ = offer.result_tx.send(OfferTrace::Declined);
+                let accept = Accept {
+                    connection_id: 0,
+                    content_keys: BitList::with_capacity(1).unwrap(),
+                };
+                let _ = responder.send(Ok(Response::Accept(accept)));
+            } else {
+                let _ = responder.send(Err(OverlayRequestError::Timeout));
+            }
+        }
+
+        // Once the offer completed, the content isn't offered again.
+        assert!(timeout(MIRROR_RETRY_BACKOFF * 2, command_rx.recv())
+            .await
+            .is_err());
+    }
+}
@@ -32,9 +32,15 @@ pub enum OverlayCommand<TContentKey> {
     FindNodeQuery {
         /// The query target.
         target: NodeId,
+        /// The max number of nodes to return, closest to the target first.
+        num_results: usize,
         /// A callback channel to transmit the result of the query.
         callback: oneshot::Sender<Vec<Enr>>,
     },
+    /// Inserts the nodes that aren't in the routing table yet, like the bootnodes on startup.
+    AddBootnodes(Vec<Enr>),
+    /// Pings all connected peers in the routing table, to advertise a changed radius.
+    PingConnectedPeers,
     /// Sets up an event stream where the overlay server will return various events.
     RequestEventStream(oneshot::Sender<broadcast::Receiver<EventEnvelope>>),
     /// Handle an event sent from another overlay.
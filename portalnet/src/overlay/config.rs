@@ -3,9 +3,25 @@
 use std::time::Duration;
 
 use discv5::kbucket::{Filter, MAX_NODES_PER_BUCKET};
-use ethportal_api::types::{cli::DEFAULT_UTP_TRANSFER_LIMIT, enr::Enr};
+use ethportal_api::types::{
+    cli::{
+        DEFAULT_FINDCONTENT_TIMEOUT_MS, DEFAULT_FINDNODES_TIMEOUT_MS, DEFAULT_LOOKUP_MAX_HOPS,
+        DEFAULT_LOOKUP_PARALLELISM, DEFAULT_MAX_FIND_CONTENT_SIZE, DEFAULT_PING_TIMEOUT_MS,
+        DEFAULT_QUERY_TRACE_BUFFER_SIZE, DEFAULT_UTP_TRANSFER_LIMIT,
+    },
+    enr::Enr,
+    query_trace::QueryHopEvent,
+};
+use tokio::sync::mpsc::UnboundedSender;
 
-use crate::{constants::DEFAULT_QUERY_TIMEOUT, types::node::Node};
+use crate::{
+    constants::DEFAULT_QUERY_TIMEOUT,
+    types::{
+        circuit_breaker::CircuitBreakerConfig, denylist::SharedDenylist,
+        gossip_history::GossipDecayConfig, node::Node, offer_log::SharedOfferLog,
+        serve_bandwidth::SharedServeBandwidthLimiter,
+    },
+};
 
 /// Configuration parameters for the overlay network.
 #[derive(Clone)]
@@ -15,15 +31,85 @@ pub struct OverlayConfig {
     pub max_incoming_per_bucket: usize,
     pub table_filter: Option<Box<dyn Filter<Node>>>,
     pub bucket_filter: Option<Box<dyn Filter<Node>>>,
+    /// The max number of peers in the routing table whose advertised addresses share an IP
+    /// subnet. It's used as the table filter, unless another one is set. Not limited if not set.
+    pub max_peers_per_subnet: Option<usize>,
     pub ping_queue_interval: Option<Duration>,
     pub query_parallelism: usize,
+    /// The max number of hops of a content query, before it gives up.
+    pub query_max_hops: usize,
     pub query_timeout: Duration,
     pub query_peer_timeout: Duration,
     pub query_num_results: usize,
     pub findnodes_query_distances_per_peer: usize,
     pub disable_poke: bool,
     pub gossip_dropped: bool,
+    /// Propagate content that was stored without validation to other peers. Otherwise, only
+    /// validated content is propagated.
+    pub gossip_unvalidated: bool,
     pub utp_transfer_limit: usize,
+    /// The target delay of uTP congestion control. The uTP library default is used if not set.
+    pub utp_target_delay: Option<Duration>,
+    /// The max number of concurrent uTP transfers requested by a single peer. Unlimited if not
+    /// set.
+    pub utp_peer_transfer_limit: Option<usize>,
+    /// Limits the bandwidth used to serve content over uTP, shared between all overlay networks.
+    /// Unlimited if not set.
+    pub serve_bandwidth_limiter: Option<SharedServeBandwidthLimiter>,
+    /// Whether content served over uTP is compressed for peers that advertise support for it,
+    /// and compressed content received from them is decompressed.
+    pub enable_compression: bool,
+    /// The max size of content received over uTP in response to a FindContent request.
+    pub max_find_content_size: usize,
+    /// The min number of connected peers in the routing table before content lookups are served.
+    pub min_lookup_peers: usize,
+    /// The number of times a content lookup that doesn't find the content is run again.
+    pub lookup_retries: u32,
+    /// Offer locally stored content to newly discovered peers whose radius contains it.
+    pub gossip_on_new_peer: bool,
+    /// Decline to serve content to peers whose known radius doesn't contain it, responding with
+    /// closer peers instead.
+    pub serve_only_in_requester_radius: bool,
+    /// Node ids and content ids that the overlay refuses to interact with.
+    pub denylist: SharedDenylist,
+    /// Records every received offer, if set.
+    pub offer_log: Option<SharedOfferLog>,
+    /// The node that every newly stored content is offered to, to keep it in sync. Content isn't
+    /// mirrored if not set.
+    pub mirror_peer: Option<Enr>,
+    /// The delay before bonding with bootnodes and discovering peers.
+    pub join_delay: Duration,
+    /// The number of most recent content lookup traces that are retained.
+    pub query_trace_buffer_size: usize,
+    /// The interval at which expired content is deleted from the store. Content is never deleted
+    /// for being expired if not set.
+    pub content_ttl_sweep_interval: Option<Duration>,
+    /// Decays the re-gossip of the same content, if set. Content is gossiped every time it's
+    /// stored otherwise.
+    pub gossip_decay: Option<GossipDecayConfig>,
+    /// The window within which the same content arriving again through gossip is dropped as a
+    /// duplicate, instead of being stored and propagated again. Duplicates aren't detected if not
+    /// set.
+    pub gossip_dedup_window: Option<Duration>,
+    /// Accept offered content that is already stored, instead of declining it.
+    pub accept_duplicate_content: bool,
+    /// The number of times an offer of gossiped content to a peer is retried with backoff if it
+    /// fails. Peers that responded to the offer aren't offered the content again.
+    pub gossip_peer_retries: u32,
+    /// The time for which peers that accepted offered content are remembered as its likely
+    /// holders, and listed first in responses to FindContent requests for it. Peers are only
+    /// listed by distance if not set.
+    pub content_holders_ttl: Option<Duration>,
+    /// Temporarily stops selecting peers that consistently fail requests for lookups and offers,
+    /// if set.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// The time after which a Ping request sent with `send_ping` is abandoned.
+    pub ping_timeout: Duration,
+    /// The time after which a FindNodes request sent with `send_find_nodes` is abandoned.
+    pub findnodes_timeout: Duration,
+    /// The time after which a FindContent request sent with `send_find_content` is abandoned, if
+    /// the peer hasn't responded. A uTP transfer of the content isn't limited by it.
+    pub findcontent_timeout: Duration,
 }
 
 impl Default for OverlayConfig {
@@ -34,21 +120,48 @@ impl Default for OverlayConfig {
             max_incoming_per_bucket: 16,
             table_filter: None,
             bucket_filter: None,
+            max_peers_per_subnet: None,
             ping_queue_interval: None,
-            query_parallelism: 3, // (recommended α from kademlia paper)
+            query_parallelism: DEFAULT_LOOKUP_PARALLELISM,
+            query_max_hops: DEFAULT_LOOKUP_MAX_HOPS,
             query_peer_timeout: Duration::from_secs(2),
             query_timeout: DEFAULT_QUERY_TIMEOUT,
             query_num_results: MAX_NODES_PER_BUCKET,
             findnodes_query_distances_per_peer: 3,
             disable_poke: false,
             gossip_dropped: false,
+            gossip_unvalidated: false,
             utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT,
+            utp_target_delay: None,
+            utp_peer_transfer_limit: None,
+            serve_bandwidth_limiter: None,
+            enable_compression: false,
+            max_find_content_size: DEFAULT_MAX_FIND_CONTENT_SIZE,
+            min_lookup_peers: 0,
+            lookup_retries: 0,
+            gossip_on_new_peer: false,
+            serve_only_in_requester_radius: false,
+            denylist: SharedDenylist::default(),
+            offer_log: None,
+            mirror_peer: None,
+            join_delay: Duration::ZERO,
+            query_trace_buffer_size: DEFAULT_QUERY_TRACE_BUFFER_SIZE,
+            content_ttl_sweep_interval: None,
+            gossip_decay: None,
+            gossip_dedup_window: None,
+            accept_duplicate_content: false,
+            gossip_peer_retries: 0,
+            content_holders_ttl: None,
+            circuit_breaker: None,
+            ping_timeout: Duration::from_millis(DEFAULT_PING_TIMEOUT_MS),
+            findnodes_timeout: Duration::from_millis(DEFAULT_FINDNODES_TIMEOUT_MS),
+            findcontent_timeout: Duration::from_millis(DEFAULT_FINDCONTENT_TIMEOUT_MS),
         }
     }
 }
 
 /// A configuration used by a single query to find content.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct FindContentConfig {
     /// Whether to trace the actions of each peer during the query
     ///
@@ -59,4 +172,15 @@ pub struct FindContentConfig {
     ///
     /// If set to None, the default timeout is used.
     pub timeout: Option<Duration>,
+
+    /// A peer that is likely to hold the content.
+    ///
+    /// If set, it is contacted before any other peer, and the query proceeds normally only if it
+    /// doesn't return the content.
+    pub hint_peer: Option<Enr>,
+
+    /// A channel on which the response of each contacted peer is sent as it arrives.
+    ///
+    /// This allows following the progress of the query live, rather than waiting for its trace.
+    pub hop_events: Option<UnboundedSender<QueryHopEvent>>,
 }
@@ -56,6 +56,14 @@ pub enum OverlayRequestError {
     #[error("Received invalid remote discv5 packet")]
     InvalidRemoteDiscv5Packet,
 
+    /// The request was sent by a node on the denylist.
+    #[error("The requesting node is on the denylist")]
+    NodeDenied,
+
+    /// The routing table doesn't contain enough peers to serve content lookups yet.
+    #[error("Node not ready: only {peers} peers known, at least {min_peers} required")]
+    NotReady { peers: usize, min_peers: usize },
+
     #[error("Content wasn't found on the network: {message}")]
     ContentNotFound {
         message: String,
@@ -1,30 +1,45 @@
 #![allow(clippy::result_large_err)]
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    fmt::Display,
     future::Future,
     marker::{PhantomData, Sync},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
 use bytes::Bytes;
 use discv5::{
     enr::NodeId,
-    kbucket::{FailureReason, InsertResult, KBucketsTable, NodeStatus},
+    kbucket::{FailureReason, Filter, InsertResult, KBucketsTable, NodeStatus},
     ConnectionDirection, ConnectionState, TalkRequest,
 };
 use ethportal_api::{
     types::{
         bootnodes::Bootnode,
-        discv5::RoutingTableInfo,
-        distance::{Distance, Metric},
+        discv5::{
+            NeighborhoodInfo, RefreshInfo, RoutingTableInfo, SubnetDistributionInfo,
+            SubnetPeersInfo,
+        },
+        distance::{Distance, Metric, XorMetric},
         enr::Enr,
         network::Subnetwork,
+        portal::{
+            CircuitBreakerInfo, CrawledPeerInfo, DebugPingInfo, GossipBatchInfo, LastErrorInfo,
+            NetworkCrawlInfo, NetworkCrawlProgressInfo, NetworkCrawlStopReason, PeerTransfersInfo,
+            PongInfo, ReadinessInfo, ServedContentInfo, SimulatedHopInfo,
+        },
         portal_wire::{
-            Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, OfferTrace,
-            Ping, Pong, PopulatedOffer, PopulatedOfferWithResult, Request, Response,
+            Accept, Content, CustomPayload, FindContent, FindNodes, Message, OfferTrace,
+            OfferTransfer, Ping, Pong, PopulatedOffer, PopulatedOfferWithResult,
+            PopulatedOfferWithTransfer, Request, Response,
         },
+        query_trace::{QueryTraceSummary, RecordedQueryTrace},
     },
     utils::bytes::hex_encode,
     OverlayContentKey, RawContentKey, RawContentValue,
@@ -34,30 +49,73 @@ use parking_lot::RwLock;
 use ssz::Encode;
 use tokio::sync::{broadcast, mpsc::UnboundedSender};
 use tracing::{debug, error, info, warn};
-use trin_metrics::{overlay::OverlayMetricsReporter, portalnet::PORTALNET_METRICS};
-use trin_storage::ContentStore;
+use trin_metrics::{
+    labels::RoutingTableChangeLabel, overlay::OverlayMetricsReporter, portalnet::PORTALNET_METRICS,
+};
+use trin_storage::{error::ContentStoreError, ContentStore};
 use trin_validation::validator::{ValidationResult, Validator};
 use utp_rs::socket::UtpSocket;
 
 use crate::{
-    discovery::{Discovery, UtpEnr},
+    discovery::{Discovery, UtpEnr, ENR_PORTAL_CLIENT_KEY},
     events::EventEnvelope,
-    find::query_info::{FindContentResult, RecursiveFindContentResult},
-    gossip::{propagate_gossip_cross_thread, trace_propagate_gossip_cross_thread, GossipResult},
+    find::{
+        iterators::query::QueryConfig,
+        query_info::{FindContentResult, RecursiveFindContentResult},
+        simulation::simulate_lookup,
+    },
+    gossip::{
+        batch_propagate_gossip_cross_thread, propagate_gossip_cross_thread,
+        trace_propagate_gossip_cross_thread, GossipResult,
+    },
+    mirror::Mirror,
     overlay::{
         command::OverlayCommand,
         config::{FindContentConfig, OverlayConfig},
         errors::OverlayRequestError,
         request::{OverlayRequest, RequestDirection},
-        service::OverlayService,
+        service::{put_content, OverlayService},
     },
     types::{
+        circuit_breaker::{CircuitBreaker, SharedCircuitBreaker},
+        content_holders::ContentHolders,
+        denylist::SharedDenylist,
+        gossip_history::GossipHistory,
         kbucket::{Entry, SharedKBucketsTable},
+        last_errors::{ErrorSubsystem, LastErrors, SharedLastErrors},
         node::Node,
+        query_trace_buffer::{QueryTraceBuffer, SharedQueryTraceBuffer},
+        readiness::ReadinessConfig,
+        seen_content::SeenContent,
+        served_content::{ServedContent, SharedServedContent},
+        store_health::{SharedStoreHealth, StoreHealth},
+        subnet_filter::SubnetFilter,
     },
-    utp_controller::UtpController,
+    utp_controller::{utp_conn_config, UtpController},
 };
 
+/// The time to wait after an offered content is transferred, before asking the peer for it to
+/// confirm that it was stored. This gives the peer time to validate and store the content.
+const OFFER_CONFIRMATION_DELAY: Duration = Duration::from_secs(1);
+
+/// The time to wait before the first retry of a content lookup that didn't find the content. The
+/// wait doubles with every further retry.
+const LOOKUP_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The number of consecutive lookups of a network crawl that discover no new peers, after which
+/// the crawl stops, since most of the reachable network was likely discovered.
+const MAX_UNPRODUCTIVE_CRAWL_LOOKUPS: usize = 16;
+
+/// The response of a peer to a FindNodes request.
+#[derive(Debug)]
+pub struct FindNodesResponse {
+    /// The returned ENRs that are at one of the requested distances from the peer.
+    pub enrs: Vec<Enr>,
+    /// The number of returned ENRs that weren't at any of the requested distances, and were
+    /// filtered out.
+    pub filtered: usize,
+}
+
 /// Overlay protocol is a layer on top of discv5 that handles all requests from the overlay networks
 /// (state, history etc.) and dispatch them to the discv5 protocol TalkReq. Each network should
 /// implement the overlay protocol and the overlay protocol is where we can encapsulate the logic
@@ -76,6 +134,16 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     pub command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
     /// uTP controller.
     utp_controller: Arc<UtpController>,
+    /// The max size of content received over uTP in response to a FindContent request.
+    max_find_content_size: usize,
+    /// The min number of connected peers in the routing table before content lookups are served.
+    min_lookup_peers: usize,
+    /// The number of times a content lookup that doesn't find the content is run again.
+    lookup_retries: u32,
+    /// The number of times a failed offer of gossiped content to a peer is retried.
+    gossip_peer_retries: u32,
+    /// Whether content dropped from the store is gossiped before it's deleted.
+    gossip_dropped: bool,
     /// Declare the allowed content key types for a given overlay network.
     /// Use a phantom, because we don't store any keys in this struct.
     /// For example, this type is used when decoding a content key received over the network.
@@ -86,6 +154,34 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     validator: Arc<TValidator>,
     /// Runtime telemetry metrics for the overlay network.
     metrics: OverlayMetricsReporter,
+    /// Node ids and content ids that the overlay refuses to interact with.
+    denylist: SharedDenylist,
+    /// Whether the data store is usable.
+    store_health: SharedStoreHealth,
+    /// The last error encountered by each subsystem.
+    last_errors: SharedLastErrors,
+    /// The delay before bonding with bootnodes.
+    join_delay: Duration,
+    /// The traces of the most recent content lookups.
+    query_traces: SharedQueryTraceBuffer,
+    /// The configuration of content lookups, used to simulate lookups.
+    query_config: QueryConfig,
+    /// The circuits of peers that failed requests, if enabled.
+    circuit_breaker: Option<SharedCircuitBreaker>,
+    /// The number of times each content was recently served to peers.
+    served_content: SharedServedContent,
+    /// Offers stored content to the mirror peer, if set.
+    mirror: Option<Mirror>,
+    /// The max number of peers in the routing table that share an IP subnet, if limited.
+    max_peers_per_subnet: Option<usize>,
+    /// The number of content lookups that found the content since the node started.
+    successful_lookups: Arc<AtomicU64>,
+    /// The time after which a Ping request is abandoned.
+    ping_timeout: Duration,
+    /// The time after which a FindNodes request is abandoned.
+    findnodes_timeout: Duration,
+    /// The time after which a FindContent request is abandoned, if the peer hasn't responded.
+    findcontent_timeout: Duration,
 }
 
 impl<
@@ -104,13 +200,39 @@ impl<
         validator: Arc<TValidator>,
     ) -> Self {
         let local_node_id = discovery.local_enr().node_id();
-        let kbuckets = SharedKBucketsTable::new(KBucketsTable::new(
-            local_node_id.into(),
-            config.bucket_pending_timeout,
-            config.max_incoming_per_bucket,
-            config.table_filter,
-            config.bucket_filter,
-        ));
+        let circuit_breaker = config.circuit_breaker.map(CircuitBreaker::shared);
+        let table_filter = config.table_filter.or_else(|| {
+            config
+                .max_peers_per_subnet
+                .map(|max_peers| Box::new(SubnetFilter::new(max_peers)) as Box<dyn Filter<Node>>)
+        });
+        let kbuckets = SharedKBucketsTable::new(
+            KBucketsTable::new(
+                local_node_id.into(),
+                config.bucket_pending_timeout,
+                config.max_incoming_per_bucket,
+                table_filter,
+                config.bucket_filter,
+            ),
+            Arc::clone(&config.denylist),
+            circuit_breaker.clone(),
+        );
+        discovery.register_overlay_table(protocol, kbuckets.clone(), config.min_lookup_peers);
+        let last_errors = LastErrors::shared();
+        let store_health = Arc::new(StoreHealth::new(protocol, Arc::clone(&last_errors)));
+        let query_traces = QueryTraceBuffer::shared(config.query_trace_buffer_size);
+        let gossip_history = config.gossip_decay.map(GossipHistory::shared);
+        let content_holders = config.content_holders_ttl.map(ContentHolders::shared);
+        let seen_content = config.gossip_dedup_window.map(SeenContent::shared);
+        let served_content = ServedContent::shared();
+        let query_config = QueryConfig {
+            parallelism: config.query_parallelism,
+            num_results: config.query_num_results,
+            max_hops: config.query_max_hops,
+            peer_timeout: config.query_peer_timeout,
+            overall_timeout: config.query_timeout,
+        };
+        discovery.register_store_health(protocol, Arc::clone(&store_health));
         // Initialize metrics, keep a reference in order to build metrics summaries for logging
         let metrics = OverlayMetricsReporter {
             overlay_metrics: PORTALNET_METRICS.overlay(),
@@ -118,28 +240,54 @@ impl<
         };
         let utp_controller = Arc::new(UtpController::new(
             config.utp_transfer_limit,
+            config.utp_peer_transfer_limit,
+            config.serve_bandwidth_limiter,
             utp_socket,
             metrics.clone(),
+            utp_conn_config(config.utp_target_delay),
+            config.enable_compression,
+            Arc::clone(&last_errors),
+            discovery.transfer_sizes(),
         ));
-        let command_tx = OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
-            Arc::clone(&discovery),
-            Arc::clone(&store),
-            kbuckets.clone(),
-            config.bootnode_enrs,
-            config.ping_queue_interval,
-            protocol,
-            Arc::clone(&utp_controller),
-            metrics.clone(),
-            Arc::clone(&validator),
-            config.query_timeout,
-            config.query_peer_timeout,
-            config.query_parallelism,
-            config.query_num_results,
-            config.findnodes_query_distances_per_peer,
-            config.disable_poke,
-            config.gossip_dropped,
-        )
-        .await;
+        let (command_tx, mirror) =
+            OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
+                Arc::clone(&discovery),
+                Arc::clone(&store),
+                kbuckets.clone(),
+                config.bootnode_enrs,
+                config.ping_queue_interval,
+                protocol,
+                Arc::clone(&utp_controller),
+                metrics.clone(),
+                Arc::clone(&validator),
+                config.query_timeout,
+                config.query_peer_timeout,
+                config.query_parallelism,
+                config.query_max_hops,
+                config.query_num_results,
+                config.findnodes_query_distances_per_peer,
+                config.disable_poke,
+                config.gossip_dropped,
+                config.gossip_unvalidated,
+                config.gossip_peer_retries,
+                config.gossip_on_new_peer,
+                config.serve_only_in_requester_radius,
+                config.accept_duplicate_content,
+                Arc::clone(&config.denylist),
+                config.offer_log,
+                Arc::clone(&store_health),
+                Arc::clone(&last_errors),
+                config.join_delay,
+                Arc::clone(&query_traces),
+                config.content_ttl_sweep_interval,
+                gossip_history,
+                content_holders,
+                circuit_breaker.clone(),
+                Arc::clone(&served_content),
+                config.mirror_peer,
+                seen_content,
+            )
+            .await;
 
         Self {
             discovery,
@@ -148,10 +296,29 @@ impl<
             protocol,
             command_tx,
             utp_controller,
+            max_find_content_size: config.max_find_content_size,
+            min_lookup_peers: config.min_lookup_peers,
+            lookup_retries: config.lookup_retries,
+            gossip_peer_retries: config.gossip_peer_retries,
+            gossip_dropped: config.gossip_dropped,
             _phantom_content_key: PhantomData,
             _phantom_metric: PhantomData,
             validator,
             metrics,
+            denylist: config.denylist,
+            store_health,
+            last_errors,
+            join_delay: config.join_delay,
+            query_traces,
+            query_config,
+            circuit_breaker,
+            served_content,
+            mirror,
+            max_peers_per_subnet: config.max_peers_per_subnet,
+            successful_lookups: Arc::new(AtomicU64::new(0)),
+            ping_timeout: config.ping_timeout,
+            findnodes_timeout: config.findnodes_timeout,
+            findcontent_timeout: config.findcontent_timeout,
         }
     }
 
@@ -165,6 +332,108 @@ impl<
         self.discovery.local_enr()
     }
 
+    /// Returns `true` if the content is on the denylist, and shouldn't be stored or served.
+    pub fn is_content_denied(&self, content_id: &[u8; 32]) -> bool {
+        self.denylist.read().is_content_denied(content_id)
+    }
+
+    /// Returns `true` if content dropped from the store is gossiped, so that it isn't lost from the
+    /// network.
+    pub fn gossips_dropped(&self) -> bool {
+        self.gossip_dropped
+    }
+
+    /// Returns `true` if the data store is unavailable, in which case no content should be
+    /// stored.
+    pub fn is_store_degraded(&self) -> bool {
+        self.store_health.is_degraded()
+    }
+
+    /// Stores the content, and offers it to the mirror peer, if any. Returns the content that was
+    /// pruned to make room for it.
+    pub fn put_content(
+        &self,
+        content_key: TContentKey,
+        content_value: RawContentValue,
+    ) -> Result<Vec<(TContentKey, RawContentValue)>, ContentStoreError> {
+        put_content(
+            &self.store,
+            self.mirror.as_ref(),
+            content_key,
+            content_value,
+        )
+    }
+
+    /// Records the error as the last error encountered by the subsystem.
+    pub fn record_error(&self, subsystem: ErrorSubsystem, error: impl Display) {
+        self.last_errors.record(subsystem, error);
+    }
+
+    /// Returns the last error encountered by each subsystem that encountered one.
+    pub fn last_errors(&self) -> Vec<LastErrorInfo> {
+        self.last_errors.snapshot()
+    }
+
+    /// Returns the retained trace of a content lookup, if it's still in the trace buffer.
+    pub fn query_trace(&self, id: u64) -> Option<RecordedQueryTrace> {
+        self.query_traces.lock().get(id)
+    }
+
+    /// Returns the summaries of the retained content lookup traces, the most recent first.
+    pub fn query_traces(&self) -> Vec<QueryTraceSummary> {
+        self.query_traces.lock().list()
+    }
+
+    /// Returns the circuits of all peers with failed requests, or none if the circuit breaker is
+    /// disabled.
+    pub fn circuit_breakers(&self) -> Vec<CircuitBreakerInfo> {
+        match &self.circuit_breaker {
+            Some(circuit_breaker) => circuit_breaker.lock().info(Instant::now()),
+            None => vec![],
+        }
+    }
+
+    /// Returns up to `count` content recently served to peers, the most served first.
+    pub fn recently_served(&self, count: usize) -> Vec<ServedContentInfo> {
+        self.served_content
+            .lock()
+            .most_served(count, Instant::now())
+    }
+
+    /// Simulates a lookup of the content id against a synthetic set of peers, without any network
+    /// traffic, and returns the peers in the order they are contacted.
+    pub fn simulate_lookup(
+        &self,
+        peers: &[NodeId],
+        content_id: &[u8; 32],
+    ) -> Vec<SimulatedHopInfo> {
+        simulate_lookup::<TMetric>(
+            self.local_enr().node_id(),
+            peers,
+            content_id,
+            self.query_config.clone(),
+        )
+    }
+
+    /// Returns the number of active uTP transfers requested by each peer.
+    pub fn peer_transfers_info(&self) -> PeerTransfersInfo {
+        self.utp_controller.peer_transfers_info()
+    }
+
+    /// Reloads the denylist from the file it was loaded from, and removes the nodes that are
+    /// now denied from the routing table.
+    pub fn reload_denylist(&self) -> anyhow::Result<()> {
+        self.denylist.write().reload()?;
+        for node_id in self.kbuckets.remove_denied() {
+            debug!(
+                protocol = %self.protocol,
+                peer.node_id = %node_id,
+                "Removed denied node from the routing table",
+            );
+        }
+        Ok(())
+    }
+
     /// Returns the data radius of the local node.
     pub fn data_radius(&self) -> Distance {
         self.store.read().radius()
@@ -182,6 +451,10 @@ impl<
             },
             Err(_) => return Err(OverlayRequestError::DecodeError),
         };
+        // Requests from denied nodes are never answered.
+        if self.kbuckets.is_denied(talk_request.node_id()) {
+            return Err(OverlayRequestError::NodeDenied);
+        }
         let direction = RequestDirection::Incoming {
             id: talk_request.id().clone(),
             source: *talk_request.node_id(),
@@ -211,7 +484,23 @@ impl<
             &self.kbuckets,
             self.command_tx.clone(),
             None,
+            None,
+            self.gossip_peer_retries,
+        )
+    }
+
+    /// Propagate a batch of gossip accepted content via OFFER/ACCEPT, returns the number of peers
+    /// that were offered and accepted each content item
+    pub async fn propagate_gossip_batch(
+        &self,
+        content: Vec<(TContentKey, RawContentValue)>,
+    ) -> GossipBatchInfo {
+        batch_propagate_gossip_cross_thread::<_, TMetric>(
+            content,
+            &self.kbuckets,
+            self.command_tx.clone(),
         )
+        .await
     }
 
     /// Propagate gossip accepted content via OFFER/ACCEPT, returns trace detailing outcome of
@@ -226,6 +515,7 @@ impl<
             data,
             &self.kbuckets,
             self.command_tx.clone(),
+            self.gossip_peer_retries,
         )
         .await
     }
@@ -243,6 +533,67 @@ impl<
         }
     }
 
+    /// Returns the number of peers within gossip range of the local node, across the whole key
+    /// space, together with a per-bucket breakdown.
+    pub fn neighborhood_info(&self) -> NeighborhoodInfo {
+        let local_node_id = self.local_enr().node_id();
+        let radius = self.data_radius();
+        let buckets = self
+            .kbuckets
+            .neighborhood_info::<TMetric>(local_node_id, radius);
+        NeighborhoodInfo {
+            local_node_id,
+            radius: *radius,
+            peers_in_range: buckets.iter().map(|bucket| bucket.peers_in_range).sum(),
+            buckets,
+        }
+    }
+
+    /// Returns the number of peers in the routing table per IP subnet of their advertised address,
+    /// the most crowded subnet first.
+    pub fn subnet_distribution(&self) -> SubnetDistributionInfo {
+        let mut subnets: Vec<SubnetPeersInfo> = self
+            .kbuckets
+            .subnet_distribution()
+            .into_iter()
+            .map(|(subnet, peers)| SubnetPeersInfo {
+                subnet: subnet.to_string(),
+                peers,
+            })
+            .collect();
+        // The sort is stable, so subnets with the same number of peers stay in address order.
+        subnets.sort_by(|a, b| b.peers.cmp(&a.peers));
+        SubnetDistributionInfo {
+            max_peers_per_subnet: self.max_peers_per_subnet,
+            subnets,
+        }
+    }
+
+    /// Returns up to `limit` connected peers in the routing table that are closest to the content.
+    pub fn closest_peers_to_content(&self, content_id: &[u8; 32], limit: usize) -> Vec<Enr> {
+        self.kbuckets
+            .closest_to_content_id::<TMetric>(content_id, limit)
+    }
+
+    /// Returns whether the overlay meets the readiness criteria of the `config`, together with the
+    /// status of each criterion.
+    pub fn readiness(&self, config: &ReadinessConfig) -> ReadinessInfo {
+        config.evaluate(
+            self.kbuckets.connected_entries_count(),
+            !self.is_store_degraded(),
+            self.successful_lookups.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Returns, per bucket, the number of nodes in it and the time of the last node lookup that
+    /// refreshed it.
+    pub fn refresh_info(&self) -> RefreshInfo {
+        RefreshInfo {
+            local_node_id: self.local_enr().node_id(),
+            buckets: self.kbuckets.refresh_info(),
+        }
+    }
+
     /// `AddEnr` adds requested `enr` to our kbucket.
     pub fn add_enr(&self, enr: Enr) -> Result<(), OverlayRequestError> {
         match self.kbuckets.insert_or_update(
@@ -255,8 +606,11 @@ impl<
                 direction: ConnectionDirection::Incoming,
             },
         ) {
-            InsertResult::Inserted
-            | InsertResult::Pending { .. }
+            InsertResult::Inserted => {
+                self.report_routing_table_change(RoutingTableChangeLabel::Inserted);
+                Ok(())
+            }
+            InsertResult::Pending { .. }
             | InsertResult::StatusUpdated { .. }
             | InsertResult::ValueUpdated
             | InsertResult::Updated { .. }
@@ -293,7 +647,42 @@ impl<
 
     /// `DeleteEnr` deletes requested `enr` from our kbucket.
     pub fn delete_enr(&self, node_id: NodeId) -> bool {
-        self.kbuckets.remove(node_id)
+        let is_deleted = self.kbuckets.remove(node_id);
+        if is_deleted {
+            self.report_routing_table_change(RoutingTableChangeLabel::Evicted);
+        }
+        is_deleted
+    }
+
+    /// `RemoveEnr` removes requested node from our kbucket.
+    ///
+    /// If `deny_ttl` is provided, the node is also prevented from being added back to our kbucket
+    /// (e.g. by discovery) until it expires. Returns whether the node was present, or an error if
+    /// `deny_ttl` is too long.
+    pub fn remove_enr(
+        &self,
+        node_id: NodeId,
+        deny_ttl: Option<Duration>,
+    ) -> Result<bool, OverlayRequestError> {
+        let is_removed = match deny_ttl {
+            Some(ttl) => self.kbuckets.remove_and_deny(node_id, ttl).ok_or_else(|| {
+                OverlayRequestError::InvalidRequest(format!(
+                    "Deny ttl of {}s is too long",
+                    ttl.as_secs()
+                ))
+            })?,
+            None => self.kbuckets.remove(node_id),
+        };
+        if is_removed {
+            self.report_routing_table_change(RoutingTableChangeLabel::Evicted);
+        }
+        Ok(is_removed)
+    }
+
+    /// Reports an insertion into or eviction from the routing table to the metrics system.
+    fn report_routing_table_change(&self, change: RoutingTableChangeLabel) {
+        self.metrics
+            .report_routing_table_change(change, self.kbuckets.entries_count());
     }
 
     /// `LookupEnr` finds requested `enr` from our kbucket, FindNode, and RecursiveFindNode.
@@ -310,7 +699,7 @@ impl<
                 let enr_highest_seq = nodes.enrs.into_iter().max_by(|a, b| a.seq().cmp(&b.seq()));
 
                 if let Some(enr_highest_seq) = enr_highest_seq {
-                    return Ok(enr_highest_seq.into());
+                    return Ok(enr_highest_seq);
                 }
             }
         }
@@ -347,7 +736,7 @@ impl<
 
         // Send the request and wait on the response.
         match self
-            .send_overlay_request(Request::Ping(request), direction)
+            .send_overlay_request_with_timeout(Request::Ping(request), direction, self.ping_timeout)
             .await
         {
             Ok(Response::Pong(pong)) => Ok(pong),
@@ -356,23 +745,89 @@ impl<
         }
     }
 
+    /// Sends a `Ping` request to `enr` directly over discv5, bypassing the overlay service, and
+    /// returns the raw request and response along with their decoded fields.
+    ///
+    /// This is a diagnostic tool: unlike [Self::send_ping], the peer isn't added to or updated in
+    /// the routing table, and a response that isn't a valid `Pong` is still returned.
+    pub async fn send_debug_ping(&self, enr: Enr) -> Result<DebugPingInfo, OverlayRequestError> {
+        let enr_seq = self.discovery.local_enr().seq();
+        let data_radius = self.data_radius();
+        let request = Message::from(Request::Ping(Ping {
+            enr_seq,
+            custom_payload: CustomPayload::from(data_radius.as_ssz_bytes()),
+        }))
+        .as_ssz_bytes();
+
+        let start = Instant::now();
+        let response = tokio::time::timeout(
+            self.ping_timeout,
+            self.discovery
+                .send_talk_req(enr, self.protocol, request.clone()),
+        )
+        .await
+        .map_err(|_| OverlayRequestError::Timeout)??;
+        let duration = start.elapsed();
+
+        let (pong, decode_error) = match Message::try_from(response.to_vec()) {
+            Ok(Message::Pong(pong)) => (
+                Some(PongInfo {
+                    enr_seq: pong.enr_seq,
+                    data_radius: *Distance::from(pong.custom_payload),
+                }),
+                None,
+            ),
+            Ok(message) => (
+                None,
+                Some(format!("Expected a Pong, received: {message:?}")),
+            ),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        Ok(DebugPingInfo {
+            request: request.into(),
+            response: response.to_vec().into(),
+            local_enr_seq: enr_seq,
+            local_data_radius: *data_radius,
+            pong,
+            decode_error,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+        })
+    }
+
     /// Sends a `FindNodes` request to `enr`.
+    ///
+    /// ENRs in the response that aren't at any of the requested distances from the peer are
+    /// filtered out.
     pub async fn send_find_nodes(
         &self,
         enr: Enr,
         distances: Vec<u16>,
-    ) -> Result<Nodes, OverlayRequestError> {
+    ) -> Result<FindNodesResponse, OverlayRequestError> {
         // Construct the request.
         validate_find_nodes_distances(&distances)?;
-        let request = FindNodes { distances };
+        let peer = enr.node_id();
+        let request = FindNodes {
+            distances: distances.clone(),
+        };
         let direction = RequestDirection::Outgoing { destination: enr };
 
         // Send the request and wait on the response.
         match self
-            .send_overlay_request(Request::FindNodes(request), direction)
+            .send_overlay_request_with_timeout(
+                Request::FindNodes(request),
+                direction,
+                self.findnodes_timeout,
+            )
             .await
         {
-            Ok(Response::Nodes(nodes)) => Ok(nodes),
+            Ok(Response::Nodes(nodes)) => {
+                let enrs = nodes.enrs.into_iter().map(Enr::from).collect();
+                let (enrs, out_of_range) = partition_enrs_by_distances(&peer, &distances, enrs);
+                Ok(FindNodesResponse {
+                    enrs,
+                    filtered: out_of_range.len(),
+                })
+            }
             Ok(_) => Err(OverlayRequestError::InvalidResponse),
             Err(error) => Err(error),
         }
@@ -399,7 +854,11 @@ impl<
 
         // Send the request and wait on the response.
         match self
-            .send_overlay_request(Request::FindContent(request), direction)
+            .send_overlay_request_with_timeout(
+                Request::FindContent(request),
+                direction,
+                self.findcontent_timeout,
+            )
             .await
         {
             Ok(Response::Content(found_content)) => {
@@ -460,7 +919,7 @@ impl<
             peer: UtpEnr(enr),
         };
         self.utp_controller
-            .connect_inbound_stream(cid)
+            .connect_inbound_stream_with_limit(cid, self.max_find_content_size)
             .await
             .map_err(|err| OverlayRequestError::ContentNotFound {
                 message: format!("Unable to locate content on the network: {err:?}"),
@@ -490,6 +949,49 @@ impl<
         }
     }
 
+    /// Send a populated Offer request, without storing the content into db, and measure the uTP
+    /// transfer of the accepted content.
+    ///
+    /// Returns the accept response, and the outcome of the transfer, if any content was accepted.
+    /// All the accepted content is transferred in a single uTP stream.
+    pub async fn send_offer_with_transfer(
+        &self,
+        enr: Enr,
+        content_items: Vec<(RawContentKey, RawContentValue)>,
+    ) -> Result<(Accept, Option<OfferTransfer>), OverlayRequestError> {
+        let (result_tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let request = Request::PopulatedOfferWithTransfer(PopulatedOfferWithTransfer {
+            content_items,
+            result_tx,
+        });
+
+        let direction = RequestDirection::Outgoing {
+            destination: enr.clone(),
+        };
+
+        let accept = match self.send_overlay_request(request, direction).await? {
+            Response::Accept(accept) => accept,
+            _ => return Err(OverlayRequestError::InvalidResponse),
+        };
+        if accept.content_keys.is_zero() {
+            return Ok((accept, None));
+        }
+
+        // Wait for the transfer to complete.
+        match rx.recv().await {
+            Some(transfer) => Ok((accept, Some(transfer))),
+            None => {
+                warn!(
+                    protocol = %self.protocol,
+                    "Error receiving the outcome of the offered content transfer"
+                );
+                Err(OverlayRequestError::ChannelFailure(
+                    "Error receiving the outcome of the offered content transfer".to_string(),
+                ))
+            }
+        }
+    }
+
     /// Send Offer request with trace, without storing the content into db
     pub async fn send_offer_trace(
         &self,
@@ -527,7 +1029,49 @@ impl<
         }
     }
 
+    /// Send Offer request with trace, without storing the content into db, and once the content
+    /// is transferred, confirm that the peer stored it by requesting it back with FindContent.
+    ///
+    /// Returns the trace of the offer, and whether the peer served back the offered content.
+    pub async fn send_offer_with_confirmation(
+        &self,
+        enr: Enr,
+        content_key: RawContentKey,
+        content_value: RawContentValue,
+    ) -> Result<(OfferTrace, bool), OverlayRequestError> {
+        let trace = self
+            .send_offer_trace(enr.clone(), content_key.clone(), content_value.clone())
+            .await?;
+        // Only content that was transferred can be confirmed.
+        if !matches!(trace, OfferTrace::Success(_)) {
+            return Ok((trace, false));
+        }
+
+        tokio::time::sleep(OFFER_CONFIRMATION_DELAY).await;
+        let confirmed = match self.send_find_content(enr.clone(), content_key).await {
+            Ok((Content::Content(content), _)) => content == content_value,
+            Ok(_) => false,
+            Err(err) => {
+                debug!(
+                    protocol = %self.protocol,
+                    error = %err,
+                    peer = %enr.node_id(),
+                    "Failed to confirm offered content"
+                );
+                false
+            }
+        };
+        Ok((trace, confirmed))
+    }
+
     pub async fn lookup_node(&self, target: NodeId) -> Vec<Enr> {
+        self.lookup_nodes(target, self.query_config.num_results)
+            .await
+    }
+
+    /// Performs a node lookup for `target`.
+    /// Returns up to `num_results` of the discovered nodes, sorted by distance to the target.
+    pub async fn lookup_nodes(&self, target: NodeId, num_results: usize) -> Vec<Enr> {
         if target == self.local_enr().node_id() {
             return vec![self.local_enr()];
         }
@@ -550,6 +1094,7 @@ impl<
         let (tx, rx) = oneshot::channel();
         if let Err(err) = self.command_tx.send(OverlayCommand::FindNodeQuery {
             target,
+            num_results,
             callback: tx,
         }) {
             warn!(
@@ -569,13 +1114,154 @@ impl<
         })
     }
 
+    /// Crawls the network by looking up random node ids across the key space, one at a time, and
+    /// returns the distinct peers discovered.
+    ///
+    /// The crawl stops once `max_peers` are discovered, `timeout` elapses, or lookups stop
+    /// discovering new peers. The radius of a peer is only known if it's in the routing table.
+    pub async fn crawl(&self, max_peers: usize, timeout: Duration) -> NetworkCrawlInfo {
+        let start = Instant::now();
+        let local_node_id = self.local_enr().node_id();
+        let mut discovered: HashMap<NodeId, Enr> = HashMap::new();
+        let mut progress = vec![];
+        let mut lookups = 0;
+        let mut unproductive_lookups = 0;
+        let stop_reason = loop {
+            if discovered.len() >= max_peers {
+                break NetworkCrawlStopReason::MaxPeers;
+            }
+            if unproductive_lookups >= MAX_UNPRODUCTIVE_CRAWL_LOOKUPS {
+                break NetworkCrawlStopReason::Exhausted;
+            }
+            let Some(remaining) = timeout.checked_sub(start.elapsed()) else {
+                break NetworkCrawlStopReason::Timeout;
+            };
+            let Ok(enrs) =
+                tokio::time::timeout(remaining, self.lookup_node(NodeId::random())).await
+            else {
+                break NetworkCrawlStopReason::Timeout;
+            };
+            lookups += 1;
+
+            let discovered_count = discovered.len();
+            for enr in enrs {
+                let node_id = enr.node_id();
+                if node_id == local_node_id {
+                    continue;
+                }
+                // The most recent ENR of each peer is kept.
+                match discovered.get(&node_id) {
+                    Some(known_enr) if known_enr.seq() >= enr.seq() => {}
+                    Some(_) => {
+                        discovered.insert(node_id, enr);
+                    }
+                    None if discovered.len() < max_peers => {
+                        discovered.insert(node_id, enr);
+                    }
+                    None => {}
+                }
+            }
+            if discovered.len() > discovered_count {
+                unproductive_lookups = 0;
+            } else {
+                unproductive_lookups += 1;
+            }
+            progress.push(NetworkCrawlProgressInfo {
+                elapsed_ms: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+                lookups,
+                discovered_peers: discovered.len(),
+            });
+        };
+        let duration = start.elapsed();
+        info!(
+            protocol = %self.protocol,
+            peers = discovered.len(),
+            lookups,
+            ?stop_reason,
+            duration_ms = duration.as_millis(),
+            "Network crawl finished"
+        );
+
+        let mut peers: Vec<CrawledPeerInfo> = discovered
+            .into_values()
+            .map(|enr| CrawledPeerInfo {
+                client: enr
+                    .get(ENR_PORTAL_CLIENT_KEY)
+                    .and_then(|client| String::from_utf8(client.to_vec()).ok()),
+                data_radius: self
+                    .kbuckets
+                    .entry(enr.node_id())
+                    .present_or_pending()
+                    .map(|node| *node.data_radius),
+                enr,
+            })
+            .collect();
+        peers.sort_by_key(|peer| {
+            XorMetric::distance(&local_node_id.raw(), &peer.enr.node_id().raw())
+        });
+        let peers_per_second = match duration.as_secs_f64() {
+            secs if secs > 0.0 => peers.len() as f64 / secs,
+            _ => 0.0,
+        };
+        NetworkCrawlInfo {
+            peers,
+            stop_reason,
+            lookups,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            peers_per_second,
+            progress,
+        }
+    }
+
     /// Performs a content lookup for `target`.
     /// Returns the target content along with the peers traversed during content lookup.
+    ///
+    /// If the content isn't found, the lookup is run again from scratch up to `lookup_retries`
+    /// times, with an exponential backoff. The trace of every attempt is recorded separately.
     pub async fn lookup_content(
         &self,
         target: TContentKey,
         config: FindContentConfig,
     ) -> Result<RecursiveFindContentResult, OverlayRequestError> {
+        let mut backoff = LOOKUP_RETRY_BACKOFF;
+        for retry in 0..self.lookup_retries {
+            match self
+                .lookup_content_once(target.clone(), config.clone())
+                .await
+            {
+                Err(OverlayRequestError::ContentNotFound { .. }) => {
+                    debug!(
+                        protocol = %self.protocol,
+                        content.id = %hex_encode(target.content_id()),
+                        retry = retry + 1,
+                        ?backoff,
+                        "Content lookup didn't find the content, retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+        self.lookup_content_once(target, config).await
+    }
+
+    /// Performs a single content lookup for `target`.
+    async fn lookup_content_once(
+        &self,
+        target: TContentKey,
+        config: FindContentConfig,
+    ) -> Result<RecursiveFindContentResult, OverlayRequestError> {
+        // A lookup with too few peers is likely to miss content that is available on the
+        // network, so it is refused rather than reported as absent.
+        let peers = self.kbuckets.connected_entries_count();
+        if peers < self.min_lookup_peers {
+            return Err(OverlayRequestError::NotReady {
+                peers,
+                min_peers: self.min_lookup_peers,
+            });
+        }
+
         let (tx, rx) = oneshot::channel();
         let content_id = target.content_id();
 
@@ -594,7 +1280,7 @@ impl<
         }
 
         // Wait on the response.
-        rx.await.map_err(|err| {
+        let result = rx.await.map_err(|err| {
             warn!(
                 protocol = %self.protocol,
                 error = %err,
@@ -602,7 +1288,11 @@ impl<
                 "Error receiving FindContent query response"
             );
             OverlayRequestError::ChannelFailure(err.to_string())
-        })
+        });
+        if let Ok(Ok(_)) = result {
+            self.successful_lookups.fetch_add(1, Ordering::Relaxed);
+        }
+        result
     }
 
     /// Sends a request through the overlay service.
@@ -630,7 +1320,46 @@ impl<
             .unwrap_or_else(|err| Err(OverlayRequestError::ChannelFailure(err.to_string())))
     }
 
+    /// Sends a request through the overlay service, and abandons it if no response is received
+    /// within `timeout`.
+    async fn send_overlay_request_with_timeout(
+        &self,
+        request: Request,
+        direction: RequestDirection,
+        timeout: Duration,
+    ) -> Result<Response, OverlayRequestError> {
+        tokio::time::timeout(timeout, self.send_overlay_request(request, direction))
+            .await
+            .unwrap_or(Err(OverlayRequestError::Timeout))
+    }
+
+    /// Inserts the nodes that aren't in the routing table yet into it, like the bootnodes on
+    /// startup.
+    pub fn add_bootnodes(&self, enrs: Vec<Enr>) {
+        if let Err(err) = self.command_tx.send(OverlayCommand::AddBootnodes(enrs)) {
+            warn!(
+                protocol = %self.protocol,
+                error = %err,
+                "Error submitting bootnodes to service"
+            );
+        }
+    }
+
+    /// Pings all connected peers in the routing table, so that they learn the current radius.
+    pub fn ping_connected_peers(&self) {
+        if let Err(err) = self.command_tx.send(OverlayCommand::PingConnectedPeers) {
+            warn!(
+                protocol = %self.protocol,
+                error = %err,
+                "Error submitting ping of connected peers to service"
+            );
+        }
+    }
+
     pub async fn ping_bootnodes(&self) {
+        if !self.join_delay.is_zero() {
+            tokio::time::sleep(self.join_delay).await;
+        }
         // Trigger bonding with bootnodes, at both the base layer and portal overlay.
         // The overlay ping via talkreq will trigger a session at the base layer, then
         // a session on the (overlay) portal network.
@@ -678,6 +1407,11 @@ impl<
         }
     }
 
+    /// Returns the reporter of the runtime metrics of the overlay.
+    pub fn metrics(&self) -> &OverlayMetricsReporter {
+        &self.metrics
+    }
+
     pub fn get_message_summary(&self) -> String {
         self.metrics.get_message_summary()
     }
@@ -707,6 +1441,23 @@ impl<
     }
 }
 
+/// Splits the ENRs that `peer` returned in response to a FindNodes request into those at one of
+/// the requested `distances` from it, and those that aren't.
+pub(crate) fn partition_enrs_by_distances(
+    peer: &NodeId,
+    distances: &[u16],
+    enrs: Vec<Enr>,
+) -> (Vec<Enr>, Vec<Enr>) {
+    enrs.into_iter().partition(|enr| {
+        let distance = XorMetric::distance(&peer.raw(), &enr.node_id().raw())
+            .log2()
+            .unwrap_or(0);
+        distances
+            .iter()
+            .any(|requested| usize::from(*requested) == distance)
+    })
+}
+
 fn validate_find_nodes_distances(distances: &[u16]) -> Result<(), OverlayRequestError> {
     if distances.is_empty() {
         return Err(OverlayRequestError::InvalidRequest(
@@ -736,6 +1487,7 @@ fn validate_find_nodes_distances(distances: &[u16]) -> Result<(), OverlayRequest
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod test {
+    use ethportal_api::types::enr::generate_random_remote_enr;
     use rstest::rstest;
 
     use super::*;
@@ -761,4 +1513,28 @@ mod test {
             Err(err) => assert!(err.to_string().contains(&msg)),
         }
     }
+
+    #[test]
+    fn test_partition_enrs_by_distances() {
+        let (_, peer_enr) = generate_random_remote_enr();
+        let peer = peer_enr.node_id();
+        let enrs: Vec<Enr> = (0..8).map(|_| generate_random_remote_enr().1).collect();
+        let log2_distance = |enr: &Enr| {
+            XorMetric::distance(&peer.raw(), &enr.node_id().raw())
+                .log2()
+                .unwrap() as u16
+        };
+        let distances = vec![log2_distance(&enrs[0]), 0];
+
+        let mut all_enrs = enrs.clone();
+        all_enrs.push(peer_enr.clone());
+        let (in_range, out_of_range) = partition_enrs_by_distances(&peer, &distances, all_enrs);
+
+        // The peer's own ENR is at distance 0.
+        assert!(in_range.contains(&enrs[0]) && in_range.contains(&peer_enr));
+        assert_eq!(in_range.len() + out_of_range.len(), enrs.len() + 1);
+        assert!(out_of_range
+            .iter()
+            .all(|enr| !distances.contains(&log2_distance(enr))));
+    }
 }
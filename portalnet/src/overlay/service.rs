@@ -1,9 +1,9 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     marker::{PhantomData, Sync},
     sync::Arc,
     task::Poll,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
@@ -26,10 +26,10 @@ use ethportal_api::{
         network::Subnetwork,
         portal_wire::{
             Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer,
-            OfferTrace, Ping, Pong, PopulatedOffer, Request, Response,
+            OfferTrace, OfferTransfer, Ping, Pong, PopulatedOffer, Request, Response,
             MAX_PORTAL_CONTENT_PAYLOAD_SIZE, MAX_PORTAL_NODES_ENRS_SIZE,
         },
-        query_trace::{QueryFailureKind, QueryTrace},
+        query_trace::{QueryFailureKind, QueryHopResponse, QueryTrace},
     },
     utils::bytes::hex_encode_compact,
     OverlayContentKey, RawContentKey, RawContentValue,
@@ -49,9 +49,9 @@ use tokio::{
     task::JoinHandle,
 };
 use tracing::{debug, enabled, error, info, trace, warn, Level};
-use trin_metrics::overlay::OverlayMetricsReporter;
-use trin_storage::{ContentStore, ShouldWeStoreContent};
-use trin_validation::validator::Validator;
+use trin_metrics::{labels::RoutingTableChangeLabel, overlay::OverlayMetricsReporter};
+use trin_storage::{error::ContentStoreError, ContentStore, ShouldWeStoreContent};
+use trin_validation::validator::{ValidationResult, Validator};
 use utp_rs::cid::ConnectionId;
 
 use crate::{
@@ -67,22 +67,34 @@ use crate::{
             findnodes::FindNodeQuery,
             query::{Query, QueryConfig},
         },
-        query_info::{QueryInfo, QueryType, RecursiveFindContentResult},
+        query_info::{QueryHopStream, QueryInfo, QueryType, RecursiveFindContentResult},
         query_pool::{QueryId, QueryPool, QueryPoolState, TargetKey},
     },
     gossip::propagate_gossip_cross_thread,
+    mirror::Mirror,
     overlay::{
         command::OverlayCommand,
         config::FindContentConfig,
         errors::OverlayRequestError,
+        protocol::partition_enrs_by_distances,
         request::{
             ActiveOutgoingRequest, OverlayRequest, OverlayRequestId, OverlayResponse,
             RequestDirection,
         },
     },
     types::{
+        circuit_breaker::SharedCircuitBreaker,
+        content_holders::SharedContentHolders,
+        denylist::SharedDenylist,
+        gossip_history::SharedGossipHistory,
         kbucket::{DiscoveredNodesUpdateResult, Entry, SharedKBucketsTable},
+        last_errors::{ErrorSubsystem, SharedLastErrors},
         node::Node,
+        offer_log::{DeclinedContentKey, OfferDeclineReason, OfferLogRecord, SharedOfferLog},
+        query_trace_buffer::SharedQueryTraceBuffer,
+        seen_content::SharedSeenContent,
+        served_content::SharedServedContent,
+        store_health::SharedStoreHealth,
     },
     utils::portal_wire,
     utp_controller::UtpController,
@@ -93,6 +105,10 @@ pub const FIND_NODES_MAX_NODES: usize = 32;
 /// Maximum number of ENRs in response to FindContent.
 pub const FIND_CONTENT_MAX_NODES: usize = 32;
 
+/// Maximum number of times a failed uTP transfer of content for a FindContent query is retried
+/// with the same peer.
+const FIND_CONTENT_UTP_RETRIES: usize = 1;
+
 /// With even distribution assumptions, 2**17 is enough to put each node (estimating 100k nodes,
 /// which is more than 10x the ethereum mainnet node count) into a unique bucket by the 17th bucket
 /// index.
@@ -101,9 +117,15 @@ const EXPECTED_NON_EMPTY_BUCKETS: usize = 17;
 /// Bucket refresh lookup interval in seconds
 const BUCKET_REFRESH_INTERVAL_SECS: u64 = 60;
 
+/// Interval in seconds at which an unavailable data store is checked for recovery.
+const STORE_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
 /// The capacity of the event-stream's broadcast channel.
 const EVENT_STREAM_CHANNEL_CAPACITY: usize = 10;
 
+/// Maximum number of locally stored content items offered to a newly discovered peer.
+const NEW_PEER_GOSSIP_MAX_CONTENT_ITEMS: usize = 16;
+
 /// The overlay service.
 pub struct OverlayService<TContentKey, TMetric, TValidator, TStore>
 where
@@ -143,6 +165,8 @@ where
     query_timeout: Duration,
     /// Number of peers to request data from in parallel for a single query.
     query_parallelism: usize,
+    /// The max number of hops of a content query, before it gives up.
+    query_max_hops: usize,
     /// Number of new peers to discover before considering a FINDNODES query complete.
     query_num_results: usize,
     /// The number of buckets we simultaneously request from each peer in a FINDNODES query.
@@ -167,8 +191,46 @@ where
     disable_poke: bool,
     /// Gossip content as it gets dropped from local storage
     gossip_dropped: bool,
+    /// Gossip content that was stored without validation
+    gossip_unvalidated: bool,
+    /// The number of times a failed offer of gossiped content to a peer is retried
+    gossip_peer_retries: u32,
     /// Accept Queue for inbound content keys
     accept_queue: Arc<RwLock<AcceptQueue<TContentKey>>>,
+    /// Offer locally stored content to newly discovered peers
+    gossip_on_new_peer: bool,
+    /// Newly discovered peers that will be offered content once their radius is known.
+    new_peers_to_gossip: HashSet<NodeId>,
+    /// Decline to serve content to peers whose known radius doesn't contain it.
+    serve_only_in_requester_radius: bool,
+    /// Accept offered content that is already stored, instead of declining it.
+    accept_duplicate_content: bool,
+    /// Denied content is never accepted, stored or served.
+    denylist: SharedDenylist,
+    /// Records every received offer, if set.
+    offer_log: Option<SharedOfferLog>,
+    /// Whether the data store is usable. While it isn't, offered content is declined and
+    /// lookups are only routed to peers.
+    store_health: SharedStoreHealth,
+    /// The last error encountered by each subsystem, which failed requests are recorded in.
+    last_errors: SharedLastErrors,
+    /// The traces of the most recent content lookups. While it's enabled, every content lookup
+    /// is traced.
+    query_traces: SharedQueryTraceBuffer,
+    /// The gossip rounds of each content id, if the re-gossip of the same content decays.
+    gossip_history: Option<SharedGossipHistory>,
+    /// The peers that recently accepted offered content, if requests for content are redirected
+    /// to its likely holders.
+    content_holders: Option<SharedContentHolders>,
+    /// The circuits of peers that failed requests, if peers that consistently fail are
+    /// temporarily not selected for lookups and offers.
+    circuit_breaker: Option<SharedCircuitBreaker>,
+    /// The number of times each content was recently served to peers.
+    served_content: SharedServedContent,
+    /// Offers newly stored content to the mirror peer, if set.
+    mirror: Option<Mirror>,
+    /// The content that recently arrived through gossip, if duplicates are dropped.
+    seen_content: Option<SharedSeenContent>,
 }
 
 impl<
@@ -178,7 +240,8 @@ impl<
         TStore: 'static + ContentStore<Key = TContentKey> + Send + Sync,
     > OverlayService<TContentKey, TMetric, TValidator, TStore>
 {
-    /// Spawns the overlay network service.
+    /// Spawns the overlay network service, and returns the sender of its commands, along with
+    /// the mirror that stored content is offered to, if a mirror peer is set.
     ///
     /// The state of the overlay network largely consists of its routing table. The routing table
     /// is updated according to incoming requests and responses as well as autonomous maintenance
@@ -197,13 +260,34 @@ impl<
         query_timeout: Duration,
         query_peer_timeout: Duration,
         query_parallelism: usize,
+        query_max_hops: usize,
         query_num_results: usize,
         findnodes_query_distances_per_peer: usize,
         disable_poke: bool,
         gossip_dropped: bool,
-    ) -> UnboundedSender<OverlayCommand<TContentKey>> {
+        gossip_unvalidated: bool,
+        gossip_peer_retries: u32,
+        gossip_on_new_peer: bool,
+        serve_only_in_requester_radius: bool,
+        accept_duplicate_content: bool,
+        denylist: SharedDenylist,
+        offer_log: Option<SharedOfferLog>,
+        store_health: SharedStoreHealth,
+        last_errors: SharedLastErrors,
+        join_delay: Duration,
+        query_traces: SharedQueryTraceBuffer,
+        content_ttl_sweep_interval: Option<Duration>,
+        gossip_history: Option<SharedGossipHistory>,
+        content_holders: Option<SharedContentHolders>,
+        circuit_breaker: Option<SharedCircuitBreaker>,
+        served_content: SharedServedContent,
+        mirror_peer: Option<Enr>,
+        seen_content: Option<SharedSeenContent>,
+    ) -> (UnboundedSender<OverlayCommand<TContentKey>>, Option<Mirror>) {
         let (command_tx, command_rx) = mpsc::unbounded_channel();
         let internal_command_tx = command_tx.clone();
+        let mirror = mirror_peer.map(|peer| Mirror::spawn(peer, command_tx.clone(), protocol));
+        let service_mirror = mirror.clone();
 
         let peers_to_ping = if let Some(interval) = ping_queue_interval {
             HashSetDelay::new(interval)
@@ -233,6 +317,7 @@ impl<
                 query_peer_timeout,
                 query_timeout,
                 query_parallelism,
+                query_max_hops,
                 query_num_results,
                 findnodes_query_distances_per_peer,
                 response_rx,
@@ -245,15 +330,33 @@ impl<
                 event_stream,
                 disable_poke,
                 gossip_dropped,
+                gossip_unvalidated,
+                gossip_peer_retries,
                 accept_queue: Arc::new(RwLock::new(AcceptQueue::default())),
+                gossip_on_new_peer,
+                new_peers_to_gossip: HashSet::new(),
+                serve_only_in_requester_radius,
+                accept_duplicate_content,
+                denylist,
+                offer_log,
+                store_health,
+                last_errors,
+                query_traces,
+                gossip_history,
+                content_holders,
+                circuit_breaker,
+                served_content,
+                mirror: service_mirror,
+                seen_content,
             };
 
             info!(protocol = %protocol, "Starting overlay service");
-            service.initialize_routing_table(bootnode_enrs);
-            service.start().await;
+            service
+                .start(bootnode_enrs, join_delay, content_ttl_sweep_interval)
+                .await;
         });
 
-        command_tx
+        (command_tx, mirror)
     }
 
     /// Insert a vector of enrs into the routing table
@@ -291,12 +394,15 @@ impl<
                         "Error inserting bootnode into routing table",
                     );
                 }
-                _ => {
+                result => {
                     debug!(
                         protocol = %self.protocol,
                         bootnode = %node_id,
                         "Inserted bootnode into routing table",
                     );
+                    if matches!(result, InsertResult::Inserted) {
+                        self.report_routing_table_change(RoutingTableChangeLabel::Inserted);
+                    }
 
                     // Queue the node in the ping queue.
                     self.peers_to_ping.insert(node_id);
@@ -311,11 +417,11 @@ impl<
         let local_node_id = self.local_enr().node_id();
 
         // Begin request for our local node ID.
-        self.init_find_nodes_query(&local_node_id, None);
+        self.init_find_nodes_query(&local_node_id, self.query_num_results, None);
 
         for bucket_index in (255 - EXPECTED_NON_EMPTY_BUCKETS as u8)..255 {
             let target_node_id = generate_random_node_id(bucket_index, self.local_enr().into());
-            self.init_find_nodes_query(&target_node_id, None);
+            self.init_find_nodes_query(&target_node_id, self.query_num_results, None);
         }
     }
 
@@ -332,10 +438,37 @@ impl<
     /// information relevant to the overlay network.
     ///
     /// Bucket maintenance: Maintain the routing table (more info documented above function).
-    async fn start(&mut self) {
+    ///
+    /// Content expiry: Delete expired content from the store, every `content_ttl_sweep_interval`
+    /// if set.
+    ///
+    /// The routing table is initialized from the bootnodes, and buckets are refreshed, only once
+    /// `join_delay` has elapsed. Incoming requests are processed in the meantime.
+    async fn start(
+        &mut self,
+        bootnode_enrs: Vec<Enr>,
+        join_delay: Duration,
+        content_ttl_sweep_interval: Option<Duration>,
+    ) {
+        let join_timer = tokio::time::sleep(join_delay);
+        tokio::pin!(join_timer);
+        let mut bootnode_enrs = Some(bootnode_enrs);
+        if !join_delay.is_zero() {
+            info!(
+                protocol = %self.protocol,
+                delay_ms = join_delay.as_millis(),
+                "Delaying joining the network"
+            );
+        }
+
         // Construct bucket refresh interval
         let mut bucket_refresh_interval =
             tokio::time::interval(Duration::from_secs(BUCKET_REFRESH_INTERVAL_SECS));
+        let mut store_health_check_interval =
+            tokio::time::interval(Duration::from_secs(STORE_HEALTH_CHECK_INTERVAL_SECS));
+        // The interval is never polled if content doesn't expire.
+        let mut content_expiry_interval =
+            tokio::time::interval(content_ttl_sweep_interval.unwrap_or(Duration::MAX));
 
         loop {
             tokio::select! {
@@ -353,8 +486,8 @@ impl<
                                 );
                             }
                         }
-                        OverlayCommand::FindNodeQuery { target, callback } => {
-                            if let Some(query_id) = self.init_find_nodes_query(&target, Some(callback)) {
+                        OverlayCommand::FindNodeQuery { target, num_results, callback } => {
+                            if let Some(query_id) = self.init_find_nodes_query(&target, num_results, Some(callback)) {
                                 trace!(
                                     query.id = %query_id,
                                     node.id = %hex_encode_compact(target),
@@ -362,6 +495,20 @@ impl<
                                 );
                             }
                         }
+                        OverlayCommand::AddBootnodes(enrs) => {
+                            let enrs = enrs
+                                .into_iter()
+                                .filter(|enr| {
+                                    matches!(self.kbuckets.entry(enr.node_id()), Entry::Absent)
+                                })
+                                .collect();
+                            self.add_bootnodes(enrs, false);
+                        }
+                        OverlayCommand::PingConnectedPeers => {
+                            for enr in self.kbuckets.connected_enrs() {
+                                self.ping_node(&enr);
+                            }
+                        }
                         OverlayCommand::RequestEventStream(callback) => {
                             if callback.send(self.event_stream.subscribe()).is_err() {
                                 error!("Failed to return the event stream channel");
@@ -385,7 +532,17 @@ impl<
                                 self.metrics.report_inbound_response(&response);
                                 self.process_response(response, request.destination, request.request, request.query_id, request.request_permit)
                             }
-                            Err(error) => self.process_request_failure(response.request_id, request.destination, error),
+                            Err(error) => {
+                                if let Some(query_id) = request.query_id {
+                                    self.stream_find_content_query_hop(
+                                        &query_id,
+                                        request.destination.node_id(),
+                                        QueryHopResponse::Failure { reason: error.to_string() },
+                                    );
+                                }
+                                self.record_request_failure(&request.request, &request.destination, &error);
+                                self.process_request_failure(response.request_id, request.destination, error)
+                            }
                         }
 
                     } else {
@@ -405,14 +562,32 @@ impl<
                 query_event = OverlayService::<TContentKey, TMetric, TValidator, TStore>::query_event_poll(&mut self.find_content_query_pool) => {
                     self.handle_find_content_query_event(query_event);
                 }
-                _ = OverlayService::<TContentKey, TMetric, TValidator, TStore>::bucket_maintenance_poll(self.protocol, &self.kbuckets) => {}
+                _ = OverlayService::<TContentKey, TMetric, TValidator, TStore>::bucket_maintenance_poll(self.protocol, &self.kbuckets, &self.metrics) => {}
                 Some(trace_event) = self.content_query_trace_events_rx.recv() => {
                     self.track_content_query_trace_event(trace_event);
                 }
-                _ = bucket_refresh_interval.tick() => {
+                _ = &mut join_timer, if bootnode_enrs.is_some() => {
+                    if let Some(bootnode_enrs) = bootnode_enrs.take() {
+                        self.initialize_routing_table(bootnode_enrs);
+                    }
+                }
+                _ = bucket_refresh_interval.tick(), if bootnode_enrs.is_none() => {
                     trace!(protocol = %self.protocol, "Routing table bucket refresh");
+                    self.metrics.report_routing_table_size(self.kbuckets.entries_count());
                     self.bucket_refresh_lookup();
                 }
+                _ = store_health_check_interval.tick() => {
+                    if self.store_health.is_degraded() {
+                        let result = self.store.read().check_health();
+                        self.store_health.report(&result);
+                    }
+                }
+                _ = content_expiry_interval.tick(), if content_ttl_sweep_interval.is_some() => {
+                    if !self.store_health.is_degraded() {
+                        let result = self.store.write().delete_expired();
+                        self.store_health.report(&result);
+                    }
+                }
             }
         }
     }
@@ -439,7 +614,13 @@ impl<
             }
         };
 
-        self.init_find_nodes_query(&target_node_id, None);
+        self.init_find_nodes_query(&target_node_id, self.query_num_results, None);
+    }
+
+    /// Reports an insertion into or eviction from the routing table to the metrics system.
+    fn report_routing_table_change(&self, change: RoutingTableChangeLabel) {
+        self.metrics
+            .report_routing_table_change(change, self.kbuckets.entries_count());
     }
 
     /// Returns the local ENR of the node.
@@ -460,10 +641,23 @@ impl<
     /// Consumes previously applied pending entries from the `KBucketsTable`. An `AppliedPending`
     /// result is recorded when a pending bucket entry replaces a disconnected entry in the
     /// respective bucket.
-    async fn bucket_maintenance_poll(protocol: Subnetwork, kbuckets: &SharedKBucketsTable) {
+    async fn bucket_maintenance_poll(
+        protocol: Subnetwork,
+        kbuckets: &SharedKBucketsTable,
+        metrics: &OverlayMetricsReporter,
+    ) {
         future::poll_fn(move |_cx| {
             // Drain applied pending entries from the routing table.
             if let Some(entry) = kbuckets.take_applied_pending() {
+                let entries_count = kbuckets.entries_count();
+                metrics
+                    .report_routing_table_change(RoutingTableChangeLabel::Inserted, entries_count);
+                if entry.evicted.is_some() {
+                    metrics.report_routing_table_change(
+                        RoutingTableChangeLabel::Evicted,
+                        entries_count,
+                    );
+                }
                 debug!(
                     %protocol,
                     inserted = %entry.inserted.into_preimage(),
@@ -498,7 +692,7 @@ impl<
                         return Poll::Pending;
                     }
                 };
-                let is_tracing = query_info.trace.is_some();
+                let is_tracing = query_info.trace.is_some() || query_info.hop_stream.is_some();
                 // Generate the query result here instead of in handle_find_content_query_event,
                 // because the query is only borrowed, and can't be moved to the query event.
                 let query_result = query.pending_validation_result(sending_peer);
@@ -541,6 +735,14 @@ impl<
             QueryEvent::Waiting(query_id, node_id, request) => {
                 // Look up the node's ENR.
                 if let Some(enr) = self.find_enr(&node_id) {
+                    // The circuit is only checked once the request can be sent, as that marks a
+                    // half-open circuit as probing.
+                    if !self.try_circuit_request(&node_id) {
+                        if let Some((_, query)) = self.find_node_query_pool.get_mut(query_id) {
+                            query.on_failure(&node_id);
+                        }
+                        return;
+                    }
                     let request = OverlayRequest::new(
                         request,
                         RequestDirection::Outgoing { destination: enr },
@@ -620,6 +822,14 @@ impl<
         match query_event {
             QueryEvent::Waiting(query_id, node_id, request) => {
                 if let Some(enr) = self.find_enr(&node_id) {
+                    // The circuit is only checked once the request can be sent, as that marks a
+                    // half-open circuit as probing.
+                    if !self.try_circuit_request(&node_id) {
+                        if let Some((_, query)) = self.find_content_query_pool.get_mut(query_id) {
+                            query.on_failure(&node_id);
+                        }
+                        return;
+                    }
                     // If we find the node's ENR, then send the request on behalf of the
                     // query. No callback channel is necessary for the request, because the
                     // response will be incorporated into the query.
@@ -698,12 +908,14 @@ impl<
                                 send: connection_id.wrapping_add(1),
                                 peer: UtpEnr(source),
                             };
-                            let data = match utp_processing
-                                .utp_controller
-                                .connect_inbound_stream(cid)
-                                .await
+                            let data = match Self::receive_find_content_stream(
+                                &utp_processing,
+                                cid,
+                                &content_key,
+                            )
+                            .await
                             {
-                                Ok(data) => RawContentValue::from(data),
+                                Ok(data) => data,
                                 Err(e) => {
                                     debug!(
                                         %e,
@@ -738,10 +950,14 @@ impl<
                     }
                 };
             }
-            QueryEvent::Finished(_, query_info, query)
-            | QueryEvent::TimedOut(_, query_info, query) => {
-                let callback = match query_info.query_type {
-                    QueryType::FindContent { callback, .. } => callback,
+            QueryEvent::Finished(_, mut query_info, query)
+            | QueryEvent::TimedOut(_, mut query_info, query) => {
+                let max_hops_reached = query.max_hops_reached();
+                if let Some(trace) = query_info.trace.as_mut() {
+                    trace.max_hops_reached = max_hops_reached;
+                }
+                let (target, callback) = match query_info.query_type {
+                    QueryType::FindContent { target, callback } => (target, callback),
                     _ => {
                         error!(
                             "Received wrong QueryType when handling a FindContent Timeout. This is a: {:?}",
@@ -752,21 +968,21 @@ impl<
                 };
                 match query.into_result() {
                     FindContentQueryResult::ValidContent(valid_content, cancelled_peers) => {
-                        if let Some(responder) = callback {
-                            let ValidatedContent {
-                                content,
-                                was_utp_transfer,
-                                sending_peer,
-                            } = valid_content;
-
-                            let trace = if let Some(mut trace) = query_info.trace {
-                                trace.content_validated(sending_peer);
-                                trace.cancelled = cancelled_peers;
-                                Some(trace)
-                            } else {
-                                None
-                            };
+                        let ValidatedContent {
+                            content,
+                            was_utp_transfer,
+                            sending_peer,
+                        } = valid_content;
+
+                        let trace = query_info.trace.map(|mut trace| {
+                            trace.content_validated(sending_peer);
+                            trace.cancelled = cancelled_peers;
+                            trace
+                        });
+                        let trace =
+                            self.record_query_trace(&target, trace, query_info.return_trace);
 
+                        if let Some(responder) = callback {
                             if responder
                                 .send(Ok((content, was_utp_transfer, trace)))
                                 .is_err()
@@ -778,12 +994,21 @@ impl<
                         }
                     }
                     FindContentQueryResult::NoneFound => {
+                        let trace = self.record_query_trace(
+                            &target,
+                            query_info.trace,
+                            query_info.return_trace,
+                        );
+                        let message = if max_hops_reached {
+                            "Unable to locate content on the network within the max number of hops"
+                        } else {
+                            "Unable to locate content on the network before timeout"
+                        };
                         if let Some(responder) = callback {
                             let _ = responder.send(Err(OverlayRequestError::ContentNotFound {
-                                message: "Unable to locate content on the network before timeout"
-                                    .to_string(),
+                                message: message.to_string(),
                                 utp: false,
-                                trace: query_info.trace,
+                                trace,
                             }));
                         }
                     }
@@ -792,6 +1017,28 @@ impl<
         }
     }
 
+    /// Retains the trace of a finished content query in the trace buffer, and returns it if the
+    /// initiator of the query asked for it.
+    fn record_query_trace(
+        &self,
+        target: &TContentKey,
+        trace: Option<QueryTrace>,
+        return_trace: bool,
+    ) -> Option<QueryTrace> {
+        let trace = trace?;
+        let mut query_traces = self.query_traces.lock();
+        if !query_traces.is_enabled() {
+            return return_trace.then_some(trace);
+        }
+        if return_trace {
+            query_traces.record(target.to_hex(), trace.clone());
+            Some(trace)
+        } else {
+            query_traces.record(target.to_hex(), trace);
+            None
+        }
+    }
+
     /// Handles a queued event, used to trace the progress of a content query.
     /// These events can be issued from spawned tasks, such as when processing received content.
     fn track_content_query_trace_event(&mut self, trace_event: QueryTraceEvent) {
@@ -804,7 +1051,15 @@ impl<
                     failure = ?fail_kind,
                     "Peer failed during content query"
                 );
-                if let Some((query_info, _)) = self.find_content_query_pool.get_mut(query_id) {
+                if let Some((query_info, query)) = self.find_content_query_pool.get_mut(query_id) {
+                    if let Some(hop_stream) = &query_info.hop_stream {
+                        let reason = format!("{fail_kind:?}");
+                        hop_stream.send(
+                            node_id,
+                            QueryHopResponse::Failure { reason },
+                            query.closest_known_peers(),
+                        );
+                    }
                     if let Some(trace) = &mut query_info.trace {
                         trace.node_failed(node_id, fail_kind);
                     }
@@ -907,6 +1162,20 @@ impl<
                 self.process_incoming_request(request.request, id, source);
             }
             RequestDirection::Outgoing { destination } => {
+                // Gossip is only offered to peers whose circuit is closed, or to a single probe
+                // of a half-open circuit.
+                let is_gossip = matches!(
+                    request.request,
+                    Request::Offer(_) | Request::PopulatedOffer(_)
+                ) && request.responder.is_none();
+                if is_gossip && !self.try_circuit_request(&destination.node_id()) {
+                    debug!(
+                        protocol = %self.protocol,
+                        request.dest = %destination.node_id(),
+                        "Not offering gossip to peer with an open circuit",
+                    );
+                    return;
+                }
                 self.active_outgoing_requests.write().insert(
                     request.id,
                     ActiveOutgoingRequest {
@@ -945,12 +1214,12 @@ impl<
                 id,
             )?)),
             Request::Offer(offer) => Ok(Response::Accept(self.handle_offer(offer, source, id)?)),
-            Request::PopulatedOffer(_) | Request::PopulatedOfferWithResult(_) => {
-                Err(OverlayRequestError::InvalidRequest(
-                    "An offer with content attached is not a valid network message to receive"
-                        .to_owned(),
-                ))
-            }
+            Request::PopulatedOffer(_)
+            | Request::PopulatedOfferWithResult(_)
+            | Request::PopulatedOfferWithTransfer(_) => Err(OverlayRequestError::InvalidRequest(
+                "An offer with content attached is not a valid network message to receive"
+                    .to_owned(),
+            )),
         }
     }
 
@@ -1026,14 +1295,53 @@ impl<
                 ))
             }
         };
-        match (
-            self.store.read().get(&content_key),
-            self.utp_controller.get_outbound_semaphore(),
-        ) {
+        // Peers that request content outside their radius are likely fetching it for themselves
+        // only, so they're routed to closer peers instead, if serving them is declined.
+        if self.serve_only_in_requester_radius
+            && !self.is_within_requester_radius(&content_key, source)
+        {
+            debug!(
+                protocol = %self.protocol,
+                request.source = %source,
+                content.key = %content_key,
+                "Declining to serve content outside the requester's radius",
+            );
+            return Ok(Content::Enrs(
+                self.closest_content_enrs(&content_key, source),
+            ));
+        }
+        // Denied content is never served, respond as if we don't have it. While the store is
+        // unavailable, the request is only routed to closer peers.
+        let content = if self
+            .denylist
+            .read()
+            .is_content_denied(&content_key.content_id())
+            || self.store_health.is_degraded()
+        {
+            Ok(None)
+        } else {
+            let content = self.store.read().get(&content_key);
+            self.store_health.report(&content);
+            content
+        };
+        match (content, self.utp_controller.get_outbound_semaphore()) {
             (Ok(Some(content)), Some(permit)) => {
                 if content.len() <= MAX_PORTAL_CONTENT_PAYLOAD_SIZE {
+                    self.record_served(&content_key);
                     Ok(Content::Content(content))
                 } else {
+                    // If the serve bandwidth is saturated or the peer reached its limit of
+                    // concurrent transfers, send it a list of closer ENRs instead.
+                    if self.utp_controller.is_serve_bandwidth_saturated() {
+                        return Ok(Content::Enrs(
+                            self.closest_content_enrs(&content_key, source),
+                        ));
+                    }
+                    let Some(peer_permit) = self.utp_controller.get_peer_permit(*source) else {
+                        return Ok(Content::Enrs(
+                            self.closest_content_enrs(&content_key, source),
+                        ));
+                    };
                     // Generate a connection ID for the uTP connection.
                     let enr = self.find_enr(source).ok_or_else(|| {
                         OverlayRequestError::AcceptError(
@@ -1048,9 +1356,11 @@ impl<
                     // over the uTP stream.
                     let utp = Arc::clone(&self.utp_controller);
                     tokio::spawn(async move {
-                        utp.accept_outbound_stream(cid, &content).await;
+                        utp.accept_serve_stream(cid, &content).await;
                         drop(permit);
+                        drop(peer_permit);
                     });
+                    self.record_served(&content_key);
 
                     // Connection id is sent as BE because uTP header values are stored also as BE
                     Ok(Content::ConnectionId(cid_send.to_be()))
@@ -1058,26 +1368,69 @@ impl<
             }
             // If we can't obtain a permit or don't have data to send back, send the requester a
             // list of closer ENRs.
-            (Ok(_), None) | (Ok(None), _) => {
-                let mut enrs = self
-                    .kbuckets
-                    .closest_to_content_id::<TMetric>(
-                        &content_key.content_id(),
-                        FIND_CONTENT_MAX_NODES,
-                    )
-                    .into_iter()
-                    .filter(|enr| &enr.node_id() != source)
-                    .map(SszEnr)
-                    .collect::<Vec<_>>();
-                pop_while_ssz_bytes_len_gt(&mut enrs, MAX_PORTAL_CONTENT_PAYLOAD_SIZE);
-                Ok(Content::Enrs(enrs))
-            }
+            (Ok(_), None) | (Ok(None), _) => Ok(Content::Enrs(
+                self.closest_content_enrs(&content_key, source),
+            )),
             (Err(msg), _) => Err(OverlayRequestError::Failure(format!(
                 "Unable to respond to FindContent: {msg}",
             ))),
         }
     }
 
+    /// Returns whether the content is within the radius of the requesting peer, or the radius of
+    /// the peer isn't known, since it isn't in the routing table.
+    fn is_within_requester_radius(&self, content_key: &TContentKey, source: &NodeId) -> bool {
+        match self.kbuckets.entry(*source).present_or_pending() {
+            Some(node) => {
+                TMetric::distance(&source.raw(), &content_key.content_id()) <= node.data_radius
+            }
+            None => true,
+        }
+    }
+
+    /// Records that the content of `content_key` was served to a peer, both in the served content
+    /// and in the store, which may protect it from eviction.
+    fn record_served(&self, content_key: &TContentKey) {
+        self.served_content.lock().record(
+            &content_key.content_id(),
+            content_key.to_bytes(),
+            Instant::now(),
+        );
+        let result = self.store.read().record_served(content_key);
+        self.store_health.report(&result);
+    }
+
+    /// Returns the ENRs closest to `content_key`, other than the one of `source`, that fit in a
+    /// `Content` response.
+    ///
+    /// Peers that recently accepted the content are listed first, since they likely hold it.
+    fn closest_content_enrs(&self, content_key: &TContentKey, source: &NodeId) -> Vec<SszEnr> {
+        let content_id = content_key.content_id();
+        let holder_enrs: Vec<Enr> = match &self.content_holders {
+            Some(content_holders) => content_holders
+                .lock()
+                .holders(&content_id, Instant::now())
+                .iter()
+                .filter_map(|node_id| self.find_enr(node_id))
+                .collect(),
+            None => vec![],
+        };
+        let closest_enrs = self
+            .kbuckets
+            .closest_to_content_id::<TMetric>(&content_id, FIND_CONTENT_MAX_NODES);
+
+        let mut node_ids = HashSet::new();
+        let mut enrs = holder_enrs
+            .into_iter()
+            .chain(closest_enrs)
+            .filter(|enr| &enr.node_id() != source && node_ids.insert(enr.node_id()))
+            .take(FIND_CONTENT_MAX_NODES)
+            .map(SszEnr)
+            .collect::<Vec<_>>();
+        pop_while_ssz_bytes_len_gt(&mut enrs, MAX_PORTAL_CONTENT_PAYLOAD_SIZE);
+        enrs
+    }
+
     /// Attempts to build an `Accept` response for an `Offer` request.
     #[allow(clippy::result_large_err)]
     fn handle_offer(
@@ -1110,9 +1463,36 @@ impl<
         //   should be avoided.
         // so by trying to acquire the semaphore before the storage call we avoid unnecessary work
         // **Note:** if we are not accepting any content `requested_keys` should be empty
+        if self.store_health.is_degraded() {
+            if let Some(mut record) = self.new_offer_log_record(&request, source) {
+                record.decline_all(OfferDeclineReason::StoreUnavailable);
+                self.log_offer(record);
+            }
+            return Ok(Accept {
+                connection_id: 0,
+                content_keys: requested_keys,
+            });
+        }
         let permit = match self.utp_controller.get_inbound_semaphore() {
             Some(permit) => permit,
             None => {
+                if let Some(mut record) = self.new_offer_log_record(&request, source) {
+                    record.decline_all(OfferDeclineReason::TransferLimitReached);
+                    self.log_offer(record);
+                }
+                return Ok(Accept {
+                    connection_id: 0,
+                    content_keys: requested_keys,
+                });
+            }
+        };
+        let peer_permit = match self.utp_controller.get_peer_permit(*source) {
+            Some(peer_permit) => peer_permit,
+            None => {
+                if let Some(mut record) = self.new_offer_log_record(&request, source) {
+                    record.decline_all(OfferDeclineReason::PeerTransferLimitReached);
+                    self.log_offer(record);
+                }
                 return Ok(Accept {
                     connection_id: 0,
                     content_keys: requested_keys,
@@ -1132,6 +1512,7 @@ impl<
             })?;
 
         let mut accepted_keys: Vec<TContentKey> = Vec::default();
+        let mut offer_log_record = self.new_offer_log_record(&request, source);
 
         // if we're unable to find the ENR for the source node we throw an error
         // since the enr is required for the accept queue, and it is expected to be present
@@ -1141,23 +1522,47 @@ impl<
             )
         })?;
         for (i, key) in content_keys.iter().enumerate() {
-            // Accept content if within radius and not already present in the data store.
-            let mut accept = self
-                .store
-                .read()
-                .is_key_within_radius_and_unavailable(key)
-                .map(|value| matches!(value, ShouldWeStoreContent::Store))
-                .map_err(|err| {
+            // Accept content if not denied, within radius and not already present in the data
+            // store, unless content that is already present is accepted too.
+            let decline_reason = if self.denylist.read().is_content_denied(&key.content_id()) {
+                Some(OfferDeclineReason::Denied)
+            } else {
+                let should_store = self.store.read().is_key_within_radius_and_unavailable(key);
+                self.store_health.report(&should_store);
+                let should_store = should_store.map_err(|err| {
                     OverlayRequestError::AcceptError(format!(
                         "Unable to check content availability {err}"
                     ))
                 })?;
+                match should_store {
+                    ShouldWeStoreContent::NotWithinRadius => {
+                        Some(OfferDeclineReason::NotWithinRadius)
+                    }
+                    ShouldWeStoreContent::AlreadyStored if !self.accept_duplicate_content => {
+                        self.metrics.report_offer_duplicate_declined();
+                        Some(OfferDeclineReason::AlreadyStored)
+                    }
+                    // accept all keys that are successfully added to the queue
+                    ShouldWeStoreContent::Store | ShouldWeStoreContent::AlreadyStored => {
+                        if self.accept_queue.write().add_key_to_queue(key, &enr) {
+                            None
+                        } else {
+                            Some(OfferDeclineReason::AlreadyQueued)
+                        }
+                    }
+                }
+            };
+            let accept = decline_reason.is_none();
             if accept {
-                // accept all keys that are successfully added to the queue
-                if self.accept_queue.write().add_key_to_queue(key, &enr) {
-                    accepted_keys.push(key.clone());
-                } else {
-                    accept = false;
+                accepted_keys.push(key.clone());
+            }
+            if let Some(record) = &mut offer_log_record {
+                match decline_reason {
+                    None => record.accepted.push(key.to_bytes()),
+                    Some(reason) => record.declined.push(DeclinedContentKey {
+                        content_key: key.to_bytes(),
+                        reason,
+                    }),
                 }
             }
             requested_keys.set(i, accept).map_err(|err| {
@@ -1166,6 +1571,9 @@ impl<
                 ))
             })?;
         }
+        if let Some(record) = offer_log_record {
+            self.log_offer(record);
+        }
 
         // If no content keys were accepted, then return an Accept with a connection ID value of
         // zero.
@@ -1237,6 +1645,7 @@ impl<
                         .collect();
                     let _ = join_all(handles).await;
                     drop(permit);
+                    drop(peer_permit);
                     return;
                 }
             };
@@ -1261,6 +1670,7 @@ impl<
                         .collect();
                     let _ = join_all(handles).await;
                     drop(permit);
+                    drop(peer_permit);
                     return;
                 }
             };
@@ -1318,9 +1728,12 @@ impl<
                 &utp_processing.kbuckets,
                 utp_processing.command_tx.clone(),
                 Some(utp_processing.utp_controller),
+                utp_processing.gossip_history.as_ref(),
+                utp_processing.gossip_peer_retries,
             );
             // explicitly drop semaphore permit in thread so the permit is moved into the thread
             drop(permit);
+            drop(peer_permit);
         });
 
         let accept = Accept {
@@ -1331,6 +1744,24 @@ impl<
         Ok(accept)
     }
 
+    /// Returns a record of the offer for the offer log, if one is configured.
+    fn new_offer_log_record(&self, request: &Offer, source: &NodeId) -> Option<OfferLogRecord> {
+        self.offer_log.as_ref()?;
+        Some(OfferLogRecord::new(
+            self.protocol.to_string(),
+            *source,
+            self.find_enr(source),
+            request.content_keys.clone(),
+        ))
+    }
+
+    /// Queues the record to be appended to the offer log, if one is configured.
+    fn log_offer(&self, record: OfferLogRecord) {
+        if let Some(offer_log) = &self.offer_log {
+            offer_log.append(record);
+        }
+    }
+
     /// Sends a TALK request via Discovery v5 to some destination node.
     fn send_talk_req(&self, request: Request, request_id: OverlayRequestId, destination: Enr) {
         let discovery = Arc::clone(&self.discovery);
@@ -1422,6 +1853,42 @@ impl<
         }
     }
 
+    /// Returns `true` if a lookup or offer request can be sent to the peer, which is the case
+    /// unless its circuit is open. If its circuit is half-open, the request is the probe.
+    fn try_circuit_request(&self, node_id: &NodeId) -> bool {
+        self.circuit_breaker
+            .as_ref()
+            .map_or(true, |circuit_breaker| {
+                circuit_breaker.lock().try_request(node_id, Instant::now())
+            })
+    }
+
+    /// Records the failed request as the last error of the gossip subsystem if it's an offer, or
+    /// of the discovery subsystem otherwise.
+    fn record_request_failure(
+        &self,
+        request: &Request,
+        destination: &Enr,
+        error: &OverlayRequestError,
+    ) {
+        let (subsystem, kind) = match request {
+            Request::Ping(_) => (ErrorSubsystem::Discovery, "Ping"),
+            Request::FindNodes(_) => (ErrorSubsystem::Discovery, "FindNodes"),
+            Request::FindContent(_) => (ErrorSubsystem::Discovery, "FindContent"),
+            Request::Offer(_)
+            | Request::PopulatedOffer(_)
+            | Request::PopulatedOfferWithResult(_)
+            | Request::PopulatedOfferWithTransfer(_) => (ErrorSubsystem::Gossip, "Offer"),
+        };
+        self.last_errors.record(
+            subsystem,
+            format!(
+                "{kind} request to {} failed: {error}",
+                destination.node_id()
+            ),
+        );
+    }
+
     /// Processes a failed request intended for some destination node.
     fn process_request_failure(
         &mut self,
@@ -1439,9 +1906,15 @@ impl<
 
         // Attempt to mark the node as disconnected.
         let node_id = destination.node_id();
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker
+                .lock()
+                .record_failure(&node_id, Instant::now());
+        }
         let _ = self.update_node_connection_state(node_id, ConnectionState::Disconnected);
         // Remove the node from the ping queue.
         self.peers_to_ping.remove(&node_id);
+        self.new_peers_to_gossip.remove(&node_id);
     }
 
     /// Processes a response to an outgoing request from some source node.
@@ -1453,6 +1926,10 @@ impl<
         query_id: Option<QueryId>,
         request_permit: Option<OwnedSemaphorePermit>,
     ) {
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.lock().record_success(&source.node_id());
+        }
+
         // If the node is present in the routing table, but the node is not connected, then
         // use the existing entry's value and direction. Otherwise, build a new entry from
         // the source ENR and establish a connection in the outgoing direction, because this
@@ -1495,7 +1972,7 @@ impl<
 
         match response {
             Response::Pong(pong) => self.process_pong(pong, source),
-            Response::Nodes(nodes) => self.process_nodes(nodes, source, query_id),
+            Response::Nodes(nodes) => self.process_nodes(nodes, source, &request, query_id),
             Response::Content(content) => self.process_content(content, source, query_id),
             Response::Accept(accept) => {
                 if let Err(err) = self.process_accept(accept, source, request, request_permit) {
@@ -1515,17 +1992,62 @@ impl<
     ) -> anyhow::Result<Accept> {
         // Check that a valid triggering request was sent
         let mut gossip_result_tx = None;
+        let mut transfer_result_tx = None;
         match &offer {
             Request::Offer(_) => {}
             Request::PopulatedOffer(_) => {}
             Request::PopulatedOfferWithResult(req) => {
                 gossip_result_tx = Some(req.result_tx.clone())
             }
+            Request::PopulatedOfferWithTransfer(req) => {
+                transfer_result_tx = Some(req.result_tx.clone())
+            }
             _ => {
                 return Err(anyhow!("Invalid request message paired with ACCEPT"));
             }
         };
 
+        if let (Some(gossip_history), Request::PopulatedOffer(offer)) =
+            (&self.gossip_history, &offer)
+        {
+            let mut gossip_history = gossip_history.lock();
+            for (is_accepted, (content_key, _)) in
+                response.content_keys.iter().zip(&offer.content_items)
+            {
+                if let Ok(content_key) = TContentKey::try_from_bytes(content_key) {
+                    gossip_history.record_offer_result(&content_key.content_id(), is_accepted);
+                }
+            }
+        }
+
+        if let Some(content_holders) = &self.content_holders {
+            let offered_keys: Vec<RawContentKey> = match &offer {
+                Request::Offer(offer) => offer.content_keys.clone(),
+                Request::PopulatedOffer(offer) => offer
+                    .content_items
+                    .iter()
+                    .map(|(content_key, _)| content_key.clone())
+                    .collect(),
+                Request::PopulatedOfferWithResult(offer) => vec![offer.content_item.0.clone()],
+                Request::PopulatedOfferWithTransfer(offer) => offer
+                    .content_items
+                    .iter()
+                    .map(|(content_key, _)| content_key.clone())
+                    .collect(),
+                _ => vec![],
+            };
+            let now = Instant::now();
+            let mut content_holders = content_holders.lock();
+            for (is_accepted, content_key) in response.content_keys.iter().zip(&offered_keys) {
+                if !is_accepted {
+                    continue;
+                }
+                if let Ok(content_key) = TContentKey::try_from_bytes(content_key) {
+                    content_holders.record(&content_key.content_id(), enr.node_id(), now);
+                }
+            }
+        }
+
         // Do not initialize uTP stream if remote node doesn't have interest in the offered content
         // keys
         if response.content_keys.is_zero() {
@@ -1565,6 +2087,13 @@ impl<
                     .filter(|(is_accepted, _item)| *is_accepted)
                     .map(|(_is_accepted, (_key, val))| val)
                     .collect()),
+                Request::PopulatedOfferWithTransfer(offer) => Ok(response_clone
+                    .content_keys
+                    .iter()
+                    .zip(offer.content_items)
+                    .filter(|(is_accepted, _item)| *is_accepted)
+                    .map(|(_is_accepted, (_key, val))| val)
+                    .collect()),
                 // Unreachable because of early return at top of method:
                 _ => Err(anyhow!("Invalid request message paired with ACCEPT")),
             };
@@ -1585,6 +2114,9 @@ impl<
                     if let Some(tx) = gossip_result_tx {
                         let _ = tx.send(OfferTrace::Failed);
                     }
+                    if let Some(tx) = transfer_result_tx {
+                        let _ = tx.send(OfferTransfer::FAILED);
+                    }
                     return;
                 }
             };
@@ -1596,12 +2128,23 @@ impl<
                     if let Some(tx) = gossip_result_tx {
                         let _ = tx.send(OfferTrace::Failed);
                     }
+                    if let Some(tx) = transfer_result_tx {
+                        let _ = tx.send(OfferTransfer::FAILED);
+                    }
                     return;
                 }
             };
+            let transfer_start = Instant::now();
             let result = utp_controller
                 .connect_outbound_stream(cid, &content_payload)
                 .await;
+            if let Some(tx) = transfer_result_tx {
+                let _ = tx.send(OfferTransfer {
+                    success: result,
+                    bytes: content_payload.len(),
+                    duration: transfer_start.elapsed(),
+                });
+            }
             if let Some(tx) = gossip_result_tx {
                 if result {
                     let _ = tx.send(OfferTrace::Success(response_clone.content_keys));
@@ -1621,7 +2164,8 @@ impl<
     /// Validates & stores content value received from peer.
     /// Checks if validated content should be stored, and stores it if true
     /// Returns validated content/content dropped from storage to
-    /// propagate to other peers.
+    /// propagate to other peers. Content that arrived again within the dedup window yields no
+    /// content to propagate.
     // (this step requires a dedicated task since it might require
     // non-blocking requests to this/other overlay networks).
     async fn validate_and_store_content(
@@ -1629,6 +2173,41 @@ impl<
         content_value: RawContentValue,
         utp_processing: UtpProcessing<TValidator, TStore, TContentKey>,
     ) -> Option<Vec<(TContentKey, RawContentValue)>> {
+        // Skip storing & propagating denied content
+        if utp_processing
+            .denylist
+            .read()
+            .is_content_denied(&key.content_id())
+        {
+            warn!(
+                content.key = %key.to_hex(),
+                "Accepted content is denied - not storing or propagating it"
+            );
+            return None;
+        }
+
+        // Skip storing & propagating content that was already processed, e.g. because multiple
+        // peers gossiped it at about the same time
+        if let Some(seen_content) = &utp_processing.seen_content {
+            if seen_content
+                .lock()
+                .mark_seen(&key.content_id(), Instant::now())
+            {
+                utp_processing.metrics.report_gossip_dedup_hit();
+                debug!(
+                    content.key = %key.to_hex(),
+                    "Accepted content arrived again within the dedup window - not storing or propagating it"
+                );
+                return Some(vec![]);
+            }
+        }
+        // Invalid content is forgotten, so that a valid copy of it is processed.
+        let forget_seen_content = || {
+            if let Some(seen_content) = &utp_processing.seen_content {
+                seen_content.lock().forget(&key.content_id());
+            }
+        };
+
         // Validate received content
         let validation_result = utp_processing
             .validator
@@ -1647,6 +2226,7 @@ impl<
                     content.key = %key.to_hex(),
                     "Error validating accepted content"
                 );
+                forget_seen_content();
                 return None;
             }
         };
@@ -1658,15 +2238,22 @@ impl<
                 content.key = %key.to_hex(),
                 "Error validating accepted content - not valid for storing"
             );
+            forget_seen_content();
             return None;
         }
 
         // Collect all content to propagate
-        let mut content_to_propagate = vec![(key.clone(), content_value.clone())];
-        if let Some(additional_content_to_propagate) =
-            validation_result.additional_content_to_propagate
-        {
-            content_to_propagate.push(additional_content_to_propagate);
+        let mut content_to_propagate =
+            utp_processing.content_to_propagate(&key, &content_value, validation_result);
+
+        // The store became unavailable while the content was being received, so it is only
+        // propagated.
+        if utp_processing.store_health.is_degraded() {
+            warn!(
+                content.key = %key.to_hex(),
+                "Content store unavailable - not storing accepted content"
+            );
+            return Some(content_to_propagate);
         }
 
         // Check if data should be stored, and store if it is within our radius and not
@@ -1675,13 +2262,17 @@ impl<
             .store
             .read()
             .is_key_within_radius_and_unavailable(&key);
+        utp_processing.store_health.report(&key_desired);
         match key_desired {
             Ok(ShouldWeStoreContent::Store) => {
-                match utp_processing
-                    .store
-                    .write()
-                    .put(key.clone(), &content_value)
-                {
+                let put_result = put_content(
+                    &utp_processing.store,
+                    utp_processing.mirror.as_ref(),
+                    key.clone(),
+                    content_value.clone(),
+                );
+                utp_processing.store_health.report(&put_result);
+                match put_result {
                     Ok(dropped_content) => {
                         if !dropped_content.is_empty() && utp_processing.gossip_dropped {
                             // add dropped content to validation result, so it will be propagated
@@ -1702,8 +2293,10 @@ impl<
                     "Accepted content outside radius"
                 );
             }
+            // Content that is already stored is only accepted with `accept_duplicate_content`, or
+            // if it was stored while being received.
             Ok(ShouldWeStoreContent::AlreadyStored) => {
-                warn!(
+                debug!(
                     content.key = %key.to_hex(),
                     "Accepted content already stored"
                 );
@@ -1794,6 +2387,8 @@ impl<
             &utp_processing.kbuckets,
             utp_processing.command_tx.clone(),
             Some(utp_processing.utp_controller),
+            utp_processing.gossip_history.as_ref(),
+            utp_processing.gossip_peer_retries,
         );
         Ok(())
     }
@@ -1801,7 +2396,7 @@ impl<
     /// Processes a Pong response.
     ///
     /// Refreshes the node if necessary. Attempts to mark the node as connected.
-    fn process_pong(&self, pong: Pong, source: Enr) {
+    fn process_pong(&mut self, pong: Pong, source: Enr) {
         let node_id = source.node_id();
         trace!(
             protocol = %self.protocol,
@@ -1820,11 +2415,92 @@ impl<
 
             let data_radius: Distance = pong.custom_payload.into();
             if node.data_radius != data_radius {
-                self.update_node_radius(source, data_radius);
+                self.update_node_radius(source.clone(), data_radius);
+            }
+
+            // Now that the radius of a newly discovered peer is known, offer it our content.
+            if self.new_peers_to_gossip.remove(&node_id) {
+                self.offer_content_to_new_peer(source, data_radius);
             }
         }
     }
 
+    /// Offers locally stored content that falls within the radius of a newly discovered peer.
+    ///
+    /// At most `NEW_PEER_GOSSIP_MAX_CONTENT_ITEMS` content items are offered, in a single offer.
+    fn offer_content_to_new_peer(&self, enr: Enr, data_radius: Distance) {
+        let store = Arc::clone(&self.store);
+        let command_tx = self.command_tx.clone();
+        let utp_controller = Arc::clone(&self.utp_controller);
+        tokio::spawn(async move {
+            let node_id = enr.node_id();
+            // The store is scanned on a blocking thread, so that async workers aren't held up.
+            let lookup = tokio::task::spawn_blocking(move || {
+                store.read().content_within_radius_of(
+                    &node_id,
+                    data_radius,
+                    NEW_PEER_GOSSIP_MAX_CONTENT_ITEMS,
+                )
+            });
+            let content = match lookup.await {
+                Ok(Ok(content)) => content,
+                Ok(Err(err)) => {
+                    warn!(
+                        error = %err,
+                        peer.node_id = %node_id,
+                        "Unable to look up content to offer to new peer"
+                    );
+                    return;
+                }
+                Err(err) => {
+                    warn!(
+                        error = %err,
+                        peer.node_id = %node_id,
+                        "Content lookup task for new peer failed"
+                    );
+                    return;
+                }
+            };
+            if content.is_empty() {
+                return;
+            }
+
+            // If we have met the max outbound utp transfer limit, skip offering to this peer.
+            let Some(permit) = utp_controller.get_outbound_semaphore() else {
+                debug!(
+                    peer.node_id = %node_id,
+                    "Unable to offer content to new peer, outbound uTP transfer limit reached"
+                );
+                return;
+            };
+
+            let content_count = content.len();
+            let content_items = content
+                .into_iter()
+                .map(|(key, value)| (key.to_bytes(), value))
+                .collect();
+            let request = OverlayRequest::new(
+                Request::PopulatedOffer(PopulatedOffer { content_items }),
+                RequestDirection::Outgoing { destination: enr },
+                None,
+                None,
+                Some(permit),
+            );
+            match command_tx.send(OverlayCommand::Request(request)) {
+                Ok(_) => debug!(
+                    peer.node_id = %node_id,
+                    content.count = content_count,
+                    "Offered stored content to new peer"
+                ),
+                Err(err) => warn!(
+                    peer.node_id = %node_id,
+                    %err,
+                    "Failed to offer stored content to new peer"
+                ),
+            }
+        });
+    }
+
     /// Update the recorded radius of a node in our routing table.
     fn update_node_radius(&self, enr: Enr, data_radius: Distance) {
         let node_id = enr.node_id();
@@ -1839,7 +2515,16 @@ impl<
     }
 
     /// Processes a Nodes response.
-    fn process_nodes(&mut self, nodes: Nodes, source: Enr, query_id: Option<QueryId>) {
+    ///
+    /// ENRs that aren't at any of the distances requested from the source are discarded, so that
+    /// they can't pollute the routing table.
+    fn process_nodes(
+        &mut self,
+        nodes: Nodes,
+        source: Enr,
+        request: &Request,
+        query_id: Option<QueryId>,
+    ) {
         trace!(
             protocol = %self.protocol,
             response.source = %source.node_id(),
@@ -1852,6 +2537,24 @@ impl<
             .into_iter()
             .map(|ssz_enr| ssz_enr.into())
             .collect();
+        let enrs = match request {
+            Request::FindNodes(find_nodes) => {
+                let (enrs, out_of_range) =
+                    partition_enrs_by_distances(&source.node_id(), &find_nodes.distances, enrs);
+                if !out_of_range.is_empty() {
+                    debug!(
+                        protocol = %self.protocol,
+                        response.source = %source.node_id(),
+                        distances = ?find_nodes.distances,
+                        filtered = ?out_of_range.iter().map(|enr| enr.node_id()).collect::<Vec<_>>(),
+                        "Filtered ENRs that aren't at the requested distances from a Nodes response",
+                    );
+                }
+                enrs
+            }
+            _ => enrs,
+        };
+        let enrs = self.without_denied(enrs);
 
         self.process_discovered_enrs(enrs.clone());
         if let Some(query_id) = query_id {
@@ -1859,6 +2562,13 @@ impl<
         }
     }
 
+    /// Removes the ENRs of denied nodes, so that queries never contact them.
+    fn without_denied(&self, enrs: Vec<Enr>) -> Vec<Enr> {
+        enrs.into_iter()
+            .filter(|enr| !self.kbuckets.is_denied(&enr.node_id()))
+            .collect()
+    }
+
     /// Processes a Content response.
     fn process_content(&mut self, content: Content, source: Enr, query_id: Option<QueryId>) {
         trace!(
@@ -1880,6 +2590,7 @@ impl<
             }
             Content::Enrs(enrs) => {
                 let enrs: Vec<Enr> = enrs.into_iter().map(|ssz_enr| ssz_enr.into()).collect();
+                let enrs = self.without_denied(enrs);
                 self.process_discovered_enrs(enrs.clone());
                 if let Some(query_id) = query_id {
                     self.advance_find_content_query_with_enrs(&query_id, source, enrs);
@@ -1888,6 +2599,56 @@ impl<
         }
     }
 
+    /// Receives the content of a FindContent query over the uTP stream with `cid`.
+    ///
+    /// A failed transfer is retried up to `FIND_CONTENT_UTP_RETRIES` times, by requesting the
+    /// content from the same peer again. The portal wire protocol has no way to request the
+    /// remainder of a partially transferred content, so each retry restarts the transfer from
+    /// scratch.
+    async fn receive_find_content_stream(
+        utp_processing: &UtpProcessing<TValidator, TStore, TContentKey>,
+        mut cid: ConnectionId<UtpEnr>,
+        content_key: &TContentKey,
+    ) -> anyhow::Result<RawContentValue> {
+        let mut retries = 0;
+        loop {
+            let err = match utp_processing
+                .utp_controller
+                .connect_inbound_stream(cid.clone())
+                .await
+            {
+                Ok(data) => return Ok(RawContentValue::from(data)),
+                Err(err) => err,
+            };
+            if retries == FIND_CONTENT_UTP_RETRIES {
+                return Err(err);
+            }
+            retries += 1;
+            let UtpEnr(source) = cid.peer;
+            debug!(
+                %err,
+                peer = %source.node_id(),
+                retries,
+                "Retrying failed uTP transfer for FindContent"
+            );
+
+            match request_find_content_retry(&utp_processing.command_tx, &source, content_key).await
+            {
+                Some(Content::ConnectionId(connection_id)) => {
+                    let connection_id = u16::from_be(connection_id);
+                    cid = ConnectionId {
+                        recv: connection_id,
+                        send: connection_id.wrapping_add(1),
+                        peer: UtpEnr(source),
+                    };
+                }
+                Some(Content::Content(content)) => return Ok(content),
+                // The peer no longer offers the content, so the original error is returned.
+                Some(Content::Enrs(_)) | None => return Err(err),
+            }
+        }
+    }
+
     // This method should be used in a non-blocking thread to allow for
     // requests to this/other overlay services.
     #[allow(clippy::too_many_arguments)]
@@ -1903,8 +2664,16 @@ impl<
         query_trace_events_tx: Option<UnboundedSender<QueryTraceEvent>>,
     ) {
         let mut content = content;
-        // Operate under assumption that all content in the store is valid
-        let local_value = utp_processing.store.read().get(&content_key);
+        // Operate under assumption that all content in the store is valid. While the store is
+        // unavailable, the content is validated and returned without touching the store.
+        let store_degraded = utp_processing.store_health.is_degraded();
+        let local_value = if store_degraded {
+            Ok(None)
+        } else {
+            let local_value = utp_processing.store.read().get(&content_key);
+            utp_processing.store_health.report(&local_value);
+            local_value
+        };
         if let Ok(Some(val)) = local_value {
             // todo validate & replace content value if different & punish bad peer
             content = val;
@@ -1940,33 +2709,44 @@ impl<
                 }
             };
 
-            // skip storing if content is not valid for storing, the content
-            // is already stored or if there's an error reading the store
+            // skip storing if content is not valid for storing, the content is denied, the
+            // store is unavailable, the content is already stored or if there's an error reading
+            // the store
             let should_store = validation_result.valid_for_storing
-                && utp_processing
-                    .store
+                && !store_degraded
+                && !utp_processing
+                    .denylist
                     .read()
-                    .is_key_within_radius_and_unavailable(&content_key)
-                    .map_or_else(
+                    .is_content_denied(&content_key.content_id())
+                && {
+                    let key_desired = utp_processing
+                        .store
+                        .read()
+                        .is_key_within_radius_and_unavailable(&content_key);
+                    utp_processing.store_health.report(&key_desired);
+                    key_desired.map_or_else(
                         |err| {
                             error!("Unable to read store: {err}");
                             false
                         },
                         |val| matches!(val, ShouldWeStoreContent::Store),
-                    );
+                    )
+                };
             if should_store {
-                match utp_processing
-                    .store
-                    .write()
-                    .put(content_key.clone(), content.clone())
-                {
+                let put_result = put_content(
+                    &utp_processing.store,
+                    utp_processing.mirror.as_ref(),
+                    content_key.clone(),
+                    content.clone(),
+                );
+                utp_processing.store_health.report(&put_result);
+                match put_result {
                     Ok(dropped_content) => {
-                        let mut content_to_propagate = vec![(content_key.clone(), content.clone())];
-                        if let Some(additional_content_to_propagate) =
-                            validation_result.additional_content_to_propagate
-                        {
-                            content_to_propagate.push(additional_content_to_propagate);
-                        }
+                        let mut content_to_propagate = utp_processing.content_to_propagate(
+                            &content_key,
+                            &content,
+                            validation_result,
+                        );
                         if !dropped_content.is_empty() && utp_processing.gossip_dropped {
                             debug!(
                                 "Dropped {:?} pieces of content after inserting new content, propagating them back into the network.",
@@ -1979,6 +2759,8 @@ impl<
                             &utp_processing.kbuckets,
                             utp_processing.command_tx.clone(),
                             Some(utp_processing.utp_controller.clone()),
+                            utp_processing.gossip_history.as_ref(),
+                            utp_processing.gossip_peer_retries,
                         );
                     }
                     Err(err) => error!(
@@ -2029,10 +2811,16 @@ impl<
         } = self.kbuckets.insert_or_update_discovered_nodes(enrs);
 
         for node_id in inserted_nodes {
+            self.report_routing_table_change(RoutingTableChangeLabel::Inserted);
             self.peers_to_ping.insert(node_id);
+            if self.gossip_on_new_peer {
+                self.new_peers_to_gossip.insert(node_id);
+            }
         }
         for node_id in removed_nodes {
+            self.report_routing_table_change(RoutingTableChangeLabel::Evicted);
             self.peers_to_ping.remove(&node_id);
+            self.new_peers_to_gossip.remove(&node_id);
         }
     }
 
@@ -2135,8 +2923,17 @@ impl<
             // Mark the query successful for the source of the response with the closest ENRs.
             query.on_success(
                 &source.node_id(),
-                FindContentQueryResponse::ClosestNodes(closest_nodes),
+                FindContentQueryResponse::ClosestNodes(closest_nodes.clone()),
             );
+            if let Some(hop_stream) = &query_info.hop_stream {
+                hop_stream.send(
+                    source.node_id(),
+                    QueryHopResponse::Nodes {
+                        enrs: closest_nodes,
+                    },
+                    query.closest_known_peers(),
+                );
+            }
         }
     }
 
@@ -2156,6 +2953,13 @@ impl<
                 &source.node_id(),
                 FindContentQueryResponse::ConnectionId(utp),
             );
+            if let Some(hop_stream) = &query_info.hop_stream {
+                hop_stream.send(
+                    source.node_id(),
+                    QueryHopResponse::Content,
+                    query.closest_known_peers(),
+                );
+            }
         }
     }
 
@@ -2176,6 +2980,28 @@ impl<
                 &source.node_id(),
                 FindContentQueryResponse::Content(content),
             );
+            if let Some(hop_stream) = &query_info.hop_stream {
+                hop_stream.send(
+                    source.node_id(),
+                    QueryHopResponse::Content,
+                    query.closest_known_peers(),
+                );
+            }
+        }
+    }
+
+    /// Streams the response of a peer contacted by a find content query (if one exists for
+    /// `query_id`) whose progress is being followed.
+    fn stream_find_content_query_hop(
+        &mut self,
+        query_id: &QueryId,
+        peer: NodeId,
+        response: QueryHopResponse,
+    ) {
+        if let Some((query_info, query)) = self.find_content_query_pool.get_mut(*query_id) {
+            if let Some(hop_stream) = &query_info.hop_stream {
+                hop_stream.send(peer, response, query.closest_known_peers());
+            }
         }
     }
 
@@ -2239,8 +3065,12 @@ impl<
                     inserted = %node_id,
                     "Node inserted into routing table",
                 );
+                self.report_routing_table_change(RoutingTableChangeLabel::Inserted);
 
                 self.peers_to_ping.insert(node_id);
+                if self.gossip_on_new_peer {
+                    self.new_peers_to_gossip.insert(node_id);
+                }
             }
             InsertResult::Pending { disconnected } => {
                 // The disconnected node is the least-recently connected entry that is
@@ -2309,15 +3139,14 @@ impl<
         }
     }
 
-    /// Starts a FindNode query to find nodes with IDs closest to `target`.
+    /// Starts a FindNode query to find up to `num_results` nodes with IDs closest to `target`.
     fn init_find_nodes_query(
         &mut self,
         target: &NodeId,
+        num_results: usize,
         callback: Option<oneshot::Sender<Vec<Enr>>>,
     ) -> Option<QueryId> {
-        let closest_enrs = self
-            .kbuckets
-            .closest_to_node_id(*target, self.query_num_results);
+        let closest_enrs = self.kbuckets.closest_to_node_id(*target, num_results);
         if closest_enrs.is_empty() {
             // If there are no nodes whatsoever in the routing table the query cannot proceed.
             warn!("No nodes in routing table, find nodes query cannot proceed.");
@@ -2326,10 +3155,12 @@ impl<
             }
             return None;
         }
+        self.kbuckets.mark_refreshed(target);
 
         let query_config = QueryConfig {
             parallelism: self.query_parallelism,
-            num_results: self.query_num_results,
+            num_results,
+            max_hops: self.query_max_hops,
             peer_timeout: self.query_peer_timeout,
             overall_timeout: self.query_timeout,
         };
@@ -2342,6 +3173,8 @@ impl<
             },
             untrusted_enrs: SmallVec::from_vec(closest_enrs),
             trace: None,
+            return_trace: false,
+            hop_stream: None,
         };
 
         let known_closest_peers: Vec<Key<NodeId>> = query_info
@@ -2379,6 +3212,7 @@ impl<
         let query_config = QueryConfig {
             parallelism: self.query_parallelism,
             num_results: self.query_num_results,
+            max_hops: self.query_max_hops,
             peer_timeout: self.query_peer_timeout,
             overall_timeout: config.timeout.unwrap_or(self.query_timeout),
         };
@@ -2386,7 +3220,7 @@ impl<
         let closest_enrs = self
             .kbuckets
             .closest_to_content_id::<TMetric>(&target.content_id(), query_config.num_results);
-        if closest_enrs.is_empty() {
+        if closest_enrs.is_empty() && config.hint_peer.is_none() {
             // If there are no connected nodes in the routing table the query cannot proceed.
             warn!("No connected nodes in routing table, find content query cannot proceed.");
             if let Some(callback) = callback {
@@ -2407,23 +3241,37 @@ impl<
             .collect();
 
         let trace: Option<QueryTrace> = {
-            if config.is_trace {
+            if config.is_trace || self.query_traces.lock().is_enabled() {
                 let mut trace = QueryTrace::new(&self.local_enr(), target_node_id.raw().into());
                 let local_enr = self.local_enr();
                 trace.node_responded_with(&local_enr, closest_enrs.iter().collect());
+                if let Some(hint_peer) = &config.hint_peer {
+                    trace.hint_peer_contacted(hint_peer);
+                }
                 Some(trace)
             } else {
                 None
             }
         };
 
+        let mut query = FindContentQuery::with_config(query_config, target_key, closest_nodes);
+        let mut untrusted_enrs = SmallVec::from_vec(closest_enrs);
+        if let Some(hint_peer) = config.hint_peer {
+            query = query.with_hint_peer(Key::from(hint_peer.node_id()));
+            // The hint peer's ENR must be known to the query in order to contact it.
+            if !untrusted_enrs.contains(&hint_peer) {
+                untrusted_enrs.push(hint_peer);
+            }
+        }
+
         let query_info = QueryInfo {
             query_type: QueryType::FindContent { target, callback },
-            untrusted_enrs: SmallVec::from_vec(closest_enrs),
+            untrusted_enrs,
             trace,
+            return_trace: config.is_trace,
+            hop_stream: config.hop_events.map(QueryHopStream::new),
         };
 
-        let query = FindContentQuery::with_config(query_config, target_key, closest_nodes);
         Some(self.find_content_query_pool.add_query(query_info, query))
     }
 
@@ -2518,6 +3366,57 @@ fn pop_while_ssz_bytes_len_gt(enrs: &mut Vec<SszEnr>, max_size: usize) {
     }
 }
 
+/// Stores the content, and offers it to the mirror peer, if any, once it's stored. All content
+/// stored by the overlay goes through here, so that the mirror peer receives all of it.
+///
+/// Returns the content that was pruned to make room for it.
+/// Requests the content of `content_key` from `source` again, to retry a failed uTP transfer of a
+/// FindContent query.
+///
+/// Returns the peer's response, which is the connection id of a new transfer, the content itself
+/// if it's small enough, or ENRs if the peer no longer has the content. Returns `None` if the
+/// request failed.
+async fn request_find_content_retry<TContentKey: OverlayContentKey>(
+    command_tx: &UnboundedSender<OverlayCommand<TContentKey>>,
+    source: &Enr,
+    content_key: &TContentKey,
+) -> Option<Content> {
+    let (tx, rx) = oneshot::channel();
+    let request = OverlayRequest::new(
+        Request::FindContent(FindContent {
+            content_key: content_key.to_bytes(),
+        }),
+        RequestDirection::Outgoing {
+            destination: source.clone(),
+        },
+        Some(tx),
+        None,
+        None,
+    );
+    command_tx.send(OverlayCommand::Request(request)).ok()?;
+    match rx.await {
+        Ok(Ok(Response::Content(content))) => Some(content),
+        _ => None,
+    }
+}
+
+pub(crate) fn put_content<TStore: ContentStore>(
+    store: &RwLock<TStore>,
+    mirror: Option<&Mirror>,
+    content_key: TStore::Key,
+    content_value: RawContentValue,
+) -> Result<Vec<(TStore::Key, RawContentValue)>, ContentStoreError>
+where
+    TStore::Key: OverlayContentKey,
+{
+    let raw_content_key = content_key.to_bytes();
+    let dropped_content = store.write().put(content_key, &content_value)?;
+    if let Some(mirror) = mirror {
+        mirror.notify_stored(raw_content_key, content_value);
+    }
+    Ok(dropped_content)
+}
+
 /// References to `OverlayService` components required for processing
 /// a utp stream. This is basically a utility struct to avoid passing
 /// around a large number of individual references.
@@ -2536,6 +3435,44 @@ where
     accept_queue: Arc<RwLock<AcceptQueue<TContentKey>>>,
     disable_poke: bool,
     gossip_dropped: bool,
+    gossip_unvalidated: bool,
+    gossip_peer_retries: u32,
+    denylist: SharedDenylist,
+    store_health: SharedStoreHealth,
+    gossip_history: Option<SharedGossipHistory>,
+    mirror: Option<Mirror>,
+    seen_content: Option<SharedSeenContent>,
+}
+
+impl<TValidator, TStore, TContentKey> UtpProcessing<TValidator, TStore, TContentKey>
+where
+    TContentKey: OverlayContentKey + Send + Sync,
+    TValidator: Validator<TContentKey>,
+    TStore: ContentStore<Key = TContentKey>,
+{
+    /// Returns the validated content to propagate to other peers, along with any additional
+    /// content of the validation result.
+    ///
+    /// Content that was trusted without validation is only propagated if explicitly enabled, so
+    /// that the node doesn't amplify invalid content.
+    fn content_to_propagate(
+        &self,
+        key: &TContentKey,
+        content_value: &RawContentValue,
+        validation_result: ValidationResult<TContentKey>,
+    ) -> Vec<(TContentKey, RawContentValue)> {
+        if !validation_result.validated && !self.gossip_unvalidated {
+            self.metrics.report_gossip_blocked_by_validation();
+            debug!(
+                content.key = %key.to_hex(),
+                "Content was stored without validation - not propagating it"
+            );
+            return vec![];
+        }
+        let mut content_to_propagate = vec![(key.clone(), content_value.clone())];
+        content_to_propagate.extend(validation_result.additional_content_to_propagate);
+        content_to_propagate
+    }
 }
 
 impl<TContentKey, TMetric, TValidator, TStore>
@@ -2557,6 +3494,13 @@ where
             accept_queue: Arc::clone(&service.accept_queue),
             disable_poke: service.disable_poke,
             gossip_dropped: service.gossip_dropped,
+            gossip_unvalidated: service.gossip_unvalidated,
+            gossip_peer_retries: service.gossip_peer_retries,
+            denylist: Arc::clone(&service.denylist),
+            store_health: Arc::clone(&service.store_health),
+            gossip_history: service.gossip_history.clone(),
+            mirror: service.mirror.clone(),
+            seen_content: service.seen_content.clone(),
         }
     }
 }
@@ -2578,6 +3522,13 @@ where
             accept_queue: Arc::clone(&self.accept_queue),
             disable_poke: self.disable_poke,
             gossip_dropped: self.gossip_dropped,
+            gossip_unvalidated: self.gossip_unvalidated,
+            gossip_peer_retries: self.gossip_peer_retries,
+            denylist: Arc::clone(&self.denylist),
+            store_health: Arc::clone(&self.store_health),
+            gossip_history: self.gossip_history.clone(),
+            mirror: self.mirror.clone(),
+            seen_content: self.seen_content.clone(),
         }
     }
 }
@@ -2634,6 +3585,11 @@ mod tests {
         config::PortalnetConfig,
         discovery::{Discovery, NodeAddress},
         overlay::config::OverlayConfig,
+        types::{
+            last_errors::LastErrors, query_trace_buffer::QueryTraceBuffer,
+            served_content::ServedContent, store_health::StoreHealth,
+        },
+        utp_controller::UTP_CONN_CFG,
     };
 
     macro_rules! poll_command_rx {
@@ -2665,10 +3621,17 @@ mod tests {
             overlay_metrics: PORTALNET_METRICS.overlay(),
             protocol: "test".to_string(),
         };
+        let last_errors = LastErrors::shared();
         let utp_controller = UtpController::new(
             DEFAULT_UTP_TRANSFER_LIMIT,
+            None,
+            None,
             Arc::new(utp_socket),
             metrics.clone(),
+            *UTP_CONN_CFG,
+            false,
+            Arc::clone(&last_errors),
+            discovery.transfer_sizes(),
         );
         let utp_controller = Arc::new(utp_controller);
 
@@ -2677,13 +3640,18 @@ mod tests {
         let store = Arc::new(RwLock::new(store));
 
         let overlay_config = OverlayConfig::default();
-        let kbuckets = SharedKBucketsTable::new(KBucketsTable::new(
-            node_id.into(),
-            overlay_config.bucket_pending_timeout,
-            overlay_config.max_incoming_per_bucket,
-            overlay_config.table_filter,
-            overlay_config.bucket_filter,
-        ));
+        let denylist = SharedDenylist::default();
+        let kbuckets = SharedKBucketsTable::new(
+            KBucketsTable::new(
+                node_id.into(),
+                overlay_config.bucket_pending_timeout,
+                overlay_config.max_incoming_per_bucket,
+                overlay_config.table_filter,
+                overlay_config.bucket_filter,
+            ),
+            Arc::clone(&denylist),
+            /* circuit_breaker= */ None,
+        );
 
         let protocol = Subnetwork::History;
         let active_outgoing_requests = Arc::new(RwLock::new(HashMap::new()));
@@ -2712,6 +3680,7 @@ mod tests {
             query_peer_timeout: overlay_config.query_peer_timeout,
             query_timeout: overlay_config.query_timeout,
             query_parallelism: overlay_config.query_parallelism,
+            query_max_hops: overlay_config.query_max_hops,
             query_num_results: overlay_config.query_num_results,
             findnodes_query_distances_per_peer: overlay_config.findnodes_query_distances_per_peer,
             response_tx,
@@ -2723,10 +3692,80 @@ mod tests {
             event_stream: broadcast::channel(EVENT_STREAM_CHANNEL_CAPACITY).0,
             disable_poke: false,
             gossip_dropped: false,
+            gossip_unvalidated: false,
+            gossip_peer_retries: 0,
             accept_queue,
+            gossip_on_new_peer: false,
+            new_peers_to_gossip: HashSet::new(),
+            serve_only_in_requester_radius: false,
+            accept_duplicate_content: false,
+            denylist,
+            offer_log: None,
+            store_health: Arc::new(StoreHealth::new(protocol, Arc::clone(&last_errors))),
+            last_errors,
+            query_traces: QueryTraceBuffer::shared(0),
+            gossip_history: None,
+            content_holders: None,
+            circuit_breaker: None,
+            served_content: ServedContent::shared(),
+            mirror: None,
+            seen_content: None,
         }
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn offered_content_already_stored_is_declined() {
+        let mut service = build_service();
+
+        let (_, source) = generate_random_remote_enr();
+        let node_id = source.node_id();
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let _ = service
+            .kbuckets
+            .insert_or_update(Node::new(source, Distance::MAX), status);
+
+        let stored_key = IdentityContentKey::random();
+        service
+            .store
+            .write()
+            .put(stored_key.clone(), [0xef])
+            .unwrap();
+        let declined_counter = service
+            .metrics
+            .overlay_metrics
+            .offer_duplicate_declined_total
+            .with_label_values(&[&service.metrics.protocol]);
+        let declined_count = declined_counter.get();
+
+        let offer = Offer {
+            content_keys: vec![
+                stored_key.to_bytes(),
+                IdentityContentKey::random().to_bytes(),
+            ],
+        };
+        let accept = service
+            .handle_offer(offer, &node_id, RequestId::random())
+            .unwrap();
+        assert!(!accept.content_keys.get(0).unwrap());
+        assert!(accept.content_keys.get(1).unwrap());
+        assert_eq!(declined_counter.get(), declined_count + 1);
+
+        // Content that is already stored is accepted with `accept_duplicate_content`.
+        service.accept_duplicate_content = true;
+        let offer = Offer {
+            content_keys: vec![stored_key.to_bytes()],
+        };
+        let accept = service
+            .handle_offer(offer, &node_id, RequestId::random())
+            .unwrap();
+        assert!(accept.content_keys.get(0).unwrap());
+        assert_eq!(declined_counter.get(), declined_count + 1);
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_ping_source_in_table_higher_enr_seq() {
@@ -2799,6 +3838,40 @@ mod tests {
         assert_pending!(poll_command_rx!(service));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn unvalidated_content_is_only_propagated_if_enabled() {
+        let mut service = build_service();
+        let content_key = IdentityContentKey::new([1; 32]);
+        let content_value = RawContentValue::from(vec![1, 2, 3]);
+
+        let utp_processing = UtpProcessing::from(&service);
+        let content_to_propagate = utp_processing.content_to_propagate(
+            &content_key,
+            &content_value,
+            ValidationResult::new(true),
+        );
+        assert_eq!(
+            content_to_propagate,
+            vec![(content_key.clone(), content_value.clone())]
+        );
+        let content_to_propagate = utp_processing.content_to_propagate(
+            &content_key,
+            &content_value,
+            ValidationResult::new_unvalidated(),
+        );
+        assert!(content_to_propagate.is_empty());
+
+        service.gossip_unvalidated = true;
+        let utp_processing = UtpProcessing::from(&service);
+        let content_to_propagate = utp_processing.content_to_propagate(
+            &content_key,
+            &content_value,
+            ValidationResult::new_unvalidated(),
+        );
+        assert_eq!(content_to_propagate, vec![(content_key, content_value)]);
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_request_failure() {
@@ -2904,6 +3977,73 @@ mod tests {
         assert_pending!(poll_command_rx!(service));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn process_pong_offers_content_to_new_peer() {
+        let mut service = task::spawn(build_service());
+        service.gossip_on_new_peer = true;
+
+        let (_, peer) = generate_random_remote_enr();
+        let content_key = IdentityContentKey::new(peer.node_id().raw());
+        let content = vec![0xef];
+        let _ = service.store.write().put(content_key.clone(), &content);
+
+        service.process_discovered_enrs(vec![peer.clone()]);
+        assert!(service.new_peers_to_gossip.contains(&peer.node_id()));
+
+        let pong = Pong {
+            enr_seq: peer.seq(),
+            custom_payload: CustomPayload::from(Distance::MAX.as_ssz_bytes()),
+        };
+        service.process_pong(pong, peer.clone());
+        assert!(!service.new_peers_to_gossip.contains(&peer.node_id()));
+
+        let command = timeout(Duration::from_secs(1), service.command_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let request = if let OverlayCommand::Request(request) = command {
+            request
+        } else {
+            panic!("Unexpected overlay command variant");
+        };
+        assert_eq!(
+            RequestDirection::Outgoing { destination: peer },
+            request.direction
+        );
+        match request.request {
+            Request::PopulatedOffer(offer) => {
+                assert_eq!(
+                    offer.content_items,
+                    vec![(content_key.to_bytes(), RawContentValue::from(content))]
+                );
+            }
+            _ => panic!("Unexpected request variant"),
+        };
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn requester_radius_is_checked_for_known_peers() {
+        let service = build_service();
+
+        let (_, peer) = generate_random_remote_enr();
+        let node_id = peer.node_id();
+        let near_key = IdentityContentKey::new(node_id.raw());
+        let far_key = IdentityContentKey::new(node_id.raw().map(|byte| !byte));
+        assert!(service.is_within_requester_radius(&far_key, &node_id));
+
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let _ = service
+            .kbuckets
+            .insert_or_update(Node::new(peer, Distance::ZERO), status);
+        assert!(service.is_within_requester_radius(&near_key, &node_id));
+        assert!(!service.is_within_requester_radius(&far_key, &node_id));
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn process_discovered_enrs_local_enr() {
@@ -3339,7 +4479,7 @@ mod tests {
         service.add_bootnodes(bootnodes, true);
 
         // Initialize the query and call `poll` so that it starts
-        service.init_find_nodes_query(&target_node_id, None);
+        service.init_find_nodes_query(&target_node_id, service.query_num_results, None);
         let _ = service.find_node_query_pool.poll();
 
         let expected_distances_per_peer = service.findnodes_query_distances_per_peer;
@@ -3376,7 +4516,7 @@ mod tests {
 
         service.add_bootnodes(bootnodes, true);
         service.query_num_results = 3;
-        service.init_find_nodes_query(&target_node_id, None);
+        service.init_find_nodes_query(&target_node_id, service.query_num_results, None);
 
         // Test that the first query event contains a proper query ID and request to the bootnode
         let event = OverlayService::<
@@ -3497,7 +4637,7 @@ mod tests {
 
         service.add_bootnodes(bootnodes, true);
 
-        service.init_find_nodes_query(&target_node_id, None);
+        service.init_find_nodes_query(&target_node_id, service.query_num_results, None);
 
         let _event = OverlayService::<
             IdentityContentKey,
@@ -3595,6 +4735,37 @@ mod tests {
         assert!(query_info.untrusted_enrs.contains(&bootnode_enr));
     }
 
+    #[tokio::test]
+    async fn init_find_content_query_with_hint_peer() {
+        let mut service = task::spawn(build_service());
+
+        let (_, hint_peer_enr) = generate_random_remote_enr();
+
+        let target_content = NodeId::random();
+        let target_content_key = IdentityContentKey::new(target_content.raw());
+
+        // The routing table is empty, but the query can still proceed with the hint peer.
+        let query_id = service.init_find_content_query(
+            target_content_key,
+            None,
+            FindContentConfig {
+                is_trace: true,
+                hint_peer: Some(hint_peer_enr.clone()),
+                ..Default::default()
+            },
+        );
+        let query_id = query_id.expect("Query ID for new find content query is `None`");
+
+        let pool = &mut service.find_content_query_pool;
+        let (query_info, _) = pool
+            .get_mut(query_id)
+            .expect("Query pool does not contain query");
+
+        assert!(query_info.untrusted_enrs.contains(&hint_peer_enr));
+        let trace = query_info.trace.as_ref().expect("Query trace is missing");
+        assert_eq!(trace.hint_peer, Some(hint_peer_enr.node_id()));
+    }
+
     #[tokio::test]
     async fn test_find_content_no_nodes() {
         let mut service = task::spawn(build_service());
@@ -3672,6 +4843,116 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn find_content_retry_is_requested_from_the_same_peer() {
+        let (command_tx, mut command_rx) =
+            unbounded_channel::<OverlayCommand<IdentityContentKey>>();
+        let (_, source) = generate_random_remote_enr();
+        let content_key = IdentityContentKey::random();
+
+        let responses = [
+            (
+                Ok(Response::Content(Content::ConnectionId(7))),
+                Some(Content::ConnectionId(7)),
+            ),
+            (
+                Ok(Response::Content(Content::Content(RawContentValue::from(
+                    vec![1, 2, 3],
+                )))),
+                Some(Content::Content(RawContentValue::from(vec![1, 2, 3]))),
+            ),
+            (
+                Ok(Response::Content(Content::Enrs(vec![]))),
+                Some(Content::Enrs(vec![])),
+            ),
+            (Err(OverlayRequestError::Timeout), None),
+        ];
+        for (response, expected) in responses {
+            let respond = async {
+                let Some(OverlayCommand::Request(request)) = command_rx.recv().await else {
+                    panic!("Expected a request");
+                };
+                assert!(matches!(
+                    request.request,
+                    Request::FindContent(ref find_content)
+                        if find_content.content_key == content_key.to_bytes()
+                ));
+                assert!(matches!(
+                    request.direction,
+                    RequestDirection::Outgoing { ref destination } if destination == &source
+                ));
+                request.responder.unwrap().send(response).unwrap();
+            };
+            let (retry, ()) = tokio::join!(
+                request_find_content_retry(&command_tx, &source, &content_key),
+                respond
+            );
+            assert_eq!(retry, expected);
+        }
+
+        // The retry fails if the service is gone.
+        drop(command_rx);
+        assert_eq!(
+            request_find_content_retry(&command_tx, &source, &content_key).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn process_content_ignores_denied_enrs() {
+        let mut service = task::spawn(build_service());
+
+        let (_, bootnode_enr) = generate_random_remote_enr();
+        let bootnode = Node {
+            enr: bootnode_enr.clone(),
+            data_radius: Distance::MAX,
+        };
+        let status = NodeStatus {
+            state: ConnectionState::Connected,
+            direction: ConnectionDirection::Outgoing,
+        };
+        let _ = service.kbuckets.insert_or_update(bootnode, status);
+
+        let target_content_key = IdentityContentKey::new(NodeId::random().raw());
+        let query_id = service
+            .init_find_content_query(target_content_key, None, FindContentConfig::default())
+            .expect("Query ID for new find content query is `None`");
+        {
+            let (_, query) = service
+                .find_content_query_pool
+                .get_mut(query_id)
+                .expect("Query pool does not contain query");
+            query.poll(Instant::now());
+        }
+
+        let (_, denied_enr) = generate_random_remote_enr();
+        let (_, enr) = generate_random_remote_enr();
+        service.denylist.write().deny_node_until(
+            denied_enr.node_id(),
+            Instant::now() + Duration::from_secs(60),
+        );
+        service.process_content(
+            Content::Enrs(vec![
+                SszEnr::new(denied_enr.clone()),
+                SszEnr::new(enr.clone()),
+            ]),
+            bootnode_enr,
+            Some(query_id),
+        );
+
+        let (query_info, _) = service
+            .find_content_query_pool
+            .get_mut(query_id)
+            .expect("Query pool does not contain query");
+        assert!(query_info.untrusted_enrs.contains(&enr));
+        assert!(!query_info.untrusted_enrs.contains(&denied_enr));
+        assert!(service
+            .kbuckets
+            .entry(denied_enr.node_id())
+            .present_or_pending()
+            .is_none());
+    }
+
     #[tokio::test]
     async fn advance_find_content_query_with_content() {
         let mut service = task::spawn(build_service());
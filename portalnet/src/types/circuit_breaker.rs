@@ -0,0 +1,226 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::{CircuitBreakerInfo, CircuitState};
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The circuit breaker that is shared between the overlay protocol, service and routing table.
+pub type SharedCircuitBreaker = Arc<Mutex<CircuitBreaker>>;
+
+/// The max number of peers whose failures are tracked. Once it's reached, the peer that failed
+/// the longest time ago is forgotten.
+pub const CIRCUIT_BREAKER_CAPACITY: usize = 10_000;
+
+/// Configures when peers stop being selected for lookups and offers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive failed requests after which a peer's circuit opens.
+    pub failure_threshold: u32,
+    /// The time for which an open circuit stays open, before the peer is probed again.
+    pub cooldown: Duration,
+}
+
+/// The consecutive failures of a single peer.
+#[derive(Debug)]
+struct PeerCircuit {
+    consecutive_failures: u32,
+    /// The instant at which the cooldown ends, if the circuit was opened.
+    open_until: Option<Instant>,
+    /// Whether a probe request was sent since the cooldown ended.
+    probing: bool,
+}
+
+/// Stops selecting peers that consistently fail requests for lookups and offers.
+///
+/// A peer's circuit opens after a number of consecutive failed requests, and the peer isn't
+/// selected until its cooldown ends. The circuit is then half-open: a single probe request is
+/// sent, which closes the circuit if it succeeds, or opens it for another cooldown if it fails.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    peers: LruCache<NodeId, PeerCircuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            peers: LruCache::new(CIRCUIT_BREAKER_CAPACITY),
+        }
+    }
+
+    pub fn shared(config: CircuitBreakerConfig) -> SharedCircuitBreaker {
+        Arc::new(Mutex::new(Self::new(config)))
+    }
+
+    /// Returns the state of the peer's circuit at `now`.
+    pub fn state(&self, node_id: &NodeId, now: Instant) -> CircuitState {
+        match self.peers.peek(node_id) {
+            Some(circuit) => Self::circuit_state(circuit, now),
+            None => CircuitState::Closed,
+        }
+    }
+
+    fn circuit_state(circuit: &PeerCircuit, now: Instant) -> CircuitState {
+        match circuit.open_until {
+            None => CircuitState::Closed,
+            Some(open_until) if now < open_until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Returns `true` if the peer can be selected at `now`, which is the case unless its circuit
+    /// is open, or half-open while a probe is already in flight.
+    pub fn is_selectable(&self, node_id: &NodeId, now: Instant) -> bool {
+        match self.peers.peek(node_id) {
+            Some(circuit) => match Self::circuit_state(circuit, now) {
+                CircuitState::Closed => true,
+                CircuitState::Open => false,
+                CircuitState::HalfOpen => !circuit.probing,
+            },
+            None => true,
+        }
+    }
+
+    /// Returns `true` if a request can be sent to the peer at `now`. If its circuit is half-open,
+    /// the request is the probe, and no other request is allowed until it completes.
+    pub fn try_request(&mut self, node_id: &NodeId, now: Instant) -> bool {
+        if !self.is_selectable(node_id, now) {
+            return false;
+        }
+        if let Some(circuit) = self.peers.get_mut(node_id) {
+            if Self::circuit_state(circuit, now) == CircuitState::HalfOpen {
+                circuit.probing = true;
+            }
+        }
+        true
+    }
+
+    /// Records a successful request to the peer, which closes its circuit.
+    pub fn record_success(&mut self, node_id: &NodeId) {
+        self.peers.pop(node_id);
+    }
+
+    /// Records a failed request to the peer at `now`, which opens its circuit once the failure
+    /// threshold is reached, or if the request was the probe of a half-open circuit.
+    pub fn record_failure(&mut self, node_id: &NodeId, now: Instant) {
+        let cooldown = self.config.cooldown;
+        let failure_threshold = self.config.failure_threshold;
+        let Some(circuit) = self.peers.get_mut(node_id) else {
+            let open_until = (failure_threshold <= 1).then(|| now + cooldown);
+            self.peers.put(
+                *node_id,
+                PeerCircuit {
+                    consecutive_failures: 1,
+                    open_until,
+                    probing: false,
+                },
+            );
+            return;
+        };
+        circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+        match Self::circuit_state(circuit, now) {
+            CircuitState::Closed => {
+                if circuit.consecutive_failures >= failure_threshold {
+                    circuit.open_until = Some(now + cooldown);
+                }
+            }
+            // A request that was sent before the circuit opened failed, the cooldown continues.
+            CircuitState::Open => {}
+            CircuitState::HalfOpen => {
+                circuit.open_until = Some(now + cooldown);
+                circuit.probing = false;
+            }
+        }
+    }
+
+    /// Returns the circuits of all peers with failed requests at `now`.
+    pub fn info(&self, now: Instant) -> Vec<CircuitBreakerInfo> {
+        self.peers
+            .iter()
+            .map(|(node_id, circuit)| CircuitBreakerInfo {
+                node_id: *node_id,
+                state: Self::circuit_state(circuit, now),
+                consecutive_failures: circuit.consecutive_failures,
+                cooldown_remaining_ms: circuit.open_until.and_then(|open_until| {
+                    let remaining = open_until.saturating_duration_since(now);
+                    (!remaining.is_zero()).then(|| remaining.as_millis() as u64)
+                }),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: CircuitBreakerConfig = CircuitBreakerConfig {
+        failure_threshold: 3,
+        cooldown: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let mut circuit_breaker = CircuitBreaker::new(CONFIG);
+        let node_id = NodeId::random();
+        let now = Instant::now();
+
+        circuit_breaker.record_failure(&node_id, now);
+        circuit_breaker.record_failure(&node_id, now);
+        assert_eq!(circuit_breaker.state(&node_id, now), CircuitState::Closed);
+        // A success resets the consecutive failures.
+        circuit_breaker.record_success(&node_id);
+        circuit_breaker.record_failure(&node_id, now);
+        circuit_breaker.record_failure(&node_id, now);
+        assert!(circuit_breaker.try_request(&node_id, now));
+
+        circuit_breaker.record_failure(&node_id, now);
+        assert_eq!(circuit_breaker.state(&node_id, now), CircuitState::Open);
+        assert!(!circuit_breaker.is_selectable(&node_id, now));
+        assert!(!circuit_breaker.try_request(&node_id, now));
+        assert!(circuit_breaker.is_selectable(&NodeId::random(), now));
+
+        let info = circuit_breaker.info(now);
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].consecutive_failures, 3);
+        assert_eq!(info[0].cooldown_remaining_ms, Some(60_000));
+    }
+
+    #[test]
+    fn half_open_circuit_is_probed_once() {
+        let mut circuit_breaker = CircuitBreaker::new(CONFIG);
+        let node_id = NodeId::random();
+        let now = Instant::now();
+        for _ in 0..CONFIG.failure_threshold {
+            circuit_breaker.record_failure(&node_id, now);
+        }
+
+        // The cooldown ends, and a single probe is allowed.
+        let later = now + CONFIG.cooldown;
+        assert_eq!(
+            circuit_breaker.state(&node_id, later),
+            CircuitState::HalfOpen
+        );
+        assert!(circuit_breaker.try_request(&node_id, later));
+        assert!(!circuit_breaker.try_request(&node_id, later));
+
+        // The probe fails, and the circuit opens for another cooldown.
+        circuit_breaker.record_failure(&node_id, later);
+        assert_eq!(circuit_breaker.state(&node_id, later), CircuitState::Open);
+
+        // The next probe succeeds, and the circuit closes.
+        let even_later = later + CONFIG.cooldown;
+        assert!(circuit_breaker.try_request(&node_id, even_later));
+        circuit_breaker.record_success(&node_id);
+        assert_eq!(
+            circuit_breaker.state(&node_id, even_later),
+            CircuitState::Closed
+        );
+        assert!(circuit_breaker.info(even_later).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::enr::NodeId;
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The content holders that are shared between the overlay service and its tasks.
+pub type SharedContentHolders = Arc<Mutex<ContentHolders>>;
+
+/// The max number of content ids whose holders are remembered. Once it's reached, the holders of
+/// the least recently offered content are forgotten.
+pub const CONTENT_HOLDERS_CAPACITY: usize = 10_000;
+
+/// The max number of holders that are remembered per content id. Once it's reached, the holder
+/// that accepted the content the longest time ago is forgotten.
+pub const MAX_HOLDERS_PER_CONTENT: usize = 4;
+
+/// Remembers, for a short time, which peers accepted offered content, so that requests for that
+/// content can be redirected to them.
+#[derive(Debug)]
+pub struct ContentHolders {
+    ttl: Duration,
+    /// The holders of each content id, and when they accepted it, the most recent first.
+    holders: LruCache<[u8; 32], Vec<(NodeId, Instant)>>,
+}
+
+impl ContentHolders {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            holders: LruCache::new(CONTENT_HOLDERS_CAPACITY),
+        }
+    }
+
+    pub fn shared(ttl: Duration) -> SharedContentHolders {
+        Arc::new(Mutex::new(Self::new(ttl)))
+    }
+
+    /// Records that the peer accepted the content at `now`.
+    pub fn record(&mut self, content_id: &[u8; 32], node_id: NodeId, now: Instant) {
+        let Some(holders) = self.holders.get_mut(content_id) else {
+            self.holders.put(*content_id, vec![(node_id, now)]);
+            return;
+        };
+        holders.retain(|(holder, _)| holder != &node_id);
+        holders.insert(0, (node_id, now));
+        holders.truncate(MAX_HOLDERS_PER_CONTENT);
+    }
+
+    /// Returns the peers that accepted the content within the time-to-live before `now`, the most
+    /// recent first.
+    pub fn holders(&mut self, content_id: &[u8; 32], now: Instant) -> Vec<NodeId> {
+        let ttl = self.ttl;
+        let Some(holders) = self.holders.get_mut(content_id) else {
+            return vec![];
+        };
+        holders.retain(|(_, accepted_at)| now.saturating_duration_since(*accepted_at) < ttl);
+        let node_ids = holders.iter().map(|(node_id, _)| *node_id).collect();
+        if holders.is_empty() {
+            self.holders.pop(content_id);
+        }
+        node_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn holders_are_listed_most_recent_first_until_they_expire() {
+        let mut content_holders = ContentHolders::new(TTL);
+        let content_id = [1; 32];
+        let (first, second) = (NodeId::random(), NodeId::random());
+        let start = Instant::now();
+
+        content_holders.record(&content_id, first, start);
+        content_holders.record(&content_id, second, start + TTL / 2);
+        assert_eq!(
+            content_holders.holders(&content_id, start + TTL / 2),
+            vec![second, first]
+        );
+        assert_eq!(content_holders.holders(&[2; 32], start), vec![]);
+
+        // The first holder expires.
+        assert_eq!(
+            content_holders.holders(&content_id, start + TTL),
+            vec![second]
+        );
+        assert_eq!(
+            content_holders.holders(&content_id, start + TTL * 2),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn holders_per_content_are_bounded() {
+        let mut content_holders = ContentHolders::new(TTL);
+        let content_id = [1; 32];
+        let now = Instant::now();
+        let node_ids: Vec<NodeId> = (0..MAX_HOLDERS_PER_CONTENT + 2)
+            .map(|_| NodeId::random())
+            .collect();
+
+        for node_id in &node_ids {
+            content_holders.record(&content_id, *node_id, now);
+        }
+        // Accepting the content again makes the first holder the most recent one.
+        content_holders.record(&content_id, node_ids[0], now);
+
+        let mut expected: Vec<NodeId> = node_ids[3..].iter().rev().copied().collect();
+        expected.insert(0, node_ids[0]);
+        assert_eq!(content_holders.holders(&content_id, now), expected);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
+
+use alloy::primitives::B256;
+use anyhow::{anyhow, bail};
+use discv5::enr::NodeId;
+use parking_lot::RwLock;
+
+/// The denylist that is shared between the overlay protocol, service and routing table.
+pub type SharedDenylist = Arc<RwLock<Denylist>>;
+
+/// Prefix of the denylist file lines that contain a node id.
+const NODE_ID_PREFIX: &str = "node:";
+/// Prefix of the denylist file lines that contain a content id.
+const CONTENT_ID_PREFIX: &str = "content:";
+
+/// A list of node ids and content ids that the node refuses to interact with.
+///
+/// Denied nodes are never inserted into the routing table, contacted by queries or answered, and
+/// denied content is never accepted, stored or served. Nodes can also be denied temporarily, in which case the restriction is
+/// lifted once it expires.
+///
+/// The denylist file contains one entry per line, either `node:<node_id>` or
+/// `content:<content_id>`, where ids are hex encoded. Empty lines and lines starting with `#` are
+/// ignored.
+#[derive(Debug, Default)]
+pub struct Denylist {
+    /// The file that the denylist was loaded from, if any.
+    path: Option<PathBuf>,
+    node_ids: HashSet<NodeId>,
+    content_ids: HashSet<[u8; 32]>,
+    /// Temporarily denied nodes, together with the instant at which the restriction expires.
+    temporary_node_ids: HashMap<NodeId, Instant>,
+}
+
+impl Denylist {
+    /// Loads the denylist from the file at `path`.
+    pub fn from_file(path: PathBuf) -> anyhow::Result<Self> {
+        let mut denylist = Self {
+            path: Some(path),
+            ..Default::default()
+        };
+        denylist.reload()?;
+        Ok(denylist)
+    }
+
+    /// Loads the denylist from the file at `path`, if one is provided. Otherwise, returns an empty
+    /// denylist.
+    pub fn shared_from_file(path: Option<PathBuf>) -> anyhow::Result<SharedDenylist> {
+        let denylist = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        Ok(Arc::new(RwLock::new(denylist)))
+    }
+
+    /// Reloads node ids and content ids from the file that the denylist was loaded from.
+    ///
+    /// The denylist is left unchanged if the file can't be read or parsed. Temporarily denied
+    /// nodes are not affected.
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Unable to read denylist file {}: {err}", path.display()))?;
+        let (node_ids, content_ids) = Self::parse(&contents)?;
+        self.node_ids = node_ids;
+        self.content_ids = content_ids;
+        Ok(())
+    }
+
+    /// Parses the contents of the denylist file.
+    fn parse(contents: &str) -> anyhow::Result<(HashSet<NodeId>, HashSet<[u8; 32]>)> {
+        let mut node_ids = HashSet::new();
+        let mut content_ids = HashSet::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_id = |id: &str| {
+                B256::from_str(id.trim())
+                    .map_err(|err| anyhow!("Invalid id on denylist line {}: {err}", index + 1))
+            };
+            if let Some(node_id) = line.strip_prefix(NODE_ID_PREFIX) {
+                node_ids.insert(NodeId::new(&parse_id(node_id)?.0));
+            } else if let Some(content_id) = line.strip_prefix(CONTENT_ID_PREFIX) {
+                content_ids.insert(parse_id(content_id)?.0);
+            } else {
+                bail!(
+                    "Invalid denylist line {}: expected \"{NODE_ID_PREFIX}<node_id>\" or \"{CONTENT_ID_PREFIX}<content_id>\"",
+                    index + 1
+                );
+            }
+        }
+        Ok((node_ids, content_ids))
+    }
+
+    /// Denies the node until the given instant. Expired temporary restrictions are removed.
+    pub fn deny_node_until(&mut self, node_id: NodeId, expires_at: Instant) {
+        let now = Instant::now();
+        self.temporary_node_ids
+            .retain(|_, expires_at| *expires_at > now);
+        self.temporary_node_ids.insert(node_id, expires_at);
+    }
+
+    /// Returns `true` if the node is denied, either permanently or temporarily.
+    pub fn is_node_denied(&self, node_id: &NodeId) -> bool {
+        self.node_ids.contains(node_id)
+            || self
+                .temporary_node_ids
+                .get(node_id)
+                .is_some_and(|expires_at| *expires_at > Instant::now())
+    }
+
+    /// Returns `true` if the content is denied.
+    pub fn is_content_denied(&self, content_id: &[u8; 32]) -> bool {
+        self.content_ids.contains(content_id)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const NODE_ID: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+    const CONTENT_ID: &str = "0x0202020202020202020202020202020202020202020202020202020202020202";
+
+    #[test]
+    fn parse() {
+        let contents = format!("# comment\n\nnode:{NODE_ID}\n  content:{CONTENT_ID}  \n");
+        let (node_ids, content_ids) = Denylist::parse(&contents).unwrap();
+        assert_eq!(node_ids, HashSet::from([NodeId::new(&[1; 32])]));
+        assert_eq!(content_ids, HashSet::from([[2; 32]]));
+    }
+
+    #[test]
+    fn parse_invalid_line() {
+        assert!(Denylist::parse(NODE_ID).is_err());
+        assert!(Denylist::parse("node:0x0101").is_err());
+    }
+
+    #[test]
+    fn reload() {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), format!("node:{NODE_ID}\n")).unwrap();
+        let mut denylist = Denylist::from_file(file.path().to_path_buf()).unwrap();
+        assert!(denylist.is_node_denied(&NodeId::new(&[1; 32])));
+        assert!(!denylist.is_content_denied(&[2; 32]));
+
+        fs::write(file.path(), format!("content:{CONTENT_ID}\n")).unwrap();
+        denylist.reload().unwrap();
+        assert!(!denylist.is_node_denied(&NodeId::new(&[1; 32])));
+        assert!(denylist.is_content_denied(&[2; 32]));
+
+        // Invalid file leaves the denylist unchanged
+        fs::write(file.path(), "invalid\n").unwrap();
+        assert!(denylist.reload().is_err());
+        assert!(denylist.is_content_denied(&[2; 32]));
+    }
+
+    #[test]
+    fn temporary_node() {
+        let mut denylist = Denylist::default();
+        let node_id = NodeId::random();
+        denylist.deny_node_until(node_id, Instant::now() + Duration::from_secs(60));
+        assert!(denylist.is_node_denied(&node_id));
+
+        denylist.deny_node_until(node_id, Instant::now());
+        assert!(!denylist.is_node_denied(&node_id));
+
+        // Expired restrictions are removed once another node is denied.
+        denylist.deny_node_until(NodeId::random(), Instant::now() + Duration::from_secs(60));
+        assert!(!denylist.temporary_node_ids.contains_key(&node_id));
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The gossip history that is shared between the overlay protocol and service.
+pub type SharedGossipHistory = Arc<Mutex<GossipHistory>>;
+
+/// The max number of content ids whose gossip history is retained. Once it's reached, the history
+/// of the least recently gossiped content is dropped, and that content is gossiped as if it's new.
+pub const GOSSIP_HISTORY_CAPACITY: usize = 100_000;
+
+/// Configures how the re-gossip of the same content decays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GossipDecayConfig {
+    /// The min interval between the first and second gossip round of the same content. The
+    /// interval doubles with every following round.
+    pub interval: Duration,
+    /// The max number of gossip rounds of the same content.
+    pub max_rounds: u32,
+    /// Content isn't gossiped again once the share of peers that accepted it in its latest round
+    /// drops to this ratio, since most interested peers already store it.
+    pub target_acceptance_ratio: f64,
+}
+
+/// The gossip rounds of a single content id.
+#[derive(Debug)]
+struct GossipRecord {
+    rounds: u32,
+    last_round: Instant,
+    /// The number of peers that were offered the content in the latest round.
+    offered: u32,
+    /// The number of peers that accepted the content in the latest round.
+    accepted: u32,
+}
+
+impl GossipRecord {
+    fn acceptance_ratio(&self) -> Option<f64> {
+        (self.offered > 0).then(|| f64::from(self.accepted) / f64::from(self.offered))
+    }
+}
+
+/// Tracks the gossip rounds of each content id, so that the node gossips the same content less
+/// and less often, and stops once it's widely distributed.
+#[derive(Debug)]
+pub struct GossipHistory {
+    config: GossipDecayConfig,
+    records: LruCache<[u8; 32], GossipRecord>,
+}
+
+impl GossipHistory {
+    pub fn new(config: GossipDecayConfig) -> Self {
+        Self {
+            config,
+            records: LruCache::new(GOSSIP_HISTORY_CAPACITY),
+        }
+    }
+
+    pub fn shared(config: GossipDecayConfig) -> SharedGossipHistory {
+        Arc::new(Mutex::new(Self::new(config)))
+    }
+
+    /// Starts a new gossip round of the content if it's due at `now`, and returns whether it
+    /// should be gossiped.
+    ///
+    /// Content that wasn't gossiped before is always due.
+    pub fn start_round(&mut self, content_id: &[u8; 32], now: Instant) -> bool {
+        let Some(record) = self.records.get_mut(content_id) else {
+            self.records.put(
+                *content_id,
+                GossipRecord {
+                    rounds: 1,
+                    last_round: now,
+                    offered: 0,
+                    accepted: 0,
+                },
+            );
+            return true;
+        };
+        if record.rounds >= self.config.max_rounds {
+            return false;
+        }
+        if record
+            .acceptance_ratio()
+            .is_some_and(|ratio| ratio <= self.config.target_acceptance_ratio)
+        {
+            return false;
+        }
+        let interval = self
+            .config
+            .interval
+            .saturating_mul(2u32.saturating_pow(record.rounds - 1));
+        if now.saturating_duration_since(record.last_round) < interval {
+            return false;
+        }
+        record.rounds += 1;
+        record.last_round = now;
+        record.offered = 0;
+        record.accepted = 0;
+        true
+    }
+
+    /// Records whether a peer accepted the content in its latest gossip round. Offers of content
+    /// that the node didn't gossip itself are ignored.
+    pub fn record_offer_result(&mut self, content_id: &[u8; 32], accepted: bool) {
+        if let Some(record) = self.records.get_mut(content_id) {
+            record.offered += 1;
+            if accepted {
+                record.accepted += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    fn new_history(max_rounds: u32) -> GossipHistory {
+        GossipHistory::new(GossipDecayConfig {
+            interval: INTERVAL,
+            max_rounds,
+            target_acceptance_ratio: 0.25,
+        })
+    }
+
+    #[test]
+    fn interval_between_rounds_doubles_until_max_rounds() {
+        let mut history = new_history(4);
+        let content_id = [1; 32];
+        let start = Instant::now();
+
+        assert!(history.start_round(&content_id, start));
+        assert!(!history.start_round(&content_id, start + INTERVAL / 2));
+        assert!(history.start_round(&content_id, start + INTERVAL));
+        // The third round is due 2 intervals after the second.
+        assert!(!history.start_round(&content_id, start + INTERVAL * 2));
+        assert!(history.start_round(&content_id, start + INTERVAL * 3));
+        assert!(history.start_round(&content_id, start + INTERVAL * 7));
+        // The max number of rounds is reached.
+        assert!(!history.start_round(&content_id, start + INTERVAL * 100));
+
+        // Other content is unaffected.
+        assert!(history.start_round(&[2; 32], start + INTERVAL * 100));
+    }
+
+    #[test]
+    fn stops_once_target_acceptance_ratio_is_reached() {
+        let mut history = new_history(10);
+        let content_id = [1; 32];
+        let start = Instant::now();
+
+        assert!(history.start_round(&content_id, start));
+        for accepted in [true, true, false, false] {
+            history.record_offer_result(&content_id, accepted);
+        }
+        assert!(history.start_round(&content_id, start + INTERVAL));
+
+        // Only 1 of 4 peers accepts the content in the second round.
+        for accepted in [true, false, false, false] {
+            history.record_offer_result(&content_id, accepted);
+        }
+        assert!(!history.start_round(&content_id, start + INTERVAL * 100));
+    }
+}
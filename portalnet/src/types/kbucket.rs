@@ -1,19 +1,29 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use discv5::{
     enr::NodeId,
     kbucket::{
-        AppliedPending, Entry as KBucketEntry, InsertResult, KBucketsTable, NodeStatus,
-        UpdateResult,
+        AppliedPending, Entry as KBucketEntry, FailureReason, InsertResult, KBucketsTable,
+        NodeStatus, UpdateResult,
     },
     ConnectionDirection, ConnectionState, Enr, Key,
 };
-use ethportal_api::types::distance::{Distance, Metric};
+use ethportal_api::{
+    types::distance::{Distance, Metric},
+    BucketNeighborhoodInfo, BucketRefreshInfo,
+};
 use itertools::Itertools;
 use parking_lot::RwLock;
 use tracing::debug;
 
-use super::node::Node;
+use super::{
+    circuit_breaker::SharedCircuitBreaker, denylist::SharedDenylist, node::Node,
+    subnet_filter::IpSubnet,
+};
 
 /// Information regarding single entry in the routing table.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -77,12 +87,26 @@ pub struct DiscoveredNodesUpdateResult {
 #[derive(Clone)]
 pub struct SharedKBucketsTable {
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+    /// Nodes on the denylist are prevented from being inserted into the routing table.
+    denylist: SharedDenylist,
+    /// Nodes whose circuit is open are not selected as gossip recipients.
+    circuit_breaker: Option<SharedCircuitBreaker>,
+    /// The unix timestamp, in milliseconds, of the last node lookup targeting each bucket, keyed
+    /// by the log2 distance of the bucket.
+    last_refreshed: Arc<RwLock<HashMap<u16, u64>>>,
 }
 
 impl SharedKBucketsTable {
-    pub fn new(kbuckets: KBucketsTable<NodeId, Node>) -> Self {
+    pub fn new(
+        kbuckets: KBucketsTable<NodeId, Node>,
+        denylist: SharedDenylist,
+        circuit_breaker: Option<SharedCircuitBreaker>,
+    ) -> Self {
         Self {
             kbuckets: Arc::new(RwLock::new(kbuckets)),
+            denylist,
+            circuit_breaker,
+            last_refreshed: Arc::default(),
         }
     }
 
@@ -97,6 +121,48 @@ impl SharedKBucketsTable {
         self.kbuckets.write().remove(&Key::from(node_id))
     }
 
+    /// Removes a node from the routing table and prevents it from being inserted again for the
+    /// duration of `ttl`. Returns `true` if the node existed.
+    ///
+    /// Returns `None`, without removing the node, if `ttl` is too long for its expiry to be
+    /// represented.
+    pub fn remove_and_deny(&self, node_id: NodeId, ttl: Duration) -> Option<bool> {
+        let deny_until = Instant::now().checked_add(ttl)?;
+        self.denylist.write().deny_node_until(node_id, deny_until);
+        Some(self.remove(node_id))
+    }
+
+    /// Returns `true` if the node is on the denylist, in which case it can't be inserted into the
+    /// routing table, and its requests aren't answered.
+    pub fn is_denied(&self, node_id: &NodeId) -> bool {
+        self.denylist.read().is_node_denied(node_id)
+    }
+
+    /// Removes the nodes on the denylist from the routing table, e.g. after the denylist is
+    /// reloaded. Returns the node ids of the removed nodes.
+    pub fn remove_denied(&self) -> Vec<NodeId> {
+        let mut kbuckets = self.kbuckets.write();
+        let denied_node_ids: Vec<NodeId> = kbuckets
+            .iter()
+            .map(|entry| *entry.node.key.preimage())
+            .filter(|node_id| self.is_denied(node_id))
+            .collect();
+        for node_id in &denied_node_ids {
+            kbuckets.remove(&Key::from(*node_id));
+        }
+        denied_node_ids
+    }
+
+    /// Returns `true` unless the circuit of the node is open, in which case it shouldn't be
+    /// selected as a gossip recipient.
+    fn is_selectable(&self, node_id: &NodeId, now: Instant) -> bool {
+        self.circuit_breaker
+            .as_ref()
+            .map_or(true, |circuit_breaker| {
+                circuit_breaker.lock().is_selectable(node_id, now)
+            })
+    }
+
     /// Updates a node's value if it exists in the table.
     ///
     /// Optionally the connection state can be modified.
@@ -120,7 +186,12 @@ impl SharedKBucketsTable {
     }
 
     /// Attempts to insert or update in the routing table.
+    ///
+    /// Insertion of denied nodes fails with [FailureReason::TableFilter].
     pub fn insert_or_update(&self, node: Node, node_status: NodeStatus) -> InsertResult<NodeId> {
+        if self.is_denied(&node.enr.node_id()) {
+            return InsertResult::Failed(FailureReason::TableFilter);
+        }
         let key = Key::from(node.enr.node_id());
         self.kbuckets
             .write()
@@ -131,6 +202,7 @@ impl SharedKBucketsTable {
     ///
     /// If node is already in the routing table and provided enr is newer, updates the node.
     /// If node is not in the routing table, insert it with max distance and as disconnected.
+    /// Denied nodes are ignored.
     pub fn insert_or_update_discovered_nodes(
         &self,
         enrs: impl IntoIterator<Item = Enr>,
@@ -143,6 +215,11 @@ impl SharedKBucketsTable {
         for enr in enrs {
             let node_id = enr.node_id();
 
+            if self.is_denied(&node_id) {
+                debug!(peer = %node_id, "Discovered node is denied, ignoring");
+                continue;
+            }
+
             let key = Key::from(node_id);
 
             // If the node is in the routing table, then check to see if we should update its entry.
@@ -196,6 +273,136 @@ impl SharedKBucketsTable {
         self.kbuckets.read().buckets_iter().count()
     }
 
+    /// Returns, for every non-empty bucket, the number of connected nodes and how many of them are
+    /// within gossip range of the local node, given its `radius`.
+    ///
+    /// A node is within gossip range if the distance to it doesn't exceed the sum of the local
+    /// radius and the radius of the node.
+    pub fn neighborhood_info<TMetric: Metric>(
+        &self,
+        local_node_id: NodeId,
+        radius: Distance,
+    ) -> Vec<BucketNeighborhoodInfo> {
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .enumerate()
+            .filter_map(|(index, bucket)| {
+                let mut peers = 0;
+                let mut peers_in_range = 0;
+                // Filter out disconnected nodes.
+                for node in bucket.iter().filter(|node| node.status.is_connected()) {
+                    peers += 1;
+                    let distance =
+                        TMetric::distance(&local_node_id.raw(), &node.key.preimage().raw());
+                    if *distance <= radius.saturating_add(*node.value.data_radius) {
+                        peers_in_range += 1;
+                    }
+                }
+                (peers > 0).then(|| BucketNeighborhoodInfo {
+                    log2_distance: index as u16 + 1,
+                    peers,
+                    peers_in_range,
+                })
+            })
+            .collect()
+    }
+
+    /// Records that a node lookup for `target` was started, which refreshes the bucket that
+    /// `target` falls into.
+    pub fn mark_refreshed(&self, target: &NodeId) {
+        let Some(log2_distance) = self
+            .kbuckets
+            .read()
+            .local_key()
+            .log2_distance(&Key::from(*target))
+        else {
+            // The local node isn't in any bucket.
+            return;
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        self.last_refreshed.write().insert(
+            log2_distance as u16,
+            u64::try_from(timestamp_ms).unwrap_or(u64::MAX),
+        );
+    }
+
+    /// Returns, for every bucket that is non-empty or was ever refreshed, the number of nodes in
+    /// it and the time of the last node lookup targeting it.
+    pub fn refresh_info(&self) -> Vec<BucketRefreshInfo> {
+        let last_refreshed = self.last_refreshed.read();
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .enumerate()
+            .filter_map(|(index, bucket)| {
+                let log2_distance = index as u16 + 1;
+                let entries = bucket.iter().count();
+                let last_refresh_ms = last_refreshed.get(&log2_distance).copied();
+                (entries > 0 || last_refresh_ms.is_some()).then_some(BucketRefreshInfo {
+                    log2_distance,
+                    entries,
+                    last_refresh_ms,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of entries in the routing table.
+    pub fn entries_count(&self) -> usize {
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .map(|bucket| bucket.iter().count())
+            .sum()
+    }
+
+    /// Returns the number of entries in the routing table per IP subnet of their advertised
+    /// address. Entries that don't advertise an address aren't counted.
+    pub fn subnet_distribution(&self) -> BTreeMap<IpSubnet, usize> {
+        let mut distribution = BTreeMap::new();
+        for bucket in self.kbuckets.read().buckets_iter() {
+            for node in bucket.iter() {
+                if let Some(subnet) = IpSubnet::of(&node.value.enr) {
+                    *distribution.entry(subnet).or_default() += 1;
+                }
+            }
+        }
+        distribution
+    }
+
+    /// Returns the number of connected entries in the routing table.
+    pub fn connected_entries_count(&self) -> usize {
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .map(|bucket| {
+                bucket
+                    .iter()
+                    .filter(|node| node.status.is_connected())
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Returns the ENRs of the connected entries in the routing table.
+    pub fn connected_enrs(&self) -> Vec<Enr> {
+        self.kbuckets
+            .read()
+            .buckets_iter()
+            .flat_map(|bucket| {
+                bucket
+                    .iter()
+                    .filter(|node| node.status.is_connected())
+                    .map(|node| node.value.enr())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     /// Returns up to `limit` connected nodes that are at any given log2 distances.
     ///
     /// We can't use [KBucketsTable::nodes_by_distances] to retrieve nodes from all distances in
@@ -278,14 +485,17 @@ impl SharedKBucketsTable {
             .collect()
     }
 
-    /// Returns all nodes that are connected and interested into provided content id.
+    /// Returns all nodes that are connected and interested into provided content id, unless their
+    /// circuit is open.
     pub fn interested_enrs<TMetric: Metric>(&self, content_id: &[u8; 32]) -> Vec<Enr> {
+        let now = Instant::now();
         self.kbuckets
             .write()
             .iter()
             // Filter out disconnected nodes.
             .filter(|entry| entry.status.is_connected())
             .map(|entry| entry.node)
+            .filter(|node| self.is_selectable(node.key.preimage(), now))
             // Keep only nodes that are interested in content
             .filter(|node| {
                 TMetric::distance(content_id, &node.key.preimage().raw()) <= node.value.data_radius
@@ -294,7 +504,8 @@ impl SharedKBucketsTable {
             .collect()
     }
 
-    /// For each content id, returns all ENRs that are connected and interested into it.
+    /// For each content id, returns all ENRs that are connected and interested into it, unless
+    /// their circuit is open.
     ///
     /// The keys of the resulting map will always contain all `content_ids`. If none of the nodes is
     /// interested into specific content id, it will still be present in the result but the value
@@ -307,9 +518,10 @@ impl SharedKBucketsTable {
             .iter()
             .map(|content_id| (**content_id, vec![]))
             .collect::<HashMap<_, _>>();
+        let now = Instant::now();
         for entry in self.kbuckets.write().iter() {
-            // Skip non-connected nodes
-            if !entry.status.is_connected() {
+            // Skip non-connected nodes, and nodes whose circuit is open
+            if !entry.status.is_connected() || !self.is_selectable(entry.node.key.preimage(), now) {
                 continue;
             }
             let node = entry.node;
@@ -363,10 +575,7 @@ impl From<&SharedKBucketsTable> for ethportal_api::KBucketsTable {
 mod tests {
     use std::{thread::sleep, time::Duration};
 
-    use discv5::{
-        enr::CombinedKey,
-        kbucket::{FailureReason, MAX_NODES_PER_BUCKET},
-    };
+    use discv5::{enr::CombinedKey, kbucket::MAX_NODES_PER_BUCKET};
     use ethportal_api::{
         generate_random_remote_enr,
         types::{cli::DEFAULT_DISCOVERY_PORT, distance::XorMetric},
@@ -393,13 +602,17 @@ mod tests {
 
     fn create_kbuckets_table() -> (Enr, SharedKBucketsTable) {
         let (_, local_enr) = generate_random_remote_enr();
-        let kbuckets = SharedKBucketsTable::new(KBucketsTable::new(
-            Key::from(local_enr.node_id()),
-            *PENDING_TIMEOUT_SEC,
-            MAX_INCOMING_PER_BUCKET,
-            /* table_filter= */ None,
-            /* bucket_filter= */ None,
-        ));
+        let kbuckets = SharedKBucketsTable::new(
+            KBucketsTable::new(
+                Key::from(local_enr.node_id()),
+                *PENDING_TIMEOUT_SEC,
+                MAX_INCOMING_PER_BUCKET,
+                /* table_filter= */ None,
+                /* bucket_filter= */ None,
+            ),
+            SharedDenylist::default(),
+            /* circuit_breaker= */ None,
+        );
         (local_enr, kbuckets)
     }
 
@@ -672,6 +885,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn connected_entries_count() {
+        let (local_enr, kbuckets) = create_kbuckets_table();
+        let local_node_id = local_enr.node_id();
+
+        for status in [*CONNECTED, *CONNECTED, *DISCONNECTED] {
+            let enr = generate_random_enr(local_node_id, 256);
+            let _ = kbuckets.insert_or_update(Node::new(enr, Distance::ZERO), status);
+        }
+
+        assert_eq!(kbuckets.entries_count(), 3);
+        assert_eq!(kbuckets.connected_entries_count(), 2);
+    }
+
     mod nodes_by_distances {
         use super::*;
 
@@ -944,4 +1171,164 @@ mod tests {
             );
         }
     }
+
+    mod deny {
+        use super::*;
+
+        #[test]
+        fn remove_and_deny() {
+            let (_local_enr, kbuckets) = create_kbuckets_table();
+            let (_, enr) = generate_random_remote_enr();
+            let node = Node::new(enr.clone(), Distance::MAX);
+
+            // Removing a node that isn't present still denies it
+            assert_eq!(
+                kbuckets.remove_and_deny(enr.node_id(), *PENDING_TIMEOUT_SEC),
+                Some(false)
+            );
+            assert!(kbuckets.is_denied(&enr.node_id()));
+            assert!(matches!(
+                kbuckets.insert_or_update(node.clone(), *CONNECTED),
+                InsertResult::Failed(FailureReason::TableFilter),
+            ));
+            let result = kbuckets.insert_or_update_discovered_nodes([enr.clone()]);
+            assert!(result.inserted_nodes.is_empty());
+            assert_eq!(kbuckets.entry(enr.node_id()), Entry::Absent);
+
+            // The node can be inserted again once the restriction expires
+            sleep(*PENDING_TIMEOUT_SEC);
+            assert!(!kbuckets.is_denied(&enr.node_id()));
+            assert!(matches!(
+                kbuckets.insert_or_update(node, *CONNECTED),
+                InsertResult::Inserted,
+            ));
+
+            // Removing a present node returns true
+            assert_eq!(
+                kbuckets.remove_and_deny(enr.node_id(), *PENDING_TIMEOUT_SEC),
+                Some(true)
+            );
+            assert_eq!(kbuckets.entry(enr.node_id()), Entry::Absent);
+        }
+
+        #[test]
+        fn remove_denied() {
+            let (_local_enr, kbuckets) = create_kbuckets_table();
+            let (_, denied_enr) = generate_random_remote_enr();
+            let (_, enr) = generate_random_remote_enr();
+            for enr in [&denied_enr, &enr] {
+                let node = Node::new(enr.clone(), Distance::MAX);
+                assert!(matches!(
+                    kbuckets.insert_or_update(node, *CONNECTED),
+                    InsertResult::Inserted,
+                ));
+            }
+
+            kbuckets.denylist.write().deny_node_until(
+                denied_enr.node_id(),
+                Instant::now() + Duration::from_secs(60),
+            );
+            assert_eq!(kbuckets.remove_denied(), vec![denied_enr.node_id()]);
+            assert!(kbuckets.entry(denied_enr.node_id()).present().is_none());
+            assert!(kbuckets.entry(enr.node_id()).present().is_some());
+        }
+
+        #[test]
+        fn remove_and_deny_with_unrepresentable_ttl() {
+            let (_local_enr, kbuckets) = create_kbuckets_table();
+            let (_, enr) = generate_random_remote_enr();
+            let node = Node::new(enr.clone(), Distance::MAX);
+            assert!(matches!(
+                kbuckets.insert_or_update(node, *CONNECTED),
+                InsertResult::Inserted,
+            ));
+
+            // The node is neither removed nor denied
+            assert_eq!(
+                kbuckets.remove_and_deny(enr.node_id(), Duration::from_secs(u64::MAX)),
+                None
+            );
+            assert!(!kbuckets.is_denied(&enr.node_id()));
+            assert_ne!(kbuckets.entry(enr.node_id()), Entry::Absent);
+        }
+    }
+
+    mod neighborhood_info {
+        use alloy::primitives::U256;
+
+        use super::*;
+
+        #[test]
+        fn counts_connected_nodes_within_range() {
+            let (local_enr, kbuckets) = create_kbuckets_table();
+            let local_node_id = local_enr.node_id();
+            let quarter_max_distance = Distance::from(U256::MAX.wrapping_shr(2));
+
+            // Connected node at log2 distance 256 with max radius, within range
+            let enr_max_connected = generate_random_enr(local_node_id, 256);
+            let _ =
+                kbuckets.insert_or_update(Node::new(enr_max_connected, Distance::MAX), *CONNECTED);
+
+            // Connected node at log2 distance 256 with zero radius, out of range
+            let enr_zero_connected = generate_random_enr(local_node_id, 256);
+            let _ = kbuckets
+                .insert_or_update(Node::new(enr_zero_connected, Distance::ZERO), *CONNECTED);
+
+            // Disconnected node at log2 distance 255 with max radius, not counted
+            let enr_max_disconnected = generate_random_enr(local_node_id, 255);
+            let _ = kbuckets.insert_or_update(
+                Node::new(enr_max_disconnected, Distance::MAX),
+                *DISCONNECTED,
+            );
+
+            // Connected node at log2 distance 254 with quarter radius, within range
+            let enr_quarter_connected = generate_random_enr(local_node_id, 254);
+            let _ = kbuckets.insert_or_update(
+                Node::new(enr_quarter_connected, quarter_max_distance),
+                *CONNECTED,
+            );
+
+            assert_eq!(
+                kbuckets.neighborhood_info::<XorMetric>(local_node_id, quarter_max_distance),
+                vec![
+                    BucketNeighborhoodInfo {
+                        log2_distance: 254,
+                        peers: 1,
+                        peers_in_range: 1,
+                    },
+                    BucketNeighborhoodInfo {
+                        log2_distance: 256,
+                        peers: 2,
+                        peers_in_range: 1,
+                    },
+                ]
+            );
+        }
+    }
+
+    mod refresh_info {
+        use super::*;
+
+        #[test]
+        fn reports_refreshed_and_non_empty_buckets() {
+            let (local_enr, kbuckets) = create_kbuckets_table();
+            let local_node_id = local_enr.node_id();
+            assert!(kbuckets.refresh_info().is_empty());
+
+            let enr = generate_random_enr(local_node_id, 256);
+            let _ = kbuckets.insert_or_update(Node::new(enr, Distance::MAX), *CONNECTED);
+            kbuckets.mark_refreshed(&generate_random_enr(local_node_id, 255).node_id());
+            // A lookup of the local node doesn't refresh any bucket.
+            kbuckets.mark_refreshed(&local_node_id);
+
+            let refresh_info = kbuckets.refresh_info();
+            assert_eq!(refresh_info.len(), 2);
+            assert_eq!(refresh_info[0].log2_distance, 255);
+            assert_eq!(refresh_info[0].entries, 0);
+            assert!(refresh_info[0].last_refresh_ms.is_some());
+            assert_eq!(refresh_info[1].log2_distance, 256);
+            assert_eq!(refresh_info[1].entries, 1);
+            assert_eq!(refresh_info[1].last_refresh_ms, None);
+        }
+    }
 }
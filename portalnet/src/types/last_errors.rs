@@ -0,0 +1,99 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethportal_api::types::portal::LastErrorInfo;
+use parking_lot::Mutex;
+
+/// The last errors that are shared between the overlay protocol, service and uTP controller.
+pub type SharedLastErrors = Arc<LastErrors>;
+
+/// A subsystem of an overlay network whose last error is retained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorSubsystem {
+    /// Operations of the data store.
+    Store,
+    /// uTP transfers of content.
+    Utp,
+    /// Requests to peers, other than offers.
+    Discovery,
+    /// Offers of content to peers.
+    Gossip,
+}
+
+impl fmt::Display for ErrorSubsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Store => "store",
+            Self::Utp => "utp",
+            Self::Discovery => "discovery",
+            Self::Gossip => "gossip",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Retains the last error encountered by each subsystem of an overlay network, with the time at
+/// which it was encountered, so that errors can be inspected without searching the logs.
+///
+/// Only a single error is retained per subsystem, each replacing the previous one.
+#[derive(Debug, Default)]
+pub struct LastErrors {
+    errors: Mutex<BTreeMap<ErrorSubsystem, LastErrorInfo>>,
+}
+
+impl LastErrors {
+    pub fn shared() -> SharedLastErrors {
+        Arc::new(Self::default())
+    }
+
+    /// Records the error as the last error of the subsystem.
+    pub fn record(&self, subsystem: ErrorSubsystem, error: impl Display) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        self.errors.lock().insert(
+            subsystem,
+            LastErrorInfo {
+                subsystem: subsystem.to_string(),
+                error: error.to_string(),
+                timestamp_ms: u64::try_from(timestamp_ms).unwrap_or(u64::MAX),
+            },
+        );
+    }
+
+    /// Returns the last error of each subsystem that encountered one.
+    pub fn snapshot(&self) -> Vec<LastErrorInfo> {
+        self.errors.lock().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_last_error_of_each_subsystem_is_retained() {
+        let last_errors = LastErrors::default();
+        assert!(last_errors.snapshot().is_empty());
+
+        last_errors.record(ErrorSubsystem::Utp, "connection reset");
+        last_errors.record(ErrorSubsystem::Store, "disk full");
+        last_errors.record(ErrorSubsystem::Store, "database locked");
+
+        let snapshot = last_errors.snapshot();
+        let errors = snapshot
+            .iter()
+            .map(|info| (info.subsystem.as_str(), info.error.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![("store", "database locked"), ("utp", "connection reset")]
+        );
+        assert!(snapshot.iter().all(|info| info.timestamp_ms > 0));
+    }
+}
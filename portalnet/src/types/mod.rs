@@ -1,2 +1,18 @@
+pub mod circuit_breaker;
+pub mod content_holders;
+pub mod denylist;
+pub mod gossip_history;
 pub mod kbucket;
+pub mod last_errors;
 pub mod node;
+pub mod node_addr_cache;
+pub mod offer_log;
+pub mod peer_transfers;
+pub mod query_trace_buffer;
+pub mod readiness;
+pub mod seen_content;
+pub mod serve_bandwidth;
+pub mod served_content;
+pub mod store_health;
+pub mod subnet_filter;
+pub mod transfer_sizes;
@@ -0,0 +1,131 @@
+use discv5::enr::NodeId;
+use lru::LruCache;
+use trin_metrics::{labels::EnrCacheLookupLabel, overlay::OverlayMetrics};
+
+use crate::discovery::NodeAddress;
+
+/// A cache of the latest observed address of nodes, including nodes outside of the routing
+/// tables, keyed by node id.
+///
+/// Once the cache holds `capacity` addresses, the least recently used address is evicted, except
+/// for the addresses of nodes in a routing table, which are never evicted. The cache can only
+/// exceed its capacity if every cached node is in a routing table.
+pub struct NodeAddrCache {
+    addrs: LruCache<NodeId, NodeAddress>,
+    capacity: usize,
+    metrics: Option<OverlayMetrics>,
+}
+
+impl NodeAddrCache {
+    pub fn new(capacity: usize, metrics: Option<OverlayMetrics>) -> Self {
+        Self {
+            addrs: LruCache::unbounded(),
+            capacity,
+            metrics,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// Returns the cached address of the node, marking it as the most recently used.
+    pub fn get(&mut self, node_id: &NodeId) -> Option<&NodeAddress> {
+        let node_addr = self.addrs.get(node_id);
+        if let Some(metrics) = &self.metrics {
+            metrics.report_enr_cache_lookup(match node_addr {
+                Some(_) => EnrCacheLookupLabel::Hit,
+                None => EnrCacheLookupLabel::Miss,
+            });
+        }
+        node_addr
+    }
+
+    /// Caches the address of the node, returning its previously cached address, if any.
+    ///
+    /// If the cache is over capacity, the least recently used addresses of nodes for which
+    /// `in_routing_table` returns false are evicted.
+    pub fn put(
+        &mut self,
+        node_addr: NodeAddress,
+        in_routing_table: impl Fn(&NodeId) -> bool,
+    ) -> Option<NodeAddress> {
+        let node_id = node_addr.enr.node_id();
+        let old = self.addrs.put(node_id, node_addr);
+        if self.addrs.len() > self.capacity {
+            // Iterates from the least to the most recently used address.
+            let evicted = self
+                .addrs
+                .iter()
+                .rev()
+                .map(|(cached_id, _)| *cached_id)
+                .filter(|cached_id| *cached_id != node_id && !in_routing_table(cached_id))
+                .take(self.addrs.len() - self.capacity)
+                .collect::<Vec<_>>();
+            for cached_id in evicted {
+                self.addrs.pop(&cached_id);
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.report_enr_cache_size(self.addrs.len());
+        }
+        old
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use ethportal_api::types::enr::generate_random_remote_enr;
+
+    use super::*;
+
+    fn node_addr() -> NodeAddress {
+        let (_, enr) = generate_random_remote_enr();
+        NodeAddress {
+            enr,
+            socket_addr: SocketAddr::from(([127, 0, 0, 1], 9009)),
+        }
+    }
+
+    #[test]
+    fn least_recently_used_addresses_are_evicted() {
+        let mut cache = NodeAddrCache::new(2, None);
+        let addrs = [node_addr(), node_addr(), node_addr()];
+        let node_id = |i: usize| addrs[i].enr.node_id();
+
+        cache.put(addrs[0].clone(), |_| false);
+        cache.put(addrs[1].clone(), |_| false);
+        assert!(cache.get(&node_id(0)).is_some());
+        cache.put(addrs[2].clone(), |_| false);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&node_id(0)).is_some());
+        assert!(cache.get(&node_id(1)).is_none());
+        assert!(cache.get(&node_id(2)).is_some());
+    }
+
+    #[test]
+    fn routing_table_addresses_are_not_evicted() {
+        let mut cache = NodeAddrCache::new(1, None);
+        let addrs = [node_addr(), node_addr(), node_addr()];
+        let node_id = |i: usize| addrs[i].enr.node_id();
+        let in_routing_table = |cached_id: &NodeId| *cached_id == node_id(0);
+
+        cache.put(addrs[0].clone(), in_routing_table);
+        cache.put(addrs[1].clone(), in_routing_table);
+        assert_eq!(cache.len(), 2);
+        cache.put(addrs[2].clone(), in_routing_table);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&node_id(0)).is_some());
+        assert!(cache.get(&node_id(1)).is_none());
+        assert!(cache.get(&node_id(2)).is_some());
+    }
+}
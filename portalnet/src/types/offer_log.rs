@@ -0,0 +1,301 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use discv5::enr::NodeId;
+use ethportal_api::{types::enr::Enr, RawContentKey};
+use serde::Serialize;
+use tracing::warn;
+
+/// The size in bytes after which the offer log file is rotated.
+pub const MAX_OFFER_LOG_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// The number of records that can wait to be written to the offer log file. Records are dropped
+/// while the queue is full.
+const OFFER_LOG_QUEUE_SIZE: usize = 1024;
+
+/// The reason why an offered content key was declined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OfferDeclineReason {
+    /// The limit of concurrent inbound uTP transfers was reached.
+    TransferLimitReached,
+    /// The limit of concurrent uTP transfers requested by the offering peer was reached.
+    PeerTransferLimitReached,
+    /// The content id is on the denylist.
+    Denied,
+    /// The content is not within the radius of the local node.
+    NotWithinRadius,
+    /// The content is already stored.
+    AlreadyStored,
+    /// The content is already being received from another peer.
+    AlreadyQueued,
+    /// The data store is unavailable, so no content is accepted.
+    StoreUnavailable,
+}
+
+/// An offered content key that was declined.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclinedContentKey {
+    pub content_key: RawContentKey,
+    pub reason: OfferDeclineReason,
+}
+
+/// A record of a single offer received by the local node.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferLogRecord {
+    /// Milliseconds since the unix epoch at which the offer was handled.
+    pub timestamp_ms: u64,
+    pub subnetwork: String,
+    pub source_node_id: NodeId,
+    /// The ENR of the peer that sent the offer, if known.
+    pub source_enr: Option<Enr>,
+    pub offered: Vec<RawContentKey>,
+    pub accepted: Vec<RawContentKey>,
+    pub declined: Vec<DeclinedContentKey>,
+}
+
+impl OfferLogRecord {
+    /// Creates a record of an offer from `source_node_id` in which nothing is accepted or
+    /// declined yet.
+    pub fn new(
+        subnetwork: String,
+        source_node_id: NodeId,
+        source_enr: Option<Enr>,
+        offered: Vec<RawContentKey>,
+    ) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        Self {
+            timestamp_ms: u64::try_from(timestamp_ms).unwrap_or(u64::MAX),
+            subnetwork,
+            source_node_id,
+            source_enr,
+            offered,
+            accepted: vec![],
+            declined: vec![],
+        }
+    }
+
+    /// Marks all offered content keys as declined for the given reason.
+    pub fn decline_all(&mut self, reason: OfferDeclineReason) {
+        self.declined = self
+            .offered
+            .iter()
+            .map(|content_key| DeclinedContentKey {
+                content_key: content_key.clone(),
+                reason,
+            })
+            .collect();
+    }
+}
+
+/// Appends a JSON line per received offer to a file.
+///
+/// Once the file grows beyond the maximum size, it is renamed by appending `.1` to its name,
+/// replacing any previously rotated file, and a new file is started.
+#[derive(Debug)]
+pub struct OfferLog {
+    path: PathBuf,
+    max_file_size: u64,
+    /// The open file and its current size. The file is opened on the first append.
+    file: Option<(File, u64)>,
+}
+
+impl OfferLog {
+    pub fn new(path: PathBuf, max_file_size: u64) -> Self {
+        Self {
+            path,
+            max_file_size,
+            file: None,
+        }
+    }
+
+    /// Creates an offer log writing to `path`, if one is provided.
+    pub fn shared_from_file(path: Option<PathBuf>) -> Option<SharedOfferLog> {
+        path.map(|path| SharedOfferLog::spawn(Self::new(path, MAX_OFFER_LOG_FILE_SIZE)))
+    }
+
+    /// Appends the record to the file, rotating the file first if needed.
+    pub fn append(&mut self, record: &OfferLogRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let (_, size) = self.file()?;
+        if *size > 0 && *size + line.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+
+        let (file, size) = self.file()?;
+        file.write_all(&line)
+            .map_err(|err| anyhow!("Unable to write to offer log file: {err}"))?;
+        *size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Returns the open file and its size, opening it if necessary.
+    fn file(&mut self) -> anyhow::Result<&mut (File, u64)> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|err| {
+                    anyhow!(
+                        "Unable to open offer log file {}: {err}",
+                        self.path.display()
+                    )
+                })?;
+            let size = file.metadata()?.len();
+            self.file = Some((file, size));
+        }
+        self.file
+            .as_mut()
+            .ok_or_else(|| anyhow!("Offer log file is not open"))
+    }
+
+    /// Moves the current file aside, so that the next append starts a new file.
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        self.file = None;
+        let mut rotated_path = OsString::from(self.path.as_os_str());
+        rotated_path.push(".1");
+        fs::rename(&self.path, &rotated_path).map_err(|err| {
+            anyhow!(
+                "Unable to rotate offer log file {}: {err}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+/// The offer log that is shared between the overlay networks.
+///
+/// Records are written to the file by a background thread, so that the overlay networks never wait
+/// on the file system while handling offers.
+#[derive(Clone, Debug)]
+pub struct SharedOfferLog {
+    records: SyncSender<OfferLogRecord>,
+}
+
+impl SharedOfferLog {
+    /// Starts the thread writing to `offer_log`. The thread stops once all handles are dropped.
+    pub fn spawn(mut offer_log: OfferLog) -> Self {
+        let (records, rx) = mpsc::sync_channel::<OfferLogRecord>(OFFER_LOG_QUEUE_SIZE);
+        thread::spawn(move || {
+            for record in rx {
+                if let Err(err) = offer_log.append(&record) {
+                    warn!(error = %err, "Unable to log offer");
+                }
+            }
+        });
+        Self { records }
+    }
+
+    /// Queues the record to be appended to the offer log, without waiting for it to be written.
+    pub fn append(&self, record: OfferLogRecord) {
+        match self.records.try_send(record) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Offer log queue is full, dropping record"),
+            Err(TrySendError::Disconnected(_)) => warn!("Offer log writer stopped"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn record() -> OfferLogRecord {
+        let mut record = OfferLogRecord::new(
+            "History".to_string(),
+            NodeId::new(&[1; 32]),
+            None,
+            vec![
+                RawContentKey::from([0x00, 0x01]),
+                RawContentKey::from([0x00, 0x02]),
+            ],
+        );
+        record.accepted.push(RawContentKey::from([0x00, 0x01]));
+        record.declined.push(DeclinedContentKey {
+            content_key: RawContentKey::from([0x00, 0x02]),
+            reason: OfferDeclineReason::AlreadyStored,
+        });
+        record
+    }
+
+    #[test]
+    fn append() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("offers.jsonl");
+        let mut offer_log = OfferLog::new(path.clone(), MAX_OFFER_LOG_FILE_SIZE);
+
+        offer_log.append(&record()).unwrap();
+        offer_log.append(&record()).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["accepted"], serde_json::json!(["0x0001"]));
+        assert_eq!(lines[0]["declined"][0]["reason"], "alreadyStored");
+    }
+
+    #[test]
+    fn rotate() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("offers.jsonl");
+        let line_size = serde_json::to_vec(&record()).unwrap().len() as u64 + 1;
+        let mut offer_log = OfferLog::new(path.clone(), 2 * line_size);
+
+        for _ in 0..3 {
+            offer_log.append(&record()).unwrap();
+        }
+
+        let rotated_path = temp_dir.path().join("offers.jsonl.1");
+        assert_eq!(
+            fs::read_to_string(&rotated_path).unwrap().lines().count(),
+            2
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn shared_offer_log_writes_in_background() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("offers.jsonl");
+        let offer_log = OfferLog::shared_from_file(Some(path.clone())).unwrap();
+
+        offer_log.append(record());
+        offer_log.append(record());
+
+        // The records are written in order, so the second one being written means both are.
+        let started = Instant::now();
+        while fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .count()
+            < 2
+        {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, sync::Arc};
+
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::{PeerTransferInfo, PeerTransfersInfo};
+use parking_lot::Mutex;
+
+/// Limits the number of concurrent uTP transfers with any single peer, so that one peer can't
+/// monopolize the transfer capacity of a subnetwork.
+///
+/// Active transfers are counted even without a limit, so that they can be inspected.
+#[derive(Debug, Default)]
+pub struct PeerTransferLimiter {
+    limit: Option<usize>,
+    active_transfers: Arc<Mutex<HashMap<NodeId, usize>>>,
+}
+
+impl PeerTransferLimiter {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            active_transfers: Arc::default(),
+        }
+    }
+
+    /// Non-blocking method to try and acquire a permit for a transfer with `node_id`.
+    ///
+    /// Returns `None` if the limit of concurrent transfers with the peer is reached. The
+    /// transfer is counted until the returned permit is dropped.
+    pub fn try_acquire(&self, node_id: NodeId) -> Option<PeerTransferPermit> {
+        let mut active_transfers = self.active_transfers.lock();
+        let count = active_transfers.entry(node_id).or_default();
+        if self.limit.is_some_and(|limit| *count >= limit) {
+            if *count == 0 {
+                active_transfers.remove(&node_id);
+            }
+            return None;
+        }
+        *count += 1;
+        Some(PeerTransferPermit {
+            node_id,
+            active_transfers: Arc::clone(&self.active_transfers),
+        })
+    }
+
+    pub fn info(&self) -> PeerTransfersInfo {
+        let mut peers: Vec<PeerTransferInfo> = self
+            .active_transfers
+            .lock()
+            .iter()
+            .map(|(node_id, active_transfers)| PeerTransferInfo {
+                node_id: *node_id,
+                active_transfers: *active_transfers,
+            })
+            .collect();
+        peers.sort_by(|a, b| b.active_transfers.cmp(&a.active_transfers));
+        PeerTransfersInfo {
+            limit: self.limit,
+            peers,
+        }
+    }
+}
+
+/// A transfer with a peer, that is counted towards its limit until dropped.
+#[derive(Debug)]
+pub struct PeerTransferPermit {
+    node_id: NodeId,
+    active_transfers: Arc<Mutex<HashMap<NodeId, usize>>>,
+}
+
+impl Drop for PeerTransferPermit {
+    fn drop(&mut self) {
+        let mut active_transfers = self.active_transfers.lock();
+        if let Some(count) = active_transfers.get_mut(&self.node_id) {
+            *count -= 1;
+            if *count == 0 {
+                active_transfers.remove(&self.node_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declines_transfers_over_limit_until_permit_is_dropped() {
+        let limiter = PeerTransferLimiter::new(Some(2));
+        let peer = NodeId::random();
+        let other_peer = NodeId::random();
+
+        let first = limiter.try_acquire(peer);
+        let second = limiter.try_acquire(peer);
+        assert!(first.is_some() && second.is_some());
+        assert!(limiter.try_acquire(peer).is_none());
+        // Other peers are unaffected.
+        assert!(limiter.try_acquire(other_peer).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(peer).is_some());
+    }
+
+    #[test]
+    fn counts_transfers_without_limit() {
+        let limiter = PeerTransferLimiter::new(None);
+        let peer = NodeId::random();
+
+        let permits: Vec<_> = (0..3).map(|_| limiter.try_acquire(peer)).collect();
+        assert!(permits.iter().all(Option::is_some));
+        assert_eq!(
+            limiter.info(),
+            PeerTransfersInfo {
+                limit: None,
+                peers: vec![PeerTransferInfo {
+                    node_id: peer,
+                    active_transfers: 3,
+                }],
+            }
+        );
+
+        drop(permits);
+        assert!(limiter.info().peers.is_empty());
+    }
+}
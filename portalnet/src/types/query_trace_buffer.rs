@@ -0,0 +1,113 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use ethportal_api::types::query_trace::{QueryTrace, QueryTraceSummary, RecordedQueryTrace};
+use parking_lot::Mutex;
+
+/// The query trace buffer that is shared between the overlay protocol and service.
+pub type SharedQueryTraceBuffer = Arc<Mutex<QueryTraceBuffer>>;
+
+/// Retains the traces of the most recent content lookups, so that a slow lookup can be
+/// investigated after it finished, without having to reproduce it with tracing enabled.
+///
+/// Once the buffer is full, the oldest trace is dropped for every new one. A buffer with zero
+/// capacity retains nothing.
+#[derive(Debug)]
+pub struct QueryTraceBuffer {
+    capacity: usize,
+    next_id: u64,
+    traces: VecDeque<RecordedQueryTrace>,
+}
+
+impl QueryTraceBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_id: 1,
+            traces: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn shared(capacity: usize) -> SharedQueryTraceBuffer {
+        Arc::new(Mutex::new(Self::new(capacity)))
+    }
+
+    /// Returns `true` if traces are retained.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Retains the trace of a finished lookup of `content_key`, and returns its id.
+    ///
+    /// Returns `None` if the buffer is disabled.
+    pub fn record(&mut self, content_key: String, trace: QueryTrace) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        if self.traces.len() == self.capacity {
+            self.traces.pop_front();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.traces.push_back(RecordedQueryTrace {
+            id,
+            content_key,
+            duration_ms: trace.elapsed_ms(),
+            trace,
+        });
+        Some(id)
+    }
+
+    /// Returns the trace with the given id, if it's still retained.
+    pub fn get(&self, id: u64) -> Option<RecordedQueryTrace> {
+        self.traces.iter().find(|trace| trace.id == id).cloned()
+    }
+
+    /// Returns the summaries of all retained traces, the most recent first.
+    pub fn list(&self) -> Vec<QueryTraceSummary> {
+        self.traces
+            .iter()
+            .rev()
+            .map(QueryTraceSummary::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+    use ethportal_api::types::enr::generate_random_remote_enr;
+
+    use super::*;
+
+    fn new_trace() -> QueryTrace {
+        let (_, enr) = generate_random_remote_enr();
+        QueryTrace::new(&enr, B256::random())
+    }
+
+    #[test]
+    fn drops_oldest_trace_when_full() {
+        let mut buffer = QueryTraceBuffer::new(2);
+
+        let first = buffer.record("0x00".to_string(), new_trace());
+        let second = buffer.record("0x01".to_string(), new_trace());
+        let third = buffer.record("0x02".to_string(), new_trace());
+        assert_eq!((first, second, third), (Some(1), Some(2), Some(3)));
+
+        assert!(buffer.get(1).is_none());
+        assert_eq!(
+            buffer.get(2).map(|trace| trace.content_key),
+            Some("0x01".to_string())
+        );
+        let ids: Vec<u64> = buffer.list().iter().map(|summary| summary.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+
+    #[test]
+    fn zero_capacity_retains_nothing() {
+        let mut buffer = QueryTraceBuffer::new(0);
+
+        assert!(!buffer.is_enabled());
+        assert_eq!(buffer.record("0x00".to_string(), new_trace()), None);
+        assert!(buffer.list().is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use ethportal_api::types::portal::ReadinessInfo;
+
+/// The criteria that a subnetwork must meet before it's reported as ready to serve traffic, e.g.
+/// to the readiness probe of an orchestrator, so that a node that is still warming up isn't
+/// routed traffic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadinessConfig {
+    /// The min number of connected peers in the routing table.
+    pub min_peers: usize,
+    /// The min number of content lookups that found the content since the node started.
+    pub min_successful_lookups: u64,
+}
+
+impl ReadinessConfig {
+    /// Returns whether the subnetwork is ready, together with the status of each criterion.
+    pub fn evaluate(
+        &self,
+        peers: usize,
+        store_initialized: bool,
+        successful_lookups: u64,
+    ) -> ReadinessInfo {
+        ReadinessInfo {
+            ready: peers >= self.min_peers
+                && store_initialized
+                && successful_lookups >= self.min_successful_lookups,
+            peers,
+            min_peers: self.min_peers,
+            store_initialized,
+            successful_lookups,
+            min_successful_lookups: self.min_successful_lookups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_criteria_must_be_met() {
+        let config = ReadinessConfig {
+            min_peers: 8,
+            min_successful_lookups: 1,
+        };
+
+        assert!(config.evaluate(8, true, 1).ready);
+        assert!(!config.evaluate(7, true, 1).ready);
+        assert!(!config.evaluate(8, false, 1).ready);
+        assert!(!config.evaluate(8, true, 0).ready);
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The seen content that is shared between the overlay protocol and service.
+pub type SharedSeenContent = Arc<Mutex<SeenContent>>;
+
+/// The max number of content ids that are remembered as seen. Once it's reached, the least
+/// recently seen content is forgotten, and processed again if it arrives within its window.
+pub const SEEN_CONTENT_CAPACITY: usize = 100_000;
+
+/// Remembers the content that recently arrived through gossip, so that the same content arriving
+/// from multiple peers within a window is only stored and propagated once.
+#[derive(Debug)]
+pub struct SeenContent {
+    window: Duration,
+    /// The time at which each content first arrived within its current window.
+    first_seen: LruCache<[u8; 32], Instant>,
+}
+
+impl SeenContent {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            first_seen: LruCache::new(SEEN_CONTENT_CAPACITY),
+        }
+    }
+
+    pub fn shared(window: Duration) -> SharedSeenContent {
+        Arc::new(Mutex::new(Self::new(window)))
+    }
+
+    /// Marks the content as seen at `now`, and returns whether it's a duplicate, i.e. it was
+    /// already seen within the window.
+    ///
+    /// The window starts when the content first arrives, and isn't extended by duplicates.
+    pub fn mark_seen(&mut self, content_id: &[u8; 32], now: Instant) -> bool {
+        if let Some(first_seen) = self.first_seen.get(content_id) {
+            if now.saturating_duration_since(*first_seen) < self.window {
+                return true;
+            }
+        }
+        self.first_seen.put(*content_id, now);
+        false
+    }
+
+    /// Forgets that the content was seen, e.g. because it turned out to be invalid, so that a
+    /// valid copy arriving later is processed.
+    pub fn forget(&mut self, content_id: &[u8; 32]) {
+        self.first_seen.pop(content_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn duplicates_within_the_window_are_detected() {
+        let mut seen_content = SeenContent::new(WINDOW);
+        let start = Instant::now();
+
+        assert!(!seen_content.mark_seen(&[1; 32], start));
+        assert!(seen_content.mark_seen(&[1; 32], start + WINDOW / 2));
+        assert!(!seen_content.mark_seen(&[2; 32], start + WINDOW / 2));
+
+        // The duplicate didn't extend the window.
+        assert!(!seen_content.mark_seen(&[1; 32], start + WINDOW));
+        assert!(seen_content.mark_seen(&[1; 32], start + WINDOW));
+    }
+
+    #[test]
+    fn forgotten_content_is_not_a_duplicate() {
+        let mut seen_content = SeenContent::new(WINDOW);
+        let now = Instant::now();
+
+        assert!(!seen_content.mark_seen(&[1; 32], now));
+        seen_content.forget(&[1; 32]);
+        assert!(!seen_content.mark_seen(&[1; 32], now));
+    }
+}
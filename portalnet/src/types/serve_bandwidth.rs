@@ -0,0 +1,81 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// The serve bandwidth limiter that is shared between the overlay networks.
+pub type SharedServeBandwidthLimiter = Arc<ServeBandwidthLimiter>;
+
+/// The number of bytes that are written to a uTP stream at a time, while the serve bandwidth is
+/// limited.
+pub const SERVE_CHUNK_SIZE: usize = 8 * 1024;
+
+/// New transfers are declined while the data of ongoing transfers is queued for longer than this.
+/// It's well below the uTP idle timeout, so that queued transfers don't time out.
+pub const MAX_SERVE_BACKLOG: Duration = Duration::from_secs(1);
+
+/// Limits the outbound bandwidth used to serve content in response to FindContent requests.
+///
+/// Each chunk of served data reserves its share of the bandwidth, and is sent once all data
+/// reserved before it would have been sent at the limited rate.
+#[derive(Debug)]
+pub struct ServeBandwidthLimiter {
+    bytes_per_sec: u64,
+    /// The time at which all data reserved so far has been sent at the limited rate.
+    next_free: Mutex<Instant>,
+}
+
+impl ServeBandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn shared(bytes_per_sec: u64) -> SharedServeBandwidthLimiter {
+        Arc::new(Self::new(bytes_per_sec))
+    }
+
+    /// Reserves bandwidth to send `bytes`, and returns how long to wait from `now` before sending
+    /// them.
+    pub fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut next_free = self.next_free.lock();
+        let start = (*next_free).max(now);
+        let send_duration = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        *next_free = start + send_duration;
+        start - now
+    }
+
+    /// Returns `true` if the data of ongoing transfers is queued for too long to start new ones.
+    pub fn is_saturated(&self, now: Instant) -> bool {
+        self.next_free.lock().saturating_duration_since(now) > MAX_SERVE_BACKLOG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reservations_are_queued_at_the_limited_rate() {
+        let limiter = ServeBandwidthLimiter::new(1000);
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(500, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(1000, now), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(500, now), Duration::from_millis(1500));
+        assert!(limiter.is_saturated(now));
+
+        // The queue drains over time.
+        let later = now + Duration::from_secs(1);
+        assert!(!limiter.is_saturated(later));
+        assert_eq!(limiter.reserve(100, later), Duration::from_secs(1));
+        assert_eq!(
+            limiter.reserve(100, now + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+    }
+}
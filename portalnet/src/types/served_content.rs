@@ -0,0 +1,170 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethportal_api::{types::portal::ServedContentInfo, RawContentKey};
+use lru::LruCache;
+use parking_lot::Mutex;
+
+/// The served content that is shared between the overlay protocol and service.
+pub type SharedServedContent = Arc<Mutex<ServedContent>>;
+
+/// The max number of content ids whose serve count is retained. Once it's reached, the count of
+/// the least recently served content is dropped.
+pub const SERVED_CONTENT_CAPACITY: usize = 10_000;
+
+/// The window over which serves are counted. The count of content restarts once its first
+/// counted serve is older than the window, and content that wasn't served within the window
+/// isn't listed.
+pub const SERVED_CONTENT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// The serves of a single content id within the current window.
+#[derive(Debug)]
+struct ServeRecord {
+    content_key: RawContentKey,
+    hits: u64,
+    window_start: Instant,
+    last_served: Instant,
+}
+
+/// Counts how often the local node served each content to peers, to reveal which content is in
+/// demand on the network.
+#[derive(Debug)]
+pub struct ServedContent {
+    records: LruCache<[u8; 32], ServeRecord>,
+}
+
+impl Default for ServedContent {
+    fn default() -> Self {
+        Self {
+            records: LruCache::new(SERVED_CONTENT_CAPACITY),
+        }
+    }
+}
+
+impl ServedContent {
+    pub fn shared() -> SharedServedContent {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    /// Records that the content was served to a peer at `now`.
+    pub fn record(&mut self, content_id: &[u8; 32], content_key: RawContentKey, now: Instant) {
+        let Some(record) = self.records.get_mut(content_id) else {
+            self.records.put(
+                *content_id,
+                ServeRecord {
+                    content_key,
+                    hits: 1,
+                    window_start: now,
+                    last_served: now,
+                },
+            );
+            return;
+        };
+        if now.saturating_duration_since(record.window_start) >= SERVED_CONTENT_WINDOW {
+            record.hits = 0;
+            record.window_start = now;
+        }
+        record.hits += 1;
+        record.last_served = now;
+    }
+
+    /// Returns up to `count` content served within the window before `now`, the most served
+    /// first.
+    pub fn most_served(&self, count: usize, now: Instant) -> Vec<ServedContentInfo> {
+        let mut records: Vec<&ServeRecord> = self
+            .records
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| {
+                now.saturating_duration_since(record.last_served) < SERVED_CONTENT_WINDOW
+            })
+            .collect();
+        records.sort_by(|a, b| {
+            b.hits
+                .cmp(&a.hits)
+                .then_with(|| b.last_served.cmp(&a.last_served))
+        });
+        records
+            .into_iter()
+            .take(count)
+            .map(|record| ServedContentInfo {
+                content_key: record.content_key.clone(),
+                hits: record.hits,
+                last_served_ms_ago: now
+                    .saturating_duration_since(record.last_served)
+                    .as_millis() as u64,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_served_content_is_listed_first() {
+        let mut served_content = ServedContent::default();
+        let now = Instant::now();
+        let (hot, cold) = (RawContentKey::from(vec![1]), RawContentKey::from(vec![2]));
+
+        served_content.record(&[1; 32], hot.clone(), now);
+        served_content.record(&[2; 32], cold.clone(), now);
+        served_content.record(&[1; 32], hot.clone(), now);
+
+        let most_served = served_content.most_served(10, now);
+        assert_eq!(
+            most_served
+                .iter()
+                .map(|info| (info.content_key.clone(), info.hits))
+                .collect::<Vec<_>>(),
+            vec![(hot.clone(), 2), (cold, 1)]
+        );
+        assert_eq!(served_content.most_served(1, now).len(), 1);
+    }
+
+    #[test]
+    fn serves_are_counted_within_the_window() {
+        let mut served_content = ServedContent::default();
+        let start = Instant::now();
+        let content_key = RawContentKey::from(vec![1]);
+
+        served_content.record(&[1; 32], content_key.clone(), start);
+        served_content.record(
+            &[1; 32],
+            content_key.clone(),
+            start + SERVED_CONTENT_WINDOW / 2,
+        );
+        assert_eq!(
+            served_content.most_served(10, start + SERVED_CONTENT_WINDOW / 2)[0].hits,
+            2
+        );
+
+        // The count restarts in the next window.
+        let next_window = start + SERVED_CONTENT_WINDOW;
+        served_content.record(&[1; 32], content_key, next_window);
+        assert_eq!(served_content.most_served(10, next_window)[0].hits, 1);
+
+        // Content that wasn't served within the window isn't listed.
+        assert!(served_content
+            .most_served(10, next_window + SERVED_CONTENT_WINDOW)
+            .is_empty());
+    }
+
+    #[test]
+    fn served_content_is_bounded() {
+        let mut served_content = ServedContent::default();
+        let now = Instant::now();
+        for i in 0..SERVED_CONTENT_CAPACITY as u32 + 1 {
+            let mut content_id = [0; 32];
+            content_id[..4].copy_from_slice(&i.to_be_bytes());
+            served_content.record(&content_id, RawContentKey::from(content_id.to_vec()), now);
+        }
+        assert_eq!(
+            served_content.most_served(usize::MAX, now).len(),
+            SERVED_CONTENT_CAPACITY
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use ethportal_api::types::{discv5::StoreHealthInfo, network::Subnetwork};
+use tracing::{error, info};
+use trin_storage::error::ContentStoreError;
+
+use super::last_errors::{ErrorSubsystem, SharedLastErrors};
+
+/// The store health that is shared between the overlay protocol and service.
+pub type SharedStoreHealth = Arc<StoreHealth>;
+
+/// The number of consecutive failed store operations after which the store is considered
+/// unavailable.
+pub const STORE_FAILURE_THRESHOLD: u32 = 5;
+
+/// Tracks whether the data store of an overlay network is usable.
+///
+/// After `STORE_FAILURE_THRESHOLD` consecutive failed store operations, the store is considered
+/// unavailable and the overlay enters a degraded mode: it stops accepting offered content and
+/// only routes lookups to other peers. The store leaves the degraded mode after its next
+/// successful operation.
+#[derive(Debug)]
+pub struct StoreHealth {
+    protocol: Subnetwork,
+    consecutive_failures: AtomicU32,
+    degraded: AtomicBool,
+    /// The last errors of the overlay network, which failed store operations are recorded in.
+    last_errors: SharedLastErrors,
+}
+
+impl StoreHealth {
+    pub fn new(protocol: Subnetwork, last_errors: SharedLastErrors) -> Self {
+        Self {
+            protocol,
+            consecutive_failures: AtomicU32::new(0),
+            degraded: AtomicBool::new(false),
+            last_errors,
+        }
+    }
+
+    /// Records the outcome of a store operation.
+    pub fn report<T>(&self, result: &Result<T, ContentStoreError>) {
+        match result {
+            Ok(_) => self.report_success(),
+            Err(err) => self.report_failure(err),
+        }
+    }
+
+    /// Records a successful store operation, leaving the degraded mode if the store was in it.
+    pub fn report_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        if self.degraded.swap(false, Ordering::Relaxed) {
+            info!(
+                protocol = %self.protocol,
+                "Content store recovered, leaving degraded mode"
+            );
+        }
+    }
+
+    /// Records a failed store operation, entering the degraded mode once the failure threshold
+    /// is reached.
+    pub fn report_failure(&self, err: &impl Display) {
+        self.last_errors.record(ErrorSubsystem::Store, err);
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= STORE_FAILURE_THRESHOLD && !self.degraded.swap(true, Ordering::Relaxed) {
+            error!(
+                protocol = %self.protocol,
+                error = %err,
+                failures,
+                "CONTENT STORE UNAVAILABLE: entering degraded mode. Offered content is declined \
+                and lookups are only routed to peers until the store recovers."
+            );
+        }
+    }
+
+    /// Returns `true` if the store is considered unavailable.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    pub fn info(&self) -> StoreHealthInfo {
+        StoreHealthInfo {
+            degraded: self.is_degraded(),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::last_errors::LastErrors;
+
+    #[test]
+    fn degrades_after_threshold_and_recovers() {
+        let health = StoreHealth::new(Subnetwork::History, LastErrors::shared());
+        let err = ContentStoreError::Database("disk full".to_string());
+
+        for _ in 1..STORE_FAILURE_THRESHOLD {
+            health.report::<()>(&Err(ContentStoreError::Database("disk full".to_string())));
+        }
+        assert!(!health.is_degraded());
+
+        health.report_failure(&err);
+        assert!(health.is_degraded());
+        assert_eq!(health.info().consecutive_failures, STORE_FAILURE_THRESHOLD);
+
+        health.report(&Ok(()));
+        assert_eq!(
+            health.info(),
+            StoreHealthInfo {
+                degraded: false,
+                consecutive_failures: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let health = StoreHealth::new(Subnetwork::History, LastErrors::shared());
+        let err = ContentStoreError::Database("locked".to_string());
+
+        for _ in 1..STORE_FAILURE_THRESHOLD {
+            health.report_failure(&err);
+        }
+        health.report_success();
+        health.report_failure(&err);
+        assert!(!health.is_degraded());
+        assert_eq!(health.info().consecutive_failures, 1);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use discv5::kbucket::Filter;
+use ethportal_api::types::enr::Enr;
+
+use super::node::Node;
+
+/// The length of the prefix of the IPv4 subnets that peers are grouped by.
+const IPV4_SUBNET_PREFIX_LEN: u8 = 24;
+
+/// The length of the prefix of the IPv6 subnets that peers are grouped by.
+const IPV6_SUBNET_PREFIX_LEN: u8 = 64;
+
+/// The IP subnet of the address that a peer advertises in its ENR: a /24 for IPv4 addresses, and
+/// a /64 for IPv6 addresses.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IpSubnet {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpSubnet {
+    /// Returns the subnet of the address advertised by the `enr`, preferring its IPv4 address, if
+    /// it advertises any.
+    pub fn of(enr: &Enr) -> Option<Self> {
+        if let Some(ip4) = enr.ip4() {
+            let [a, b, c, _] = ip4.octets();
+            return Some(Self {
+                network: Ipv4Addr::new(a, b, c, 0).into(),
+                prefix_len: IPV4_SUBNET_PREFIX_LEN,
+            });
+        }
+        enr.ip6().map(|ip6| {
+            let mask = u128::MAX << (128 - IPV6_SUBNET_PREFIX_LEN);
+            Self {
+                network: Ipv6Addr::from(u128::from(ip6) & mask).into(),
+                prefix_len: IPV6_SUBNET_PREFIX_LEN,
+            }
+        })
+    }
+}
+
+impl fmt::Display for IpSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// A routing table filter that limits the number of peers whose advertised addresses share an IP
+/// subnet, so that peers from a single network, e.g. run by a single operator, can't dominate the
+/// routing table, as in an eclipse attack.
+///
+/// Peers that don't advertise an address aren't limited.
+#[derive(Clone, Debug)]
+pub struct SubnetFilter {
+    max_peers_per_subnet: usize,
+}
+
+impl SubnetFilter {
+    pub fn new(max_peers_per_subnet: usize) -> Self {
+        Self {
+            max_peers_per_subnet,
+        }
+    }
+}
+
+impl Filter<Node> for SubnetFilter {
+    fn filter(&self, node: &Node, other_nodes: &mut dyn Iterator<Item = &Node>) -> bool {
+        let Some(subnet) = IpSubnet::of(&node.enr) else {
+            return true;
+        };
+        let node_id = node.enr.node_id();
+        // The node itself is skipped, so that updates of nodes already in the table pass.
+        let peers_in_subnet = other_nodes
+            .filter(|other| {
+                other.enr.node_id() != node_id && IpSubnet::of(&other.enr) == Some(subnet)
+            })
+            .count();
+        peers_in_subnet < self.max_peers_per_subnet
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use discv5::enr::CombinedKey;
+    use ethportal_api::types::distance::Distance;
+
+    use super::*;
+
+    fn node_with_ip(ip: IpAddr) -> Node {
+        let key = CombinedKey::generate_secp256k1();
+        let enr = Enr::builder().ip(ip).build(&key).unwrap();
+        Node::new(enr, Distance::MAX)
+    }
+
+    #[test]
+    fn subnets_group_nearby_addresses() {
+        let subnet = |ip: &str| IpSubnet::of(&node_with_ip(ip.parse().unwrap()).enr).unwrap();
+
+        assert_eq!(subnet("10.1.2.3"), subnet("10.1.2.200"));
+        assert_ne!(subnet("10.1.2.3"), subnet("10.1.3.3"));
+        assert_eq!(subnet("10.1.2.3").to_string(), "10.1.2.0/24");
+        assert_eq!(subnet("2001:db8:1:2:3::1").to_string(), "2001:db8:1:2::/64");
+    }
+
+    #[test]
+    fn peers_per_subnet_are_limited() {
+        let filter = SubnetFilter::new(2);
+        let table = [
+            node_with_ip("10.1.2.3".parse().unwrap()),
+            node_with_ip("10.1.2.4".parse().unwrap()),
+            node_with_ip("10.9.9.9".parse().unwrap()),
+        ];
+
+        let crowded = node_with_ip("10.1.2.5".parse().unwrap());
+        assert!(!filter.filter(&crowded, &mut table.iter()));
+        let diverse = node_with_ip("10.9.9.10".parse().unwrap());
+        assert!(filter.filter(&diverse, &mut table.iter()));
+        // Nodes already in the table can be updated.
+        assert!(filter.filter(&table[0], &mut table.iter()));
+    }
+}
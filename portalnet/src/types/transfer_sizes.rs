@@ -0,0 +1,172 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use discv5::enr::NodeId;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+/// The size of the fixed uTP packet header, which is followed by the extensions and the payload.
+const UTP_HEADER_SIZE: usize = 20;
+
+/// The type of the uTP packets that carry data.
+const UTP_ST_DATA: u8 = 0;
+
+/// Meters the data received by size limited inbound uTP transfers as their packets arrive, so that
+/// a transfer can be aborted as soon as it exceeds its limit, rather than once it completes.
+///
+/// Transfers are keyed by the peer and the connection id that the peer addresses its packets to.
+#[derive(Clone, Debug, Default)]
+pub struct TransferSizeMeter {
+    transfers: Arc<Mutex<HashMap<(NodeId, u16), MeteredTransfer>>>,
+}
+
+#[derive(Debug)]
+struct MeteredTransfer {
+    max_size: usize,
+    received: usize,
+    /// The sequence numbers of the data packets received so far, so that retransmitted packets
+    /// aren't counted twice.
+    seq_nrs: HashSet<u16>,
+    exceeded: Option<oneshot::Sender<()>>,
+}
+
+impl TransferSizeMeter {
+    /// Starts metering the data received from `peer` on the connection with the receive id
+    /// `conn_id`. The transfer is metered until the returned guard is dropped.
+    pub fn register(&self, peer: NodeId, conn_id: u16, max_size: usize) -> MeteredTransferGuard {
+        let (tx, rx) = oneshot::channel();
+        self.transfers.lock().insert(
+            (peer, conn_id),
+            MeteredTransfer {
+                max_size,
+                received: 0,
+                seq_nrs: HashSet::new(),
+                exceeded: Some(tx),
+            },
+        );
+        MeteredTransferGuard {
+            meter: self.clone(),
+            key: (peer, conn_id),
+            exceeded: rx,
+        }
+    }
+
+    /// Counts the payload of the uTP `packet` received from `peer`, if it's a data packet of a
+    /// metered transfer.
+    pub fn record(&self, peer: NodeId, packet: &[u8]) {
+        let Some((conn_id, seq_nr, payload_len)) = data_packet(packet) else {
+            return;
+        };
+        let mut transfers = self.transfers.lock();
+        let Some(transfer) = transfers.get_mut(&(peer, conn_id)) else {
+            return;
+        };
+        if !transfer.seq_nrs.insert(seq_nr) {
+            return;
+        }
+        transfer.received += payload_len;
+        if transfer.received > transfer.max_size {
+            if let Some(exceeded) = transfer.exceeded.take() {
+                let _ = exceeded.send(());
+            }
+        }
+    }
+}
+
+/// A metered transfer, that is no longer metered once dropped.
+#[derive(Debug)]
+pub struct MeteredTransferGuard {
+    meter: TransferSizeMeter,
+    key: (NodeId, u16),
+    exceeded: oneshot::Receiver<()>,
+}
+
+impl MeteredTransferGuard {
+    /// Resolves once more data than the size limit of the transfer was received.
+    pub async fn exceeded(&mut self) {
+        if (&mut self.exceeded).await.is_err() {
+            // The transfer was replaced by another one with the same key, which is metered
+            // instead.
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for MeteredTransferGuard {
+    fn drop(&mut self) {
+        self.meter.transfers.lock().remove(&self.key);
+    }
+}
+
+/// Returns the connection id, the sequence number and the payload length of `packet`, if it's a
+/// well formed uTP data packet.
+fn data_packet(packet: &[u8]) -> Option<(u16, u16, usize)> {
+    if packet.len() < UTP_HEADER_SIZE || packet[0] >> 4 != UTP_ST_DATA {
+        return None;
+    }
+    let conn_id = u16::from_be_bytes([packet[2], packet[3]]);
+    let seq_nr = u16::from_be_bytes([packet[16], packet[17]]);
+
+    // Each extension starts with the type of the next extension and its own length.
+    let mut next_extension = packet[1];
+    let mut offset = UTP_HEADER_SIZE;
+    while next_extension != 0 {
+        next_extension = *packet.get(offset)?;
+        let len = *packet.get(offset + 1)? as usize;
+        offset += 2 + len;
+    }
+    let payload_len = packet.len().checked_sub(offset)?;
+    Some((conn_id, seq_nr, payload_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    fn packet(conn_id: u16, seq_nr: u16, payload_len: usize) -> Vec<u8> {
+        let mut packet = vec![0; UTP_HEADER_SIZE + payload_len];
+        packet[0] = (UTP_ST_DATA << 4) | 1;
+        packet[2..4].copy_from_slice(&conn_id.to_be_bytes());
+        packet[16..18].copy_from_slice(&seq_nr.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn exceeded_once_more_than_max_size_is_received() {
+        let meter = TransferSizeMeter::default();
+        let peer = NodeId::random();
+        let mut guard = meter.register(peer, 7, 1000);
+
+        meter.record(peer, &packet(7, 1, 600));
+        // Retransmissions, other connections and other peers aren't counted.
+        meter.record(peer, &packet(7, 1, 600));
+        meter.record(peer, &packet(8, 2, 600));
+        meter.record(NodeId::random(), &packet(7, 2, 600));
+        assert!(guard.exceeded().now_or_never().is_none());
+
+        meter.record(peer, &packet(7, 2, 600));
+        assert!(guard.exceeded().now_or_never().is_some());
+    }
+
+    #[test]
+    fn transfer_is_no_longer_metered_once_guard_is_dropped() {
+        let meter = TransferSizeMeter::default();
+        let peer = NodeId::random();
+        drop(meter.register(peer, 7, 1000));
+        assert!(meter.transfers.lock().is_empty());
+    }
+
+    #[test]
+    fn extensions_are_not_counted_as_payload() {
+        let mut packet = packet(7, 1, 10);
+        // A selective ack extension of 4 bytes, followed by 4 bytes of payload.
+        packet[1] = 1;
+        packet[UTP_HEADER_SIZE] = 0;
+        packet[UTP_HEADER_SIZE + 1] = 4;
+        assert_eq!(data_packet(&packet), Some((7, 1, 4)));
+    }
+}
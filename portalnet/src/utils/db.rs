@@ -15,6 +15,7 @@ use trin_utils::dir::setup_data_dir;
 const APP_NAME: &str = "trin";
 const TRIN_DATA_ENV_VAR: &str = "TRIN_DATA_PATH";
 const UNSAFE_PRIVATE_KEY_FILE_NAME: &str = "unsafe_private_key.hex";
+const ENR_SEQ_FILE_NAME: &str = "enr_seq";
 
 pub fn configure_trin_data_dir(
     data_dir: Option<PathBuf>,
@@ -62,6 +63,31 @@ fn get_node_data_dir(trin_data_dir: &Path, node_id: NodeId, network: Network) ->
     trin_data_dir.join(application_string)
 }
 
+/// Returns the file that the ENR sequence number of the node is persisted in.
+pub fn enr_seq_file(node_data_dir: &Path) -> PathBuf {
+    node_data_dir.join(ENR_SEQ_FILE_NAME)
+}
+
+/// Returns the persisted ENR sequence number, if any.
+pub fn load_enr_seq(enr_seq_file: &Path) -> anyhow::Result<Option<u64>> {
+    if !enr_seq_file.exists() {
+        return Ok(None);
+    }
+    let seq = fs::read_to_string(enr_seq_file)?;
+    Ok(Some(seq.trim().parse()?))
+}
+
+/// Persists the ENR sequence number.
+///
+/// The number is written to a temporary file first, which then replaces the persisted one, so a
+/// crash never leaves a truncated file behind.
+pub fn store_enr_seq(enr_seq_file: &Path, seq: u64) -> anyhow::Result<()> {
+    let temp_file = enr_seq_file.with_extension("tmp");
+    fs::write(&temp_file, seq.to_string())?;
+    fs::rename(temp_file, enr_seq_file)?;
+    Ok(())
+}
+
 /// Returns application private key.
 /// If the private key does not exist (eg. brand new trin data dir),
 /// a random pk is generated and stored.
@@ -108,6 +134,22 @@ pub mod test {
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn enr_seq_persists() {
+        let temp_dir = create_temp_test_dir().unwrap();
+        let enr_seq_file = enr_seq_file(temp_dir.path());
+        assert_eq!(load_enr_seq(&enr_seq_file).unwrap(), None);
+
+        store_enr_seq(&enr_seq_file, 1_700_000_000).unwrap();
+        assert_eq!(load_enr_seq(&enr_seq_file).unwrap(), Some(1_700_000_000));
+        store_enr_seq(&enr_seq_file, 1_700_000_001).unwrap();
+        assert_eq!(load_enr_seq(&enr_seq_file).unwrap(), Some(1_700_000_001));
+
+        fs::write(&enr_seq_file, "not a number").unwrap();
+        load_enr_seq(&enr_seq_file).unwrap_err();
+        temp_dir.close().unwrap();
+    }
+
     #[test]
     #[serial]
     fn activated_private_key_persists_over_reconfigurations() {
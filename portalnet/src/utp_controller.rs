@@ -1,7 +1,12 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
 use bytes::Bytes;
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::PeerTransfersInfo;
 use lazy_static::lazy_static;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::debug;
@@ -11,7 +16,16 @@ use trin_metrics::{
 };
 use utp_rs::{cid::ConnectionId, conn::ConnectionConfig, socket::UtpSocket};
 
-use crate::discovery::UtpEnr;
+use crate::{
+    compression::{decode_transfer, encode_transfer, supports_compression},
+    discovery::UtpEnr,
+    types::{
+        last_errors::{ErrorSubsystem, SharedLastErrors},
+        peer_transfers::{PeerTransferLimiter, PeerTransferPermit},
+        serve_bandwidth::{SharedServeBandwidthLimiter, SERVE_CHUNK_SIZE},
+        transfer_sizes::TransferSizeMeter,
+    },
+};
 /// UtpController is meant to be a container which contains all code related to/for managing uTP
 /// streams We are implementing this because we want the utils of controlling uTP connection to be
 /// as contained as it can, instead of extending overlay_service even more.
@@ -23,8 +37,22 @@ use crate::discovery::UtpEnr;
 pub struct UtpController {
     inbound_utp_transfer_semaphore: Arc<Semaphore>,
     outbound_utp_transfer_semaphore: Arc<Semaphore>,
+    /// Limits the concurrent transfers requested by a single peer.
+    peer_transfers: PeerTransferLimiter,
+    /// Limits the bandwidth used to serve content in response to FindContent requests, if set.
+    serve_bandwidth: Option<SharedServeBandwidthLimiter>,
     utp_socket: Arc<UtpSocket<UtpEnr>>,
     metrics: OverlayMetricsReporter,
+    /// The configuration used for all uTP connections of this controller.
+    conn_config: ConnectionConfig,
+    /// Whether content found with FindContent requests is transferred with a compression flag, and
+    /// compressed, between the local node and peers that advertise support for compression too.
+    compression: bool,
+    /// The last errors of the overlay network, which failed transfers are recorded in.
+    last_errors: SharedLastErrors,
+    /// Meters the data received by size limited inbound transfers, to abort them once they exceed
+    /// their limit.
+    transfer_sizes: TransferSizeMeter,
 }
 
 lazy_static! {
@@ -38,6 +66,16 @@ lazy_static! {
     };
 }
 
+/// Returns the default uTP connection configuration, with the congestion control target delay
+/// overridden, if provided.
+pub fn utp_conn_config(target_delay: Option<Duration>) -> ConnectionConfig {
+    let mut conn_config = *UTP_CONN_CFG;
+    if let Some(target_delay) = target_delay {
+        conn_config.target_delay = target_delay;
+    }
+    conn_config
+}
+
 /// An enum for deciding to initiate the uTP connection as connecting or accepting.
 /// The selection is specified in the Portal Wire spec, depending upon whether the
 /// data is being transferred inbound or outbound.
@@ -49,14 +87,26 @@ enum UtpConnectionSide {
 impl UtpController {
     pub fn new(
         utp_transfer_limit: usize,
+        peer_transfer_limit: Option<usize>,
+        serve_bandwidth: Option<SharedServeBandwidthLimiter>,
         utp_socket: Arc<UtpSocket<UtpEnr>>,
         metrics: OverlayMetricsReporter,
+        conn_config: ConnectionConfig,
+        compression: bool,
+        last_errors: SharedLastErrors,
+        transfer_sizes: TransferSizeMeter,
     ) -> Self {
         Self {
             utp_socket,
             inbound_utp_transfer_semaphore: Arc::new(Semaphore::new(utp_transfer_limit)),
             outbound_utp_transfer_semaphore: Arc::new(Semaphore::new(utp_transfer_limit)),
+            peer_transfers: PeerTransferLimiter::new(peer_transfer_limit),
+            serve_bandwidth,
             metrics,
+            conn_config,
+            compression,
+            last_errors,
+            transfer_sizes,
         }
     }
 
@@ -92,43 +142,119 @@ impl UtpController {
         }
     }
 
+    /// Non-blocking method to try and acquire a permit for a uTP transfer requested by `node_id`.
+    /// Returns `None` if the peer already reached its limit of concurrent transfers.
+    pub fn get_peer_permit(&self, node_id: NodeId) -> Option<PeerTransferPermit> {
+        self.peer_transfers.try_acquire(node_id)
+    }
+
+    /// Returns the number of active transfers requested by each peer.
+    pub fn peer_transfers_info(&self) -> PeerTransfersInfo {
+        self.peer_transfers.info()
+    }
+
+    /// Returns `true` if the serve bandwidth is limited, and too much data is already queued to
+    /// serve more content.
+    pub fn is_serve_bandwidth_saturated(&self) -> bool {
+        self.serve_bandwidth
+            .as_ref()
+            .is_some_and(|serve_bandwidth| serve_bandwidth.is_saturated(Instant::now()))
+    }
+
     pub async fn connect_inbound_stream(&self, cid: ConnectionId<UtpEnr>) -> anyhow::Result<Bytes> {
-        self.inbound_stream(cid, UtpConnectionSide::Connect).await
+        self.inbound_stream(cid, UtpConnectionSide::Connect, None)
+            .await
+    }
+
+    /// Same as [UtpController::connect_inbound_stream], but fails if more than `max_size` bytes
+    /// are received.
+    pub async fn connect_inbound_stream_with_limit(
+        &self,
+        cid: ConnectionId<UtpEnr>,
+        max_size: usize,
+    ) -> anyhow::Result<Bytes> {
+        self.inbound_stream(cid, UtpConnectionSide::Connect, Some(max_size))
+            .await
     }
 
     pub async fn accept_inbound_stream(&self, cid: ConnectionId<UtpEnr>) -> anyhow::Result<Bytes> {
-        self.inbound_stream(cid, UtpConnectionSide::Accept).await
+        self.inbound_stream(cid, UtpConnectionSide::Accept, None)
+            .await
     }
 
     pub async fn connect_outbound_stream(&self, cid: ConnectionId<UtpEnr>, data: &[u8]) -> bool {
-        self.outbound_stream(cid, data, UtpConnectionSide::Connect)
+        self.outbound_stream(cid, data, UtpConnectionSide::Connect, false)
             .await
     }
 
     pub async fn accept_outbound_stream(&self, cid: ConnectionId<UtpEnr>, data: &[u8]) -> bool {
-        self.outbound_stream(cid, data, UtpConnectionSide::Accept)
+        self.outbound_stream(cid, data, UtpConnectionSide::Accept, false)
             .await
     }
 
+    /// Same as [UtpController::accept_outbound_stream], for content served in response to a
+    /// FindContent request. The data is sent within the serve bandwidth limit, if set, and
+    /// encoded with [encode_transfer] if the requesting peer supports compression.
+    pub async fn accept_serve_stream(&self, cid: ConnectionId<UtpEnr>, data: &[u8]) -> bool {
+        let encoded = self
+            .uses_compression(&cid.peer)
+            .then(|| encode_transfer(data));
+        let wire_data = encoded
+            .as_ref()
+            .map_or(data, |(encoded, _)| encoded.as_slice());
+        let success = self
+            .outbound_stream(cid, wire_data, UtpConnectionSide::Accept, true)
+            .await;
+        if success {
+            self.metrics.report_utp_served_bytes(wire_data.len());
+            if let Some((_, true)) = encoded {
+                self.metrics.report_utp_compression_saved_bytes(
+                    UtpDirectionLabel::Outbound,
+                    data.len().saturating_sub(wire_data.len()),
+                );
+            }
+        }
+        success
+    }
+
+    /// Returns whether content found with FindContent requests is transferred encoded with
+    /// [encode_transfer] between the local node and the peer, which is the case if both advertise
+    /// support for compression in their ENR.
+    fn uses_compression(&self, peer: &UtpEnr) -> bool {
+        self.compression && supports_compression(&peer.0)
+    }
+
     async fn inbound_stream(
         &self,
         cid: ConnectionId<UtpEnr>,
         side: UtpConnectionSide,
+        max_size: Option<usize>,
     ) -> anyhow::Result<Bytes> {
         // Wait for an incoming connection with the given CID. Then, read the data from the uTP
         // stream.
         self.metrics
             .report_utp_active_inc(UtpDirectionLabel::Inbound);
+        // Only content found with FindContent requests, received over connected streams, is
+        // encoded, with a flag byte that precedes the content.
+        let is_encoded =
+            matches!(side, UtpConnectionSide::Connect) && self.uses_compression(&cid.peer);
+        let wire_max_size = max_size.map(|max_size| max_size + usize::from(is_encoded));
+        // The transfer is metered from before the connection is established, so that no data
+        // packet is missed.
+        let mut metered_transfer = wire_max_size.map(|max_size| {
+            self.transfer_sizes
+                .register(cid.peer.0.node_id(), cid.recv, max_size)
+        });
         let (stream, message) = match side {
             UtpConnectionSide::Connect => (
                 self.utp_socket
-                    .connect_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .connect_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "connect inbound uTP stream",
             ),
             UtpConnectionSide::Accept => (
                 self.utp_socket
-                    .accept_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .accept_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "accept inbound uTP stream",
             ),
@@ -139,19 +265,62 @@ impl UtpController {
                 UtpOutcomeLabel::FailedConnection,
             );
             debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to {message}");
+            self.last_errors
+                .record(ErrorSubsystem::Utp, format!("Unable to {message}: {err}"));
             anyhow!("Unable to locate content on the network: unable to {message}")
         })?;
 
         let mut data = vec![];
-        stream.read_to_eof(&mut data).await
-            .map_err(|err| {
+        let read = stream.read_to_eof(&mut data);
+        let read = match (&mut metered_transfer, wire_max_size) {
+            (Some(metered_transfer), Some(max_size)) => tokio::select! {
+                read = read => read,
+                _ = metered_transfer.exceeded() => {
+                    return Err(self.exceeded_size_limit(&cid, message, max_size));
+                }
+            },
+            _ => read.await,
+        };
+        drop(metered_transfer);
+        read.map_err(|err| {
+            self.metrics
+                .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx);
+            debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "error reading data from {message}");
+            self.last_errors
+                .record(ErrorSubsystem::Utp, format!("Error reading data from {message}: {err}"));
+            anyhow!("Unable to locate content on the network: error reading data from {message}")
+        })?;
+
+        // The metered size of the transfer may fall short of the data read, e.g. if sequence
+        // numbers wrapped around during the transfer, so the size limit is checked again.
+        if let Some(max_size) = wire_max_size {
+            if data.len() > max_size {
+                return Err(self.exceeded_size_limit(&cid, message, max_size));
+            }
+        }
+
+        if is_encoded {
+            let wire_size = data.len();
+            let (decoded, compressed) = decode_transfer(&data, max_size).map_err(|err| {
                 self.metrics
                     .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx);
-                debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "error reading data from {message}");
+                debug!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to decode data from {message}");
+                self.last_errors.record(
+                    ErrorSubsystem::Utp,
+                    format!("Unable to decode data from {message}: {err}"),
+                );
                 anyhow!(
-                    "Unable to locate content on the network: error reading data from {message}"
+                    "Unable to locate content on the network: unable to decode data from {message}"
                 )
             })?;
+            data = decoded;
+            if compressed {
+                self.metrics.report_utp_compression_saved_bytes(
+                    UtpDirectionLabel::Inbound,
+                    data.len().saturating_sub(wire_size),
+                );
+            }
+        }
 
         // report utp tx as successful, even if we go on to fail to process the payload
         self.metrics
@@ -159,24 +328,45 @@ impl UtpController {
         Ok(Bytes::from(data))
     }
 
+    /// Reports an inbound transfer that was aborted because it exceeded its size limit, and
+    /// returns the error to fail it with.
+    fn exceeded_size_limit(
+        &self,
+        cid: &ConnectionId<UtpEnr>,
+        message: &str,
+        max_size: usize,
+    ) -> anyhow::Error {
+        self.metrics
+            .report_utp_outcome(UtpDirectionLabel::Inbound, UtpOutcomeLabel::FailedDataTx);
+        debug!(cid.send, cid.recv, peer = ?cid.peer.client(), max_size, "data from {message} exceeds size limit");
+        self.last_errors.record(
+            ErrorSubsystem::Utp,
+            format!("Data from {message} exceeds size limit of {max_size} bytes"),
+        );
+        anyhow!(
+            "Unable to locate content on the network: data from {message} exceeds size limit of {max_size} bytes"
+        )
+    }
+
     async fn outbound_stream(
         &self,
         cid: ConnectionId<UtpEnr>,
         data: &[u8],
         side: UtpConnectionSide,
+        is_serve: bool,
     ) -> bool {
         self.metrics
             .report_utp_active_inc(UtpDirectionLabel::Outbound);
         let (stream, message) = match side {
             UtpConnectionSide::Connect => (
                 self.utp_socket
-                    .connect_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .connect_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "outbound connect with cid",
             ),
             UtpConnectionSide::Accept => (
                 self.utp_socket
-                    .accept_with_cid(cid.clone(), *UTP_CONN_CFG)
+                    .accept_with_cid(cid.clone(), self.conn_config)
                     .await,
                 "outbound accept with cid",
             ),
@@ -195,11 +385,39 @@ impl UtpController {
                     peer = ?cid.peer.client(),
                     "Unable to establish uTP conn based on {message}",
                 );
+                self.last_errors.record(
+                    ErrorSubsystem::Utp,
+                    format!("Unable to establish uTP conn based on {message}: {err}"),
+                );
                 return false;
             }
         };
 
-        match stream.write(data).await {
+        let write_result = match self.serve_bandwidth.as_ref().filter(|_| is_serve) {
+            Some(serve_bandwidth) => {
+                // Write the data in chunks, each once the serve bandwidth allows it.
+                let mut written = 0;
+                let mut result = Ok(());
+                for chunk in data.chunks(SERVE_CHUNK_SIZE) {
+                    tokio::time::sleep(serve_bandwidth.reserve(chunk.len(), Instant::now())).await;
+                    match stream.write(chunk).await {
+                        Ok(write_size) => {
+                            written += write_size;
+                            if write_size != chunk.len() {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+                result.map(|_| written)
+            }
+            None => stream.write(data).await,
+        };
+        match write_result {
             Ok(write_size) => {
                 if write_size != data.len() {
                     self.metrics.report_utp_outcome(
@@ -213,6 +431,13 @@ impl UtpController {
                         "Error sending content over uTP, in response to uTP write exited before sending all content: {write_size} bytes written, {} bytes expected",
                         data.len()
                     );
+                    self.last_errors.record(
+                        ErrorSubsystem::Utp,
+                        format!(
+                            "uTP write exited before sending all content: {write_size} bytes written, {} bytes expected",
+                            data.len()
+                        ),
+                    );
                     return false;
                 }
             }
@@ -226,6 +451,10 @@ impl UtpController {
                     peer = ?cid.peer.client(),
                     "Error sending content over uTP, in response to Error writing content to uTP stream: {err}"
                 );
+                self.last_errors.record(
+                    ErrorSubsystem::Utp,
+                    format!("Error writing content to uTP stream: {err}"),
+                );
                 return false;
             }
         }
@@ -241,6 +470,10 @@ impl UtpController {
                 peer = ?cid.peer.client(),
                 "Error sending content over uTP, in response to Error closing uTP connection: {err}"
             );
+            self.last_errors.record(
+                ErrorSubsystem::Utp,
+                format!("Error closing uTP connection: {err}"),
+            );
             return false;
         };
         self.metrics
@@ -248,3 +481,116 @@ impl UtpController {
         true
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io;
+
+    use async_trait::async_trait;
+    use discv5::enr::CombinedKey;
+    use ethportal_api::types::{cli::DEFAULT_UTP_TRANSFER_LIMIT, enr::Enr};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+    use trin_metrics::portalnet::PORTALNET_METRICS;
+    use utp_rs::udp::AsyncUdpSocket;
+
+    use super::*;
+    use crate::{
+        compression::{compress, CAPABILITY_COMPRESSION},
+        discovery::ENR_PORTAL_CAPABILITIES_KEY,
+        types::last_errors::LastErrors,
+    };
+
+    /// A socket that sends the packets of one node to another over channels.
+    struct ChannelSocket {
+        local_enr: UtpEnr,
+        tx: UnboundedSender<(Vec<u8>, UtpEnr)>,
+        rx: UnboundedReceiver<(Vec<u8>, UtpEnr)>,
+    }
+
+    #[async_trait]
+    impl AsyncUdpSocket<UtpEnr> for ChannelSocket {
+        async fn send_to(&mut self, buf: &[u8], _target: &UtpEnr) -> io::Result<usize> {
+            self.tx
+                .send((buf.to_vec(), self.local_enr.clone()))
+                .map_err(|_| io::Error::from(io::ErrorKind::NotConnected))?;
+            Ok(buf.len())
+        }
+
+        async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, UtpEnr)> {
+            match self.rx.recv().await {
+                Some((packet, source)) => {
+                    let n = std::cmp::min(buf.len(), packet.len());
+                    buf[..n].copy_from_slice(&packet[..n]);
+                    Ok((n, source))
+                }
+                None => Err(io::Error::from(io::ErrorKind::NotConnected)),
+            }
+        }
+    }
+
+    fn compression_enr() -> UtpEnr {
+        let key = CombinedKey::generate_secp256k1();
+        let enr = Enr::builder()
+            .add_value(
+                ENR_PORTAL_CAPABILITIES_KEY,
+                &[CAPABILITY_COMPRESSION].as_slice(),
+            )
+            .build(&key)
+            .unwrap();
+        UtpEnr(enr)
+    }
+
+    fn compression_controller(socket: ChannelSocket) -> UtpController {
+        UtpController::new(
+            DEFAULT_UTP_TRANSFER_LIMIT,
+            None,
+            None,
+            Arc::new(UtpSocket::with_socket(socket)),
+            OverlayMetricsReporter {
+                overlay_metrics: PORTALNET_METRICS.overlay(),
+                protocol: "test".to_string(),
+            },
+            *UTP_CONN_CFG,
+            true,
+            LastErrors::shared(),
+            TransferSizeMeter::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn found_content_is_decoded_when_both_nodes_support_compression() {
+        let (requester_enr, server_enr) = (compression_enr(), compression_enr());
+        let (requester_tx, server_rx) = unbounded_channel();
+        let (server_tx, requester_rx) = unbounded_channel();
+        let requester = compression_controller(ChannelSocket {
+            local_enr: requester_enr.clone(),
+            tx: requester_tx,
+            rx: requester_rx,
+        });
+        let server = compression_controller(ChannelSocket {
+            local_enr: server_enr.clone(),
+            tx: server_tx,
+            rx: server_rx,
+        });
+
+        // Content that compresses well, and content that doesn't, but looks like a compressed
+        // stream.
+        let compressible = [0x01, 0x02, 0x03].repeat(1000);
+        let incompressible = compress(&[0x01, 0x02, 0x03]).unwrap();
+        for content in [compressible, incompressible] {
+            let server_cid = server.cid(requester_enr.clone(), false);
+            let requester_cid = ConnectionId {
+                recv: server_cid.send,
+                send: server_cid.send.wrapping_add(1),
+                peer: server_enr.clone(),
+            };
+            let (served, received) = tokio::join!(
+                server.accept_serve_stream(server_cid, &content),
+                requester.connect_inbound_stream_with_limit(requester_cid, content.len()),
+            );
+            assert!(served);
+            assert_eq!(received.unwrap().to_vec(), content);
+        }
+    }
+}
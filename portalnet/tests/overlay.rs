@@ -21,6 +21,7 @@ use portalnet::{
     discovery::{Discovery, Discv5UdpSocket},
     overlay::{
         config::{FindContentConfig, OverlayConfig},
+        errors::OverlayRequestError,
         protocol::OverlayProtocol,
     },
 };
@@ -36,8 +37,14 @@ async fn init_overlay(
     discovery: Arc<Discovery>,
     subnetwork: Subnetwork,
 ) -> OverlayProtocol<IdentityContentKey, XorMetric, MockValidator, MemoryContentStore> {
-    let overlay_config = OverlayConfig::default();
+    init_overlay_with_config(discovery, subnetwork, OverlayConfig::default()).await
+}
 
+async fn init_overlay_with_config(
+    discovery: Arc<Discovery>,
+    subnetwork: Subnetwork,
+    overlay_config: OverlayConfig,
+) -> OverlayProtocol<IdentityContentKey, XorMetric, MockValidator, MemoryContentStore> {
     let node_id = discovery.local_enr().node_id();
     let store = MemoryContentStore::new(node_id, DistanceFunction::Xor);
     let store = Arc::new(RwLock::new(store));
@@ -263,3 +270,29 @@ async fn overlay_event_stream() {
 
     overlay.event_stream().await.unwrap();
 }
+
+#[test_log::test(tokio::test)]
+async fn lookup_refused_until_min_peers_connected() {
+    let portal_config = PortalnetConfig {
+        no_stun: true,
+        no_upnp: true,
+        ..Default::default()
+    };
+    let discovery = Arc::new(Discovery::new(portal_config, MAINNET.clone()).unwrap());
+    let overlay_config = OverlayConfig {
+        min_lookup_peers: 1,
+        ..Default::default()
+    };
+    let overlay = init_overlay_with_config(discovery, Subnetwork::History, overlay_config).await;
+
+    let result = overlay
+        .lookup_content(IdentityContentKey::random(), FindContentConfig::default())
+        .await;
+    assert!(matches!(
+        result,
+        Err(OverlayRequestError::NotReady {
+            peers: 0,
+            min_peers: 1
+        })
+    ));
+}
@@ -13,7 +13,8 @@ use ethportal_api::{
         portal::{
             AcceptInfo, DataRadius, FindContentInfo, FindNodesInfo, GetContentInfo,
             PaginateLocalContentInfo, PongInfo, TraceContentInfo, TraceGossipInfo,
-            MAX_CONTENT_KEYS_PER_OFFER,
+            DEFAULT_RECURSIVE_FIND_NODES_LIMIT, MAX_CONTENT_KEYS_PER_OFFER,
+            MAX_RECURSIVE_FIND_NODES_LIMIT,
         },
         portal_wire::OfferTrace,
     },
@@ -91,8 +92,19 @@ impl BeaconNetworkApiServer for BeaconNetworkApi {
     }
 
     /// Lookup a target node within in the network
-    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>> {
-        let endpoint = BeaconEndpoint::RecursiveFindNodes(node_id);
+    async fn recursive_find_nodes(
+        &self,
+        node_id: NodeId,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<Enr>> {
+        let limit = limit.unwrap_or(DEFAULT_RECURSIVE_FIND_NODES_LIMIT);
+        if !(1..=MAX_RECURSIVE_FIND_NODES_LIMIT).contains(&limit) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid limit: {limit}, must be between 1 and {MAX_RECURSIVE_FIND_NODES_LIMIT}"
+            ))
+            .into());
+        }
+        let endpoint = BeaconEndpoint::RecursiveFindNodes(node_id, limit);
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
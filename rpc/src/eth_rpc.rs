@@ -158,7 +158,7 @@ impl EthApi {
         &self,
         content_key: HistoryContentKey,
     ) -> Result<HistoryContentValue, RpcServeError> {
-        let endpoint = HistoryEndpoint::GetContent(content_key.clone());
+        let endpoint = HistoryEndpoint::GetContent(content_key.clone(), None);
         let GetContentInfo { content, .. } =
             proxy_to_subnet(&self.history_network, endpoint).await?;
         let content_value = HistoryContentValue::decode(&content_key, &content)?;
@@ -1,4 +1,7 @@
-use ethportal_api::types::jsonrpc::{endpoints::SubnetworkEndpoint, request::JsonRpcRequest};
+use ethportal_api::{
+    jsonrpsee::{core::SubscriptionResult, PendingSubscriptionSink, SubscriptionMessage},
+    types::jsonrpc::{endpoints::SubnetworkEndpoint, request::JsonRpcRequest},
+};
 use serde_json::Value;
 use tokio::sync::mpsc;
 
@@ -42,3 +45,31 @@ where
         }
     }
 }
+
+/// Forward every response of a Portal subnetwork to a subscription, until the subnetwork stops
+/// responding.
+pub async fn stream_from_subnet<TEndpoint>(
+    network: &mpsc::UnboundedSender<JsonRpcRequest<TEndpoint>>,
+    endpoint: TEndpoint,
+    pending: PendingSubscriptionSink,
+) -> SubscriptionResult
+where
+    TEndpoint: SubnetworkEndpoint + Clone,
+{
+    let sink = pending.accept().await?;
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
+    let message = JsonRpcRequest {
+        endpoint,
+        resp: resp_tx,
+    };
+    let _ = network.send(message);
+
+    while let Some(response) = resp_rx.recv().await {
+        let message = SubscriptionMessage::from_json(&response?)?;
+        if sink.send(message).await.is_err() {
+            // The subscriber is gone.
+            break;
+        }
+    }
+    Ok(())
+}
@@ -1,24 +1,45 @@
+use std::path::PathBuf;
+
+use alloy::primitives::B256;
 use discv5::enr::NodeId;
 use ethportal_api::{
     types::{
+        content_key::history::{HistoryBlockId, HistoryContentType},
         enr::Enr,
         jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
         portal::{
-            AcceptInfo, DataRadius, FindContentInfo, FindNodesInfo, GetContentInfo,
-            PaginateLocalContentInfo, PongInfo, TraceContentInfo, TraceGossipInfo,
-            MAX_CONTENT_KEYS_PER_OFFER,
+            AcceptInfo, AuditPeerRadiusInfo, BackfillInfo, CircuitBreakerInfo, ClientInfo,
+            ConfirmedOfferInfo, ContentDistanceDistributionInfo, ContentRangeInfo, DataRadius,
+            DebugPingInfo, EncodedContentKeyInfo, ExportStoreInfo, FindAndStoreInfo,
+            FindContentInfo, FindContentRangeInfo, FindNodesInfo, GetContentInfo, GossipBatchInfo,
+            InFlightRequestInfo, InboundReachabilityInfo, LastErrorInfo, NetworkCrawlInfo,
+            NodeDistanceInfo, OfferThroughputInfo, PaginateLocalContentInfo, PeerTransfersInfo,
+            PongInfo, RadiusChangeInfo, RawStoredBytesInfo, ReadinessInfo,
+            RejectionExplanationInfo, ReplayTraceInfo, SampleAvailabilityInfo, ServedContentInfo,
+            SetRadiusInfo, SimulatedHopInfo, StorageInfo, StoreBenchmarkInfo, TraceContentInfo,
+            TraceGossipInfo, DEFAULT_BACKFILL_CONCURRENCY, DEFAULT_NETWORK_CRAWL_MAX_PEERS,
+            DEFAULT_NETWORK_CRAWL_TIMEOUT_SECS, DEFAULT_RECURSIVE_FIND_NODES_LIMIT,
+            MAX_BACKFILL_CONCURRENCY, MAX_BACKFILL_CONTENT_KEYS, MAX_CONTENT_KEYS_PER_OFFER,
+            MAX_FIND_CONTENT_RANGE_BLOCKS, MAX_NETWORK_CRAWL_MAX_PEERS,
+            MAX_NETWORK_CRAWL_TIMEOUT_SECS, MAX_RECURSIVE_FIND_NODES_LIMIT,
+            MAX_SAMPLE_AVAILABILITY_SIZE,
         },
-        portal_wire::OfferTrace,
+        portal_wire::{Accept, OfferTrace},
+        query_trace::{QueryTraceSummary, RecordedQueryTrace},
     },
-    ContentValue, HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer, RawContentValue,
-    RoutingTableInfo,
+    ContentValue, HistoryContentKey, HistoryContentValue, HistoryNetworkApiServer,
+    NeighborhoodInfo, RawContentKey, RawContentValue, RefreshInfo, RoutingTableInfo,
+    SubnetDistributionInfo,
 };
 use tokio::sync::mpsc;
 
 use crate::{
     errors::RpcServeError,
-    fetch::proxy_to_subnet,
-    jsonrpsee::core::{async_trait, RpcResult},
+    fetch::{proxy_to_subnet, stream_from_subnet},
+    jsonrpsee::{
+        core::{async_trait, RpcResult, SubscriptionResult},
+        PendingSubscriptionSink,
+    },
 };
 
 pub struct HistoryNetworkApi {
@@ -39,6 +60,26 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Returns the number of peers within gossip range across the whole key space.
+    async fn neighborhood_info(&self) -> RpcResult<NeighborhoodInfo> {
+        let endpoint = HistoryEndpoint::NeighborhoodInfo;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the number of peers in the routing table per IP subnet of their advertised
+    /// address.
+    async fn subnet_distribution(&self) -> RpcResult<SubnetDistributionInfo> {
+        let endpoint = HistoryEndpoint::SubnetDistribution;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns, per bucket, the number of nodes in it and the time of the last node lookup that
+    /// refreshed it.
+    async fn bucket_refresh_info(&self) -> RpcResult<RefreshInfo> {
+        let endpoint = HistoryEndpoint::BucketRefreshInfo;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Write an Ethereum Node Record to the overlay routing table.
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool> {
         let endpoint = HistoryEndpoint::AddEnr(enr);
@@ -57,6 +98,18 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Remove Node ID from the overlay routing table, optionally denying it for a while.
+    async fn remove_enr(&self, node_id: NodeId, deny_ttl_secs: Option<u64>) -> RpcResult<bool> {
+        let endpoint = HistoryEndpoint::RemoveEnr(node_id, deny_ttl_secs);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Reload the denylist from the file it was loaded from at startup.
+    async fn reload_denylist(&self) -> RpcResult<bool> {
+        let endpoint = HistoryEndpoint::ReloadDenylist;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Fetch the ENR representation associated with the given Node ID.
     async fn lookup_enr(&self, node_id: NodeId) -> RpcResult<Enr> {
         let endpoint = HistoryEndpoint::LookupEnr(node_id);
@@ -69,6 +122,27 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Send a PING message to the designated node and return the raw request and response
+    /// payloads along with their decoded fields.
+    async fn debug_ping(&self, enr: Enr) -> RpcResult<DebugPingInfo> {
+        let endpoint = HistoryEndpoint::DebugPing(enr);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Check whether the designated node holds content consistent with its advertised radius, by
+    /// probing it for locally stored content that falls within that radius
+    async fn audit_peer_radius(&self, enr: Enr) -> RpcResult<AuditPeerRadiusInfo> {
+        let endpoint = HistoryEndpoint::AuditPeerRadius(enr);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Check whether peers can reach the local node at the address advertised in its ENR, by
+    /// pinging the designated peer and comparing the address it observed the local node at
+    async fn test_inbound_reachability(&self, enr: Enr) -> RpcResult<InboundReachabilityInfo> {
+        let endpoint = HistoryEndpoint::TestInboundReachability(enr);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Send a FINDNODES request for nodes that fall within the given set of distances, to the
     /// designated peer and wait for a response
     async fn find_nodes(&self, enr: Enr, distances: Vec<u16>) -> RpcResult<FindNodesInfo> {
@@ -76,9 +150,81 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Returns the XOR distance, and its log2, between two arbitrary node ids.
+    async fn node_distance(
+        &self,
+        node_id_a: NodeId,
+        node_id_b: NodeId,
+    ) -> RpcResult<NodeDistanceInfo> {
+        let endpoint = HistoryEndpoint::NodeDistance(node_id_a, node_id_b);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the content key of the given content type for a block, identified by its hash or
+    /// number, and the content id of that key.
+    async fn encode_content_key(
+        &self,
+        content_type: HistoryContentType,
+        block_id: HistoryBlockId,
+    ) -> RpcResult<EncodedContentKeyInfo> {
+        let endpoint = HistoryEndpoint::EncodeContentKey(content_type, block_id);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the number of the block that the content key refers to.
+    async fn content_key_block_number(&self, content_key: HistoryContentKey) -> RpcResult<u64> {
+        let endpoint = HistoryEndpoint::ContentKeyBlockNumber(content_key);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Simulates a lookup of the content id against the given synthetic peers.
+    async fn simulate_lookup(
+        &self,
+        enrs: Vec<Enr>,
+        content_id: B256,
+    ) -> RpcResult<Vec<SimulatedHopInfo>> {
+        let endpoint = HistoryEndpoint::SimulateLookup(enrs, content_id);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Lookup a target node within in the network
-    async fn recursive_find_nodes(&self, node_id: NodeId) -> RpcResult<Vec<Enr>> {
-        let endpoint = HistoryEndpoint::RecursiveFindNodes(node_id);
+    async fn recursive_find_nodes(
+        &self,
+        node_id: NodeId,
+        limit: Option<usize>,
+    ) -> RpcResult<Vec<Enr>> {
+        let limit = limit.unwrap_or(DEFAULT_RECURSIVE_FIND_NODES_LIMIT);
+        if !(1..=MAX_RECURSIVE_FIND_NODES_LIMIT).contains(&limit) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid limit: {limit}, must be between 1 and {MAX_RECURSIVE_FIND_NODES_LIMIT}"
+            ))
+            .into());
+        }
+        let endpoint = HistoryEndpoint::RecursiveFindNodes(node_id, limit);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Crawl the network and return the distinct peers discovered.
+    async fn network_crawl(
+        &self,
+        max_peers: Option<usize>,
+        timeout_secs: Option<u64>,
+    ) -> RpcResult<NetworkCrawlInfo> {
+        let max_peers = max_peers.unwrap_or(DEFAULT_NETWORK_CRAWL_MAX_PEERS);
+        if !(1..=MAX_NETWORK_CRAWL_MAX_PEERS).contains(&max_peers) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid max peers: {max_peers}, must be between 1 and {MAX_NETWORK_CRAWL_MAX_PEERS}"
+            ))
+            .into());
+        }
+        let timeout_secs = timeout_secs.unwrap_or(DEFAULT_NETWORK_CRAWL_TIMEOUT_SECS);
+        if !(1..=MAX_NETWORK_CRAWL_TIMEOUT_SECS).contains(&timeout_secs) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid timeout: {timeout_secs}, must be between 1 and {MAX_NETWORK_CRAWL_TIMEOUT_SECS} seconds"
+            ))
+            .into());
+        }
+        let endpoint = HistoryEndpoint::NetworkCrawl(max_peers, timeout_secs);
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
@@ -99,19 +245,137 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
     }
 
     /// First checks local storage if content is not found lookup a target content key in the
-    /// network
-    async fn get_content(&self, content_key: HistoryContentKey) -> RpcResult<GetContentInfo> {
-        let endpoint = HistoryEndpoint::GetContent(content_key);
+    /// network. If `hint_peer` is provided, it is asked for the content before the network lookup.
+    async fn get_content(
+        &self,
+        content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
+    ) -> RpcResult<GetContentInfo> {
+        let endpoint = HistoryEndpoint::GetContent(content_key, hint_peer);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Looks up the content key in local storage only, without starting a network lookup.
+    async fn local_lookup(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<Option<GetContentInfo>> {
+        let endpoint = HistoryEndpoint::LocalLookup(content_key);
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
     /// First checks local storage if content is not found lookup a target content key in the
-    /// network. Return tracing info.
+    /// network. If `hint_peer` is provided, it is asked for the content before the network lookup.
+    /// Return tracing info.
     async fn trace_get_content(
         &self,
         content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
     ) -> RpcResult<TraceContentInfo> {
-        let endpoint = HistoryEndpoint::TraceGetContent(content_key);
+        let endpoint = HistoryEndpoint::TraceGetContent(content_key, hint_peer);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Look up content like `get_content`, and store it if it's within the radius.
+    async fn find_and_store(&self, content_key: HistoryContentKey) -> RpcResult<FindAndStoreInfo> {
+        let endpoint = HistoryEndpoint::FindAndStore(content_key);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Fetch content from a single peer with bounded concurrency, and store it.
+    async fn backfill_from_peer(
+        &self,
+        enr: Enr,
+        content_keys: Vec<HistoryContentKey>,
+        concurrency: Option<usize>,
+    ) -> RpcResult<BackfillInfo> {
+        if !(1..=MAX_BACKFILL_CONTENT_KEYS).contains(&content_keys.len()) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid amount of content keys: {}, must be between 1 and {MAX_BACKFILL_CONTENT_KEYS}",
+                content_keys.len()
+            ))
+            .into());
+        }
+        let concurrency = concurrency.unwrap_or(DEFAULT_BACKFILL_CONCURRENCY);
+        if !(1..=MAX_BACKFILL_CONCURRENCY).contains(&concurrency) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid concurrency: {concurrency}, must be between 1 and {MAX_BACKFILL_CONCURRENCY}"
+            ))
+            .into());
+        }
+        let endpoint = HistoryEndpoint::BackfillFromPeer(enr, content_keys, concurrency);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Look up the content of the given type of each block in the range, like `get_content`.
+    async fn find_content_range(
+        &self,
+        content_type: HistoryContentType,
+        start_block: u64,
+        end_block: u64,
+    ) -> RpcResult<FindContentRangeInfo> {
+        if end_block < start_block || end_block - start_block >= MAX_FIND_CONTENT_RANGE_BLOCKS {
+            return Err(RpcServeError::Message(format!(
+                "Invalid block range: {start_block}-{end_block}, must contain between 1 and {MAX_FIND_CONTENT_RANGE_BLOCKS} blocks"
+            ))
+            .into());
+        }
+        let endpoint = HistoryEndpoint::FindContentRange(content_type, start_block, end_block);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Estimate the share of the content of the given type in the block range that is
+    /// retrievable from the network, by probing peers for a random sample of it.
+    async fn sample_availability(
+        &self,
+        content_type: HistoryContentType,
+        start_block: u64,
+        end_block: u64,
+        sample_size: u64,
+    ) -> RpcResult<SampleAvailabilityInfo> {
+        if end_block < start_block {
+            return Err(RpcServeError::Message(format!(
+                "Invalid block range: {start_block}-{end_block}"
+            ))
+            .into());
+        }
+        if sample_size == 0 || sample_size > MAX_SAMPLE_AVAILABILITY_SIZE {
+            return Err(RpcServeError::Message(format!(
+                "Invalid sample size: {sample_size}, must be between 1 and {MAX_SAMPLE_AVAILABILITY_SIZE}"
+            ))
+            .into());
+        }
+        let endpoint =
+            HistoryEndpoint::SampleAvailability(content_type, start_block, end_block, sample_size);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Look up content like `trace_get_content`, streaming the progress of the lookup.
+    async fn subscribe_trace_get_content(
+        &self,
+        pending: PendingSubscriptionSink,
+        content_key: HistoryContentKey,
+        hint_peer: Option<Enr>,
+    ) -> SubscriptionResult {
+        let endpoint = HistoryEndpoint::StreamTraceGetContent(content_key, hint_peer);
+        stream_from_subnet(&self.network, endpoint, pending).await
+    }
+
+    /// Returns the trace of a recent content lookup.
+    async fn get_trace(&self, query_trace_id: u64) -> RpcResult<RecordedQueryTrace> {
+        let endpoint = HistoryEndpoint::GetTrace(query_trace_id);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns a summary of each retained content lookup trace.
+    async fn list_traces(&self) -> RpcResult<Vec<QueryTraceSummary>> {
+        let endpoint = HistoryEndpoint::ListTraces;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Looks up the content of a recorded trace again, and compares the peers that responded.
+    async fn replay_trace(&self, recorded_trace: RecordedQueryTrace) -> RpcResult<ReplayTraceInfo> {
+        let endpoint = HistoryEndpoint::ReplayTrace(recorded_trace);
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
@@ -125,6 +389,28 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Returns the block number range of local content.
+    async fn content_range(&self) -> RpcResult<ContentRangeInfo> {
+        let endpoint = HistoryEndpoint::ContentRange;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the number of local content items per log2 distance from the local node id.
+    async fn content_distance_distribution(&self) -> RpcResult<ContentDistanceDistributionInfo> {
+        let endpoint = HistoryEndpoint::ContentDistanceDistribution;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the keys of local content closest to the given node id.
+    async fn closest_content(
+        &self,
+        node_id: NodeId,
+        count: u64,
+    ) -> RpcResult<Vec<HistoryContentKey>> {
+        let endpoint = HistoryEndpoint::ClosestContent(node_id, count);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Send the provided content to interested peers. Clients may choose to send to some or all
     /// peers. Return the number of peers that the content was gossiped to.
     async fn gossip(
@@ -151,6 +437,24 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Send the provided content items to interested peers. Clients may choose to send to some or
+    /// all peers. Return the number of peers that were offered and accepted each content item.
+    async fn gossip_batch(
+        &self,
+        content_items: Vec<(HistoryContentKey, RawContentValue)>,
+    ) -> RpcResult<GossipBatchInfo> {
+        let content_items = content_items
+            .into_iter()
+            .map(|(key, value)| {
+                HistoryContentValue::decode(&key, &value)
+                    .map(|value| (key, value))
+                    .map_err(RpcServeError::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let endpoint = HistoryEndpoint::GossipBatch(content_items);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Send an OFFER request with given ContentItems, to the designated peer and wait for a
     /// response. Does not store content locally.
     /// Returns the content keys bitlist upon successful content transmission or empty bitlist
@@ -195,6 +499,65 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Send an OFFER request with given ContentItem, to the designated peer, and once the content
+    /// is transferred, send a FINDCONTENT request for it to the same peer, to confirm that the
+    /// peer stored it. Does not store the content locally.
+    async fn offer_with_confirmation(
+        &self,
+        enr: Enr,
+        content_key: HistoryContentKey,
+        content_value: RawContentValue,
+    ) -> RpcResult<ConfirmedOfferInfo> {
+        let content_value = HistoryContentValue::decode(&content_key, &content_value)
+            .map_err(RpcServeError::from)?;
+        let endpoint = HistoryEndpoint::OfferWithConfirmation(enr, content_key, content_value);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Send an OFFER request with given ContentItems, to the designated peer, and measure the
+    /// uTP transfer of the accepted content. Does not store the content locally.
+    async fn offer_with_throughput(
+        &self,
+        enr: Enr,
+        content_items: Vec<(HistoryContentKey, RawContentValue)>,
+    ) -> RpcResult<OfferThroughputInfo> {
+        if !(1..=MAX_CONTENT_KEYS_PER_OFFER).contains(&content_items.len()) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid amount of content items: {}",
+                content_items.len()
+            ))
+            .into());
+        }
+        let content_items = content_items
+            .into_iter()
+            .map(|(key, value)| {
+                HistoryContentValue::decode(&key, &value)
+                    .map(|value| (key, value))
+                    .map_err(RpcServeError::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let endpoint = HistoryEndpoint::OfferWithThroughput(enr, content_items);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Send an OFFER request with the given raw ContentItems, unchecked, to the designated peer.
+    /// Does not store the content locally.
+    async fn raw_offer(
+        &self,
+        enr: Enr,
+        content_items: Vec<(RawContentKey, RawContentValue)>,
+    ) -> RpcResult<Accept> {
+        if !(1..=MAX_CONTENT_KEYS_PER_OFFER).contains(&content_items.len()) {
+            return Err(RpcServeError::Message(format!(
+                "Invalid amount of content items: {}",
+                content_items.len()
+            ))
+            .into());
+        }
+        let endpoint = HistoryEndpoint::RawOffer(enr, content_items);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Store content key with a content data to the local database.
     async fn store(
         &self,
@@ -207,11 +570,127 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
 
+    /// Reports whether the content key would be accepted if it were offered or stored now, and
+    /// every reason why it would be rejected otherwise. Nothing is stored.
+    async fn explain_rejection(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<RejectionExplanationInfo> {
+        let endpoint = HistoryEndpoint::ExplainRejection(content_key);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Force all content stored in the local database to be durably written to disk.
+    async fn sync_store(&self) -> RpcResult<bool> {
+        let endpoint = HistoryEndpoint::SyncStore;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Write all locally stored content to a new file, in content id order.
+    async fn export_store(&self, path: PathBuf) -> RpcResult<ExportStoreInfo> {
+        let endpoint = HistoryEndpoint::ExportStore(path);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the radius of the local node and the number and size of stored entries, in total
+    /// and per content type.
+    async fn storage_info(&self) -> RpcResult<StorageInfo> {
+        let endpoint = HistoryEndpoint::StorageInfo;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the latest changes of the radius of the local node.
+    async fn radius_history(&self) -> RpcResult<Vec<RadiusChangeInfo>> {
+        let endpoint = HistoryEndpoint::RadiusHistory;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the version and build of trin, and the protocols of the enabled subnetworks.
+    async fn client_info(&self) -> RpcResult<ClientInfo> {
+        let endpoint = HistoryEndpoint::ClientInfo;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns whether the node is ready to serve traffic.
+    async fn readiness(&self) -> RpcResult<ReadinessInfo> {
+        let endpoint = HistoryEndpoint::Readiness;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the node's effective configuration, with secrets redacted.
+    async fn get_config(&self) -> RpcResult<serde_json::Value> {
+        let endpoint = HistoryEndpoint::GetConfig;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the last error encountered by each subsystem of the node.
+    async fn last_errors(&self) -> RpcResult<Vec<LastErrorInfo>> {
+        let endpoint = HistoryEndpoint::LastErrors;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Sets the data radius of the local node, or clears the radius override.
+    async fn set_radius(&self, radius: Option<DataRadius>) -> RpcResult<SetRadiusInfo> {
+        let endpoint = HistoryEndpoint::SetRadius(radius);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Measures the write and read throughput of the local database.
+    async fn benchmark_store(
+        &self,
+        entry_count: u64,
+        value_size: u64,
+    ) -> RpcResult<StoreBenchmarkInfo> {
+        let endpoint = HistoryEndpoint::BenchmarkStore(entry_count, value_size);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the number of active uTP transfers requested by each peer.
+    async fn peer_transfers(&self) -> RpcResult<PeerTransfersInfo> {
+        let endpoint = HistoryEndpoint::PeerTransfers;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the circuit breakers of peers that failed requests.
+    async fn circuit_breakers(&self) -> RpcResult<Vec<CircuitBreakerInfo>> {
+        let endpoint = HistoryEndpoint::CircuitBreakers;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns up to `count` content keys that the local node recently served to peers, with the
+    /// number of times each was served within the last hour, the most served first.
+    async fn recently_served(&self, count: u64) -> RpcResult<Vec<ServedContentInfo>> {
+        let endpoint = HistoryEndpoint::RecentlyServed(count);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Returns the requests that are currently being executed.
+    async fn list_in_flight(&self) -> RpcResult<Vec<InFlightRequestInfo>> {
+        let endpoint = HistoryEndpoint::ListInFlight;
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
+    /// Cancels the execution of a request.
+    async fn cancel_request(&self, request_id: u64) -> RpcResult<bool> {
+        let endpoint = HistoryEndpoint::CancelRequest(request_id);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
+
     /// Get a content from the local database.
     async fn local_content(&self, content_key: HistoryContentKey) -> RpcResult<RawContentValue> {
         let endpoint = HistoryEndpoint::LocalContent(content_key);
         Ok(proxy_to_subnet(&self.network, endpoint).await?)
     }
+
+    /// Returns the content value as it's stored in the local database, before it's decoded or its
+    /// checksum is verified, to debug store-level issues.
+    async fn raw_stored_bytes(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> RpcResult<RawStoredBytesInfo> {
+        let endpoint = HistoryEndpoint::RawStoredBytes(content_key);
+        Ok(proxy_to_subnet(&self.network, endpoint).await?)
+    }
 }
 
 impl std::fmt::Debug for HistoryNetworkApi {
@@ -0,0 +1,315 @@
+#[cfg(unix)]
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+    time::Instant,
+};
+use tower::{Layer, Service};
+#[cfg(unix)]
+use tracing::{debug, warn};
+use trin_metrics::rpc::RpcMetrics;
+
+#[cfg(unix)]
+use crate::jsonrpsee::server::ServerHandle;
+
+/// The size of the buffers that data is forwarded with, in each direction of an ipc connection.
+#[cfg(unix)]
+const IPC_FORWARD_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The middleware of the ipc server that counts its open connections.
+///
+/// The ipc server applies it to the service of each accepted connection, which is dropped once
+/// the connection is closed. Connections rejected because the server has too many open
+/// connections aren't counted.
+#[derive(Clone, Debug)]
+pub struct IpcConnectionsLayer {
+    metrics: RpcMetrics,
+}
+
+impl IpcConnectionsLayer {
+    pub fn new(metrics: RpcMetrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for IpcConnectionsLayer {
+    type Service = IpcConnection<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        self.metrics.report_ipc_connection_opened();
+        IpcConnection {
+            service,
+            guard: Arc::new(IpcConnectionGuard {
+                metrics: self.metrics.clone(),
+            }),
+        }
+    }
+}
+
+/// The service of [IpcConnectionsLayer], for a single connection.
+#[derive(Clone, Debug)]
+pub struct IpcConnection<S> {
+    service: S,
+    guard: Arc<IpcConnectionGuard>,
+}
+
+impl<S, Request> Service<Request> for IpcConnection<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.service.call(request)
+    }
+}
+
+/// Reports the connection as closed when dropped, along with the last copy of its service.
+#[derive(Debug)]
+struct IpcConnectionGuard {
+    metrics: RpcMetrics,
+}
+
+impl Drop for IpcConnectionGuard {
+    fn drop(&mut self) {
+        self.metrics.report_ipc_connection_closed();
+    }
+}
+
+/// Returns the path that the ipc server listens at, when the connections to `path` are forwarded
+/// to it by an [IpcIdleTimeout].
+pub fn upstream_ipc_path(path: &str) -> String {
+    format!("{path}.upstream")
+}
+
+/// Closes the connections to the ipc endpoint that stay idle for the idle timeout.
+///
+/// The ipc server can't close a connection itself, so the connections to the ipc endpoint are
+/// accepted here, and forwarded to the ipc server, which listens at [upstream_ipc_path].
+///
+/// A connection is idle while no request is in progress and no data is sent either way. A request
+/// is in progress from when the client sends data until the server responds, so slow requests
+/// aren't cut off.
+#[cfg(unix)]
+pub struct IpcIdleTimeout {
+    listener: UnixListener,
+    path: PathBuf,
+    upstream_path: PathBuf,
+    idle_timeout: Duration,
+}
+
+#[cfg(unix)]
+impl IpcIdleTimeout {
+    /// Listens at `path` for connections, which are forwarded to `upstream_path`.
+    ///
+    /// A socket left at `path` by a node that's no longer running is replaced.
+    pub async fn bind(
+        path: impl Into<PathBuf>,
+        upstream_path: impl Into<PathBuf>,
+        idle_timeout: Duration,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        if path.exists() && UnixStream::connect(&path).await.is_err() {
+            std::fs::remove_file(&path)?;
+        }
+        Ok(Self {
+            listener: UnixListener::bind(&path)?,
+            path,
+            upstream_path: upstream_path.into(),
+            idle_timeout,
+        })
+    }
+
+    /// Forwards the accepted connections until the ipc server is stopped.
+    pub async fn run(self, server: ServerHandle) {
+        let stopped = server.stopped();
+        tokio::pin!(stopped);
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                accepted = self.listener.accept() => match accepted {
+                    Ok((client, _)) => {
+                        let upstream_path = self.upstream_path.clone();
+                        let idle_timeout = self.idle_timeout;
+                        tokio::spawn(async move {
+                            if let Err(err) =
+                                forward_connection(client, &upstream_path, idle_timeout).await
+                            {
+                                debug!(%err, "IPC connection closed with error");
+                            }
+                        });
+                    }
+                    Err(err) => warn!(%err, "Unable to accept IPC connection"),
+                },
+            }
+        }
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            debug!(%err, path = %self.path.display(), "Unable to remove IPC socket");
+        }
+    }
+}
+
+/// Forwards the data of the `client` connection to a new connection to `upstream_path`, and back,
+/// until either side closes its connection, or the connection is idle for `idle_timeout`.
+#[cfg(unix)]
+async fn forward_connection(
+    client: UnixStream,
+    upstream_path: &Path,
+    idle_timeout: Duration,
+) -> io::Result<()> {
+    let upstream = UnixStream::connect(upstream_path).await?;
+    let (mut client_read, mut client_write) = client.into_split();
+    let (mut upstream_read, mut upstream_write) = upstream.into_split();
+    let mut client_buf = vec![0; IPC_FORWARD_BUFFER_SIZE];
+    let mut upstream_buf = vec![0; IPC_FORWARD_BUFFER_SIZE];
+    let mut client_closed = false;
+    let mut request_in_progress = false;
+    let idle = tokio::time::sleep(idle_timeout);
+    tokio::pin!(idle);
+    loop {
+        tokio::select! {
+            read = client_read.read(&mut client_buf), if !client_closed => {
+                let n = read?;
+                if n == 0 {
+                    // The client may still wait for the responses to its requests.
+                    client_closed = true;
+                    upstream_write.shutdown().await?;
+                } else {
+                    upstream_write.write_all(&client_buf[..n]).await?;
+                    request_in_progress = true;
+                }
+            }
+            read = upstream_read.read(&mut upstream_buf) => {
+                let n = read?;
+                if n == 0 {
+                    return Ok(());
+                }
+                client_write.write_all(&upstream_buf[..n]).await?;
+                request_in_progress = false;
+            }
+            _ = &mut idle, if !request_in_progress => {
+                debug!(idle_timeout_secs = idle_timeout.as_secs(), "Closing idle IPC connection");
+                return Ok(());
+            }
+        }
+        idle.as_mut().reset(Instant::now() + idle_timeout);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    #[cfg(unix)]
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use tower::service_fn;
+    use trin_metrics::portalnet::PORTALNET_METRICS;
+
+    use super::*;
+
+    #[cfg(unix)]
+    const IDLE_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Returns a client connection, that's forwarded with the idle timeout to a server that
+    /// answers each request with the same data after `response_delay`.
+    #[cfg(unix)]
+    async fn forwarded_connection(response_delay: Duration) -> UnixStream {
+        static NEXT_SOCKET: AtomicU64 = AtomicU64::new(0);
+        let socket = NEXT_SOCKET.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("trin-ipc-{}-{socket}", std::process::id()));
+        let upstream_path = PathBuf::from(upstream_ipc_path(&path.to_string_lossy()));
+
+        let upstream = UnixListener::bind(&upstream_path).unwrap();
+        tokio::spawn(async move {
+            let (mut server, _) = upstream.accept().await.unwrap();
+            let mut buf = [0; 64];
+            loop {
+                let n = server.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    return;
+                }
+                tokio::time::sleep(response_delay).await;
+                server.write_all(&buf[..n]).await.unwrap();
+            }
+        });
+
+        let listener = UnixListener::bind(&path).unwrap();
+        let client = UnixStream::connect(&path).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        tokio::spawn(async move {
+            forward_connection(accepted, &upstream_path, IDLE_TIMEOUT)
+                .await
+                .unwrap();
+            std::fs::remove_file(&upstream_path).unwrap();
+        });
+        client
+    }
+
+    #[test]
+    fn connections_are_counted_until_dropped() {
+        let metrics = PORTALNET_METRICS.rpc();
+        let layer = IpcConnectionsLayer::new(metrics.clone());
+        let open_connections = metrics.ipc_connections.get();
+
+        let connection = layer.layer(service_fn(|request: String| async move {
+            Ok::<_, ()>(Some(request))
+        }));
+        let other_connection = layer.layer(connection.service.clone());
+        assert_eq!(metrics.ipc_connections.get(), open_connections + 2);
+
+        let connection_copy = connection.clone();
+        drop(connection);
+        assert_eq!(metrics.ipc_connections.get(), open_connections + 2);
+        drop(connection_copy);
+        assert_eq!(metrics.ipc_connections.get(), open_connections + 1);
+        drop(other_connection);
+        assert_eq!(metrics.ipc_connections.get(), open_connections);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn idle_connections_are_closed() {
+        let mut client = forwarded_connection(Duration::ZERO).await;
+        let mut buf = [0; 64];
+
+        // Requests keep the connection open beyond the idle timeout.
+        for _ in 0..3 {
+            tokio::time::sleep(IDLE_TIMEOUT / 2).await;
+            client.write_all(b"{}").await.unwrap();
+            let n = client.read(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"{}");
+        }
+
+        let read = tokio::time::timeout(IDLE_TIMEOUT * 2, client.read(&mut buf)).await;
+        assert_eq!(read.unwrap().unwrap(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn connections_are_kept_open_while_a_request_is_in_progress() {
+        let mut client = forwarded_connection(IDLE_TIMEOUT * 2).await;
+        let mut buf = [0; 64];
+
+        client.write_all(b"{}").await.unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"{}");
+    }
+}
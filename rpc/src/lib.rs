@@ -10,6 +10,8 @@ mod eth_rpc;
 mod evm_state;
 mod fetch;
 mod history_rpc;
+mod ipc;
+mod pretty;
 mod rpc_server;
 mod serde;
 mod state_rpc;
@@ -18,6 +20,7 @@ mod web3_rpc;
 use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     sync::Arc,
+    time::Duration,
 };
 
 use beacon_rpc::BeaconNetworkApi;
@@ -81,7 +84,11 @@ pub async fn launch_jsonrpc_server(
                         .to_str()
                         .expect("Path should be string"),
                 )
-                .with_ipc(IpcServerBuilder::default())
+                .with_ipc(
+                    IpcServerBuilder::default().max_connections(trin_config.ipc_max_connections),
+                )
+                .with_ipc_idle_timeout(trin_config.ipc_idle_timeout.map(Duration::from_secs))
+                .with_pretty(trin_config.rpc_pretty)
                 .start(transport_modules)
                 .await?
         }
@@ -105,7 +112,8 @@ pub async fn launch_jsonrpc_server(
                         .socket_addrs(|| None)
                         .expect("Invalid socket address")[0],
                 )
-                .with_http(ServerBuilder::default());
+                .with_http(ServerBuilder::default())
+                .with_pretty(trin_config.rpc_pretty);
             let rpc_server_config = match trin_config.ws {
                 true => rpc_server_config
                     .with_ws_address(SocketAddr::V4(SocketAddrV4::new(
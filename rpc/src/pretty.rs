@@ -0,0 +1,107 @@
+use std::{future::Future, pin::Pin};
+
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+use tower::Layer;
+
+use crate::jsonrpsee::{
+    server::middleware::rpc::RpcServiceT,
+    types::{Id, Request, ResponsePayload},
+    MethodResponse,
+};
+
+/// The part of a successful JSON-RPC response that is pretty-printed.
+#[derive(Deserialize)]
+struct SuccessResponse<'a> {
+    #[serde(borrow)]
+    result: &'a RawValue,
+}
+
+/// The RPC middleware that pretty-prints the result of successful method calls, if enabled.
+///
+/// Only the whitespace of the response changes, and each response is still a single JSON
+/// document, sent the same way as a compact one. Errors, subscriptions and batches are left as is.
+#[derive(Clone, Copy, Debug)]
+pub struct PrettyJsonLayer {
+    enabled: bool,
+}
+
+impl PrettyJsonLayer {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl<S> Layer<S> for PrettyJsonLayer {
+    type Service = PrettyJson<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        PrettyJson {
+            service,
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// The service of [PrettyJsonLayer].
+#[derive(Clone, Debug)]
+pub struct PrettyJson<S> {
+    service: S,
+    enabled: bool,
+}
+
+impl<'a, S> RpcServiceT<'a> for PrettyJson<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let enabled = self.enabled;
+        let id = request.id().into_owned();
+        Box::pin(async move {
+            let response = service.call(request).await;
+            if !enabled || !response.is_success() || !response.is_method_call() {
+                return response;
+            }
+            pretty_print(id, response)
+        })
+    }
+}
+
+/// Returns the response with its result pretty-printed. The response is returned as is if it
+/// can't be parsed.
+fn pretty_print(id: Id<'static>, response: MethodResponse) -> MethodResponse {
+    let Some(result) = pretty_result(response.as_result()) else {
+        return response;
+    };
+    MethodResponse::response(id, ResponsePayload::success(result), usize::MAX)
+}
+
+/// Returns the pretty-printed result of the serialized successful response.
+fn pretty_result(response: &str) -> Option<Box<RawValue>> {
+    let response: SuccessResponse = serde_json::from_str(response).ok()?;
+    let result: Value = serde_json::from_str(response.result.get()).ok()?;
+    // The raw value is serialized verbatim, which keeps its whitespace.
+    RawValue::from_string(serde_json::to_string_pretty(&result).ok()?).ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_is_pretty_printed() {
+        let response = r#"{"jsonrpc":"2.0","id":1,"result":{"enr":"enr:-abc","nodeId":"0x01"}}"#;
+        assert_eq!(
+            pretty_result(response).unwrap().get(),
+            "{\n  \"enr\": \"enr:-abc\",\n  \"nodeId\": \"0x01\"\n}"
+        );
+
+        let error =
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}"#;
+        assert!(pretty_result(error).is_none());
+    }
+}
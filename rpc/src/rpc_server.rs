@@ -1,6 +1,7 @@
 use std::{
     fmt,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
 };
 
 use ethportal_api::{
@@ -11,26 +12,45 @@ use reth_ipc::server::{Builder as IpcServerBuilder, IpcServer};
 use tower::layer::util::{Identity, Stack};
 use tower_http::cors::CorsLayer;
 use tracing::instrument;
+use trin_metrics::portalnet::PORTALNET_METRICS;
 
+#[cfg(unix)]
+use crate::ipc::IpcIdleTimeout;
 use crate::{
     builder::TransportRpcModules,
     cors,
     errors::WsHttpSamePortError,
+    ipc::{upstream_ipc_path, IpcConnectionsLayer},
     jsonrpsee::{
         http_client::{HttpClient, HttpClientBuilder},
-        server::{Server, ServerBuilder, ServerHandle},
+        server::{middleware::rpc::RpcServiceBuilder, Server, ServerBuilder, ServerHandle},
         ws_client::{WsClient, WsClientBuilder},
         RpcModule,
     },
+    pretty::PrettyJsonLayer,
     RpcError, TransportRpcModuleConfig,
 };
 
+/// The RPC middleware of all servers.
+type RpcMiddleware = Stack<PrettyJsonLayer, Identity>;
+
+/// The middleware of the connections of the ipc server.
+type IpcMiddleware = Stack<IpcConnectionsLayer, Identity>;
+
+/// Returns the RPC middleware of all servers.
+fn rpc_middleware(pretty: bool) -> RpcServiceBuilder<RpcMiddleware> {
+    RpcServiceBuilder::new().layer(PrettyJsonLayer::new(pretty))
+}
+
 /// Container type for each transport ie. http, ws, and ipc server
 pub struct RpcServer {
     /// Configured ws,http servers
     pub ws_http: WsHttpServer,
     /// ipc server
-    pub ipc: Option<IpcServer>,
+    pub ipc: Option<IpcServer<IpcMiddleware, RpcMiddleware>>,
+    /// The endpoint whose connections are forwarded to the ipc server, and closed once idle for
+    /// the timeout, if set.
+    pub ipc_idle_timeout: Option<(String, Duration)>,
 }
 
 impl RpcServer {
@@ -38,6 +58,7 @@ impl RpcServer {
         RpcServer {
             ws_http: Default::default(),
             ipc: None,
+            ipc_idle_timeout: None,
         }
     }
 
@@ -53,7 +74,10 @@ impl RpcServer {
 
     /// Returns the `endpoint` of the ipc server if started.
     pub fn ipc_endpoint(&self) -> Option<String> {
-        self.ipc.as_ref().map(|ipc| ipc.endpoint())
+        match &self.ipc_idle_timeout {
+            Some((endpoint, _)) => Some(endpoint.clone()),
+            None => self.ipc.as_ref().map(|ipc| ipc.endpoint()),
+        }
     }
 
     /// Starts the configured server by spawning the servers on the tokio runtime.
@@ -73,6 +97,7 @@ impl RpcServer {
         let Self {
             ws_http,
             ipc: ipc_server,
+            ipc_idle_timeout,
         } = self;
         let TransportRpcModules {
             config,
@@ -95,7 +120,22 @@ impl RpcServer {
         if let Some((server, module)) =
             ipc_server.and_then(|server| ipc.map(|module| (server, module)))
         {
-            handle.ipc = Some(server.start(module).await?);
+            let upstream_endpoint = server.endpoint();
+            let ipc_handle = server.start(module).await?;
+            #[cfg(unix)]
+            if let Some((endpoint, idle_timeout)) = ipc_idle_timeout {
+                let idle_timeout = IpcIdleTimeout::bind(&endpoint, upstream_endpoint, idle_timeout)
+                    .await
+                    .map_err(|err| {
+                        RpcError::Custom(format!(
+                            "Unable to listen for IPC connections at {endpoint}: {err}"
+                        ))
+                    })?;
+                tokio::spawn(idle_timeout.run(ipc_handle.clone()));
+            }
+            #[cfg(not(unix))]
+            let _ = (upstream_endpoint, ipc_idle_timeout);
+            handle.ipc = Some(ipc_handle);
         }
 
         Ok(handle)
@@ -244,6 +284,10 @@ pub struct RpcServerConfig {
     ipc_server_config: Option<IpcServerBuilder<Identity, Identity>>,
     /// The endpoint where to launch the ipc server
     ipc_endpoint: Option<String>,
+    /// The time after which idle connections to the ipc server are closed, if set
+    ipc_idle_timeout: Option<Duration>,
+    /// Whether the results of responses are pretty-printed, for all transports
+    pretty: bool,
 }
 
 impl RpcServerConfig {
@@ -314,6 +358,20 @@ impl RpcServerConfig {
         self
     }
 
+    /// Configures the time after which idle connections to the ipc server are closed
+    ///
+    /// Default is to keep them open
+    pub fn with_ipc_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.ipc_idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Configures whether the results of responses are pretty-printed, for all transports
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
     /// Sets a custom [IdProvider] for all configured transports.
     ///
     /// By default all transports use [EthSubscriptionIdProvider]
@@ -418,6 +476,7 @@ impl RpcServerConfig {
                 builder,
                 http_socket_addr,
                 cors,
+                self.pretty,
                 ServerKind::WsHttp(http_socket_addr),
             )
             .await?;
@@ -446,6 +505,7 @@ impl RpcServerConfig {
                 builder,
                 ws_socket_addr,
                 self.ws_cors_domains.take(),
+                self.pretty,
                 ServerKind::WS(ws_socket_addr),
             )
             .await?;
@@ -459,6 +519,7 @@ impl RpcServerConfig {
                 builder,
                 http_socket_addr,
                 self.http_cors_domains.take(),
+                self.pretty,
                 ServerKind::Http(http_socket_addr),
             )
             .await?;
@@ -486,10 +547,21 @@ impl RpcServerConfig {
         server.ws_http = self.build_ws_http().await?;
 
         if let Some(builder) = self.ipc_server_config {
-            let ipc_path = self
+            let mut ipc_path = self
                 .ipc_endpoint
                 .unwrap_or_else(|| DEFAULT_WEB3_IPC_PATH.to_string());
-            let ipc = builder.build(ipc_path);
+            if let Some(idle_timeout) = self.ipc_idle_timeout {
+                let upstream_path = upstream_ipc_path(&ipc_path);
+                server.ipc_idle_timeout = Some((ipc_path, idle_timeout));
+                ipc_path = upstream_path;
+            }
+            let ipc = builder
+                .set_http_middleware(
+                    tower::ServiceBuilder::new()
+                        .layer(IpcConnectionsLayer::new(PORTALNET_METRICS.rpc())),
+                )
+                .set_rpc_middleware(rpc_middleware(self.pretty))
+                .build(ipc_path);
             server.ipc = Some(ipc);
         }
 
@@ -571,9 +643,9 @@ impl Default for WsHttpServers {
 /// Http Servers Enum
 pub enum WsHttpServerKind {
     /// Http server
-    Plain(Server),
+    Plain(Server<Identity, RpcMiddleware>),
     /// Http server with cors
-    WithCors(Server<Stack<CorsLayer, Identity>>),
+    WithCors(Server<Stack<CorsLayer, Identity>, RpcMiddleware>),
 }
 
 impl WsHttpServerKind {
@@ -590,8 +662,10 @@ impl WsHttpServerKind {
         builder: ServerBuilder<Identity, Identity>,
         socket_addr: SocketAddr,
         cors_domains: Option<String>,
+        pretty: bool,
         server_kind: ServerKind,
     ) -> Result<Self, RpcError> {
+        let builder = builder.set_rpc_middleware(rpc_middleware(pretty));
         if let Some(cors) = cors_domains.as_deref().map(cors::create_cors_layer) {
             let cors = cors.map_err(|err| RpcError::Custom(err.to_string()))?;
             let server = builder
@@ -625,9 +699,17 @@ mod tests {
 
     use ethportal_api::types::portal_wire::MAINNET;
     use portalnet::discovery::Discovery;
+    use serde_json::Value;
 
     use super::*;
-    use crate::{builder::RpcModuleSelection, PortalRpcModule, RpcModuleBuilder};
+    use crate::{
+        builder::RpcModuleSelection,
+        jsonrpsee::{
+            core::{client::ClientT, params::BatchRequestBuilder},
+            rpc_params,
+        },
+        PortalRpcModule, RpcModuleBuilder,
+    };
 
     /// Localhost with port 0 so a free port is used.
     pub fn test_address() -> SocketAddr {
@@ -733,6 +815,26 @@ mod tests {
         assert_eq!(ws_addr, http_addr);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_batch_request() {
+        let handle = launch_http(vec![PortalRpcModule::Discv5, PortalRpcModule::Web3]).await;
+        let client = handle.http_client().unwrap();
+
+        let mut batch = BatchRequestBuilder::new();
+        batch.insert("web3_clientVersion", rpc_params![]).unwrap();
+        batch.insert("discv5_unknownMethod", rpc_params![]).unwrap();
+        batch.insert("discv5_nodeInfo", rpc_params![]).unwrap();
+        let responses = client.batch_request::<Value>(batch).await.unwrap();
+
+        // A failed call doesn't fail the others, and responses are in the order of the calls.
+        assert_eq!(responses.num_successful_calls(), 2);
+        assert_eq!(responses.num_failed_calls(), 1);
+        let responses = responses.into_iter().collect::<Vec<_>>();
+        assert!(responses[0].as_ref().unwrap().is_string());
+        assert!(responses[1].is_err());
+        assert!(responses[2].as_ref().unwrap().get("enr").is_some());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_launch_same_port_different_modules() {
         let builder = test_rpc_builder();
@@ -237,7 +237,7 @@ async fn audit_content_key(
 ) -> anyhow::Result<Instant> {
     let mut attempts = 0;
     while Instant::now() - timestamp < timeout {
-        match client.get_content(content_key.clone()).await {
+        match client.get_content(content_key.clone(), None).await {
             Ok(_) => return Ok(Instant::now()),
             _ => {
                 attempts += 1;
@@ -37,6 +37,7 @@ pub fn main() -> Result<()> {
         },
         node_id,
         node_data_dir,
+        false,
     )
     .unwrap()
     .create(&Subnetwork::History)
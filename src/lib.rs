@@ -1,30 +1,42 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
-use std::sync::Arc;
+pub mod stdin_import;
+
+use std::{sync::Arc, time::Duration};
 
 #[cfg(windows)]
 use ethportal_api::types::cli::Web3TransportType;
 use ethportal_api::{
-    types::{cli::TrinConfig, network::Subnetwork},
+    types::{
+        cli::{RadiusShrinkPolicy, TrinConfig},
+        network::Subnetwork,
+    },
     utils::bytes::hex_encode,
     version::get_trin_version,
 };
 use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, Discv5UdpSocket},
+    dns::refresh_bootnodes,
     events::PortalnetEvents,
-    utils::db::{configure_node_data_dir, configure_trin_data_dir},
+    types::denylist::Denylist,
+    utils::db::{configure_node_data_dir, configure_trin_data_dir, enr_seq_file},
 };
 use rpc::{launch_jsonrpc_server, RpcServerHandle};
-use tokio::sync::{mpsc, RwLock};
-use tracing::info;
+use tokio::{
+    io::BufReader,
+    sync::{mpsc, RwLock},
+};
+use tracing::{error, info};
 use tree_hash::TreeHash;
 use trin_beacon::initialize_beacon_network;
 use trin_history::initialize_history_network;
 use trin_state::initialize_state_network;
 use trin_storage::PortalStorageConfigFactory;
-use trin_validation::oracle::HeaderOracle;
+use trin_validation::{
+    accumulator::PreMergeAccumulator, header_validator::HeaderValidator, oracle::HeaderOracle,
+};
 use utp_rs::socket::UtpSocket;
 
 pub async fn run_trin(
@@ -52,7 +64,9 @@ pub async fn run_trin(
         trin_config.network.network(),
     )?;
 
-    let portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+    let mut portalnet_config = PortalnetConfig::new(&trin_config, private_key);
+    portalnet_config.enr_seq_file = Some(enr_seq_file(&node_data_dir));
+    portalnet_config.denylist = Denylist::shared_from_file(trin_config.denylist_file.clone())?;
 
     // Initialize base discovery protocol
     let mut discovery = Discovery::new(portalnet_config.clone(), trin_config.network.clone())?;
@@ -64,8 +78,18 @@ pub async fn run_trin(
         prometheus_exporter::start(addr)?;
     }
 
+    // Initialize statsd metrics
+    if let Some(addr) = trin_config.statsd_addr {
+        trin_metrics::statsd::start(addr, Duration::from_secs(trin_config.statsd_interval))?;
+    }
+
     // Initialize validation oracle
-    let header_oracle = HeaderOracle::default();
+    let mut header_oracle = HeaderOracle::default();
+    if let Some(path) = &trin_config.header_accumulator_path {
+        let pre_merge_acc = PreMergeAccumulator::try_from_path(path)?;
+        header_oracle.header_validator = HeaderValidator::new_with_pre_merge_acc(pre_merge_acc);
+        info!(path = %path.display(), "Validating headers against configured pre-merge accumulator");
+    }
     info!(
         hash_tree_root = %hex_encode(header_oracle.header_validator.pre_merge_acc.tree_hash_root().0),
         "Loaded pre-merge accumulator."
@@ -94,7 +118,20 @@ pub async fn run_trin(
         trin_config.storage_capacity_config(),
         discovery.local_enr().node_id(),
         node_data_dir,
-    )?;
+        trin_config.enable_wal,
+    )?
+    .with_content_ttl(trin_config.content_ttl.map(Duration::from_secs))
+    .with_recency_floor_blocks(trin_config.recency_floor_blocks)
+    .with_verify_checksums(trin_config.verify_checksums)
+    .with_keep_content(trin_config.on_radius_shrink == Some(RadiusShrinkPolicy::Keep))
+    .with_readonly_store_paths(trin_config.readonly_store_paths.clone())
+    .with_warmup_threads(trin_config.store_warmup_threads)
+    .with_storage_quotas(trin_config.storage_quotas.clone())
+    .with_serve_eviction_protection(
+        trin_config
+            .serve_eviction_protection
+            .map(Duration::from_secs),
+    );
 
     // Initialize state sub-network service and event handlers, if selected
     let (state_handler, state_network_task, state_event_tx, state_jsonrpc_tx, state_event_stream) =
@@ -154,6 +191,13 @@ pub async fn run_trin(
         (None, None, None, None, None)
     };
 
+    // Keep a handle to the history subnetwork for importing content from stdin
+    let import_history_jsonrpc_tx = if trin_config.import_stdin {
+        history_jsonrpc_tx.clone()
+    } else {
+        None
+    };
+
     // Launch JSON-RPC server
     let jsonrpc_trin_config = trin_config.clone();
     let jsonrpc_discovery = Arc::clone(&discovery);
@@ -176,6 +220,31 @@ pub async fn run_trin(
         tokio::spawn(async move { handler.handle_client_queries().await });
     }
 
+    if let Some(history_jsonrpc_tx) = import_history_jsonrpc_tx {
+        tokio::spawn(async move {
+            let stdin = BufReader::new(tokio::io::stdin());
+            if let Err(err) = stdin_import::import_history_content(stdin, history_jsonrpc_tx).await
+            {
+                error!(%err, "Importing content from stdin failed");
+            }
+        });
+    }
+
+    // Resolve additional bootnodes from the DNS node list in the background, if any
+    if let Some(url) = trin_config.bootnode_dns.clone() {
+        let overlay_txs = [&history_event_tx, &state_event_tx, &beacon_event_tx]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        tokio::spawn(refresh_bootnodes(
+            url,
+            Duration::from_secs(trin_config.bootnode_dns_refresh_interval),
+            Arc::clone(&discovery),
+            overlay_txs,
+        ));
+    }
+
     // Spawn main portal events handler
     tokio::spawn(async move {
         let events = PortalnetEvents::new(
@@ -0,0 +1,165 @@
+use std::io::ErrorKind;
+
+use anyhow::{anyhow, bail};
+use ethportal_api::{
+    types::jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
+    ContentValue, HistoryContentKey, HistoryContentValue, OverlayContentKey,
+};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::mpsc,
+};
+use tracing::{info, warn};
+
+/// The maximum size of a single content key or content value in the import stream.
+pub const MAX_IMPORT_ITEM_SIZE: u32 = 32 * 1024 * 1024;
+
+/// The number of imported entries after which progress is reported.
+const IMPORT_PROGRESS_INTERVAL: u64 = 1000;
+
+/// The outcome of an import.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: u64,
+    pub skipped: u64,
+}
+
+/// Reads the next length-prefixed item from `reader`.
+///
+/// Each item is encoded as a big-endian `u32` length followed by that many bytes. Returns `None`
+/// if the stream ends before the length prefix.
+async fn read_item<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+    let len = match reader.read_u32().await {
+        Ok(len) => len,
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if len > MAX_IMPORT_ITEM_SIZE {
+        bail!("Item of {len} bytes exceeds the maximum size of {MAX_IMPORT_ITEM_SIZE} bytes");
+    }
+    let mut item = vec![0; len as usize];
+    reader.read_exact(&mut item).await?;
+    Ok(Some(item))
+}
+
+/// Reads the next content key/value record from `reader`.
+///
+/// A record is a length-prefixed content key followed by a length-prefixed content value.
+/// Returns `None` once the stream ends cleanly between records.
+pub async fn read_record<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some(content_key) = read_item(reader).await? else {
+        return Ok(None);
+    };
+    let content_value = read_item(reader)
+        .await?
+        .ok_or_else(|| anyhow!("Stream ended before the content value of the last record"))?;
+    Ok(Some((content_key, content_value)))
+}
+
+/// Decodes a record and stores it in the history subnetwork.
+async fn store_record(
+    history_jsonrpc_tx: &mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    content_key: Vec<u8>,
+    content_value: Vec<u8>,
+) -> anyhow::Result<()> {
+    let content_key = HistoryContentKey::try_from_bytes(content_key)?;
+    let content_value = HistoryContentValue::decode(&content_key, &content_value)?;
+
+    let endpoint = HistoryEndpoint::Store(content_key, content_value);
+    let (resp, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
+    history_jsonrpc_tx.send(HistoryJsonRpcRequest { endpoint, resp })?;
+    match resp_rx.recv().await {
+        Some(Ok(Value::Bool(true))) => Ok(()),
+        Some(Ok(val)) => Err(anyhow!("Unable to store content: {val}")),
+        Some(Err(err)) => Err(anyhow!("Unable to store content: {err}")),
+        None => Err(anyhow!("No response from chain history subnetwork")),
+    }
+}
+
+/// Imports content key/value records from `reader` into the history subnetwork, one record at a
+/// time.
+///
+/// Records that fail to decode or store are skipped. A running count of imported entries is
+/// reported to stderr.
+pub async fn import_history_content<R: AsyncRead + Unpin>(
+    mut reader: R,
+    history_jsonrpc_tx: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+) -> anyhow::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    while let Some((content_key, content_value)) = read_record(&mut reader).await? {
+        match store_record(&history_jsonrpc_tx, content_key, content_value).await {
+            Ok(()) => {
+                summary.imported += 1;
+                if summary.imported % IMPORT_PROGRESS_INTERVAL == 0 {
+                    eprintln!("Imported {} entries", summary.imported);
+                }
+            }
+            Err(err) => {
+                summary.skipped += 1;
+                warn!(%err, "Skipping record from import stream");
+            }
+        }
+    }
+    eprintln!(
+        "Imported {} entries, skipped {}",
+        summary.imported, summary.skipped
+    );
+    info!(
+        imported = summary.imported,
+        skipped = summary.skipped,
+        "Finished importing content from stdin"
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn encode_item(item: &[u8]) -> Vec<u8> {
+        let mut encoded = (item.len() as u32).to_be_bytes().to_vec();
+        encoded.extend_from_slice(item);
+        encoded
+    }
+
+    #[tokio::test]
+    async fn read_records() {
+        let mut stream = vec![];
+        stream.extend(encode_item(&[0x00, 0x01]));
+        stream.extend(encode_item(&[0x02, 0x03, 0x04]));
+        stream.extend(encode_item(&[0x05]));
+        stream.extend(encode_item(&[]));
+        let mut reader = stream.as_slice();
+
+        assert_eq!(
+            read_record(&mut reader).await.unwrap(),
+            Some((vec![0x00, 0x01], vec![0x02, 0x03, 0x04]))
+        );
+        assert_eq!(
+            read_record(&mut reader).await.unwrap(),
+            Some((vec![0x05], vec![]))
+        );
+        assert_eq!(read_record(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn read_truncated_record() {
+        let mut stream = vec![];
+        stream.extend(encode_item(&[0x00, 0x01]));
+        let mut reader = stream.as_slice();
+
+        assert!(read_record(&mut reader).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_oversized_item() {
+        let stream = (MAX_IMPORT_ITEM_SIZE + 1).to_be_bytes();
+        let mut reader = stream.as_slice();
+
+        assert!(read_record(&mut reader).await.is_err());
+    }
+}
@@ -6,7 +6,7 @@ use ethportal_api::{
         content_value::ContentValue,
         distance::Distance,
         jsonrpc::{endpoints::BeaconEndpoint, request::BeaconJsonRpcRequest},
-        portal::{AcceptInfo, FindNodesInfo, GetContentInfo, PongInfo, TraceContentInfo},
+        portal::{AcceptInfo, GetContentInfo, PongInfo, TraceContentInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
     },
@@ -79,7 +79,9 @@ async fn complete_request(network: Arc<BeaconNetwork>, request: BeaconJsonRpcReq
             serde_json::to_value(network.overlay.routing_table_info())
                 .map_err(|err| err.to_string())
         }
-        BeaconEndpoint::RecursiveFindNodes(node_id) => recursive_find_nodes(network, node_id).await,
+        BeaconEndpoint::RecursiveFindNodes(node_id, limit) => {
+            recursive_find_nodes(network, node_id, limit).await
+        }
         BeaconEndpoint::OptimisticStateRoot => {
             let beacon_client = network.beacon_client.lock().await;
             match beacon_client.as_ref() {
@@ -281,13 +283,11 @@ async fn store(
     content_key: BeaconContentKey,
     content_value: BeaconContentValue,
 ) -> Result<Value, String> {
+    if network.overlay.is_store_degraded() {
+        return Err("Content store is unavailable".to_string());
+    }
     let data = content_value.encode().to_vec();
-    let response = match network
-        .overlay
-        .store
-        .write()
-        .put::<Vec<u8>>(content_key, data)
-    {
+    let response = match network.overlay.put_content(content_key, data.into()) {
         Ok(_) => Ok(Value::Bool(true)),
         Err(msg) => Ok(Value::String(msg.to_string())),
     };
@@ -357,11 +357,7 @@ async fn find_nodes(
     distances: Vec<u16>,
 ) -> Result<Value, String> {
     match network.overlay.send_find_nodes(enr, distances).await {
-        Ok(nodes) => Ok(json!(nodes
-            .enrs
-            .into_iter()
-            .map(|enr| enr.into())
-            .collect::<FindNodesInfo>())),
+        Ok(nodes) => Ok(json!(nodes.enrs)),
         Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
     }
 }
@@ -441,8 +437,9 @@ async fn ping(
 async fn recursive_find_nodes(
     network: Arc<BeaconNetwork>,
     node_id: NodeId,
+    limit: usize,
 ) -> Result<Value, String> {
-    let nodes = network.overlay.lookup_node(node_id).await;
+    let nodes = network.overlay.lookup_nodes(node_id, limit).await;
     Ok(json!(nodes))
 }
 
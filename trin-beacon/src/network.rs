@@ -43,7 +43,35 @@ impl BeaconNetwork {
         let config = OverlayConfig {
             bootnode_enrs: portal_config.bootnodes,
             utp_transfer_limit: portal_config.utp_transfer_limit,
-            gossip_dropped: GOSSIP_DROPPED,
+            utp_target_delay: portal_config.utp_target_delay,
+            utp_peer_transfer_limit: portal_config.utp_peer_transfer_limit,
+            serve_bandwidth_limiter: portal_config.serve_bandwidth_limiter,
+            enable_compression: portal_config.enable_compression,
+            max_find_content_size: portal_config.max_find_content_size,
+            min_lookup_peers: portal_config.min_lookup_peers,
+            lookup_retries: portal_config.lookup_retries,
+            query_parallelism: portal_config.lookup_parallelism,
+            query_max_hops: portal_config.lookup_max_hops,
+            gossip_dropped: portal_config
+                .on_radius_shrink
+                .map_or(GOSSIP_DROPPED, |policy| policy.gossips_evicted()),
+            denylist: portal_config.denylist,
+            offer_log: portal_config.offer_log,
+            mirror_peer: portal_config.mirror_peer,
+            join_delay: portal_config.join_delay,
+            query_trace_buffer_size: portal_config.query_trace_buffer_size,
+            content_ttl_sweep_interval: portal_config.content_ttl_sweep_interval,
+            gossip_decay: portal_config.gossip_decay,
+            gossip_dedup_window: portal_config.gossip_dedup_window,
+            accept_duplicate_content: portal_config.accept_duplicate_content,
+            gossip_peer_retries: portal_config.gossip_peer_retries,
+            content_holders_ttl: portal_config.content_holders_ttl,
+            circuit_breaker: portal_config.circuit_breaker,
+            max_peers_per_subnet: portal_config.max_peers_per_subnet,
+            serve_only_in_requester_radius: portal_config.serve_only_in_requester_radius,
+            ping_timeout: portal_config.ping_timeout,
+            findnodes_timeout: portal_config.findnodes_timeout,
+            findcontent_timeout: portal_config.findcontent_timeout,
             ..Default::default()
         };
         let storage = Arc::new(PLRwLock::new(BeaconStorage::new(storage_config)?));
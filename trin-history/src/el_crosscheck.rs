@@ -0,0 +1,358 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::primitives::B256;
+use anyhow::{anyhow, bail};
+use ethportal_api::{
+    types::{
+        execution::{
+            block_body::BlockBody, header_with_proof::HeaderWithProof, receipts::Receipts,
+        },
+        network::Subnetwork,
+    },
+    HistoryContentKey,
+};
+use parking_lot::RwLock;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use ssz::Decode;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, warn};
+use trin_metrics::{
+    labels::ElCrosscheckLabel, overlay::OverlayMetricsReporter, portalnet::PORTALNET_METRICS,
+};
+use url::Url;
+
+use crate::storage::HistoryStorage;
+
+/// The time after which a request to the execution client is abandoned, and the content isn't
+/// cross-checked.
+const EL_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The max number of cross-checks in flight. Sampled content is skipped while it's reached, which
+/// bounds the load on the execution client.
+const MAX_CONCURRENT_EL_CROSSCHECKS: usize = 16;
+
+/// The fields of a block of the execution client's canonical chain that content is cross-checked
+/// against.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElBlock {
+    pub hash: B256,
+    pub transactions_root: B256,
+    pub sha3_uncles: B256,
+    pub receipts_root: B256,
+}
+
+/// The response to a JSON-RPC request to the execution client.
+#[derive(Deserialize)]
+struct ElResponse {
+    result: Option<ElBlock>,
+    error: Option<Value>,
+}
+
+/// Cross-checks a sample of the validated History network content against the canonical chain of
+/// a trusted execution client.
+///
+/// Content is cross-checked in the background, so that validation doesn't wait for the execution
+/// client. Content that doesn't match the canonical chain is deleted from the store once its
+/// cross-check completes. Content that can't be cross-checked, e.g. because the execution client
+/// is unreachable or not synced, is kept, so that the node keeps working without it.
+#[derive(Clone)]
+pub struct ElCrosscheck {
+    client: reqwest::Client,
+    url: Url,
+    sample_ratio: f64,
+    metrics: OverlayMetricsReporter,
+    storage: Arc<RwLock<HistoryStorage>>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl ElCrosscheck {
+    /// Creates a cross-check against the execution client at `url`, of the `sample_ratio` share of
+    /// the content, between 0 and 1, that deletes mismatching content from the `storage`.
+    pub fn new(url: Url, sample_ratio: f64, storage: Arc<RwLock<HistoryStorage>>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            sample_ratio: sample_ratio.clamp(0.0, 1.0),
+            metrics: OverlayMetricsReporter {
+                overlay_metrics: PORTALNET_METRICS.overlay(),
+                protocol: Subnetwork::History.to_string(),
+            },
+            storage,
+            in_flight: Arc::new(Semaphore::new(MAX_CONCURRENT_EL_CROSSCHECKS)),
+        }
+    }
+
+    /// Returns whether content should be cross-checked, sampled at random.
+    pub fn sample(&self) -> bool {
+        rand::thread_rng().gen_bool(self.sample_ratio)
+    }
+
+    /// Cross-checks the content in the background, and deletes it from the store if it doesn't
+    /// match the canonical chain. Content is stored right after it's validated, so it's stored by
+    /// the time the execution client responds, if it's stored at all.
+    pub fn spawn_check(&self, content_key: &HistoryContentKey, content: &[u8]) {
+        let Ok(permit) = Arc::clone(&self.in_flight).try_acquire_owned() else {
+            debug!(
+                content.key = %content_key,
+                "Too many cross-checks in flight, skipping content"
+            );
+            return;
+        };
+        let el_crosscheck = self.clone();
+        let content_key = content_key.clone();
+        let content = content.to_vec();
+        tokio::spawn(async move {
+            if el_crosscheck.check(&content_key, &content).await.is_err() {
+                if let Err(err) = el_crosscheck.storage.write().delete(&content_key) {
+                    error!(
+                        content.key = %content_key,
+                        error = %err,
+                        "Failed to delete content that doesn't match the execution client"
+                    );
+                }
+            }
+            drop(permit);
+        });
+    }
+
+    /// Cross-checks the content against the canonical chain of the execution client.
+    ///
+    /// It returns an error only if the content doesn't match the canonical chain.
+    async fn check(&self, content_key: &HistoryContentKey, content: &[u8]) -> anyhow::Result<()> {
+        let discrepancy = match self.fetch_block(content_key, content).await {
+            Ok(el_block) => find_discrepancy(content_key, content, &el_block),
+            Err(err) => Err(err),
+        };
+        match discrepancy {
+            Ok(None) => {
+                debug!(content.key = %content_key, "Content matches the execution client");
+                self.metrics.report_el_crosscheck(ElCrosscheckLabel::Match);
+                Ok(())
+            }
+            Ok(Some(discrepancy)) => {
+                warn!(
+                    content.key = %content_key,
+                    discrepancy,
+                    "Content doesn't match the canonical chain of the execution client"
+                );
+                self.metrics
+                    .report_el_crosscheck(ElCrosscheckLabel::Mismatch);
+                Err(anyhow!(
+                    "Content doesn't match the canonical chain of the execution client: {discrepancy}"
+                ))
+            }
+            Err(err) => {
+                warn!(
+                    content.key = %content_key,
+                    error = %err,
+                    "Failed to cross-check content against the execution client"
+                );
+                self.metrics.report_el_crosscheck(ElCrosscheckLabel::Error);
+                Ok(())
+            }
+        }
+    }
+
+    /// Fetches the canonical block that the content belongs to from the execution client.
+    async fn fetch_block(
+        &self,
+        content_key: &HistoryContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<ElBlock> {
+        let (method, params) = match content_key {
+            // Headers are fetched by number, so that headers of non-canonical blocks are detected.
+            HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_) => {
+                let header_with_proof = HeaderWithProof::from_ssz_bytes(content)
+                    .map_err(|err| anyhow!("Header content has invalid encoding: {err:?}"))?;
+                (
+                    "eth_getBlockByNumber",
+                    json!([format!("{:#x}", header_with_proof.header.number), false]),
+                )
+            }
+            HistoryContentKey::BlockBody(key) => (
+                "eth_getBlockByHash",
+                json!([B256::from(key.block_hash), false]),
+            ),
+            HistoryContentKey::BlockReceipts(key) => (
+                "eth_getBlockByHash",
+                json!([B256::from(key.block_hash), false]),
+            ),
+        };
+        let response: ElResponse = self
+            .client
+            .post(self.url.clone())
+            .timeout(EL_REQUEST_TIMEOUT)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            bail!("Execution client returned an error: {error}");
+        }
+        // A missing block may just not be synced yet by the execution client, so it isn't
+        // considered a discrepancy.
+        response
+            .result
+            .ok_or_else(|| anyhow!("Execution client doesn't know the block"))
+    }
+}
+
+/// Compares the content with the canonical block it belongs to, and returns a description of how
+/// they differ, if they do.
+pub fn find_discrepancy(
+    content_key: &HistoryContentKey,
+    content: &[u8],
+    el_block: &ElBlock,
+) -> anyhow::Result<Option<String>> {
+    match content_key {
+        HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_) => {
+            let header_with_proof = HeaderWithProof::from_ssz_bytes(content)
+                .map_err(|err| anyhow!("Header content has invalid encoding: {err:?}"))?;
+            let header_hash = header_with_proof.header.hash();
+            Ok((header_hash != el_block.hash).then(|| {
+                format!(
+                    "Header hash {header_hash} of block {} isn't canonical, expected {}",
+                    header_with_proof.header.number, el_block.hash
+                )
+            }))
+        }
+        HistoryContentKey::BlockBody(_) => {
+            let block_body = BlockBody::from_ssz_bytes(content)
+                .map_err(|err| anyhow!("Block body content has invalid encoding: {err:?}"))?;
+            let uncles_root = block_body.uncles_root();
+            if uncles_root != el_block.sha3_uncles {
+                return Ok(Some(format!(
+                    "Uncles root {uncles_root} doesn't match {}",
+                    el_block.sha3_uncles
+                )));
+            }
+            let transactions_root = block_body.transactions_root()?;
+            Ok((transactions_root != el_block.transactions_root).then(|| {
+                format!(
+                    "Transactions root {transactions_root} doesn't match {}",
+                    el_block.transactions_root
+                )
+            }))
+        }
+        HistoryContentKey::BlockReceipts(_) => {
+            let receipts = Receipts::from_ssz_bytes(content)
+                .map_err(|err| anyhow!("Block receipts content has invalid encoding: {err:?}"))?;
+            let receipts_root = receipts.root()?;
+            Ok((receipts_root != el_block.receipts_root).then(|| {
+                format!(
+                    "Receipts root {receipts_root} doesn't match {}",
+                    el_block.receipts_root
+                )
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs;
+
+    use ethportal_api::utils::bytes::hex_decode;
+
+    use super::*;
+
+    fn header_with_proof_ssz() -> Vec<u8> {
+        let file =
+            fs::read_to_string("../trin-validation/src/assets/fluffy/header_with_proofs.json")
+                .unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let raw_header = json["1000001"]["value"].as_str().unwrap();
+        hex_decode(raw_header).unwrap()
+    }
+
+    #[test]
+    fn header_is_compared_with_canonical_hash() {
+        let content = header_with_proof_ssz();
+        let header = HeaderWithProof::from_ssz_bytes(&content).unwrap().header;
+        let content_key = HistoryContentKey::new_block_header_by_number(header.number);
+        let mut el_block = ElBlock {
+            hash: header.hash(),
+            transactions_root: header.transactions_root,
+            sha3_uncles: header.uncles_hash,
+            receipts_root: header.receipts_root,
+        };
+
+        assert_eq!(
+            find_discrepancy(&content_key, &content, &el_block).unwrap(),
+            None
+        );
+
+        el_block.hash = B256::ZERO;
+        assert!(find_discrepancy(&content_key, &content, &el_block)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn block_body_is_compared_with_canonical_roots() {
+        let content = fs::read("../test_assets/mainnet/block_body_14764013.bin").unwrap();
+        let block_body = BlockBody::from_ssz_bytes(&content).unwrap();
+        let content_key = HistoryContentKey::new_block_body(B256::random());
+        let mut el_block = ElBlock {
+            hash: B256::random(),
+            transactions_root: block_body.transactions_root().unwrap(),
+            sha3_uncles: block_body.uncles_root(),
+            receipts_root: B256::random(),
+        };
+
+        assert_eq!(
+            find_discrepancy(&content_key, &content, &el_block).unwrap(),
+            None
+        );
+
+        el_block.sha3_uncles = B256::ZERO;
+        assert!(find_discrepancy(&content_key, &content, &el_block)
+            .unwrap()
+            .unwrap()
+            .starts_with("Uncles root"));
+
+        el_block.sha3_uncles = block_body.uncles_root();
+        el_block.transactions_root = B256::ZERO;
+        assert!(find_discrepancy(&content_key, &content, &el_block)
+            .unwrap()
+            .unwrap()
+            .starts_with("Transactions root"));
+
+        find_discrepancy(&content_key, &content[1..], &el_block).unwrap_err();
+    }
+
+    #[test]
+    fn receipts_are_compared_with_canonical_root() {
+        let content = fs::read("../test_assets/mainnet/receipts_14764013.bin").unwrap();
+        let receipts = Receipts::from_ssz_bytes(&content).unwrap();
+        let content_key = HistoryContentKey::new_block_receipts(B256::random());
+        let mut el_block = ElBlock {
+            hash: B256::random(),
+            transactions_root: B256::random(),
+            sha3_uncles: B256::random(),
+            receipts_root: receipts.root().unwrap(),
+        };
+
+        assert_eq!(
+            find_discrepancy(&content_key, &content, &el_block).unwrap(),
+            None
+        );
+
+        el_block.receipts_root = B256::ZERO;
+        assert!(find_discrepancy(&content_key, &content, &el_block)
+            .unwrap()
+            .unwrap()
+            .starts_with("Receipts root"));
+    }
+}
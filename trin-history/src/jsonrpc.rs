@@ -1,62 +1,287 @@
-use std::sync::Arc;
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    fs::OpenOptions,
+    future::Future,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use alloy::primitives::Bytes;
+use alloy::primitives::{Bytes, B256};
 use discv5::enr::NodeId;
 use ethportal_api::{
     types::{
-        distance::Distance,
+        content_key::history::{HistoryBlockId, HistoryContentType},
+        distance::{Distance, Metric, XorMetric},
+        enr::Enr,
         jsonrpc::{endpoints::HistoryEndpoint, request::HistoryJsonRpcRequest},
-        portal::{AcceptInfo, FindNodesInfo, GetContentInfo, PongInfo, TraceContentInfo},
+        portal::{
+            AcceptInfo, AuditPeerRadiusInfo, AvailabilitySample, BackfillInfo, ConfirmedOfferInfo,
+            DataRadius, EncodedContentKeyInfo, ExportStoreInfo, FindAndStoreInfo,
+            FindAndStoreOutcome, FindContentRangeInfo, GetContentInfo, InFlightRequestInfo,
+            InboundReachabilityInfo, NodeDistanceInfo, OfferThroughputInfo, OfferTransferInfo,
+            OfferedItemInfo, PongInfo, RejectionExplanationInfo, RejectionReason, ReplayTraceInfo,
+            SampleAvailabilityInfo, SetRadiusInfo, TraceContentInfo, TraceGetContentEvent,
+        },
         portal_wire::Content,
-        query_trace::QueryTrace,
+        query_trace::{QueryHopEvent, QueryTrace, RecordedQueryTrace},
     },
     utils::bytes::hex_encode,
-    ContentValue, HistoryContentKey, HistoryContentValue, OverlayContentKey,
+    ContentValue, HistoryContentKey, HistoryContentValue, OverlayContentKey, RawContentKey,
+    RawContentValue,
 };
-use portalnet::overlay::{config::FindContentConfig, errors::OverlayRequestError};
+use futures::{stream, FutureExt, StreamExt};
+use parking_lot::{Mutex, RwLock};
+use portalnet::{
+    find::simulation::MAX_SIMULATED_PEERS,
+    overlay::{config::FindContentConfig, errors::OverlayRequestError},
+    types::last_errors::ErrorSubsystem,
+};
+use rand::Rng;
 use serde_json::{json, Value};
-use tokio::sync::mpsc;
-use tracing::error;
-use trin_storage::ContentStore;
+use tokio::{
+    sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore},
+    task::AbortHandle,
+};
+use tracing::{debug, error, warn};
+use trin_metrics::labels::SelfHealLabel;
+use trin_storage::{error::ContentStoreError, ContentStore, ShouldWeStoreContent};
+use trin_validation::validator::Validator;
+
+use crate::{
+    lookup_queue::{LookupQueue, MAX_QUEUED_LOOKUPS},
+    network::HistoryNetwork,
+    storage::HistoryStorage,
+};
+
+/// The maximum number of blocks whose content is looked up at the same time by a content range
+/// lookup.
+const FIND_CONTENT_RANGE_CONCURRENCY: usize = 8;
+
+/// The maximum number of sampled content items that the network is probed for at the same time
+/// when sampling availability.
+const SAMPLE_AVAILABILITY_CONCURRENCY: usize = 8;
+
+/// The number of peers closest to a sampled content item that are probed for it, until one returns
+/// it, when sampling availability.
+const SAMPLE_AVAILABILITY_PEERS: usize = 3;
+
+/// The number of stored entries that are read from the store at a time when exporting it.
+const EXPORT_STORE_PAGE_SIZE: u64 = 1000;
+
+/// The maximum number of content items that a peer is probed for when auditing its radius.
+const AUDIT_PEER_RADIUS_SAMPLE_SIZE: usize = 8;
+
+/// The number of stored entries outside the radius that are evicted at a time, after the radius
+/// is set.
+const EVICT_OUTSIDE_RADIUS_BATCH_SIZE: u64 = 100;
+
+/// The content lookups that are currently in progress, keyed by content key and whether the lookup
+/// is traced. Each entry holds the requests that are waiting for the result of the lookup.
+type InFlightLookups =
+    Arc<Mutex<HashMap<(HistoryContentKey, bool), Vec<oneshot::Sender<Result<Value, String>>>>>>;
+
+/// The requests that are currently being executed, keyed by request id.
+type InFlightRequests = Arc<Mutex<HashMap<u64, InFlightRequest>>>;
 
-use crate::network::HistoryNetwork;
+/// A request that is currently being executed by a spawned task.
+pub struct InFlightRequest {
+    endpoint: String,
+    started: Instant,
+    abort_handle: AbortHandle,
+}
 
 /// Handles History network JSON-RPC requests
 pub struct HistoryRequestHandler {
     pub network: Arc<HistoryNetwork>,
     pub history_rx: mpsc::UnboundedReceiver<HistoryJsonRpcRequest>,
+    pub in_flight_lookups: InFlightLookups,
+    pub in_flight_requests: InFlightRequests,
+    /// The id of the most recently received request.
+    pub last_request_id: u64,
+    /// The slots for content lookups executed at the same time, if their number is limited.
+    pub lookup_slots: Option<Arc<Semaphore>>,
+    /// The content lookups waiting for a free slot.
+    pub lookup_queue: LookupQueue,
+    /// The duration after which requests are logged as slow, if they are logged.
+    pub slow_request_threshold: Option<Duration>,
 }
 
 impl HistoryRequestHandler {
     /// Complete RPC requests for the History network.
+    ///
+    /// If the number of concurrent content lookups is limited, lookups wait in the queue until a
+    /// slot is free, and are executed by priority. Other requests are executed immediately.
     pub async fn handle_client_queries(mut self) {
-        while let Some(request) = self.history_rx.recv().await {
-            let network = self.network.clone();
-            tokio::spawn(async move { complete_request(network, request).await });
+        loop {
+            let request = match self.lookup_slots.clone() {
+                Some(lookup_slots) if !self.lookup_queue.is_empty() => {
+                    tokio::select! {
+                        biased;
+                        Ok(permit) = lookup_slots.acquire_owned() => {
+                            if let Some(request) = self.lookup_queue.pop() {
+                                self.spawn_request(request, Some(permit));
+                            }
+                            continue;
+                        }
+                        request = self.history_rx.recv() => request,
+                    }
+                }
+                _ => self.history_rx.recv().await,
+            };
+            let Some(request) = request else {
+                break;
+            };
+            if self.lookup_slots.is_some() && request.endpoint.lookup_content_key().is_some() {
+                if let Some(rejected) = self.lookup_queue.push(request) {
+                    warn!(
+                        endpoint = rejected.endpoint.as_ref(),
+                        "Lookup queue is full, rejecting content lookup"
+                    );
+                    let _ = rejected.resp.send(Err(format!(
+                        "Too many content lookups queued, at most {MAX_QUEUED_LOOKUPS} can wait for a free slot"
+                    )));
+                }
+                debug!(
+                    queued = self.lookup_queue.len(),
+                    "Queued content lookup until a slot is free"
+                );
+            } else {
+                self.spawn_request(request, None);
+            }
         }
     }
+
+    /// Spawns the task that executes the request, which holds the lookup slot `permit` until it
+    /// completes.
+    fn spawn_request(
+        &mut self,
+        request: HistoryJsonRpcRequest,
+        permit: Option<OwnedSemaphorePermit>,
+    ) {
+        let network = self.network.clone();
+        let in_flight_lookups = self.in_flight_lookups.clone();
+        let in_flight_requests = self.in_flight_requests.clone();
+        self.last_request_id += 1;
+        let guard = InFlightRequestGuard {
+            in_flight_requests: self.in_flight_requests.clone(),
+            request_id: self.last_request_id,
+        };
+        let request_id = self.last_request_id;
+        let slow_request_threshold = self.slow_request_threshold;
+        let endpoint = request.endpoint.as_ref().to_owned();
+
+        // Hold the lock until the request is registered, so that the guard can't remove the
+        // entry before it's inserted.
+        let mut requests = self.in_flight_requests.lock();
+        let handle = tokio::spawn(async move {
+            let _guard = guard;
+            let _permit = permit;
+            complete_request(
+                network,
+                in_flight_lookups,
+                in_flight_requests,
+                request,
+                request_id,
+                slow_request_threshold,
+            )
+            .await
+        });
+        requests.insert(
+            self.last_request_id,
+            InFlightRequest {
+                endpoint,
+                started: Instant::now(),
+                abort_handle: handle.abort_handle(),
+            },
+        );
+    }
+}
+
+/// Removes the in-flight request entry when dropped, which happens when the task executing the
+/// request completes, is aborted or panics.
+struct InFlightRequestGuard {
+    in_flight_requests: InFlightRequests,
+    request_id: u64,
+}
+
+impl Drop for InFlightRequestGuard {
+    fn drop(&mut self) {
+        self.in_flight_requests.lock().remove(&self.request_id);
+    }
 }
 
 /// Generates a response for a given request and sends it to the receiver.
-async fn complete_request(network: Arc<HistoryNetwork>, request: HistoryJsonRpcRequest) {
+async fn complete_request(
+    network: Arc<HistoryNetwork>,
+    in_flight_lookups: InFlightLookups,
+    in_flight_requests: InFlightRequests,
+    request: HistoryJsonRpcRequest,
+    request_id: u64,
+    slow_request_threshold: Option<Duration>,
+) {
+    let endpoint = request.endpoint.as_ref().to_owned();
+    let started = Instant::now();
     let response: Result<Value, String> = match request.endpoint {
         HistoryEndpoint::LocalContent(content_key) => local_content(network, content_key).await,
+        HistoryEndpoint::LocalLookup(content_key) => local_lookup(network, content_key),
+        HistoryEndpoint::RawStoredBytes(content_key) => raw_stored_bytes(network, content_key),
         HistoryEndpoint::PaginateLocalContentKeys(offset, limit) => {
             paginate_local_content_keys(network, offset, limit).await
         }
+        HistoryEndpoint::ContentRange => content_range(network).await,
+        HistoryEndpoint::ContentDistanceDistribution => {
+            content_distance_distribution(network).await
+        }
+        HistoryEndpoint::ClosestContent(node_id, count) => {
+            closest_content(network, node_id, count).await
+        }
+        HistoryEndpoint::NodeDistance(node_id_a, node_id_b) => {
+            Ok(node_distance(node_id_a, node_id_b))
+        }
+        HistoryEndpoint::EncodeContentKey(content_type, block_id) => {
+            encode_content_key(content_type, block_id)
+        }
+        HistoryEndpoint::ContentKeyBlockNumber(content_key) => {
+            content_key_block_number(content_key)
+        }
+        HistoryEndpoint::SimulateLookup(enrs, content_id) => {
+            simulate_lookup(network, enrs, content_id)
+        }
         HistoryEndpoint::Store(content_key, content_value) => {
             store(network, content_key, content_value).await
         }
-        HistoryEndpoint::GetContent(content_key) => get_content(network, content_key, false).await,
-        HistoryEndpoint::TraceGetContent(content_key) => {
-            get_content(network, content_key, true).await
+        HistoryEndpoint::ExplainRejection(content_key) => explain_rejection(network, content_key),
+        HistoryEndpoint::GetContent(content_key, hint_peer) => {
+            coalesced_get_content(network, in_flight_lookups, content_key, hint_peer, false).await
+        }
+        HistoryEndpoint::TraceGetContent(content_key, hint_peer) => {
+            coalesced_get_content(network, in_flight_lookups, content_key, hint_peer, true).await
+        }
+        HistoryEndpoint::FindAndStore(content_key) => find_and_store(network, content_key).await,
+        HistoryEndpoint::BackfillFromPeer(enr, content_keys, concurrency) => {
+            backfill_from_peer(network, enr, content_keys, concurrency).await
+        }
+        HistoryEndpoint::FindContentRange(content_type, start_block, end_block) => {
+            find_content_range(network, content_type, start_block, end_block).await
+        }
+        HistoryEndpoint::SampleAvailability(content_type, start_block, end_block, sample_size) => {
+            sample_availability(network, content_type, start_block, end_block, sample_size).await
+        }
+        HistoryEndpoint::StreamTraceGetContent(content_key, hint_peer) => {
+            stream_trace_get_content(network, content_key, hint_peer, request.resp.clone()).await
         }
         HistoryEndpoint::AddEnr(enr) => add_enr(network, enr).await,
         HistoryEndpoint::DataRadius => {
             let radius = network.overlay.data_radius();
             Ok(json!(*radius))
         }
+        HistoryEndpoint::SetRadius(radius) => set_radius(network, radius),
         HistoryEndpoint::DeleteEnr(node_id) => delete_enr(network, node_id).await,
+        HistoryEndpoint::RemoveEnr(node_id, deny_ttl_secs) => {
+            remove_enr(network, node_id, deny_ttl_secs).await
+        }
         HistoryEndpoint::FindContent(enr, content_key) => {
             find_content(network, enr, content_key).await
         }
@@ -68,39 +293,226 @@ async fn complete_request(network: Arc<HistoryNetwork>, request: HistoryJsonRpcR
         HistoryEndpoint::TraceGossip(content_key, content_value) => {
             trace_gossip(network, content_key, content_value).await
         }
+        HistoryEndpoint::GossipBatch(content_items) => gossip_batch(network, content_items).await,
         HistoryEndpoint::LookupEnr(node_id) => lookup_enr(network, node_id).await,
         HistoryEndpoint::Offer(enr, content_items) => offer(network, enr, content_items).await,
         HistoryEndpoint::TraceOffer(enr, content_key, content_value) => {
             trace_offer(network, enr, content_key, content_value).await
         }
+        HistoryEndpoint::OfferWithConfirmation(enr, content_key, content_value) => {
+            offer_with_confirmation(network, enr, content_key, content_value).await
+        }
+        HistoryEndpoint::OfferWithThroughput(enr, content_items) => {
+            offer_with_throughput(network, enr, content_items).await
+        }
+        HistoryEndpoint::RawOffer(enr, content_items) => {
+            raw_offer(network, enr, content_items).await
+        }
         HistoryEndpoint::Ping(enr) => ping(network, enr).await,
+        HistoryEndpoint::DebugPing(enr) => debug_ping(network, enr).await,
+        HistoryEndpoint::AuditPeerRadius(enr) => audit_peer_radius(network, enr).await,
+        HistoryEndpoint::TestInboundReachability(enr) => {
+            test_inbound_reachability(network, enr).await
+        }
         HistoryEndpoint::RoutingTableInfo => {
             serde_json::to_value(network.overlay.routing_table_info())
                 .map_err(|err| err.to_string())
         }
-        HistoryEndpoint::RecursiveFindNodes(node_id) => {
-            recursive_find_nodes(network, node_id).await
+        HistoryEndpoint::ReloadDenylist => reload_denylist(network).await,
+        HistoryEndpoint::SyncStore => sync_store(network).await,
+        HistoryEndpoint::ExportStore(path) => export_store(network, path).await,
+        HistoryEndpoint::StorageInfo => storage_info(network).await,
+        HistoryEndpoint::ClientInfo => Ok(json!(network.overlay.discovery.client_info())),
+        HistoryEndpoint::Readiness => Ok(json!(network.overlay.readiness(&network.readiness))),
+        HistoryEndpoint::GetConfig => Ok(network.redacted_config.clone()),
+        HistoryEndpoint::LastErrors => Ok(json!(network.overlay.last_errors())),
+        HistoryEndpoint::RadiusHistory => {
+            let radius_history = network.overlay.store.read().radius_history();
+            Ok(json!(radius_history))
+        }
+        HistoryEndpoint::BenchmarkStore(entry_count, value_size) => {
+            benchmark_store(network, entry_count, value_size).await
+        }
+        HistoryEndpoint::PeerTransfers => Ok(json!(network.overlay.peer_transfers_info())),
+        HistoryEndpoint::CircuitBreakers => Ok(json!(network.overlay.circuit_breakers())),
+        HistoryEndpoint::RecentlyServed(count) => {
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+            Ok(json!(network.overlay.recently_served(count)))
+        }
+        HistoryEndpoint::ListInFlight => Ok(list_in_flight(in_flight_requests)),
+        HistoryEndpoint::CancelRequest(request_id) => {
+            Ok(cancel_request(in_flight_requests, request_id))
+        }
+        HistoryEndpoint::GetTrace(query_trace_id) => get_trace(network, query_trace_id).await,
+        HistoryEndpoint::ListTraces => Ok(json!(network.overlay.query_traces())),
+        HistoryEndpoint::ReplayTrace(recorded_trace) => replay_trace(network, recorded_trace).await,
+        HistoryEndpoint::NeighborhoodInfo => {
+            serde_json::to_value(network.overlay.neighborhood_info()).map_err(|err| err.to_string())
+        }
+        HistoryEndpoint::SubnetDistribution => Ok(json!(network.overlay.subnet_distribution())),
+        HistoryEndpoint::BucketRefreshInfo => Ok(json!(network.overlay.refresh_info())),
+        HistoryEndpoint::RecursiveFindNodes(node_id, limit) => {
+            recursive_find_nodes(network, node_id, limit).await
+        }
+        HistoryEndpoint::NetworkCrawl(max_peers, timeout_secs) => {
+            network_crawl(network, max_peers, timeout_secs).await
         }
     };
+    let duration = started.elapsed();
+    if slow_request_threshold.is_some_and(|threshold| duration > threshold) {
+        warn!(
+            request_id,
+            endpoint,
+            duration_ms = duration.as_millis(),
+            "Slow JSON-RPC request"
+        );
+    }
     let _ = request.resp.send(response);
 }
 
+/// Constructs a JSON call for the NodeDistance method.
+fn node_distance(node_id_a: NodeId, node_id_b: NodeId) -> Value {
+    let distance = XorMetric::distance(&node_id_a.raw(), &node_id_b.raw());
+    json!(NodeDistanceInfo {
+        distance: *distance,
+        log2_distance: distance.log2(),
+    })
+}
+
+/// Removes the in-flight lookup entry when dropped, unless the lookup finished and notified its
+/// waiters. This prevents waiters, and later lookups of the same content, from hanging if the
+/// lookup doesn't complete.
+struct InFlightLookupGuard {
+    in_flight_lookups: InFlightLookups,
+    key: Option<(HistoryContentKey, bool)>,
+}
+
+impl InFlightLookupGuard {
+    /// Removes the in-flight lookup entry and sends the response to all waiters.
+    fn finish(mut self, response: &Result<Value, String>) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let waiters = self.in_flight_lookups.lock().remove(&key);
+        for waiter in waiters.into_iter().flatten() {
+            let _ = waiter.send(response.clone());
+        }
+    }
+}
+
+impl Drop for InFlightLookupGuard {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight_lookups.lock().remove(&key);
+        }
+    }
+}
+
+/// Constructs a JSON call for the GetContent method, sharing a single lookup between concurrent
+/// requests for the same content.
+///
+/// Requests with a hint peer are not shared, since the lookup they perform differs from others.
+async fn coalesced_get_content(
+    network: Arc<HistoryNetwork>,
+    in_flight_lookups: InFlightLookups,
+    content_key: HistoryContentKey,
+    hint_peer: Option<Enr>,
+    is_trace: bool,
+) -> Result<Value, String> {
+    if hint_peer.is_some() {
+        return get_content(network, content_key, hint_peer, is_trace, None).await;
+    }
+
+    let key = (content_key.clone(), is_trace);
+    coalesce_lookup(
+        in_flight_lookups,
+        key,
+        get_content(network, content_key, None, is_trace, None),
+    )
+    .await
+}
+
+/// Runs `lookup`, unless a lookup with the same key is already in flight, in which case its result
+/// is awaited instead.
+async fn coalesce_lookup(
+    in_flight_lookups: InFlightLookups,
+    key: (HistoryContentKey, bool),
+    lookup: impl Future<Output = Result<Value, String>>,
+) -> Result<Value, String> {
+    let waiter = match in_flight_lookups.lock().entry(key.clone()) {
+        Entry::Occupied(mut entry) => {
+            let (tx, rx) = oneshot::channel();
+            entry.get_mut().push(tx);
+            Some(rx)
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(vec![]);
+            None
+        }
+    };
+    if let Some(waiter) = waiter {
+        return waiter
+            .await
+            .map_err(|_| "In-flight content lookup was aborted".to_owned())?;
+    }
+
+    let guard = InFlightLookupGuard {
+        in_flight_lookups,
+        key: Some(key),
+    };
+    let response = lookup.await;
+    guard.finish(&response);
+    response
+}
+
 /// Constructs a JSON call for the GetContent method.
+///
+/// If `hop_events` is set, the response of each peer contacted during the network lookup is sent
+/// on it as it arrives.
 async fn get_content(
     network: Arc<HistoryNetwork>,
     content_key: HistoryContentKey,
+    hint_peer: Option<Enr>,
     is_trace: bool,
+    hop_events: Option<mpsc::UnboundedSender<QueryHopEvent>>,
 ) -> Result<Value, String> {
     // Check whether we have the data locally.
-    let local_content: Option<Bytes> = match network.overlay.store.read().get(&content_key) {
+    let local_value = network.overlay.store.read().get(&content_key);
+    let mut is_self_healing = false;
+    let local_content: Option<Bytes> = match local_value {
         Ok(Some(data)) => Some(data),
         Ok(None) => None,
+        // Corrupted content is reported rather than silently replaced by a network lookup, unless
+        // the node heals itself by refetching it.
+        Err(err @ ContentStoreError::Corrupted { .. }) => {
+            if !network.self_heal_corrupt_content {
+                return Err(err.to_string());
+            }
+            // The corrupted entry is deleted, so that the refetched copy is stored in its place.
+            if let Err(err) = network.overlay.store.write().delete(&content_key) {
+                network
+                    .overlay
+                    .metrics()
+                    .report_self_heal(SelfHealLabel::Failed);
+                return Err(format!("Unable to delete corrupted content: {err}"));
+            }
+            warn!(
+                content.key = %content_key,
+                "Deleted corrupted content, refetching it from the network"
+            );
+            is_self_healing = true;
+            None
+        }
         Err(err) => {
             error!(
                 error = %err,
                 content.key = %content_key,
                 "Error checking data store for content",
             );
+            network.overlay.record_error(
+                ErrorSubsystem::Store,
+                format!("Error checking data store for content {content_key}: {err}"),
+            );
             None
         }
     };
@@ -116,41 +528,51 @@ async fn get_content(
             (val, false, if is_trace { Some(trace) } else { None })
         }
         // data is not available locally, make network request
-        None => match network
-            .overlay
-            .lookup_content(
-                content_key.clone(),
-                FindContentConfig {
-                    is_trace,
-                    ..Default::default()
+        None => {
+            let lookup_result = network
+                .overlay
+                .lookup_content(
+                    content_key.clone(),
+                    FindContentConfig {
+                        is_trace,
+                        hint_peer,
+                        hop_events,
+                        ..Default::default()
+                    },
+                )
+                .await;
+            if is_self_healing {
+                let outcome = match lookup_result {
+                    Ok(Ok(_)) => SelfHealLabel::Refetched,
+                    _ => SelfHealLabel::Failed,
+                };
+                network.overlay.metrics().report_self_heal(outcome);
+            }
+            match lookup_result.map_err(|err| err.to_string())? {
+                Ok((content_bytes, utp_transfer, trace)) => (content_bytes, utp_transfer, trace),
+                Err(err) => match err.clone() {
+                    OverlayRequestError::ContentNotFound {
+                        message,
+                        utp,
+                        trace,
+                    } => {
+                        let err = json!({
+                            "message": format!("{message}: utp: {utp}"),
+                            "trace": trace
+                        });
+                        return Err(err.to_string());
+                    }
+                    _ => {
+                        error!(
+                            error = %err,
+                            content.key = %content_key,
+                            "Error looking up content",
+                        );
+                        return Err(err.to_string());
+                    }
                 },
-            )
-            .await
-            .map_err(|err| err.to_string())?
-        {
-            Ok((content_bytes, utp_transfer, trace)) => (content_bytes, utp_transfer, trace),
-            Err(err) => match err.clone() {
-                OverlayRequestError::ContentNotFound {
-                    message,
-                    utp,
-                    trace,
-                } => {
-                    let err = json!({
-                        "message": format!("{message}: utp: {utp}"),
-                        "trace": trace
-                    });
-                    return Err(err.to_string());
-                }
-                _ => {
-                    error!(
-                        error = %err,
-                        content.key = %content_key,
-                        "Error looking up content",
-                    );
-                    return Err(err.to_string());
-                }
-            },
-        },
+            }
+        }
     };
 
     // Format as string.
@@ -174,6 +596,375 @@ async fn get_content(
     }
 }
 
+/// Constructs a JSON call for the FindContentRange method.
+///
+/// The content of each block is looked up like by the GetContent method, at most
+/// `FIND_CONTENT_RANGE_CONCURRENCY` blocks at a time. Blocks whose content isn't found don't fail
+/// the request, so that partial results are returned.
+async fn find_content_range(
+    network: Arc<HistoryNetwork>,
+    content_type: HistoryContentType,
+    start_block: u64,
+    end_block: u64,
+) -> Result<Value, String> {
+    // Content types that aren't keyed by block number are refused before any lookup.
+    HistoryContentKey::from_block_id(content_type, HistoryBlockId::Number(start_block))
+        .map_err(|err| format!("FindContentRange failed: {err}"))?;
+    let content = stream::iter(start_block..=end_block)
+        .map(|block_number| {
+            let network = Arc::clone(&network);
+            async move {
+                let content_key = HistoryContentKey::from_block_id(
+                    content_type,
+                    HistoryBlockId::Number(block_number),
+                )
+                .ok()?;
+                find_block_content(&network, content_key).await
+            }
+            .map(move |content| (block_number, content))
+        })
+        .buffer_unordered(FIND_CONTENT_RANGE_CONCURRENCY)
+        .collect()
+        .await;
+    Ok(json!(FindContentRangeInfo { content }))
+}
+
+/// Returns the content of `content_key` from local storage, or from the network if it isn't
+/// stored locally, if it's found.
+async fn find_block_content(
+    network: &HistoryNetwork,
+    content_key: HistoryContentKey,
+) -> Option<RawContentValue> {
+    if let Ok(Some(content)) = network.overlay.store.read().get(&content_key) {
+        return Some(content);
+    }
+    match network
+        .overlay
+        .lookup_content(content_key.clone(), FindContentConfig::default())
+        .await
+    {
+        Ok(Ok((content, _, _))) => Some(content),
+        Ok(Err(err)) | Err(err) => {
+            debug!(
+                error = %err,
+                content.key = %content_key,
+                "Content of block range not found",
+            );
+            None
+        }
+    }
+}
+
+/// Constructs a JSON call for the SampleAvailability method.
+///
+/// Up to `sample_size` distinct blocks are drawn at random from the range, and the peers closest
+/// to the content of each are asked for it, until one returns it. Peers returned instead of the
+/// content aren't contacted, so that each probe stays cheap.
+async fn sample_availability(
+    network: Arc<HistoryNetwork>,
+    content_type: HistoryContentType,
+    start_block: u64,
+    end_block: u64,
+    sample_size: u64,
+) -> Result<Value, String> {
+    // Content types that aren't keyed by block number are refused before any probe.
+    HistoryContentKey::from_block_id(content_type, HistoryBlockId::Number(start_block))
+        .map_err(|err| format!("SampleAvailability failed: {err}"))?;
+    let block_count = (end_block - start_block).saturating_add(1);
+    let block_numbers: BTreeSet<u64> = if block_count <= sample_size {
+        (start_block..=end_block).collect()
+    } else {
+        let mut rng = rand::thread_rng();
+        let mut block_numbers = BTreeSet::new();
+        while (block_numbers.len() as u64) < sample_size {
+            block_numbers.insert(rng.gen_range(start_block..=end_block));
+        }
+        block_numbers
+    };
+    let mut samples: Vec<AvailabilitySample> = stream::iter(block_numbers)
+        .filter_map(|block_number| {
+            let content_key = HistoryContentKey::from_block_id(
+                content_type,
+                HistoryBlockId::Number(block_number),
+            )
+            .ok();
+            async move { content_key.map(|content_key| (block_number, content_key)) }
+        })
+        .map(|(block_number, content_key)| {
+            let network = Arc::clone(&network);
+            async move { probe_availability(&network, block_number, content_key).await }
+        })
+        .buffer_unordered(SAMPLE_AVAILABILITY_CONCURRENCY)
+        .collect()
+        .await;
+    samples.sort_by_key(|sample| sample.block_number);
+
+    let sampled = samples.len() as u32;
+    let found = samples.iter().filter(|sample| sample.found).count() as u32;
+    Ok(json!(SampleAvailabilityInfo {
+        sampled,
+        found,
+        availability_percent: (sampled > 0).then(|| 100.0 * found as f64 / sampled as f64),
+        samples,
+    }))
+}
+
+/// Asks the peers closest to the content for it, one at a time, until one returns it.
+async fn probe_availability(
+    network: &HistoryNetwork,
+    block_number: u64,
+    content_key: HistoryContentKey,
+) -> AvailabilitySample {
+    let peers = network
+        .overlay
+        .closest_peers_to_content(&content_key.content_id(), SAMPLE_AVAILABILITY_PEERS);
+    let mut peers_probed = 0;
+    let mut found = false;
+    for enr in peers {
+        peers_probed += 1;
+        match network
+            .overlay
+            .send_find_content(enr, content_key.to_bytes())
+            .await
+        {
+            Ok((Content::Content(_), _)) => {
+                found = true;
+                break;
+            }
+            Ok(_) => {}
+            Err(err) => debug!(
+                %err,
+                content.key = %content_key,
+                "FindContent request failed while sampling availability"
+            ),
+        }
+    }
+    AvailabilitySample {
+        block_number,
+        content_key: content_key.to_bytes(),
+        found,
+        peers_probed,
+    }
+}
+
+/// Constructs a JSON call for the StreamTraceGetContent method.
+///
+/// An event is sent on `resp` for each peer contacted during the network lookup, as it responds.
+/// The returned value is the final event, carrying the content or the reason it wasn't found.
+/// Unlike other lookups, streamed lookups are never shared between requests.
+async fn stream_trace_get_content(
+    network: Arc<HistoryNetwork>,
+    content_key: HistoryContentKey,
+    hint_peer: Option<Enr>,
+    resp: mpsc::UnboundedSender<Result<Value, String>>,
+) -> Result<Value, String> {
+    let (hop_tx, mut hop_rx) = mpsc::unbounded_channel();
+    let forward_hops = tokio::spawn(async move {
+        while let Some(hop) = hop_rx.recv().await {
+            let _ = resp.send(Ok(json!(TraceGetContentEvent::Hop(hop))));
+        }
+    });
+
+    let response = get_content(network, content_key, hint_peer, true, Some(hop_tx)).await;
+    // The query drops its sender once it's done, so all of its events are sent before the final
+    // one.
+    let _ = forward_hops.await;
+
+    let event = match response {
+        Ok(content_info) => TraceGetContentEvent::Found(
+            serde_json::from_value(content_info).map_err(|err| err.to_string())?,
+        ),
+        Err(error) => TraceGetContentEvent::Failed { error },
+    };
+    Ok(json!(event))
+}
+
+/// Constructs a JSON call for the FindAndStore method.
+///
+/// Content that is found by the lookup is usually stored by the overlay already, once it's
+/// validated, in which case it's reported as stored.
+async fn find_and_store(
+    network: Arc<HistoryNetwork>,
+    content_key: HistoryContentKey,
+) -> Result<Value, String> {
+    if !network.enable_find_and_store {
+        return Err("FindAndStore is disabled, enable it with --enable-find-and-store".to_owned());
+    }
+    if network.overlay.is_content_denied(&content_key.content_id()) {
+        return Err(format!(
+            "Content key is on the denylist: {}",
+            content_key.to_hex()
+        ));
+    }
+    if network.overlay.is_store_degraded() {
+        return Err("Content store is unavailable".to_owned());
+    }
+    match network.overlay.store.read().get(&content_key) {
+        Ok(Some(content)) => {
+            return Ok(json!(FindAndStoreInfo {
+                content,
+                utp_transfer: false,
+                outcome: FindAndStoreOutcome::AlreadyStored,
+            }))
+        }
+        Ok(None) => {}
+        Err(err) => {
+            return Err(format!(
+                "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+            ))
+        }
+    }
+
+    let (content, utp_transfer, _) = network
+        .overlay
+        .lookup_content(content_key.clone(), FindContentConfig::default())
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())?;
+
+    let should_store = network
+        .overlay
+        .store
+        .read()
+        .is_key_within_radius_and_unavailable(&content_key)
+        .map_err(|err| format!("Unable to read store: {err}"))?;
+    let outcome = match should_store {
+        ShouldWeStoreContent::Store => {
+            network
+                .overlay
+                .put_content(content_key, content.clone())
+                .map_err(|err| format!("Error storing content: {err}"))?;
+            FindAndStoreOutcome::Stored
+        }
+        ShouldWeStoreContent::AlreadyStored => FindAndStoreOutcome::Stored,
+        ShouldWeStoreContent::NotWithinRadius => FindAndStoreOutcome::OutOfRadius,
+    };
+    Ok(json!(FindAndStoreInfo {
+        content,
+        utp_transfer,
+        outcome,
+    }))
+}
+
+/// The outcome of backfilling a single content item from a peer.
+enum BackfillOutcome {
+    /// The content was fetched and stored, with its size in bytes.
+    Stored(u64),
+    AlreadyStored,
+    OutOfRadius,
+    Failed,
+}
+
+/// Constructs a JSON call for the BackfillFromPeer method.
+///
+/// At most `concurrency` find content transfers run at a time, so that a backfill doesn't starve
+/// the serving of other requests or overwhelm the peer.
+async fn backfill_from_peer(
+    network: Arc<HistoryNetwork>,
+    enr: Enr,
+    content_keys: Vec<HistoryContentKey>,
+    concurrency: usize,
+) -> Result<Value, String> {
+    if network.overlay.is_store_degraded() {
+        return Err("Content store is unavailable".to_owned());
+    }
+    let started = Instant::now();
+    let outcomes: Vec<BackfillOutcome> = stream::iter(content_keys)
+        .map(|content_key| backfill_content(&network, enr.clone(), content_key))
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    let duration = started.elapsed();
+
+    let mut info = BackfillInfo {
+        concurrency,
+        stored: 0,
+        already_stored: 0,
+        out_of_radius: 0,
+        failed: 0,
+        bytes: 0,
+        duration_ms: duration.as_millis() as u64,
+        items_per_sec: 0.0,
+        bytes_per_sec: 0.0,
+    };
+    for outcome in outcomes {
+        match outcome {
+            BackfillOutcome::Stored(bytes) => {
+                info.stored += 1;
+                info.bytes += bytes;
+            }
+            BackfillOutcome::AlreadyStored => info.already_stored += 1,
+            BackfillOutcome::OutOfRadius => info.out_of_radius += 1,
+            BackfillOutcome::Failed => info.failed += 1,
+        }
+    }
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        info.items_per_sec = info.stored as f64 / secs;
+        info.bytes_per_sec = info.bytes as f64 / secs;
+    }
+    Ok(json!(info))
+}
+
+/// Fetches the content of `content_key` from the peer, and stores it once it's validated.
+///
+/// Content that is already stored or isn't within the radius of the node isn't fetched.
+async fn backfill_content(
+    network: &HistoryNetwork,
+    enr: Enr,
+    content_key: HistoryContentKey,
+) -> BackfillOutcome {
+    if network.overlay.is_content_denied(&content_key.content_id()) {
+        return BackfillOutcome::Failed;
+    }
+    match network
+        .overlay
+        .store
+        .read()
+        .is_key_within_radius_and_unavailable(&content_key)
+    {
+        Ok(ShouldWeStoreContent::Store) => {}
+        Ok(ShouldWeStoreContent::AlreadyStored) => return BackfillOutcome::AlreadyStored,
+        Ok(ShouldWeStoreContent::NotWithinRadius) => return BackfillOutcome::OutOfRadius,
+        Err(err) => {
+            error!(%err, content.key = %content_key, "Unable to read store during backfill");
+            return BackfillOutcome::Failed;
+        }
+    }
+
+    let content = match network
+        .overlay
+        .send_find_content(enr, content_key.to_bytes())
+        .await
+    {
+        Ok((Content::Content(content), _)) => content,
+        Ok(_) => return BackfillOutcome::Failed,
+        Err(err) => {
+            warn!(%err, content.key = %content_key, "FindContent request failed during backfill");
+            return BackfillOutcome::Failed;
+        }
+    };
+    match network
+        .validator
+        .validate_content(&content_key, &content)
+        .await
+    {
+        Ok(result) if result.valid_for_storing => {}
+        Ok(_) => return BackfillOutcome::Failed,
+        Err(err) => {
+            warn!(%err, content.key = %content_key, "Backfilled content failed validation");
+            return BackfillOutcome::Failed;
+        }
+    }
+
+    let bytes = content.len() as u64;
+    if let Err(err) = network.overlay.put_content(content_key.clone(), content) {
+        error!(%err, content.key = %content_key, "Error storing backfilled content");
+        return BackfillOutcome::Failed;
+    }
+    BackfillOutcome::Stored(bytes)
+}
+
 /// Constructs a JSON call for the LocalContent method.
 async fn local_content(
     network: Arc<HistoryNetwork>,
@@ -192,6 +983,7 @@ async fn local_content(
                     return Err(err.to_string());
                 }
             },
+            Err(err @ ContentStoreError::Corrupted { .. }) => Err(err.to_string()),
             Err(err) => Err(format!(
                 "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
             )),
@@ -199,6 +991,44 @@ async fn local_content(
     response
 }
 
+/// Constructs a JSON call for the RawStoredBytes method.
+fn raw_stored_bytes(
+    network: Arc<HistoryNetwork>,
+    content_key: HistoryContentKey,
+) -> Result<Value, String> {
+    match network.overlay.store.read().raw_stored_bytes(&content_key) {
+        Ok(Some(stored_bytes)) => Ok(json!(stored_bytes)),
+        Ok(None) => Err(json!({
+            "message": "Content not found in local storage",
+        })
+        .to_string()),
+        Err(err) => Err(format!(
+            "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+        )),
+    }
+}
+
+/// Constructs a JSON call for the LocalLookup method.
+///
+/// Unlike GetContent, a miss returns null right away instead of falling through to a network
+/// lookup. Unlike LocalContent, a miss isn't an error.
+fn local_lookup(
+    network: Arc<HistoryNetwork>,
+    content_key: HistoryContentKey,
+) -> Result<Value, String> {
+    match network.overlay.store.read().get(&content_key) {
+        Ok(Some(content)) => Ok(json!(GetContentInfo {
+            content,
+            utp_transfer: false,
+        })),
+        Ok(None) => Ok(Value::Null),
+        Err(err @ ContentStoreError::Corrupted { .. }) => Err(err.to_string()),
+        Err(err) => Err(format!(
+            "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+        )),
+    }
+}
+
 /// Constructs a JSON call for the PaginateLocalContentKeys method.
 async fn paginate_local_content_keys(
     network: Arc<HistoryNetwork>,
@@ -215,19 +1045,306 @@ async fn paginate_local_content_keys(
     response
 }
 
+/// Constructs a JSON call for the ContentRange method.
+async fn content_range(network: Arc<HistoryNetwork>) -> Result<Value, String> {
+    match network.overlay.store.read().content_range() {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(format!(
+            "Database error while looking up range of local content. Error message: {err}"
+        )),
+    }
+}
+
+/// Constructs a JSON call for the ContentDistanceDistribution method.
+async fn content_distance_distribution(network: Arc<HistoryNetwork>) -> Result<Value, String> {
+    match network.overlay.store.read().distance_distribution() {
+        Ok(val) => Ok(json!(val)),
+        Err(err) => Err(format!(
+            "Database error while looking up distance distribution of local content. Error message: {err}"
+        )),
+    }
+}
+
+/// Constructs a JSON call for the ClosestContent method.
+async fn closest_content(
+    network: Arc<HistoryNetwork>,
+    node_id: NodeId,
+    count: u64,
+) -> Result<Value, String> {
+    match network.overlay.store.read().closest_content(&node_id, count) {
+        Ok(content_keys) => Ok(json!(content_keys)),
+        Err(err) => Err(format!(
+            "Database error while looking up local content closest to {node_id}. Error message: {err}"
+        )),
+    }
+}
+
+/// Constructs a JSON call for the SyncStore method.
+async fn sync_store(network: Arc<HistoryNetwork>) -> Result<Value, String> {
+    match network.overlay.store.read().sync() {
+        Ok(_) => Ok(json!(true)),
+        Err(err) => Err(format!("SyncStore failed: {err:?}")),
+    }
+}
+
+/// Constructs a JSON call for the ExportStore method.
+///
+/// The export is written on a blocking thread, and the store is only locked while each page of
+/// entries is read from it, so that it isn't locked for the whole export.
+async fn export_store(network: Arc<HistoryNetwork>, path: PathBuf) -> Result<Value, String> {
+    let store = Arc::clone(&network.overlay.store);
+    match tokio::task::spawn_blocking(move || write_store_export(&store, &path)).await {
+        Ok(Ok(export_info)) => Ok(json!(export_info)),
+        Ok(Err(err)) => Err(format!("ExportStore failed: {err}")),
+        Err(err) => Err(format!("ExportStore task failed: {err}")),
+    }
+}
+
+/// Writes all stored content to a new file at `path`, in content id order.
+///
+/// Each entry is written as a length-prefixed content key followed by a length-prefixed content
+/// value, with big-endian `u32` lengths, which is the format read by `--import-stdin`. No other
+/// data is written, so nodes that store the same content write identical files.
+fn write_store_export(
+    store: &RwLock<HistoryStorage>,
+    path: &Path,
+) -> anyhow::Result<ExportStoreInfo> {
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let mut writer = BufWriter::new(file);
+    let mut export_info = ExportStoreInfo {
+        entries: 0,
+        bytes: 0,
+    };
+    let mut after = None;
+    loop {
+        let page = store
+            .read()
+            .export_page(after.as_ref(), EXPORT_STORE_PAGE_SIZE)?;
+        let Some((last_content_id, _, _)) = page.last() else {
+            break;
+        };
+        after = Some(last_content_id.clone());
+        for (_, content_key, content_value) in page {
+            let content_key = content_key.to_bytes();
+            for item in [&content_key[..], &content_value[..]] {
+                writer.write_all(&u32::try_from(item.len())?.to_be_bytes())?;
+                writer.write_all(item)?;
+                export_info.bytes += 4 + item.len() as u64;
+            }
+            export_info.entries += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(export_info)
+}
+
+/// Constructs a JSON call for the EncodeContentKey method.
+fn encode_content_key(
+    content_type: HistoryContentType,
+    block_id: HistoryBlockId,
+) -> Result<Value, String> {
+    let content_key = HistoryContentKey::from_block_id(content_type, block_id)
+        .map_err(|err| format!("EncodeContentKey failed: {err}"))?;
+    Ok(json!(EncodedContentKeyInfo {
+        content_key: content_key.to_bytes(),
+        content_id: B256::from(content_key.content_id()),
+    }))
+}
+
+/// Constructs a JSON call for the ContentKeyBlockNumber method.
+fn content_key_block_number(content_key: HistoryContentKey) -> Result<Value, String> {
+    match content_key.block_number() {
+        Some(block_number) => Ok(json!(block_number)),
+        None => Err(format!(
+            "ContentKeyBlockNumber failed: {:?} content keys don't encode a block number",
+            content_key.content_type()
+        )),
+    }
+}
+
+/// Constructs a JSON call for the SimulateLookup method.
+fn simulate_lookup(
+    network: Arc<HistoryNetwork>,
+    enrs: Vec<Enr>,
+    content_id: B256,
+) -> Result<Value, String> {
+    if enrs.len() > MAX_SIMULATED_PEERS {
+        return Err(format!(
+            "SimulateLookup failed: at most {MAX_SIMULATED_PEERS} peers can be simulated, got {}",
+            enrs.len()
+        ));
+    }
+    let node_ids: Vec<NodeId> = enrs.iter().map(|enr| enr.node_id()).collect();
+    let hops = network.overlay.simulate_lookup(&node_ids, &content_id.0);
+    Ok(json!(hops))
+}
+
+/// Constructs a JSON call for the ListInFlight method.
+fn list_in_flight(in_flight_requests: InFlightRequests) -> Value {
+    let mut requests: Vec<InFlightRequestInfo> = in_flight_requests
+        .lock()
+        .iter()
+        .map(|(id, request)| InFlightRequestInfo {
+            id: *id,
+            endpoint: request.endpoint.clone(),
+            elapsed_ms: u64::try_from(request.started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        })
+        .collect();
+    requests.sort_by_key(|request| request.id);
+    json!(requests)
+}
+
+/// Constructs a JSON call for the CancelRequest method.
+///
+/// Aborting the task drops the futures of the request, which closes its uTP streams and abandons
+/// any network lookup it started.
+fn cancel_request(in_flight_requests: InFlightRequests, request_id: u64) -> Value {
+    match in_flight_requests.lock().remove(&request_id) {
+        Some(request) => {
+            request.abort_handle.abort();
+            json!(true)
+        }
+        None => json!(false),
+    }
+}
+
+/// Constructs a JSON call for the ReplayTrace method.
+///
+/// The content is looked up on the network even if it's stored locally, so that both lookups
+/// contact peers.
+async fn replay_trace(
+    network: Arc<HistoryNetwork>,
+    recorded_trace: RecordedQueryTrace,
+) -> Result<Value, String> {
+    let content_key = HistoryContentKey::try_from_hex(&recorded_trace.content_key)
+        .map_err(|err| format!("Invalid content key of the recorded trace: {err}"))?;
+    if B256::from(content_key.content_id()) != recorded_trace.trace.target_id {
+        return Err("The content key of the recorded trace doesn't match its target".to_owned());
+    }
+    let config = FindContentConfig {
+        is_trace: true,
+        ..Default::default()
+    };
+    let trace = match network
+        .overlay
+        .lookup_content(content_key, config)
+        .await
+        .map_err(|err| err.to_string())?
+    {
+        Ok((_, _, trace)) => trace,
+        Err(OverlayRequestError::ContentNotFound { trace, .. }) => trace,
+        Err(err) => return Err(err.to_string()),
+    };
+    let trace = trace.ok_or("Content query trace requested but none provided.")?;
+    Ok(json!(ReplayTraceInfo {
+        found: trace.received_from.is_some(),
+        diff: recorded_trace.trace.diff(&trace),
+        trace,
+    }))
+}
+
+/// Constructs a JSON call for the GetTrace method.
+async fn get_trace(network: Arc<HistoryNetwork>, query_trace_id: u64) -> Result<Value, String> {
+    match network.overlay.query_trace(query_trace_id) {
+        Some(trace) => Ok(json!(trace)),
+        None => Err(format!(
+            "Query trace {query_trace_id} not found, it may have been dropped from the trace buffer"
+        )),
+    }
+}
+
+/// Constructs a JSON call for the StorageInfo method.
+async fn storage_info(network: Arc<HistoryNetwork>) -> Result<Value, String> {
+    match network.overlay.store.read().storage_info() {
+        Ok(storage_info) => Ok(json!(storage_info)),
+        Err(err) => Err(format!("StorageInfo failed: {err:?}")),
+    }
+}
+
+/// Constructs a JSON call for the BenchmarkStore method.
+async fn benchmark_store(
+    network: Arc<HistoryNetwork>,
+    entry_count: u64,
+    value_size: u64,
+) -> Result<Value, String> {
+    // The store is only locked to set up the benchmark, which blocks on disk io for a while, so
+    // it's kept off the async workers.
+    let benchmark = network.overlay.store.read().benchmark();
+    match tokio::task::spawn_blocking(move || benchmark.run(entry_count, value_size)).await {
+        Ok(Ok(benchmark_info)) => Ok(json!(benchmark_info)),
+        Ok(Err(err)) => Err(format!("BenchmarkStore failed: {err}")),
+        Err(err) => Err(format!("BenchmarkStore task failed: {err}")),
+    }
+}
+
+/// Constructs a JSON call for the ExplainRejection method.
+fn explain_rejection(
+    network: Arc<HistoryNetwork>,
+    content_key: HistoryContentKey,
+) -> Result<Value, String> {
+    let content_id = content_key.content_id();
+    let mut reasons = vec![];
+    if network.overlay.is_store_degraded() {
+        reasons.push(RejectionReason::StoreUnavailable);
+    }
+    if network.overlay.is_content_denied(&content_id) {
+        reasons.push(RejectionReason::Denied);
+    }
+    let store = network.overlay.store.read();
+    match store
+        .is_key_within_radius_and_unavailable(&content_key)
+        .map_err(|err| format!("ExplainRejection failed: {err}"))?
+    {
+        ShouldWeStoreContent::Store => {}
+        ShouldWeStoreContent::NotWithinRadius => reasons.push(RejectionReason::NotWithinRadius),
+        ShouldWeStoreContent::AlreadyStored => reasons.push(RejectionReason::AlreadyStored),
+    }
+    if store
+        .is_capacity_exhausted(&content_key)
+        .map_err(|err| format!("ExplainRejection failed: {err}"))?
+    {
+        reasons.push(RejectionReason::CapacityExhausted);
+    }
+    Ok(json!(RejectionExplanationInfo {
+        content_id: B256::from(content_id),
+        distance: *store.distance_to_key(&content_key),
+        radius: *store.radius(),
+        accepted: reasons.is_empty(),
+        reasons,
+    }))
+}
+
 /// Constructs a JSON call for the Store method.
 async fn store(
     network: Arc<HistoryNetwork>,
     content_key: HistoryContentKey,
     content_value: ethportal_api::HistoryContentValue,
 ) -> Result<Value, String> {
+    if network.overlay.is_content_denied(&content_key.content_id()) {
+        return Err(format!(
+            "Content key is on the denylist: {}",
+            content_key.to_hex()
+        ));
+    }
+    if network.overlay.is_store_degraded() {
+        return Err("Content store is unavailable".to_string());
+    }
     let data = content_value.encode().to_vec();
-    let response = match network
-        .overlay
-        .store
-        .write()
-        .put::<Vec<u8>>(content_key, data)
+    if network.validate_stored_headers
+        && matches!(
+            content_key,
+            HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_)
+        )
     {
+        if let Err(err) = network
+            .validator
+            .validate_content(&content_key, &data)
+            .await
+        {
+            return Err(format!("Content validation failed: {err}"));
+        }
+    }
+    let response = match network.overlay.put_content(content_key, data.into()) {
         Ok(_) => Ok(Value::Bool(true)),
         Err(err) => Ok(Value::String(err.to_string())),
     };
@@ -259,6 +1376,29 @@ async fn delete_enr(network: Arc<HistoryNetwork>, node_id: NodeId) -> Result<Val
     Ok(json!(is_deleted))
 }
 
+/// Constructs a JSON call for the ReloadDenylist method.
+async fn reload_denylist(network: Arc<HistoryNetwork>) -> Result<Value, String> {
+    match network.overlay.reload_denylist() {
+        Ok(_) => Ok(json!(true)),
+        Err(err) => Err(format!("ReloadDenylist failed: {err:?}")),
+    }
+}
+
+/// Constructs a JSON call for the RemoveEnr method.
+async fn remove_enr(
+    network: Arc<HistoryNetwork>,
+    node_id: NodeId,
+    deny_ttl_secs: Option<u64>,
+) -> Result<Value, String> {
+    match network
+        .overlay
+        .remove_enr(node_id, deny_ttl_secs.map(Duration::from_secs))
+    {
+        Ok(is_removed) => Ok(json!(is_removed)),
+        Err(err) => Err(format!("RemoveEnr failed: {err}")),
+    }
+}
+
 /// Constructs a JSON call for the LookupEnr method.
 async fn lookup_enr(network: Arc<HistoryNetwork>, node_id: NodeId) -> Result<Value, String> {
     match network.overlay.lookup_enr(node_id).await {
@@ -297,11 +1437,7 @@ async fn find_nodes(
     distances: Vec<u16>,
 ) -> Result<Value, String> {
     match network.overlay.send_find_nodes(enr, distances).await {
-        Ok(nodes) => Ok(json!(nodes
-            .enrs
-            .into_iter()
-            .map(|enr| enr.into())
-            .collect::<FindNodesInfo>())),
+        Ok(nodes) => Ok(json!(nodes.enrs)),
         Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
     }
 }
@@ -319,6 +1455,20 @@ async fn gossip(
         .into())
 }
 
+/// Constructs a JSON call for the GossipBatch method.
+async fn gossip_batch(
+    network: Arc<HistoryNetwork>,
+    content_items: Vec<(HistoryContentKey, ethportal_api::HistoryContentValue)>,
+) -> Result<Value, String> {
+    let content_items = content_items
+        .into_iter()
+        .map(|(content_key, content_value)| (content_key, content_value.encode()))
+        .collect();
+    Ok(json!(
+        network.overlay.propagate_gossip_batch(content_items).await
+    ))
+}
+
 /// Constructs a JSON call for the Gossip method, with tracing enabled.
 async fn trace_gossip(
     network: Arc<HistoryNetwork>,
@@ -369,6 +1519,141 @@ async fn trace_offer(
     }
 }
 
+/// Constructs a JSON call for the OfferWithConfirmation method.
+async fn offer_with_confirmation(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_key: HistoryContentKey,
+    content_value: HistoryContentValue,
+) -> Result<Value, String> {
+    match network
+        .overlay
+        .send_offer_with_confirmation(enr, content_key.to_bytes(), content_value.encode())
+        .await
+    {
+        Ok((trace, confirmed)) => Ok(json!(ConfirmedOfferInfo { trace, confirmed })),
+        Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
+    }
+}
+
+/// Constructs a JSON call for the OfferWithThroughput method.
+async fn offer_with_throughput(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_items: Vec<(HistoryContentKey, HistoryContentValue)>,
+) -> Result<Value, String> {
+    let content_items: Vec<_> = content_items
+        .into_iter()
+        .map(|(key, value)| (key.to_bytes(), value.encode()))
+        .collect();
+    let item_sizes: Vec<usize> = content_items
+        .iter()
+        .map(|(_key, value)| value.len())
+        .collect();
+    let (accept, transfer) = network
+        .overlay
+        .send_offer_with_transfer(enr, content_items)
+        .await
+        .map_err(|msg| format!("Offer request timeout: {msg:?}"))?;
+    let items = accept
+        .content_keys
+        .iter()
+        .zip(item_sizes)
+        .map(|(accepted, bytes)| OfferedItemInfo { accepted, bytes })
+        .collect();
+    Ok(json!(OfferThroughputInfo {
+        content_keys: accept.content_keys,
+        transfer: transfer.map(|transfer| OfferTransferInfo {
+            success: transfer.success,
+            bytes: transfer.bytes,
+            duration_ms: transfer.duration.as_millis() as u64,
+            throughput_mb_per_sec: transfer.throughput_mb_per_sec(),
+        }),
+        items,
+    }))
+}
+
+/// Constructs a JSON call for the SetRadius method.
+///
+/// Stored content outside the new radius is evicted by a background task, which gossips it first
+/// if dropped content is gossiped, the same as content dropped by pruning.
+fn set_radius(network: Arc<HistoryNetwork>, radius: Option<DataRadius>) -> Result<Value, String> {
+    let (content_outside_radius, radius) = {
+        let mut store = network.overlay.store.write();
+        let content_outside_radius = store
+            .override_radius(radius.map(Distance::from))
+            .map_err(|err| format!("Unable to set radius: {err}"))?;
+        (content_outside_radius, *store.radius())
+    };
+    if content_outside_radius {
+        warn!(
+            %radius,
+            "Radius set below the distance of stored content, which is evicted unless stored content is kept"
+        );
+        tokio::spawn(evict_outside_radius(Arc::clone(&network)));
+    }
+    let enr_seq = network
+        .overlay
+        .discovery
+        .bump_enr_seq()
+        .map_err(|err| err.to_string())?;
+    network.overlay.ping_connected_peers();
+    Ok(json!(SetRadiusInfo {
+        radius,
+        enr_seq,
+        content_outside_radius,
+    }))
+}
+
+/// Evicts the stored content outside the radius in batches, until none is left. The evicted content
+/// is gossiped if dropped content is gossiped.
+async fn evict_outside_radius(network: Arc<HistoryNetwork>) {
+    let mut evicted_count = 0;
+    loop {
+        let store = Arc::clone(&network.overlay.store);
+        let evicted = match tokio::task::spawn_blocking(move || {
+            store
+                .write()
+                .evict_outside_radius(EVICT_OUTSIDE_RADIUS_BATCH_SIZE)
+        })
+        .await
+        {
+            Ok(Ok(evicted)) => evicted,
+            Ok(Err(err)) => {
+                warn!(%err, "Failed to evict content outside the radius");
+                break;
+            }
+            Err(err) => {
+                warn!(%err, "Eviction task of content outside the radius failed");
+                break;
+            }
+        };
+        if evicted.is_empty() {
+            break;
+        }
+        evicted_count += evicted.len();
+        if network.overlay.gossips_dropped() {
+            network.overlay.propagate_gossip_batch(evicted).await;
+        }
+    }
+    debug!(evicted_count, "Evicted content outside the radius");
+}
+
+/// Constructs a JSON call for the RawOffer method.
+async fn raw_offer(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+    content_items: Vec<(RawContentKey, RawContentValue)>,
+) -> Result<Value, String> {
+    if !network.enable_raw_offer {
+        return Err("RawOffer is disabled, enable it with --enable-raw-offer".to_owned());
+    }
+    match network.overlay.send_offer(enr, content_items).await {
+        Ok(accept) => Ok(json!(accept)),
+        Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
+    }
+}
+
 /// Constructs a JSON call for the Ping method.
 async fn ping(
     network: Arc<HistoryNetwork>,
@@ -383,11 +1668,228 @@ async fn ping(
     }
 }
 
+/// Constructs a JSON call for the DebugPing method.
+async fn debug_ping(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+) -> Result<Value, String> {
+    if !network.enable_debug_ping {
+        return Err("DebugPing is disabled, enable it with --enable-debug-ping".to_owned());
+    }
+    match network.overlay.send_debug_ping(enr).await {
+        Ok(info) => Ok(json!(info)),
+        Err(msg) => Err(format!("DebugPing request failed: {msg:?}")),
+    }
+}
+
+/// Constructs a JSON call for the TestInboundReachability method.
+///
+/// The wire protocol has no way to ask a peer to connect back, so the local node is considered
+/// reachable if the peer observed it at the address advertised in its ENR. Peers then reach the
+/// local node at that address, unless a NAT only maps it for the pinged peer.
+async fn test_inbound_reachability(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+) -> Result<Value, String> {
+    let discovery = &network.overlay.discovery;
+    let advertised_address = discovery.advertised_address();
+    let (observed_address, error) = match discovery.send_ping(enr).await {
+        Ok(observed_address) => (Some(observed_address), None),
+        Err(err) => (None, Some(format!("{err:?}"))),
+    };
+    Ok(json!(InboundReachabilityInfo {
+        advertised_address,
+        observed_address,
+        reachable: observed_address.is_some() && observed_address == advertised_address,
+        error,
+    }))
+}
+
+/// Constructs a JSON call for the AuditPeerRadius method.
+///
+/// The peer is probed for up to `AUDIT_PEER_RADIUS_SAMPLE_SIZE` locally stored content items that
+/// fall within its advertised radius.
+async fn audit_peer_radius(
+    network: Arc<HistoryNetwork>,
+    enr: discv5::enr::Enr<discv5::enr::CombinedKey>,
+) -> Result<Value, String> {
+    let pong = match network.overlay.send_ping(enr.clone()).await {
+        Ok(pong) => pong,
+        Err(msg) => return Err(format!("Ping request timeout: {msg:?}")),
+    };
+    let data_radius = Distance::from(pong.custom_payload);
+
+    let samples = network
+        .overlay
+        .store
+        .read()
+        .content_within_radius_of(&enr.node_id(), data_radius, AUDIT_PEER_RADIUS_SAMPLE_SIZE)
+        .map_err(|err| {
+            format!("Database error while sampling content within peer radius: {err}")
+        })?;
+
+    let mut held = 0;
+    for (content_key, _) in &samples {
+        match network
+            .overlay
+            .send_find_content(enr.clone(), content_key.to_bytes())
+            .await
+        {
+            Ok((Content::Content(_), _)) => held += 1,
+            Ok(_) => {}
+            Err(err) => error!(
+                %err,
+                content.key = %content_key,
+                "FindContent request failed while auditing peer radius"
+            ),
+        }
+    }
+
+    let sampled = samples.len() as u32;
+    Ok(json!(AuditPeerRadiusInfo {
+        data_radius: *data_radius,
+        sampled,
+        held,
+        hit_rate: (sampled > 0).then(|| held as f64 / sampled as f64),
+    }))
+}
+
 /// Constructs a JSON call for the RecursiveFindNodes method.
 async fn recursive_find_nodes(
     network: Arc<HistoryNetwork>,
     node_id: NodeId,
+    limit: usize,
 ) -> Result<Value, String> {
-    let nodes = network.overlay.lookup_node(node_id).await;
+    let nodes = network.overlay.lookup_nodes(node_id, limit).await;
     Ok(json!(nodes))
 }
+
+/// Constructs a JSON call for the NetworkCrawl method.
+async fn network_crawl(
+    network: Arc<HistoryNetwork>,
+    max_peers: usize,
+    timeout_secs: u64,
+) -> Result<Value, String> {
+    let crawl_info = network
+        .overlay
+        .crawl(max_peers, Duration::from_secs(timeout_secs))
+        .await;
+    Ok(json!(crawl_info))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use alloy::primitives::U256;
+    use tokio::task::yield_now;
+
+    use super::*;
+
+    /// Yields until the in-flight lookup of `key` has `waiters` waiters.
+    async fn wait_for_waiters(
+        in_flight_lookups: &InFlightLookups,
+        key: &(HistoryContentKey, bool),
+        waiters: usize,
+    ) {
+        while in_flight_lookups.lock().get(key).map(Vec::len) != Some(waiters) {
+            yield_now().await;
+        }
+    }
+
+    #[test]
+    fn node_distance_is_xor_distance_and_its_log2() {
+        let node_id = |first_byte: u8, last_byte: u8| {
+            let mut raw = [0; 32];
+            raw[0] = first_byte;
+            raw[31] = last_byte;
+            NodeId::new(&raw)
+        };
+        let info = |node_id_a, node_id_b| -> NodeDistanceInfo {
+            serde_json::from_value(node_distance(node_id_a, node_id_b)).unwrap()
+        };
+
+        let equal = info(node_id(0, 1), node_id(0, 1));
+        assert_eq!(equal.distance, U256::ZERO);
+        assert_eq!(equal.log2_distance, None);
+
+        let closest = info(node_id(0, 1), node_id(0, 0));
+        assert_eq!(closest.distance, U256::from(1));
+        assert_eq!(closest.log2_distance, Some(1));
+
+        // The distance is symmetric.
+        let farthest = info(node_id(0x80, 0), node_id(0, 0));
+        assert_eq!(farthest, info(node_id(0, 0), node_id(0x80, 0)));
+        assert_eq!(farthest.distance, U256::from(1) << 255);
+        assert_eq!(farthest.log2_distance, Some(256));
+    }
+
+    #[tokio::test]
+    async fn concurrent_lookups_share_one_lookup_and_its_result() {
+        let in_flight_lookups = InFlightLookups::default();
+        let key = (HistoryContentKey::new_block_body([1; 32]), false);
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+
+        let lookup = |release: Option<oneshot::Receiver<()>>| {
+            let lookups = lookups.clone();
+            async move {
+                lookups.fetch_add(1, Ordering::SeqCst);
+                if let Some(release) = release {
+                    release.await.unwrap();
+                }
+                Ok(json!("0x1234"))
+            }
+        };
+        let first = tokio::spawn(coalesce_lookup(
+            in_flight_lookups.clone(),
+            key.clone(),
+            lookup(Some(release_rx)),
+        ));
+        wait_for_waiters(&in_flight_lookups, &key, 0).await;
+        let second = tokio::spawn(coalesce_lookup(
+            in_flight_lookups.clone(),
+            key.clone(),
+            lookup(None),
+        ));
+        wait_for_waiters(&in_flight_lookups, &key, 1).await;
+        release_tx.send(()).unwrap();
+
+        assert_eq!(first.await.unwrap(), Ok(json!("0x1234")));
+        assert_eq!(second.await.unwrap(), Ok(json!("0x1234")));
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        assert!(in_flight_lookups.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn aborted_lookup_is_removed_and_fails_its_waiters() {
+        let in_flight_lookups = InFlightLookups::default();
+        let key = (HistoryContentKey::new_block_body([1; 32]), false);
+
+        let first = tokio::spawn(coalesce_lookup(
+            in_flight_lookups.clone(),
+            key.clone(),
+            std::future::pending(),
+        ));
+        wait_for_waiters(&in_flight_lookups, &key, 0).await;
+        let second = tokio::spawn(coalesce_lookup(
+            in_flight_lookups.clone(),
+            key.clone(),
+            std::future::pending(),
+        ));
+        wait_for_waiters(&in_flight_lookups, &key, 1).await;
+        first.abort();
+
+        assert!(first.await.unwrap_err().is_cancelled());
+        assert_eq!(
+            second.await.unwrap(),
+            Err("In-flight content lookup was aborted".to_owned())
+        );
+        assert!(in_flight_lookups.lock().is_empty());
+
+        // A later lookup of the same content isn't coalesced with the aborted one.
+        let third = coalesce_lookup(in_flight_lookups.clone(), key, async { Ok(json!("0x")) });
+        assert_eq!(third.await, Ok(json!("0x")));
+    }
+}
@@ -1,11 +1,14 @@
 #![warn(clippy::unwrap_used)]
 #![warn(clippy::uninlined_format_args)]
 
+pub mod el_crosscheck;
 pub mod events;
 mod jsonrpc;
+mod lookup_queue;
 pub mod network;
 mod storage;
 pub mod validation;
+pub mod validator_registry;
 
 use std::sync::Arc;
 
@@ -17,7 +20,7 @@ use portalnet::{
     events::{EventEnvelope, OverlayRequest},
 };
 use tokio::{
-    sync::{broadcast, mpsc, RwLock},
+    sync::{broadcast, mpsc, RwLock, Semaphore},
     task::JoinHandle,
     time::{interval, Duration},
 };
@@ -26,7 +29,7 @@ use trin_storage::PortalStorageConfig;
 use trin_validation::oracle::HeaderOracle;
 use utp_rs::socket::UtpSocket;
 
-use crate::{events::HistoryEvents, jsonrpc::HistoryRequestHandler};
+use crate::{events::HistoryEvents, jsonrpc::HistoryRequestHandler, lookup_queue::LookupQueue};
 
 type HistoryHandler = Option<HistoryRequestHandler>;
 type HistoryNetworkTask = Option<JoinHandle<()>>;
@@ -64,6 +67,14 @@ pub async fn initialize_history_network(
     let history_handler = HistoryRequestHandler {
         network: history_network.clone(),
         history_rx: history_jsonrpc_rx,
+        in_flight_lookups: Default::default(),
+        in_flight_requests: Default::default(),
+        last_request_id: 0,
+        lookup_slots: portalnet_config
+            .rpc_lookup_concurrency
+            .map(|concurrency| Arc::new(Semaphore::new(concurrency))),
+        lookup_queue: LookupQueue::new(portalnet_config.rpc_lookup_priorities.clone()),
+        slow_request_threshold: portalnet_config.slow_request_threshold,
     };
     let history_network_task =
         spawn_history_network(history_network.clone(), portalnet_config, history_event_rx);
@@ -0,0 +1,294 @@
+use std::{cmp::Ordering, collections::BTreeSet};
+
+use ethportal_api::types::{cli::LookupPriority, jsonrpc::request::HistoryJsonRpcRequest};
+
+/// The content lookups requested through the JSON-RPC API that wait for a free slot, when the
+/// number of concurrent lookups is limited.
+///
+/// The max number of content lookups that wait in the queue.
+pub const MAX_QUEUED_LOOKUPS: usize = 1024;
+
+/// The content lookups requested through the JSON-RPC API that wait for a free slot, when the
+/// number of concurrent lookups is limited.
+///
+/// Lookups are dequeued by priority, highest first, and in the order they were queued within the
+/// same priority. At most `MAX_QUEUED_LOOKUPS` lookups are queued, and lookups whose client is
+/// gone are dropped rather than executed.
+#[derive(Default)]
+pub struct LookupQueue {
+    priorities: Vec<LookupPriority>,
+    lookups: BTreeSet<QueuedLookup>,
+    /// The number of lookups queued so far, which orders lookups of the same priority.
+    queued_count: u64,
+}
+
+impl LookupQueue {
+    pub fn new(priorities: Vec<LookupPriority>) -> Self {
+        Self {
+            priorities,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookups.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lookups.len()
+    }
+
+    /// Returns the priority of the lookup requested by the `request`.
+    ///
+    /// A priority configured for the endpoint and the content type of the lookup takes precedence
+    /// over one configured for the endpoint only. Lookups without a configured priority have
+    /// priority 0.
+    pub fn priority(&self, request: &HistoryJsonRpcRequest) -> u32 {
+        let endpoint = request.endpoint.as_ref();
+        let content_type = request
+            .endpoint
+            .lookup_content_key()
+            .map(|content_key| content_key.content_type());
+        let configured = |content_type| {
+            self.priorities
+                .iter()
+                .find(|lookup_priority| {
+                    lookup_priority.endpoint == endpoint
+                        && lookup_priority.content_type == content_type
+                })
+                .map(|lookup_priority| lookup_priority.priority)
+        };
+        configured(content_type)
+            .or_else(|| configured(None))
+            .unwrap_or(0)
+    }
+
+    /// Queues the lookup requested by the `request`.
+    ///
+    /// If the queue is full, the lookup with the lowest priority that was queued last is rejected
+    /// and returned, which is the new lookup itself unless a queued lookup has a lower priority.
+    pub fn push(&mut self, request: HistoryJsonRpcRequest) -> Option<HistoryJsonRpcRequest> {
+        self.queued_count += 1;
+        let lookup = QueuedLookup {
+            priority: self.priority(&request),
+            sequence: self.queued_count,
+            request,
+        };
+        if self.lookups.len() >= MAX_QUEUED_LOOKUPS {
+            self.lookups
+                .retain(|lookup| !lookup.request.resp.is_closed());
+        }
+        if self.lookups.len() < MAX_QUEUED_LOOKUPS {
+            self.lookups.insert(lookup);
+            return None;
+        }
+        match self.lookups.first() {
+            Some(lowest) if *lowest < lookup => {
+                let rejected = self.lookups.pop_first().map(|lookup| lookup.request);
+                self.lookups.insert(lookup);
+                rejected
+            }
+            _ => Some(lookup.request),
+        }
+    }
+
+    /// Removes and returns the lookup with the highest priority that was queued first. Lookups
+    /// whose client is gone are dropped.
+    pub fn pop(&mut self) -> Option<HistoryJsonRpcRequest> {
+        while let Some(lookup) = self.lookups.pop_last() {
+            if !lookup.request.resp.is_closed() {
+                return Some(lookup.request);
+            }
+        }
+        None
+    }
+}
+
+struct QueuedLookup {
+    priority: u32,
+    sequence: u64,
+    request: HistoryJsonRpcRequest,
+}
+
+impl Ord for QueuedLookup {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Lookups are dequeued from the greatest, so lookups queued earlier must compare as
+        // greater.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for QueuedLookup {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedLookup {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedLookup {}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use alloy::primitives::{B256, U256};
+    use ethportal_api::{
+        types::{content_key::history::HistoryContentType, jsonrpc::endpoints::HistoryEndpoint},
+        HistoryContentKey,
+    };
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// Returns a lookup request, along with the receiver of its response, which keeps the
+    /// request open while it's held.
+    fn open_lookup(
+        endpoint: HistoryEndpoint,
+    ) -> (
+        HistoryJsonRpcRequest,
+        mpsc::UnboundedReceiver<Result<serde_json::Value, String>>,
+    ) {
+        let (resp, resp_rx) = mpsc::unbounded_channel();
+        (HistoryJsonRpcRequest { endpoint, resp }, resp_rx)
+    }
+
+    fn lookup(endpoint: HistoryEndpoint) -> HistoryJsonRpcRequest {
+        open_lookup(endpoint).0
+    }
+
+    fn lookup_priority(
+        endpoint: &str,
+        content_type: Option<HistoryContentType>,
+        priority: u32,
+    ) -> LookupPriority {
+        LookupPriority {
+            endpoint: endpoint.to_owned(),
+            content_type,
+            priority,
+        }
+    }
+
+    #[test]
+    fn most_specific_priority_applies() {
+        let queue = LookupQueue::new(vec![
+            lookup_priority("GetContent", None, 1),
+            lookup_priority(
+                "GetContent",
+                Some(HistoryContentType::BlockHeaderByHash),
+                10,
+            ),
+        ]);
+        let header_key = HistoryContentKey::new_block_header_by_hash([1; 32]);
+        let body_key = HistoryContentKey::new_block_body([1; 32]);
+
+        assert_eq!(
+            queue.priority(&lookup(HistoryEndpoint::GetContent(
+                header_key.clone(),
+                None
+            ))),
+            10
+        );
+        assert_eq!(
+            queue.priority(&lookup(HistoryEndpoint::GetContent(body_key, None))),
+            1
+        );
+        assert_eq!(
+            queue.priority(&lookup(HistoryEndpoint::TraceGetContent(header_key, None))),
+            0
+        );
+    }
+
+    #[test]
+    fn lookups_are_dequeued_by_priority_then_in_order() {
+        let mut queue = LookupQueue::new(vec![lookup_priority(
+            "GetContent",
+            Some(HistoryContentType::BlockHeaderByHash),
+            10,
+        )]);
+        let bodies = (0..2u8)
+            .map(|i| HistoryContentKey::new_block_body([i; 32]))
+            .collect::<Vec<_>>();
+        let header = HistoryContentKey::new_block_header_by_hash([0; 32]);
+
+        let mut resp_rxs = vec![];
+        for content_key in [bodies[0].clone(), bodies[1].clone(), header.clone()] {
+            let (request, resp_rx) = open_lookup(HistoryEndpoint::GetContent(content_key, None));
+            assert!(queue.push(request).is_none());
+            resp_rxs.push(resp_rx);
+        }
+        assert_eq!(queue.len(), 3);
+
+        for expected in [header, bodies[0].clone(), bodies[1].clone()] {
+            let request = queue.pop().unwrap();
+            assert_eq!(request.endpoint.lookup_content_key(), Some(&expected));
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn lookups_of_gone_clients_are_dropped() {
+        let mut queue = LookupQueue::default();
+        let gone = HistoryContentKey::new_block_body([0; 32]);
+        let open = HistoryContentKey::new_block_body([1; 32]);
+        assert!(queue
+            .push(lookup(HistoryEndpoint::GetContent(gone, None)))
+            .is_none());
+        let (request, _resp_rx) = open_lookup(HistoryEndpoint::GetContent(open.clone(), None));
+        assert!(queue.push(request).is_none());
+
+        assert_eq!(
+            queue.pop().unwrap().endpoint.lookup_content_key(),
+            Some(&open)
+        );
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn lowest_priority_lookup_is_rejected_once_full() {
+        let mut queue = LookupQueue::new(vec![lookup_priority(
+            "GetContent",
+            Some(HistoryContentType::BlockHeaderByHash),
+            10,
+        )]);
+        let body = |i: usize| {
+            HistoryEndpoint::GetContent(
+                HistoryContentKey::new_block_body(B256::from(U256::from(i))),
+                None,
+            )
+        };
+        let mut resp_rxs = vec![];
+        for i in 0..MAX_QUEUED_LOOKUPS {
+            let (request, resp_rx) = open_lookup(body(i));
+            assert!(queue.push(request).is_none());
+            resp_rxs.push(resp_rx);
+        }
+
+        // A lookup without a higher priority is rejected itself.
+        let (request, _resp_rx) = open_lookup(body(MAX_QUEUED_LOOKUPS));
+        let rejected = queue.push(request).unwrap();
+        assert_eq!(rejected.endpoint, body(MAX_QUEUED_LOOKUPS));
+
+        // A lookup with a higher priority replaces the last queued lookup of the lowest priority.
+        let header =
+            HistoryEndpoint::GetContent(HistoryContentKey::new_block_header_by_hash([0; 32]), None);
+        let (request, _resp_rx) = open_lookup(header.clone());
+        let rejected = queue.push(request).unwrap();
+        assert_eq!(rejected.endpoint, body(MAX_QUEUED_LOOKUPS - 1));
+        assert_eq!(queue.len(), MAX_QUEUED_LOOKUPS);
+
+        // Lookups of gone clients make room for new ones.
+        resp_rxs.truncate(1);
+        let (request, _resp_rx) = open_lookup(body(MAX_QUEUED_LOOKUPS + 1));
+        assert!(queue.push(request).is_none());
+        assert_eq!(queue.len(), 3);
+        for expected in [header, body(0), body(MAX_QUEUED_LOOKUPS + 1)] {
+            assert_eq!(queue.pop().unwrap().endpoint, expected);
+        }
+    }
+}
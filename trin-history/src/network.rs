@@ -9,13 +9,16 @@ use portalnet::{
     config::PortalnetConfig,
     discovery::{Discovery, UtpEnr},
     overlay::{config::OverlayConfig, protocol::OverlayProtocol},
+    types::readiness::ReadinessConfig,
 };
 use tokio::sync::RwLock;
 use trin_storage::PortalStorageConfig;
 use trin_validation::oracle::HeaderOracle;
 use utp_rs::socket::UtpSocket;
 
-use crate::{storage::HistoryStorage, validation::ChainHistoryValidator};
+use crate::{
+    el_crosscheck::ElCrosscheck, storage::HistoryStorage, validation::ChainHistoryValidator,
+};
 
 /// Gossip content as it gets dropped from local storage,
 /// enabled by default for the history network.
@@ -27,6 +30,24 @@ const GOSSIP_DROPPED: bool = true;
 pub struct HistoryNetwork {
     pub overlay:
         Arc<OverlayProtocol<HistoryContentKey, XorMetric, ChainHistoryValidator, HistoryStorage>>,
+    pub validator: Arc<ChainHistoryValidator>,
+    /// Whether headers stored through the JSON-RPC api are validated like headers from peers.
+    pub validate_stored_headers: bool,
+    /// Whether corrupted content read by a content lookup is deleted and refetched from the
+    /// network, instead of reported as an error.
+    pub self_heal_corrupt_content: bool,
+    /// Whether the FindAndStore JSON-RPC endpoint is enabled, since it mutates the store.
+    pub enable_find_and_store: bool,
+    /// Whether the RawOffer JSON-RPC endpoint is enabled, since it sends unchecked content.
+    pub enable_raw_offer: bool,
+    /// Whether the DebugPing JSON-RPC endpoint is enabled, since it bypasses the overlay service.
+    pub enable_debug_ping: bool,
+    /// The criteria that the network must meet before the Readiness JSON-RPC endpoint reports it
+    /// as ready.
+    pub readiness: ReadinessConfig,
+    /// The node's configuration with secrets redacted, as reported by the GetConfig JSON-RPC
+    /// endpoint.
+    pub redacted_config: serde_json::Value,
 }
 
 impl HistoryNetwork {
@@ -40,24 +61,73 @@ impl HistoryNetwork {
         let config = OverlayConfig {
             bootnode_enrs: portal_config.bootnodes,
             disable_poke: portal_config.disable_poke,
-            gossip_dropped: GOSSIP_DROPPED,
+            gossip_dropped: portal_config
+                .on_radius_shrink
+                .map_or(GOSSIP_DROPPED, |policy| policy.gossips_evicted()),
             utp_transfer_limit: portal_config.utp_transfer_limit,
+            utp_target_delay: portal_config.utp_target_delay,
+            utp_peer_transfer_limit: portal_config.utp_peer_transfer_limit,
+            serve_bandwidth_limiter: portal_config.serve_bandwidth_limiter,
+            enable_compression: portal_config.enable_compression,
+            max_find_content_size: portal_config.max_find_content_size,
+            min_lookup_peers: portal_config.min_lookup_peers,
+            lookup_retries: portal_config.lookup_retries,
+            query_parallelism: portal_config.lookup_parallelism,
+            query_max_hops: portal_config.lookup_max_hops,
+            gossip_on_new_peer: portal_config.gossip_on_new_peer,
+            gossip_unvalidated: portal_config.gossip_unvalidated,
+            denylist: portal_config.denylist,
+            offer_log: portal_config.offer_log,
+            mirror_peer: portal_config.mirror_peer,
+            join_delay: portal_config.join_delay,
+            query_trace_buffer_size: portal_config.query_trace_buffer_size,
+            content_ttl_sweep_interval: portal_config.content_ttl_sweep_interval,
+            gossip_decay: portal_config.gossip_decay,
+            gossip_dedup_window: portal_config.gossip_dedup_window,
+            accept_duplicate_content: portal_config.accept_duplicate_content,
+            gossip_peer_retries: portal_config.gossip_peer_retries,
+            content_holders_ttl: portal_config.content_holders_ttl,
+            circuit_breaker: portal_config.circuit_breaker,
+            max_peers_per_subnet: portal_config.max_peers_per_subnet,
+            serve_only_in_requester_radius: portal_config.serve_only_in_requester_radius,
+            ping_timeout: portal_config.ping_timeout,
+            findnodes_timeout: portal_config.findnodes_timeout,
+            findcontent_timeout: portal_config.findcontent_timeout,
             ..Default::default()
         };
         let storage = Arc::new(PLRwLock::new(HistoryStorage::new(storage_config)?));
-        let validator = Arc::new(ChainHistoryValidator { header_oracle });
+        let validator = Arc::new(ChainHistoryValidator::new(
+            header_oracle,
+            portal_config.validation_level,
+            portal_config.el_rpc_url.map(|url| {
+                ElCrosscheck::new(
+                    url,
+                    portal_config.el_crosscheck_sample_ratio,
+                    Arc::clone(&storage),
+                )
+            }),
+        ));
+        discovery.register_validators(Subnetwork::History, validator.registry.names());
         let overlay = OverlayProtocol::new(
             config,
             discovery,
             utp_socket,
             storage,
             Subnetwork::History,
-            validator,
+            Arc::clone(&validator),
         )
         .await;
 
         Ok(Self {
             overlay: Arc::new(overlay),
+            validator,
+            validate_stored_headers: portal_config.validate_stored_headers,
+            self_heal_corrupt_content: portal_config.self_heal_corrupt_content,
+            enable_find_and_store: portal_config.enable_find_and_store,
+            enable_raw_offer: portal_config.enable_raw_offer,
+            enable_debug_ping: portal_config.enable_debug_ping,
+            readiness: portal_config.readiness,
+            redacted_config: portal_config.redacted_config,
         })
     }
 }
@@ -1,13 +1,29 @@
+use discv5::enr::NodeId;
 use ethportal_api::{
-    types::{distance::Distance, network::Subnetwork, portal::PaginateLocalContentInfo},
+    types::{
+        content_key::history::HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX,
+        distance::Distance,
+        network::Subnetwork,
+        portal::{
+            BlockNumberRange, ContentDistanceDistributionInfo, ContentRangeInfo,
+            DistanceBucketInfo, PaginateLocalContentInfo, RadiusChangeInfo, RawStoredBytesInfo,
+            StorageCapacityInfo, StorageInfo, StorageUsageInfo,
+        },
+    },
     HistoryContentKey, OverlayContentKey, RawContentValue,
 };
 use trin_storage::{
     error::ContentStoreError,
-    versioned::{create_store, ContentType, IdIndexedV1Store, IdIndexedV1StoreConfig},
+    versioned::{
+        create_store, ContentType, IdIndexedV1Store, IdIndexedV1StoreConfig, StoreBenchmark,
+    },
     ContentId, ContentStore, PortalStorageConfig, ShouldWeStoreContent,
 };
 
+/// The maximum number of stored entries inspected when looking up content within the radius of
+/// another node.
+const MAX_RADIUS_SCAN_ENTRIES: u64 = 10_000;
+
 /// Storage layer for the history network. Encapsulates history network specific data and logic.
 #[derive(Debug)]
 pub struct HistoryStorage {
@@ -47,6 +63,28 @@ impl ContentStore for HistoryStorage {
     fn radius(&self) -> Distance {
         self.store.radius()
     }
+
+    fn check_health(&self) -> Result<(), ContentStoreError> {
+        self.store.check_health()
+    }
+
+    fn delete_expired(&mut self) -> Result<u64, ContentStoreError> {
+        self.store.delete_expired()
+    }
+
+    fn record_served(&self, key: &HistoryContentKey) -> Result<(), ContentStoreError> {
+        self.store.record_served(&key.content_id().into())
+    }
+
+    fn content_within_radius_of(
+        &self,
+        node_id: &NodeId,
+        radius: Distance,
+        limit: usize,
+    ) -> Result<Vec<(HistoryContentKey, RawContentValue)>, ContentStoreError> {
+        self.store
+            .lookup_within_radius_of(node_id, radius, limit, MAX_RADIUS_SCAN_ENTRIES)
+    }
 }
 
 impl HistoryStorage {
@@ -63,6 +101,17 @@ impl HistoryStorage {
         self.store.get_summary_info()
     }
 
+    /// Returns the keys of up to `count` locally stored content items whose content ids are
+    /// closest to `node_id`, sorted by distance.
+    pub fn closest_content(
+        &self,
+        node_id: &NodeId,
+        count: u64,
+    ) -> Result<Vec<HistoryContentKey>, ContentStoreError> {
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        self.store.lookup_closest_to(node_id, count)
+    }
+
     /// Returns a paginated list of all available content keys from local storage (from any
     /// subnetwork) according to the provided offset and limit.
     pub fn paginate(
@@ -76,6 +125,168 @@ impl HistoryStorage {
             total_entries: paginate_result.entry_count,
         })
     }
+
+    /// Returns the radius, the number and size of stored entries, in total and per content type,
+    /// the remaining capacity and disk usage, and the deduplication ratio of stored values.
+    pub fn storage_info(&self) -> Result<StorageInfo, ContentStoreError> {
+        let usage_stats = self.store.usage_stats();
+        let content_types = self
+            .store
+            .key_type_usage_stats()
+            .into_iter()
+            .map(|(key_type, usage_stats)| {
+                let usage = StorageUsageInfo {
+                    entry_count: usage_stats.entry_count,
+                    total_entry_size_bytes: usage_stats.total_entry_size_bytes,
+                };
+                (ContentType::History.key_type_name(key_type), usage)
+            })
+            .collect();
+        let content_type_quotas = self
+            .store
+            .key_type_quota_bytes()
+            .into_iter()
+            .map(|(key_type, quota_bytes)| {
+                (ContentType::History.key_type_name(key_type), quota_bytes)
+            })
+            .collect();
+        let capacity_bytes = self.store.storage_capacity_bytes();
+        let (database_size_bytes, database_free_bytes) = self.store.database_size()?;
+        Ok(StorageInfo {
+            radius: *self.store.radius(),
+            total: StorageUsageInfo {
+                entry_count: usage_stats.entry_count,
+                total_entry_size_bytes: usage_stats.total_entry_size_bytes,
+            },
+            capacity: StorageCapacityInfo {
+                capacity_bytes,
+                estimated_remaining_entries: usage_stats
+                    .estimated_remaining_entries(capacity_bytes),
+                database_size_bytes,
+                database_free_bytes,
+            },
+            content_types,
+            content_type_quotas,
+            dedup_ratio: self.store.dedup_ratio()?,
+        })
+    }
+
+    /// Returns the content value of `key` as it's stored, before it's decoded or its checksum is
+    /// verified, if stored.
+    pub fn raw_stored_bytes(
+        &self,
+        key: &HistoryContentKey,
+    ) -> Result<Option<RawStoredBytesInfo>, ContentStoreError> {
+        self.store.lookup_stored_value(&key.content_id().into())
+    }
+
+    /// Deletes the content of `key`, e.g. because it's corrupted.
+    pub fn delete(&mut self, key: &HistoryContentKey) -> Result<(), ContentStoreError> {
+        self.store.delete(&key.content_id().into())
+    }
+
+    /// Returns the distance from the local node to the content id of `key`.
+    pub fn distance_to_key(&self, key: &HistoryContentKey) -> Distance {
+        self.store.distance_to_content_id(&key.content_id().into())
+    }
+
+    /// Returns whether storing the content of `key` would be refused, because the storage capacity
+    /// is exhausted by recent content that is exempt from eviction, or by content that is kept.
+    pub fn is_capacity_exhausted(
+        &self,
+        key: &HistoryContentKey,
+    ) -> Result<bool, ContentStoreError> {
+        self.store.is_capacity_exhausted(key.block_number())
+    }
+
+    /// Returns the number of stored entries per log2 distance from the local node, alongside the
+    /// radius.
+    pub fn distance_distribution(
+        &self,
+    ) -> Result<ContentDistanceDistributionInfo, ContentStoreError> {
+        let buckets = self
+            .store
+            .distance_distribution()?
+            .into_iter()
+            .map(|(log2_distance, count)| DistanceBucketInfo {
+                log2_distance,
+                count,
+            })
+            .collect();
+        Ok(ContentDistanceDistributionInfo {
+            radius: *self.store.radius(),
+            buckets,
+        })
+    }
+
+    /// Sets the radius, overriding the radius derived from the stored content, or clears the
+    /// override if `radius` is `None`. Returns whether content farther than the radius is stored.
+    pub fn override_radius(&mut self, radius: Option<Distance>) -> Result<bool, ContentStoreError> {
+        self.store.override_radius(radius)
+    }
+
+    /// Deletes up to `limit` of the stored content farther than the radius, the farthest first.
+    /// Returns the deleted content.
+    pub fn evict_outside_radius(
+        &mut self,
+        limit: u64,
+    ) -> Result<Vec<(HistoryContentKey, RawContentValue)>, ContentStoreError> {
+        self.store.evict_outside_radius(limit)
+    }
+
+    /// Returns the latest changes of the radius, the oldest first.
+    pub fn radius_history(&self) -> Vec<RadiusChangeInfo> {
+        self.store.radius_history()
+    }
+
+    /// Returns a benchmark of the write and read throughput of the store with synthetic entries,
+    /// that leaves stored content untouched.
+    pub fn benchmark(&self) -> StoreBenchmark {
+        self.store.benchmark()
+    }
+
+    /// Returns up to `limit` stored content items whose content ids are greater than `after`, or
+    /// from the first content id if `after` isn't set, in content id order.
+    pub fn export_page(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<(ContentId, HistoryContentKey, RawContentValue)>, ContentStoreError> {
+        self.store.export_page(after, limit)
+    }
+
+    /// Makes all stored content durable on disk.
+    pub fn sync(&self) -> Result<(), ContentStoreError> {
+        self.store.sync()
+    }
+
+    /// Returns the range of block numbers of stored content, for content types whose content key
+    /// encodes a block number.
+    pub fn content_range(&self) -> Result<ContentRangeInfo, ContentStoreError> {
+        let mut block_header_by_number: Option<BlockNumberRange> = None;
+        self.store.for_each_content_key_with_prefix(
+            &[HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX],
+            |content_key| {
+                let HistoryContentKey::BlockHeaderByNumber(key) = content_key else {
+                    return;
+                };
+                let block_number = key.block_number;
+                block_header_by_number = Some(match block_header_by_number {
+                    Some(range) => BlockNumberRange {
+                        min: range.min.min(block_number),
+                        max: range.max.max(block_number),
+                    },
+                    None => BlockNumberRange {
+                        min: block_number,
+                        max: block_number,
+                    },
+                });
+            },
+        )?;
+        Ok(ContentRangeInfo {
+            block_header_by_number,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -131,4 +342,57 @@ pub mod test {
         temp_dir.close()?;
         Ok(())
     }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_sync() -> Result<(), ContentStoreError> {
+        let (temp_dir, storage_config) =
+            create_test_portal_storage_config_with_capacity(CAPACITY_MB).unwrap();
+        let mut storage = HistoryStorage::new(storage_config)?;
+        let content_key = HistoryContentKey::BlockHeaderByHash(BlockHeaderByHashKey::default());
+        let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+        storage.put(content_key.clone(), &value)?;
+
+        storage.sync()?;
+        assert_eq!(storage.get(&content_key)?.unwrap(), value);
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_content_range() -> Result<(), ContentStoreError> {
+        let (temp_dir, storage_config) =
+            create_test_portal_storage_config_with_capacity(CAPACITY_MB).unwrap();
+        let mut storage = HistoryStorage::new(storage_config)?;
+        assert_eq!(storage.content_range()?, ContentRangeInfo::default());
+
+        let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+        for block_number in [1_000, 7, 300_000] {
+            storage.put(
+                HistoryContentKey::new_block_header_by_number(block_number),
+                &value,
+            )?;
+        }
+        storage.put(
+            HistoryContentKey::BlockHeaderByHash(BlockHeaderByHashKey::default()),
+            &value,
+        )?;
+
+        assert_eq!(
+            storage.content_range()?,
+            ContentRangeInfo {
+                block_header_by_number: Some(BlockNumberRange {
+                    min: 7,
+                    max: 300_000,
+                }),
+            }
+        );
+
+        drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
 }
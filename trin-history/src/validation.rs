@@ -3,13 +3,21 @@ use std::sync::Arc;
 use alloy::primitives::B256;
 use anyhow::{anyhow, ensure};
 use ethportal_api::{
-    types::execution::{
-        block_body::BlockBody, header::Header, header_with_proof::HeaderWithProof,
-        receipts::Receipts,
+    types::{
+        cli::ValidationLevel,
+        content_key::history::{
+            HISTORY_BLOCK_BODY_KEY_PREFIX, HISTORY_BLOCK_HEADER_BY_HASH_KEY_PREFIX,
+            HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX, HISTORY_BLOCK_RECEIPTS_KEY_PREFIX,
+        },
+        execution::{
+            block_body::BlockBody, header::Header, header_with_proof::HeaderWithProof,
+            receipts::Receipts,
+        },
     },
     utils::bytes::hex_encode,
     HistoryContentKey,
 };
+use futures::future::BoxFuture;
 use ssz::Decode;
 use tokio::sync::RwLock;
 use trin_validation::{
@@ -17,8 +25,21 @@ use trin_validation::{
     validator::{ValidationResult, Validator},
 };
 
+use crate::{
+    el_crosscheck::ElCrosscheck,
+    validator_registry::{ContentTypeValidator, ValidatorRegistry},
+};
+
 pub struct ChainHistoryValidator {
-    pub header_oracle: Arc<RwLock<HeaderOracle>>,
+    /// How thoroughly content is validated. Below `Full`, headers are only checked against their
+    /// content key, and bodies and receipts are only decoded, since matching them against their
+    /// header requires finding it first.
+    pub validation_level: ValidationLevel,
+    /// The validator of each content type.
+    pub registry: ValidatorRegistry,
+    /// The background cross-check of a sample of the validated content against an execution
+    /// client, if one is configured.
+    pub el_crosscheck: Option<ElCrosscheck>,
 }
 
 impl Validator<HistoryContentKey> for ChainHistoryValidator {
@@ -27,95 +48,225 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
         content_key: &HistoryContentKey,
         content: &[u8],
     ) -> anyhow::Result<ValidationResult<HistoryContentKey>> {
-        match content_key {
-            HistoryContentKey::BlockHeaderByHash(key) => {
-                let header_with_proof =
-                    HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
-                        anyhow!("Header by hash content has invalid encoding: {err:?}")
-                    })?;
-                let header_hash = header_with_proof.header.hash();
-                ensure!(
-                    header_hash == B256::from(key.block_hash),
-                    "Content validation failed: Invalid header hash. Found: {header_hash:?} - Expected: {:?}",
-                    hex_encode(header_hash)
-                );
-                self.header_oracle
-                    .read()
-                    .await
-                    .header_validator
-                    .validate_header_with_proof(&header_with_proof)?;
-
-                Ok(ValidationResult::new(true))
+        if self.validation_level == ValidationLevel::None {
+            return Ok(ValidationResult::new_unvalidated());
+        }
+        let validator = self.registry.get(content_key).ok_or_else(|| {
+            anyhow!(
+                "Content validation failed: No validator registered for content key {content_key}"
+            )
+        })?;
+        let validation_result = validator.validate(content_key, content).await?;
+        if let Some(el_crosscheck) = &self.el_crosscheck {
+            if el_crosscheck.sample() {
+                el_crosscheck.spawn_check(content_key, content);
             }
-            HistoryContentKey::BlockHeaderByNumber(key) => {
-                let header_with_proof =
-                    HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
-                        anyhow!("Header by number content has invalid encoding: {err:?}")
-                    })?;
-                let header_number = header_with_proof.header.number;
-                ensure!(
-                    header_number == key.block_number,
-                    "Content validation failed: Invalid header number. Found: {header_number} - Expected: {}",
-                    key.block_number
-                );
+        }
+        Ok(validation_result)
+    }
+}
+
+impl ChainHistoryValidator {
+    /// Creates a validator with the validators of all History network content types registered.
+    pub fn new(
+        header_oracle: Arc<RwLock<HeaderOracle>>,
+        validation_level: ValidationLevel,
+        el_crosscheck: Option<ElCrosscheck>,
+    ) -> Self {
+        let mut registry = ValidatorRegistry::default();
+        registry.register(
+            HISTORY_BLOCK_HEADER_BY_HASH_KEY_PREFIX,
+            Arc::new(HeaderValidator {
+                header_oracle: Arc::clone(&header_oracle),
+                validation_level,
+            }),
+        );
+        registry.register(
+            HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX,
+            Arc::new(HeaderValidator {
+                header_oracle: Arc::clone(&header_oracle),
+                validation_level,
+            }),
+        );
+        registry.register(
+            HISTORY_BLOCK_BODY_KEY_PREFIX,
+            Arc::new(BlockBodyValidator {
+                header_oracle: Arc::clone(&header_oracle),
+                validation_level,
+            }),
+        );
+        registry.register(
+            HISTORY_BLOCK_RECEIPTS_KEY_PREFIX,
+            Arc::new(ReceiptsValidator {
+                header_oracle,
+                validation_level,
+            }),
+        );
+        Self {
+            validation_level,
+            registry,
+            el_crosscheck,
+        }
+    }
+}
+
+/// Validates headers, by hash and by number, against their content key, and their proof if the
+/// validation level is `Full`.
+struct HeaderValidator {
+    header_oracle: Arc<RwLock<HeaderOracle>>,
+    validation_level: ValidationLevel,
+}
+
+impl ContentTypeValidator for HeaderValidator {
+    fn name(&self) -> &'static str {
+        "headerWithProof"
+    }
+
+    fn validate<'a>(
+        &'a self,
+        content_key: &'a HistoryContentKey,
+        content: &'a [u8],
+    ) -> BoxFuture<'a, anyhow::Result<ValidationResult<HistoryContentKey>>> {
+        Box::pin(async move {
+            let header_with_proof = match content_key {
+                HistoryContentKey::BlockHeaderByHash(key) => {
+                    let header_with_proof =
+                        HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
+                            anyhow!("Header by hash content has invalid encoding: {err:?}")
+                        })?;
+                    let header_hash = header_with_proof.header.hash();
+                    ensure!(
+                        header_hash == B256::from(key.block_hash),
+                        "Content validation failed: Invalid header hash. Found: {header_hash:?} - Expected: {:?}",
+                        hex_encode(header_hash)
+                    );
+                    header_with_proof
+                }
+                HistoryContentKey::BlockHeaderByNumber(key) => {
+                    let header_with_proof =
+                        HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
+                            anyhow!("Header by number content has invalid encoding: {err:?}")
+                        })?;
+                    let header_number = header_with_proof.header.number;
+                    ensure!(
+                        header_number == key.block_number,
+                        "Content validation failed: Invalid header number. Found: {header_number} - Expected: {}",
+                        key.block_number
+                    );
+                    header_with_proof
+                }
+                _ => return Err(anyhow!("Header validator can't validate {content_key}")),
+            };
+            if self.validation_level == ValidationLevel::Full {
                 self.header_oracle
                     .read()
                     .await
                     .header_validator
                     .validate_header_with_proof(&header_with_proof)?;
+            }
 
-                Ok(ValidationResult::new(true))
+            Ok(ValidationResult::new(true))
+        })
+    }
+}
+
+/// Validates block bodies against the uncles and transactions roots of their header, if the
+/// validation level is `Full`.
+struct BlockBodyValidator {
+    header_oracle: Arc<RwLock<HeaderOracle>>,
+    validation_level: ValidationLevel,
+}
+
+impl ContentTypeValidator for BlockBodyValidator {
+    fn name(&self) -> &'static str {
+        "blockBody"
+    }
+
+    fn validate<'a>(
+        &'a self,
+        content_key: &'a HistoryContentKey,
+        content: &'a [u8],
+    ) -> BoxFuture<'a, anyhow::Result<ValidationResult<HistoryContentKey>>> {
+        Box::pin(async move {
+            let HistoryContentKey::BlockBody(key) = content_key else {
+                return Err(anyhow!("Block body validator can't validate {content_key}"));
+            };
+            let block_body = BlockBody::from_ssz_bytes(content)
+                .map_err(|msg| anyhow!("Block Body content has invalid encoding: {:?}", msg))?;
+            if self.validation_level == ValidationLevel::Hash {
+                return Ok(ValidationResult::new(true));
             }
-            HistoryContentKey::BlockBody(key) => {
-                let block_body = BlockBody::from_ssz_bytes(content)
-                    .map_err(|msg| anyhow!("Block Body content has invalid encoding: {:?}", msg))?;
-                let trusted_header: Header = self
-                    .header_oracle
-                    .read()
-                    .await
-                    .recursive_find_header_by_hash_with_proof(B256::from(key.block_hash))
-                    .await?
-                    .header;
-                let actual_uncles_root = block_body.uncles_root();
-                if actual_uncles_root != trusted_header.uncles_hash {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid uncles root. Found: {:?} - Expected: {:?}",
-                        actual_uncles_root,
-                        trusted_header.uncles_hash
-                    ));
-                }
-                let actual_txs_root = block_body.transactions_root()?;
-                if actual_txs_root != trusted_header.transactions_root {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid transactions root. Found: {:?} - Expected: {:?}",
-                        actual_txs_root,
-                        trusted_header.transactions_root
-                    ));
-                }
-                Ok(ValidationResult::new(true))
+            let trusted_header: Header = self
+                .header_oracle
+                .read()
+                .await
+                .recursive_find_header_by_hash_with_proof(B256::from(key.block_hash))
+                .await?
+                .header;
+            let actual_uncles_root = block_body.uncles_root();
+            if actual_uncles_root != trusted_header.uncles_hash {
+                return Err(anyhow!(
+                    "Content validation failed: Invalid uncles root. Found: {:?} - Expected: {:?}",
+                    actual_uncles_root,
+                    trusted_header.uncles_hash
+                ));
             }
-            HistoryContentKey::BlockReceipts(key) => {
-                let receipts = Receipts::from_ssz_bytes(content).map_err(|msg| {
-                    anyhow!("Block Receipts content has invalid encoding: {:?}", msg)
-                })?;
-                let trusted_header: Header = self
-                    .header_oracle
-                    .read()
-                    .await
-                    .recursive_find_header_by_hash_with_proof(B256::from(key.block_hash))
-                    .await?
-                    .header;
-                let actual_receipts_root = receipts.root()?;
-                if actual_receipts_root != trusted_header.receipts_root {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid receipts root. Found: {:?} - Expected: {:?}",
-                        actual_receipts_root,
-                        trusted_header.receipts_root
-                    ));
-                }
-                Ok(ValidationResult::new(true))
+            let actual_txs_root = block_body.transactions_root()?;
+            if actual_txs_root != trusted_header.transactions_root {
+                return Err(anyhow!(
+                    "Content validation failed: Invalid transactions root. Found: {:?} - Expected: {:?}",
+                    actual_txs_root,
+                    trusted_header.transactions_root
+                ));
             }
-        }
+            Ok(ValidationResult::new(true))
+        })
+    }
+}
+
+/// Validates receipts against the receipts root of their header, if the validation level is
+/// `Full`.
+struct ReceiptsValidator {
+    header_oracle: Arc<RwLock<HeaderOracle>>,
+    validation_level: ValidationLevel,
+}
+
+impl ContentTypeValidator for ReceiptsValidator {
+    fn name(&self) -> &'static str {
+        "receipts"
+    }
+
+    fn validate<'a>(
+        &'a self,
+        content_key: &'a HistoryContentKey,
+        content: &'a [u8],
+    ) -> BoxFuture<'a, anyhow::Result<ValidationResult<HistoryContentKey>>> {
+        Box::pin(async move {
+            let HistoryContentKey::BlockReceipts(key) = content_key else {
+                return Err(anyhow!("Receipts validator can't validate {content_key}"));
+            };
+            let receipts = Receipts::from_ssz_bytes(content)
+                .map_err(|msg| anyhow!("Block Receipts content has invalid encoding: {:?}", msg))?;
+            if self.validation_level == ValidationLevel::Hash {
+                return Ok(ValidationResult::new(true));
+            }
+            let trusted_header: Header = self
+                .header_oracle
+                .read()
+                .await
+                .recursive_find_header_by_hash_with_proof(B256::from(key.block_hash))
+                .await?
+                .header;
+            let actual_receipts_root = receipts.root()?;
+            if actual_receipts_root != trusted_header.receipts_root {
+                return Err(anyhow!(
+                    "Content validation failed: Invalid receipts root. Found: {:?} - Expected: {:?}",
+                    actual_receipts_root,
+                    trusted_header.receipts_root
+                ));
+            }
+            Ok(ValidationResult::new(true))
+        })
     }
 }
 
@@ -148,7 +299,8 @@ mod tests {
         let header_with_proof =
             HeaderWithProof::from_ssz_bytes(&header_with_proof_ssz).expect("error decoding header");
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key =
             HistoryContentKey::new_block_header_by_hash(header_with_proof.header.hash());
         chain_history_validator
@@ -169,7 +321,8 @@ mod tests {
 
         let content_value = header.as_ssz_bytes();
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key = HistoryContentKey::new_block_header_by_hash(header.header.hash());
         chain_history_validator
             .validate_content(&content_key, &content_value)
@@ -190,7 +343,8 @@ mod tests {
 
         let content_value = header.as_ssz_bytes();
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key = HistoryContentKey::new_block_header_by_hash(header.header.hash());
         chain_history_validator
             .validate_content(&content_key, &content_value)
@@ -204,7 +358,8 @@ mod tests {
         let header_with_proof =
             HeaderWithProof::from_ssz_bytes(&header_with_proof_ssz).expect("error decoding header");
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key =
             HistoryContentKey::new_block_header_by_number(header_with_proof.header.number);
         chain_history_validator
@@ -225,7 +380,8 @@ mod tests {
 
         let content_value = header.as_ssz_bytes();
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key = HistoryContentKey::new_block_header_by_number(header.header.number);
         chain_history_validator
             .validate_content(&content_key, &content_value)
@@ -246,7 +402,8 @@ mod tests {
 
         let content_value = header.as_ssz_bytes();
         let header_oracle = default_header_oracle();
-        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let chain_history_validator =
+            ChainHistoryValidator::new(header_oracle, ValidationLevel::Full, None);
         let content_key = HistoryContentKey::new_block_header_by_number(header.header.number);
         chain_history_validator
             .validate_content(&content_key, &content_value)
@@ -254,6 +411,32 @@ mod tests {
             .unwrap();
     }
 
+    #[test_log::test(tokio::test)]
+    async fn hash_validation_level_skips_header_proof() {
+        let header_with_proof_ssz = get_header_with_proof_ssz();
+        let mut header =
+            HeaderWithProof::from_ssz_bytes(&header_with_proof_ssz).expect("error decoding header");
+
+        // set invalid block gaslimit, which only fails the proof validation
+        header.header.gas_limit = U256::from(3141591);
+
+        let content_value = header.as_ssz_bytes();
+        let chain_history_validator =
+            ChainHistoryValidator::new(default_header_oracle(), ValidationLevel::Hash, None);
+        let content_key = HistoryContentKey::new_block_header_by_hash(header.header.hash());
+        chain_history_validator
+            .validate_content(&content_key, &content_value)
+            .await
+            .unwrap();
+
+        // The header must still match its content key.
+        let content_key = HistoryContentKey::new_block_header_by_hash(B256::from([1; 32]));
+        chain_history_validator
+            .validate_content(&content_key, &content_value)
+            .await
+            .expect_err("header must match its content key");
+    }
+
     fn default_header_oracle() -> Arc<RwLock<HeaderOracle>> {
         Arc::new(RwLock::new(HeaderOracle::default()))
     }
@@ -0,0 +1,96 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use ethportal_api::{utils::bytes::hex_encode, HistoryContentKey, OverlayContentKey};
+use futures::future::BoxFuture;
+use trin_validation::validator::ValidationResult;
+
+/// Validates the content of a single content type of the History network.
+pub trait ContentTypeValidator: Send + Sync {
+    /// The name of the validator, reported in the node info.
+    fn name(&self) -> &'static str;
+
+    /// Validates the content, like [trin_validation::validator::Validator::validate_content].
+    fn validate<'a>(
+        &'a self,
+        content_key: &'a HistoryContentKey,
+        content: &'a [u8],
+    ) -> BoxFuture<'a, anyhow::Result<ValidationResult<HistoryContentKey>>>;
+}
+
+/// The validators of the History network content, keyed by content type, i.e. the first byte of
+/// the content key.
+///
+/// All content, whether it's offered, found or stored through the JSON-RPC API, is validated by
+/// the validator registered for its content type, so that validating a new content type only
+/// requires registering its validator.
+#[derive(Default)]
+pub struct ValidatorRegistry {
+    validators: BTreeMap<u8, Arc<dyn ContentTypeValidator>>,
+}
+
+impl ValidatorRegistry {
+    /// Registers the validator of the content type, replacing any validator registered for it
+    /// before.
+    pub fn register(&mut self, content_type: u8, validator: Arc<dyn ContentTypeValidator>) {
+        self.validators.insert(content_type, validator);
+    }
+
+    /// Returns the validator registered for the content type of the content key, if any.
+    pub fn get(&self, content_key: &HistoryContentKey) -> Option<&Arc<dyn ContentTypeValidator>> {
+        let content_type = content_key.to_bytes().first().copied()?;
+        self.validators.get(&content_type)
+    }
+
+    /// Returns the name of each registered validator, keyed by its content type, hex encoded.
+    pub fn names(&self) -> BTreeMap<String, String> {
+        self.validators
+            .iter()
+            .map(|(content_type, validator)| {
+                (hex_encode([*content_type]), validator.name().to_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ethportal_api::types::content_key::history::HISTORY_BLOCK_BODY_KEY_PREFIX;
+
+    use super::*;
+
+    struct AcceptAllValidator;
+
+    impl ContentTypeValidator for AcceptAllValidator {
+        fn name(&self) -> &'static str {
+            "acceptAll"
+        }
+
+        fn validate<'a>(
+            &'a self,
+            _content_key: &'a HistoryContentKey,
+            _content: &'a [u8],
+        ) -> BoxFuture<'a, anyhow::Result<ValidationResult<HistoryContentKey>>> {
+            Box::pin(async { Ok(ValidationResult::new(true)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn validators_are_selected_by_content_type() {
+        let mut registry = ValidatorRegistry::default();
+        registry.register(HISTORY_BLOCK_BODY_KEY_PREFIX, Arc::new(AcceptAllValidator));
+
+        let body_key = HistoryContentKey::new_block_body([1; 32]);
+        let validator = registry.get(&body_key).unwrap();
+        assert_eq!(validator.name(), "acceptAll");
+        assert!(validator.validate(&body_key, &[]).await.is_ok());
+
+        let header_key = HistoryContentKey::new_block_header_by_hash([1; 32]);
+        assert!(registry.get(&header_key).is_none());
+
+        assert_eq!(
+            registry.names(),
+            BTreeMap::from([("0x01".to_string(), "acceptAll".to_string())])
+        );
+    }
+}
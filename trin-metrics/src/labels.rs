@@ -46,6 +46,43 @@ impl From<UtpOutcomeLabel> for MetricLabel {
     }
 }
 
+impl From<RoutingTableChangeLabel> for MetricLabel {
+    fn from(label: RoutingTableChangeLabel) -> Self {
+        match label {
+            RoutingTableChangeLabel::Inserted => "inserted",
+            RoutingTableChangeLabel::Evicted => "evicted",
+        }
+    }
+}
+
+impl From<SelfHealLabel> for MetricLabel {
+    fn from(label: SelfHealLabel) -> Self {
+        match label {
+            SelfHealLabel::Refetched => "refetched",
+            SelfHealLabel::Failed => "failed",
+        }
+    }
+}
+
+impl From<EnrCacheLookupLabel> for MetricLabel {
+    fn from(label: EnrCacheLookupLabel) -> Self {
+        match label {
+            EnrCacheLookupLabel::Hit => "hit",
+            EnrCacheLookupLabel::Miss => "miss",
+        }
+    }
+}
+
+impl From<ElCrosscheckLabel> for MetricLabel {
+    fn from(label: ElCrosscheckLabel) -> Self {
+        match label {
+            ElCrosscheckLabel::Match => "match",
+            ElCrosscheckLabel::Mismatch => "mismatch",
+            ElCrosscheckLabel::Error => "error",
+        }
+    }
+}
+
 impl From<&Request> for MessageLabel {
     fn from(request: &Request) -> Self {
         match request {
@@ -58,6 +95,9 @@ impl From<&Request> for MessageLabel {
             // Populated offers with result are the same as regular offers, from a metrics point of
             // view
             Request::PopulatedOfferWithResult(_) => MessageLabel::Offer,
+            // Populated offers with transfer are the same as regular offers, from a metrics point
+            // of view
+            Request::PopulatedOfferWithTransfer(_) => MessageLabel::Offer,
         }
     }
 }
@@ -113,3 +153,41 @@ pub enum UtpOutcomeLabel {
     FailedDataTx,
     FailedShutdown,
 }
+
+/// Execution Client Cross-check Result Labels
+#[derive(Debug, Clone, Copy)]
+pub enum ElCrosscheckLabel {
+    /// Content that matches the canonical chain of the execution client
+    Match,
+    /// Content that doesn't match the canonical chain of the execution client
+    Mismatch,
+    /// Content that couldn't be cross-checked, e.g. because the execution client was unreachable
+    Error,
+}
+
+/// Self-heal Outcome Labels
+#[derive(Debug, Clone, Copy)]
+pub enum SelfHealLabel {
+    /// Corrupted content that was deleted and refetched from the network
+    Refetched,
+    /// Corrupted content that couldn't be deleted or refetched
+    Failed,
+}
+
+/// ENR Cache Lookup Labels
+#[derive(Debug, Clone, Copy)]
+pub enum EnrCacheLookupLabel {
+    /// Lookups of node addresses that were cached
+    Hit,
+    /// Lookups of node addresses that weren't cached
+    Miss,
+}
+
+/// Routing Table Change Labels
+#[derive(Debug, Clone, Copy)]
+pub enum RoutingTableChangeLabel {
+    /// Nodes inserted into the routing table
+    Inserted,
+    /// Nodes evicted or removed from the routing table
+    Evicted,
+}
@@ -5,5 +5,7 @@ pub mod bridge;
 pub mod labels;
 pub mod overlay;
 pub mod portalnet;
+pub mod rpc;
+pub mod statsd;
 pub mod storage;
 pub mod timer;
@@ -3,22 +3,36 @@ use prometheus_exporter::{
     self,
     prometheus::{
         opts, register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry,
-        IntCounterVec, IntGaugeVec, Registry,
+        register_int_gauge_with_registry, IntCounterVec, IntGauge, IntGaugeVec, Registry,
     },
 };
 
-use crate::labels::{MessageDirectionLabel, MessageLabel, UtpDirectionLabel, UtpOutcomeLabel};
+use crate::labels::{
+    ElCrosscheckLabel, EnrCacheLookupLabel, MessageDirectionLabel, MessageLabel,
+    RoutingTableChangeLabel, SelfHealLabel, UtpDirectionLabel, UtpOutcomeLabel,
+};
 
 /// Contains metrics reporters for use in the overlay network
 /// (eg. `portalnet/src/overlay.rs` & `portalnet/src/overlay_service.rs`).
 /// Metric types reported here include protocol messages, utp transfers,
-/// and content validation.
+/// content validation and routing table changes.
 #[derive(Clone)]
 pub struct OverlayMetrics {
     pub message_total: IntCounterVec,
     pub utp_outcome_total: IntCounterVec,
     pub utp_active_gauge: IntGaugeVec,
+    pub utp_served_bytes_total: IntCounterVec,
+    pub utp_compression_saved_bytes_total: IntCounterVec,
     pub validation_total: IntCounterVec,
+    pub gossip_dedup_total: IntCounterVec,
+    pub gossip_blocked_by_validation_total: IntCounterVec,
+    pub offer_duplicate_declined_total: IntCounterVec,
+    pub el_crosscheck_total: IntCounterVec,
+    pub self_heal_total: IntCounterVec,
+    pub routing_table_entries: IntGaugeVec,
+    pub routing_table_churn_total: IntCounterVec,
+    pub enr_cache_entries: IntGauge,
+    pub enr_cache_lookups_total: IntCounterVec,
 }
 
 impl OverlayMetrics {
@@ -47,6 +61,22 @@ impl OverlayMetrics {
             &["protocol", "direction"],
             registry
         )?;
+        let utp_served_bytes_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_utp_served_bytes_total",
+                "count all bytes of content served over utp in response to find content requests"
+            ),
+            &["protocol"],
+            registry
+        )?;
+        let utp_compression_saved_bytes_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_utp_compression_saved_bytes_total",
+                "count all bytes saved on the wire by compressing content transferred over utp"
+            ),
+            &["protocol", "direction"],
+            registry
+        )?;
         let validation_total = register_int_counter_vec_with_registry!(
             opts!(
                 "trin_validation_total",
@@ -55,13 +85,110 @@ impl OverlayMetrics {
             &["protocol", "success"],
             registry
         )?;
+        let gossip_dedup_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_gossip_dedup_total",
+                "count all content that arrived again through gossip within the dedup window, and was dropped"
+            ),
+            &["protocol"],
+            registry
+        )?;
+        let gossip_blocked_by_validation_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_gossip_blocked_by_validation_total",
+                "count all content that was stored without validation, and wasn't propagated"
+            ),
+            &["protocol"],
+            registry
+        )?;
+        let offer_duplicate_declined_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_offer_duplicate_declined_total",
+                "count all offered content keys that were declined because the content is already stored"
+            ),
+            &["protocol"],
+            registry
+        )?;
+        let el_crosscheck_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_el_crosscheck_total",
+                "count all content cross-checked against the execution client, by result"
+            ),
+            &["protocol", "result"],
+            registry
+        )?;
+        let self_heal_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_self_heal_total",
+                "count all corrupted content that was deleted and refetched from the network, by outcome"
+            ),
+            &["protocol", "outcome"],
+            registry
+        )?;
+        let routing_table_entries = register_int_gauge_vec_with_registry!(
+            opts!(
+                "trin_routing_table_entries",
+                "count all entries in the routing table"
+            ),
+            &["protocol"],
+            registry
+        )?;
+        let routing_table_churn_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_routing_table_churn_total",
+                "count all insertions into and evictions from the routing table"
+            ),
+            &["protocol", "change"],
+            registry
+        )?;
+        let enr_cache_entries = register_int_gauge_with_registry!(
+            opts!(
+                "trin_enr_cache_entries",
+                "count all node addresses in the enr cache, shared by all subnetworks"
+            ),
+            registry
+        )?;
+        let enr_cache_lookups_total = register_int_counter_vec_with_registry!(
+            opts!(
+                "trin_enr_cache_lookups_total",
+                "count all lookups of node addresses in the enr cache, by whether they hit"
+            ),
+            &["result"],
+            registry
+        )?;
         Ok(Self {
             message_total,
             utp_outcome_total,
             utp_active_gauge,
+            utp_served_bytes_total,
+            utp_compression_saved_bytes_total,
             validation_total,
+            gossip_dedup_total,
+            gossip_blocked_by_validation_total,
+            offer_duplicate_declined_total,
+            el_crosscheck_total,
+            self_heal_total,
+            routing_table_entries,
+            routing_table_churn_total,
+            enr_cache_entries,
+            enr_cache_lookups_total,
         })
     }
+
+    //
+    // ENR cache
+    //
+
+    pub fn report_enr_cache_size(&self, size: usize) {
+        self.enr_cache_entries.set(size as i64);
+    }
+
+    pub fn report_enr_cache_lookup(&self, result: EnrCacheLookupLabel) {
+        let labels: [&str; 1] = [result.into()];
+        self.enr_cache_lookups_total
+            .with_label_values(&labels)
+            .inc();
+    }
 }
 
 #[derive(Clone)]
@@ -157,6 +284,21 @@ impl OverlayMetricsReporter {
             .dec();
     }
 
+    pub fn report_utp_served_bytes(&self, bytes: usize) {
+        self.overlay_metrics
+            .utp_served_bytes_total
+            .with_label_values(&[&self.protocol])
+            .inc_by(bytes as u64);
+    }
+
+    pub fn report_utp_compression_saved_bytes(&self, direction: UtpDirectionLabel, bytes: usize) {
+        let labels: [&str; 2] = [&self.protocol, direction.into()];
+        self.overlay_metrics
+            .utp_compression_saved_bytes_total
+            .with_label_values(&labels)
+            .inc_by(bytes as u64);
+    }
+
     //
     // Validations
     //
@@ -179,6 +321,67 @@ impl OverlayMetricsReporter {
             .inc();
     }
 
+    pub fn report_el_crosscheck(&self, result: ElCrosscheckLabel) {
+        let labels: [&str; 2] = [&self.protocol, result.into()];
+        self.overlay_metrics
+            .el_crosscheck_total
+            .with_label_values(&labels)
+            .inc();
+    }
+
+    pub fn report_self_heal(&self, outcome: SelfHealLabel) {
+        let labels: [&str; 2] = [&self.protocol, outcome.into()];
+        self.overlay_metrics
+            .self_heal_total
+            .with_label_values(&labels)
+            .inc();
+    }
+
+    //
+    // Gossip
+    //
+
+    pub fn report_gossip_dedup_hit(&self) {
+        self.overlay_metrics
+            .gossip_dedup_total
+            .with_label_values(&[&self.protocol])
+            .inc();
+    }
+
+    pub fn report_gossip_blocked_by_validation(&self) {
+        self.overlay_metrics
+            .gossip_blocked_by_validation_total
+            .with_label_values(&[&self.protocol])
+            .inc();
+    }
+
+    pub fn report_offer_duplicate_declined(&self) {
+        self.overlay_metrics
+            .offer_duplicate_declined_total
+            .with_label_values(&[&self.protocol])
+            .inc();
+    }
+
+    //
+    // Routing table
+    //
+
+    pub fn report_routing_table_size(&self, size: usize) {
+        self.overlay_metrics
+            .routing_table_entries
+            .with_label_values(&[&self.protocol])
+            .set(size as i64);
+    }
+
+    pub fn report_routing_table_change(&self, change: RoutingTableChangeLabel, size: usize) {
+        let labels: [&str; 2] = [&self.protocol, change.into()];
+        self.overlay_metrics
+            .routing_table_churn_total
+            .with_label_values(&labels)
+            .inc();
+        self.report_routing_table_size(size);
+    }
+
     pub fn get_utp_summary(&self) -> String {
         let inbound_success =
             self.utp_outcome_total(UtpDirectionLabel::Inbound, UtpOutcomeLabel::Success);
@@ -1,7 +1,9 @@
 use lazy_static::lazy_static;
 use prometheus_exporter::prometheus::default_registry;
 
-use crate::{bridge::BridgeMetrics, overlay::OverlayMetrics, storage::StorageMetrics};
+use crate::{
+    bridge::BridgeMetrics, overlay::OverlayMetrics, rpc::RpcMetrics, storage::StorageMetrics,
+};
 
 // We use lazy_static to ensure that the metrics registry is initialized only once, for each
 // runtime. This is important because the registry is a global singleton, and if it is
@@ -19,6 +21,7 @@ pub struct PortalnetMetrics {
     bridge: BridgeMetrics,
     overlay: OverlayMetrics,
     storage: StorageMetrics,
+    rpc: RpcMetrics,
 }
 
 impl PortalnetMetrics {
@@ -27,10 +30,12 @@ impl PortalnetMetrics {
         let overlay = OverlayMetrics::new(registry)?;
         let storage = StorageMetrics::new(registry)?;
         let bridge = BridgeMetrics::new(registry)?;
+        let rpc = RpcMetrics::new(registry)?;
         Ok(Self {
             overlay,
             storage,
             bridge,
+            rpc,
         })
     }
 
@@ -45,4 +50,8 @@ impl PortalnetMetrics {
     pub fn bridge(&self) -> BridgeMetrics {
         self.bridge.clone()
     }
+
+    pub fn rpc(&self) -> RpcMetrics {
+        self.rpc.clone()
+    }
 }
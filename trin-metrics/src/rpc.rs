@@ -0,0 +1,35 @@
+use prometheus_exporter::{
+    self,
+    prometheus::{opts, register_int_gauge_with_registry, IntGauge, Registry},
+};
+
+/// Contains metrics reporters for the JSON-RPC servers.
+#[derive(Clone, Debug)]
+pub struct RpcMetrics {
+    pub ipc_connections: IntGauge,
+}
+
+impl RpcMetrics {
+    pub fn new(registry: &Registry) -> anyhow::Result<Self> {
+        let ipc_connections = register_int_gauge_with_registry!(
+            opts!(
+                "trin_ipc_connections",
+                "count all open connections to the json-rpc ipc server"
+            ),
+            registry
+        )?;
+        Ok(Self { ipc_connections })
+    }
+
+    //
+    // IPC connections
+    //
+
+    pub fn report_ipc_connection_opened(&self) {
+        self.ipc_connections.inc();
+    }
+
+    pub fn report_ipc_connection_closed(&self) {
+        self.ipc_connections.dec();
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use prometheus_exporter::prometheus::{
+    default_registry,
+    proto::{Metric, MetricFamily, MetricType},
+};
+
+/// The max size of a statsd packet, which keeps it within the MTU of most networks.
+const MAX_PACKET_SIZE: usize = 1432;
+
+/// Starts pushing the metrics of the default registry to the statsd server at `addr`, every
+/// `interval`.
+///
+/// The metrics are the same ones that are exported to Prometheus, and the exporters can run
+/// simultaneously.
+pub fn start(addr: SocketAddr, interval: Duration) -> anyhow::Result<()> {
+    let bind_addr = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.connect(addr)?;
+    thread::spawn(move || {
+        let mut exporter = StatsdExporter::default();
+        loop {
+            thread::sleep(interval);
+            for packet in exporter.packets(&default_registry().gather()) {
+                // Like statsd itself, metrics are fire-and-forget: a lost packet is only a gap in
+                // the reported metrics.
+                let _ = socket.send(packet.as_bytes());
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Converts Prometheus metrics to statsd metrics.
+///
+/// A metric is named after its Prometheus name, followed by the name and value of each of its
+/// labels, e.g. `trin_message_total{protocol="history",type="ping"}` becomes
+/// `trin_message_total.protocol.history.type.ping`. Counters are reported as the increase since
+/// the previous push, gauges as their value, and histograms and summaries as the `_sum` and
+/// `_count` counters that Prometheus exposes for them.
+#[derive(Debug, Default)]
+pub struct StatsdExporter {
+    /// The value of each counter at the previous push.
+    previous_counters: HashMap<String, f64>,
+}
+
+impl StatsdExporter {
+    /// Returns the statsd lines of the metrics, batched into packets.
+    pub fn packets(&mut self, metric_families: &[MetricFamily]) -> Vec<String> {
+        let mut packets = vec![];
+        let mut packet = String::new();
+        for line in self.lines(metric_families) {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_SIZE {
+                packets.push(std::mem::take(&mut packet));
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            packets.push(packet);
+        }
+        packets
+    }
+
+    /// Returns the statsd lines of the metrics.
+    pub fn lines(&mut self, metric_families: &[MetricFamily]) -> Vec<String> {
+        let mut lines = vec![];
+        for metric_family in metric_families {
+            let name = metric_family.get_name();
+            for metric in metric_family.get_metric() {
+                match metric_family.get_field_type() {
+                    MetricType::COUNTER => {
+                        let value = metric.get_counter().get_value();
+                        lines.extend(self.counter_line(metric_name(name, metric), value));
+                    }
+                    MetricType::GAUGE => {
+                        let value = metric.get_gauge().get_value();
+                        lines.extend(gauge_lines(metric_name(name, metric), value));
+                    }
+                    MetricType::UNTYPED => {
+                        let value = metric.get_untyped().get_value();
+                        lines.extend(gauge_lines(metric_name(name, metric), value));
+                    }
+                    MetricType::HISTOGRAM => {
+                        let histogram = metric.get_histogram();
+                        lines.extend(self.counter_line(
+                            metric_name(&format!("{name}_sum"), metric),
+                            histogram.get_sample_sum(),
+                        ));
+                        lines.extend(self.counter_line(
+                            metric_name(&format!("{name}_count"), metric),
+                            histogram.get_sample_count() as f64,
+                        ));
+                    }
+                    MetricType::SUMMARY => {
+                        let summary = metric.get_summary();
+                        lines.extend(self.counter_line(
+                            metric_name(&format!("{name}_sum"), metric),
+                            summary.get_sample_sum(),
+                        ));
+                        lines.extend(self.counter_line(
+                            metric_name(&format!("{name}_count"), metric),
+                            summary.get_sample_count() as f64,
+                        ));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// Returns the line of a counter, with its increase since the previous push, if any.
+    fn counter_line(&mut self, name: String, value: f64) -> Option<String> {
+        let previous = self.previous_counters.insert(name.clone(), value);
+        // A counter that decreased was reset, so its whole value is new.
+        let increase = match previous {
+            Some(previous) if value >= previous => value - previous,
+            _ => value,
+        };
+        (increase > 0.0).then(|| format!("{name}:{increase}|c"))
+    }
+}
+
+/// Returns the lines of a gauge. Negative values are sent after resetting the gauge to zero, since
+/// statsd treats a leading sign as a change of the current value.
+fn gauge_lines(name: String, value: f64) -> Vec<String> {
+    if value < 0.0 {
+        vec![format!("{name}:0|g"), format!("{name}:{value}|g")]
+    } else {
+        vec![format!("{name}:{value}|g")]
+    }
+}
+
+/// Returns the statsd name of the metric: its Prometheus name, followed by the name and value of
+/// each of its labels.
+fn metric_name(name: &str, metric: &Metric) -> String {
+    let mut metric_name = sanitize(name);
+    for label in metric.get_label() {
+        metric_name.push('.');
+        metric_name.push_str(&sanitize(label.get_name()));
+        metric_name.push('.');
+        metric_name.push_str(&sanitize(label.get_value()));
+    }
+    metric_name
+}
+
+/// Replaces the characters that statsd doesn't allow in metric names, or uses as separators.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use prometheus_exporter::prometheus::{
+        histogram_opts, opts, register_histogram_vec_with_registry,
+        register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry, Registry,
+    };
+
+    use super::*;
+
+    #[test]
+    fn metrics_are_converted_to_statsd_lines() {
+        let registry = Registry::new();
+        let counter = register_int_counter_vec_with_registry!(
+            opts!("trin_message_total", "messages"),
+            &["protocol", "type"],
+            registry
+        )
+        .unwrap();
+        let gauge = register_int_gauge_vec_with_registry!(
+            opts!("trin_routing_table_entries", "entries"),
+            &["protocol"],
+            registry
+        )
+        .unwrap();
+        let histogram = register_histogram_vec_with_registry!(
+            histogram_opts!("trin_storage_process_timer", "timer"),
+            &["function"],
+            registry
+        )
+        .unwrap();
+        let mut exporter = StatsdExporter::default();
+
+        counter.with_label_values(&["history", "ping"]).inc_by(3);
+        gauge.with_label_values(&["history"]).set(12);
+        histogram.with_label_values(&["get"]).observe(0.5);
+        let mut lines = exporter.lines(&registry.gather());
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "trin_message_total.protocol.history.type.ping:3|c",
+                "trin_routing_table_entries.protocol.history:12|g",
+                "trin_storage_process_timer_count.function.get:1|c",
+                "trin_storage_process_timer_sum.function.get:0.5|c",
+            ]
+        );
+
+        // Counters are reported as their increase, and only if they increased.
+        counter.with_label_values(&["history", "ping"]).inc_by(2);
+        gauge.with_label_values(&["history"]).set(-1);
+        let mut lines = exporter.lines(&registry.gather());
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "trin_message_total.protocol.history.type.ping:2|c",
+                "trin_routing_table_entries.protocol.history:-1|g",
+                "trin_routing_table_entries.protocol.history:0|g",
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_are_batched_into_packets() {
+        let registry = Registry::new();
+        let gauge = register_int_gauge_vec_with_registry!(
+            opts!("trin_routing_table_entries", "entries"),
+            &["index"],
+            registry
+        )
+        .unwrap();
+        for index in 0..200 {
+            gauge.with_label_values(&[&index.to_string()]).set(index);
+        }
+
+        let packets = StatsdExporter::default().packets(&registry.gather());
+        assert!(packets.len() > 1);
+        assert!(packets.iter().all(|packet| packet.len() <= MAX_PACKET_SIZE));
+        let lines = packets.iter().flat_map(|packet| packet.lines()).count();
+        assert_eq!(lines, 200);
+    }
+}
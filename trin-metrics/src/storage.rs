@@ -21,6 +21,8 @@ pub struct StorageMetrics {
     pub storage_capacity_bytes: GaugeVec,
     pub radius_ratio: GaugeVec,
     pub entry_count: IntGaugeVec,
+    pub content_type_entry_count: IntGaugeVec,
+    pub content_type_storage_usage_bytes: GaugeVec,
 }
 
 const BYTES_IN_MB_F64: f64 = 1000.0 * 1000.0;
@@ -72,6 +74,22 @@ impl StorageMetrics {
             &["protocol"],
             registry
         )?;
+        let content_type_entry_count = register_int_gauge_vec_with_registry!(
+            opts!(
+                "trin_content_type_entry_count",
+                "number of storage entries, per content key type"
+            ),
+            &["protocol", "content_type"],
+            registry
+        )?;
+        let content_type_storage_usage_bytes = register_gauge_vec_with_registry!(
+            opts!(
+                "trin_content_type_storage_usage_bytes",
+                "sum of size of individual content stored, per content key type, in bytes"
+            ),
+            &["protocol", "content_type"],
+            registry
+        )?;
         Ok(Self {
             process_timer,
             content_storage_usage_bytes,
@@ -79,6 +97,8 @@ impl StorageMetrics {
             storage_capacity_bytes,
             radius_ratio,
             entry_count,
+            content_type_entry_count,
+            content_type_storage_usage_bytes,
         })
     }
 }
@@ -156,6 +176,20 @@ impl StorageMetricsReporter {
             .set(count);
     }
 
+    pub fn report_content_type_usage(&self, content_type: &str, count: u64, bytes: f64) {
+        let count: i64 = count
+            .try_into()
+            .expect("Number of db entries will be small enough to fit in i64");
+        self.storage_metrics
+            .content_type_entry_count
+            .with_label_values(&[&self.protocol, content_type])
+            .set(count);
+        self.storage_metrics
+            .content_type_storage_usage_bytes
+            .with_label_values(&[&self.protocol, content_type])
+            .set(bytes);
+    }
+
     pub fn increase_entry_count(&self) {
         self.storage_metrics
             .entry_count
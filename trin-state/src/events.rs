@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use ethportal_api::types::portal_wire::Message;
-use portalnet::events::OverlayRequest;
+use portalnet::{events::OverlayRequest, overlay::errors::OverlayRequestError};
 use tokio::sync::mpsc::UnboundedReceiver;
-use tracing::{error, warn, Instrument};
+use tracing::{debug, error, warn, Instrument};
 
 use crate::network::StateNetwork;
 
@@ -37,6 +37,7 @@ impl StateEvents {
                 OverlayRequest::Event(event) => {
                     let _ = network.overlay.process_one_event(event).await;
                 }
+                OverlayRequest::Bootnodes(enrs) => network.overlay.add_bootnodes(enrs),
             }
         });
     }
@@ -51,6 +52,14 @@ impl StateEvents {
             .await
         {
             Ok(response) => Message::from(response).into(),
+            Err(OverlayRequestError::NodeDenied) => {
+                debug!(
+                    request.discv5.id = %talk_request_id,
+                    request.source = %request.node_id(),
+                    "Ignoring request from denied node, responding with empty TALKRESP"
+                );
+                "".into()
+            }
             Err(error) => {
                 error!(
                     error = %error,
@@ -6,7 +6,7 @@ use ethportal_api::{
     types::{
         distance::Distance,
         jsonrpc::{endpoints::StateEndpoint, request::StateJsonRpcRequest},
-        portal::{AcceptInfo, FindNodesInfo, GetContentInfo, PongInfo, TraceContentInfo},
+        portal::{AcceptInfo, GetContentInfo, PongInfo, TraceContentInfo},
         portal_wire::Content,
         query_trace::QueryTrace,
     },
@@ -44,8 +44,8 @@ impl StateRequestHandler {
             StateEndpoint::GetEnr(node_id) => get_enr(network, node_id),
             StateEndpoint::LookupEnr(node_id) => lookup_enr(network, node_id).await,
             StateEndpoint::FindNodes(enr, distances) => find_nodes(network, enr, distances).await,
-            StateEndpoint::RecursiveFindNodes(node_id) => {
-                recursive_find_nodes(network, node_id).await
+            StateEndpoint::RecursiveFindNodes(node_id, limit) => {
+                recursive_find_nodes(network, node_id, limit).await
             }
             StateEndpoint::DataRadius => radius(network),
             StateEndpoint::LocalContent(content_key) => local_content(network, content_key).await,
@@ -139,21 +139,16 @@ async fn find_nodes(
             .overlay
             .send_find_nodes(enr, distances)
             .await
-            .map(|nodes| {
-                nodes
-                    .enrs
-                    .into_iter()
-                    .map(Enr::from)
-                    .collect::<FindNodesInfo>()
-            }),
+            .map(|nodes| nodes.enrs),
     )
 }
 
 async fn recursive_find_nodes(
     network: Arc<StateNetwork>,
     node_id: NodeId,
+    limit: usize,
 ) -> Result<Value, String> {
-    let nodes = network.overlay.lookup_node(node_id).await;
+    let nodes = network.overlay.lookup_nodes(node_id, limit).await;
     Ok(json!(nodes))
 }
 
@@ -293,13 +288,14 @@ async fn store(
     content_key: StateContentKey,
     content_value: StateContentValue,
 ) -> Result<Value, String> {
+    if network.overlay.is_store_degraded() {
+        return Err("Content store is unavailable".to_string());
+    }
     to_json_result(
         "Store",
         network
             .overlay
-            .store
-            .write()
-            .put(content_key, content_value.encode())
+            .put_content(content_key, content_value.encode())
             .map(|_| true),
     )
 }
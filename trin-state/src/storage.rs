@@ -66,6 +66,14 @@ impl ContentStore for StateStorage {
     fn radius(&self) -> Distance {
         IdIndexedV1Store::radius(&self.store)
     }
+
+    fn check_health(&self) -> Result<(), ContentStoreError> {
+        self.store.check_health()
+    }
+
+    fn delete_expired(&mut self) -> Result<u64, ContentStoreError> {
+        self.store.delete_expired()
+    }
 }
 
 impl StateStorage {
@@ -185,9 +185,10 @@ mod tests {
         });
         let history_jsonrpc_tx = MockJsonRpcBuilder::new()
             .with_response(
-                HistoryEndpoint::GetContent(HistoryContentKey::new_block_header_by_hash(
-                    header.hash(),
-                )),
+                HistoryEndpoint::GetContent(
+                    HistoryContentKey::new_block_header_by_hash(header.hash()),
+                    None,
+                ),
                 GetContentInfo {
                     content: history_content_value.encode(),
                     utp_transfer: false,
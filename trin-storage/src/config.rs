@@ -1,7 +1,10 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 use discv5::enr::NodeId;
-use ethportal_api::types::{cli::StorageCapacityConfig, network::Subnetwork};
+use ethportal_api::types::{
+    cli::{StorageCapacityConfig, StorageQuota, DEFAULT_STORE_WARMUP_THREADS},
+    network::Subnetwork,
+};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 
@@ -15,6 +18,14 @@ pub struct PortalStorageConfigFactory {
     node_data_dir: PathBuf,
     capacity_config: StorageCapacityConfig,
     sql_connection_pool: Pool<SqliteConnectionManager>,
+    content_ttl: Option<Duration>,
+    recency_floor_blocks: Option<u64>,
+    verify_checksums: bool,
+    readonly_store_paths: Vec<PathBuf>,
+    keep_content: bool,
+    warmup_threads: usize,
+    storage_quotas: Vec<StorageQuota>,
+    serve_eviction_protection: Option<Duration>,
 }
 
 impl PortalStorageConfigFactory {
@@ -26,17 +37,81 @@ impl PortalStorageConfigFactory {
         capacity_config: StorageCapacityConfig,
         node_id: NodeId,
         node_data_dir: PathBuf,
+        enable_wal: bool,
     ) -> Result<Self, ContentStoreError> {
-        let sql_connection_pool = setup_sql(&node_data_dir)?;
+        let sql_connection_pool = setup_sql(&node_data_dir, enable_wal)?;
 
         Ok(Self {
             node_data_dir,
             node_id,
             capacity_config,
             sql_connection_pool,
+            content_ttl: None,
+            recency_floor_blocks: None,
+            verify_checksums: false,
+            readonly_store_paths: vec![],
+            keep_content: false,
+            warmup_threads: DEFAULT_STORE_WARMUP_THREADS,
+            storage_quotas: vec![],
+            serve_eviction_protection: None,
         })
     }
 
+    /// Sets the time after which content expires, in all created configs.
+    pub fn with_content_ttl(mut self, content_ttl: Option<Duration>) -> Self {
+        self.content_ttl = content_ttl;
+        self
+    }
+
+    /// Sets the number of most recent blocks whose content is never pruned, in all created
+    /// configs.
+    pub fn with_recency_floor_blocks(mut self, recency_floor_blocks: Option<u64>) -> Self {
+        self.recency_floor_blocks = recency_floor_blocks;
+        self
+    }
+
+    /// Sets whether content values are verified against their checksum when they are read, in
+    /// all created configs.
+    pub fn with_verify_checksums(mut self, verify_checksums: bool) -> Self {
+        self.verify_checksums = verify_checksums;
+        self
+    }
+
+    /// Sets the databases of the read-only stores that are consulted when content isn't stored,
+    /// in all created configs.
+    pub fn with_readonly_store_paths(mut self, readonly_store_paths: Vec<PathBuf>) -> Self {
+        self.readonly_store_paths = readonly_store_paths;
+        self
+    }
+
+    /// Sets whether stored content is kept instead of being pruned, in all created configs.
+    pub fn with_keep_content(mut self, keep_content: bool) -> Self {
+        self.keep_content = keep_content;
+        self
+    }
+
+    /// Sets the number of threads that scan the store in parallel on startup, in all created
+    /// configs.
+    pub fn with_warmup_threads(mut self, warmup_threads: usize) -> Self {
+        self.warmup_threads = warmup_threads;
+        self
+    }
+
+    /// Sets the share of the capacity reserved for each content type, in created History configs.
+    pub fn with_storage_quotas(mut self, storage_quotas: Vec<StorageQuota>) -> Self {
+        self.storage_quotas = storage_quotas;
+        self
+    }
+
+    /// Sets the window during which served content isn't pruned, in all created configs.
+    pub fn with_serve_eviction_protection(
+        mut self,
+        serve_eviction_protection: Option<Duration>,
+    ) -> Self {
+        self.serve_eviction_protection = serve_eviction_protection;
+        self
+    }
+
     pub fn create(
         &self,
         subnetwork: &Subnetwork,
@@ -83,6 +158,14 @@ impl PortalStorageConfigFactory {
                 }
             }
         };
+        let key_type_quotas = match subnetwork {
+            Subnetwork::History => self
+                .storage_quotas
+                .iter()
+                .map(|quota| (quota.content_type.key_prefix(), quota.percent))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
 
         Ok(PortalStorageConfig {
             storage_capacity_bytes: capacity_bytes,
@@ -90,6 +173,14 @@ impl PortalStorageConfigFactory {
             node_data_dir: self.node_data_dir.clone(),
             distance_fn: DistanceFunction::Xor,
             sql_connection_pool: self.sql_connection_pool.clone(),
+            content_ttl: self.content_ttl,
+            recency_floor_blocks: self.recency_floor_blocks,
+            verify_checksums: self.verify_checksums,
+            readonly_store_paths: self.readonly_store_paths.clone(),
+            keep_content: self.keep_content,
+            warmup_threads: self.warmup_threads,
+            key_type_quotas,
+            serve_eviction_protection: self.serve_eviction_protection,
         })
     }
 
@@ -110,6 +201,30 @@ pub struct PortalStorageConfig {
     pub node_data_dir: PathBuf,
     pub distance_fn: DistanceFunction,
     pub sql_connection_pool: Pool<SqliteConnectionManager>,
+    /// The time after which inserted content expires, if any.
+    pub content_ttl: Option<Duration>,
+    /// The number of most recent blocks whose content is never pruned, if any. Only applies to
+    /// content whose content key encodes a block number.
+    pub recency_floor_blocks: Option<u64>,
+    /// Whether content values are verified against their checksum when they are read. The
+    /// checksum is the hash of the value, under which it is stored in the blob table.
+    pub verify_checksums: bool,
+    /// The databases of the read-only stores that are consulted, in order, when content isn't
+    /// stored. Content is never written to them.
+    pub readonly_store_paths: Vec<PathBuf>,
+    /// Whether stored content is kept instead of being pruned. Once the store is full, the radius
+    /// shrinks to the farthest stored content, and new content is refused.
+    pub keep_content: bool,
+    /// The number of threads that scan the store in parallel on startup, each over an equally
+    /// sized range of content ids, to compute its usage stats.
+    pub warmup_threads: usize,
+    /// The percentage of the capacity reserved for each content key type, keyed by the first
+    /// byte of the content keys. Once the store is full, content of the key type furthest over
+    /// its quota is pruned first.
+    pub key_type_quotas: BTreeMap<u8, u8>,
+    /// The window during which content that was served to a peer isn't pruned, if any. Once only
+    /// such content remains, the store grows beyond its capacity.
+    pub serve_eviction_protection: Option<Duration>,
 }
 
 #[cfg(test)]
@@ -149,6 +264,7 @@ mod tests {
             },
             NodeId::random(),
             temp_dir.path().to_path_buf(),
+            false,
         )
         .unwrap();
         match expected_capacity_bytes {
@@ -175,6 +291,7 @@ mod tests {
             },
             NodeId::random(),
             temp_dir.path().to_path_buf(),
+            false,
         )
         .unwrap();
         assert_eq!(
@@ -212,6 +329,7 @@ mod tests {
             },
             NodeId::random(),
             temp_dir.path().to_path_buf(),
+            false,
         )
         .unwrap();
         assert_eq!(
@@ -243,6 +361,7 @@ mod tests {
             },
             NodeId::random(),
             temp_dir.path().to_path_buf(),
+            false,
         )
         .unwrap();
         assert_eq!(
@@ -1,3 +1,4 @@
+use alloy::primitives::B256;
 use ethportal_api::{
     types::{content_key::error::ContentKeyError, distance::Distance},
     utils::bytes::ByteUtilsError,
@@ -23,6 +24,25 @@ pub enum ContentStoreError {
         distance: Distance,
     },
 
+    /// Unable to store content because the store is full of content that is exempt from pruning.
+    #[error(
+        "storage capacity is exhausted by content of the most recent {recency_floor_blocks} blocks"
+    )]
+    RecentContentCapacityExhausted { recency_floor_blocks: u64 },
+
+    /// Unable to store content because the store is full, and stored content is kept rather than
+    /// pruned.
+    #[error("storage capacity is exhausted, and stored content is kept rather than pruned")]
+    CapacityExhausted,
+
+    /// Unable to retrieve data because the stored value doesn't match its checksum.
+    #[error("stored content {content_id} is corrupted: its value doesn't match its checksum")]
+    Corrupted { content_id: B256 },
+
+    /// Unable to retrieve data because the stored value has an encoding that isn't known.
+    #[error("stored content has unknown value encoding version {version}")]
+    UnknownValueEncoding { version: u8 },
+
     /// Unable to store or retrieve data because it is invalid.
     #[error("data invalid {message}")]
     InvalidData { message: String },
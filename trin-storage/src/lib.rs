@@ -73,6 +73,42 @@ pub trait ContentStore {
 
     /// Returns the radius of the data store.
     fn radius(&self) -> Distance;
+
+    /// Checks whether the data store is able to serve reads and writes.
+    ///
+    /// This is used to detect when a store that was failing has recovered. Stores that can't fail
+    /// are always healthy.
+    fn check_health(&self) -> Result<(), ContentStoreError> {
+        Ok(())
+    }
+
+    /// Records that the content denoted by `key` was served to a peer.
+    ///
+    /// Stores may use this to protect content that is in demand from being evicted. Stores that
+    /// don't do so ignore it.
+    fn record_served(&self, _key: &Self::Key) -> Result<(), ContentStoreError> {
+        Ok(())
+    }
+
+    /// Returns up to `limit` locally stored content items whose content ids are within `radius`
+    /// of `node_id`.
+    ///
+    /// This is used to offer content to peers, so the returned values should be in the same
+    /// format they are offered over the network. Stores that can't provide this return no items.
+    /// Deletes content that has expired, and returns the number of deleted entries.
+    fn delete_expired(&mut self) -> Result<u64, ContentStoreError> {
+        Ok(0)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn content_within_radius_of(
+        &self,
+        _node_id: &NodeId,
+        _radius: Distance,
+        _limit: usize,
+    ) -> Result<Vec<(Self::Key, RawContentValue)>, ContentStoreError> {
+        Ok(vec![])
+    }
 }
 
 /// An in-memory `ContentStore`.
@@ -154,6 +190,25 @@ impl ContentStore for MemoryContentStore {
     fn radius(&self) -> Distance {
         self.radius
     }
+
+    fn content_within_radius_of(
+        &self,
+        node_id: &NodeId,
+        radius: Distance,
+        limit: usize,
+    ) -> Result<Vec<(Self::Key, RawContentValue)>, ContentStoreError> {
+        let content = self
+            .store
+            .iter()
+            .filter_map(|(content_id, value)| {
+                let content_id: [u8; 32] = content_id.as_slice().try_into().ok()?;
+                (self.distance_fn.distance(node_id, &content_id) <= radius)
+                    .then(|| (IdentityContentKey::new(content_id), value.clone()))
+            })
+            .take(limit)
+            .collect();
+        Ok(content)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -283,6 +338,28 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn memory_store_content_within_radius_of() {
+        let node_id = NodeId::random();
+        let mut store = MemoryContentStore::new(node_id, DistanceFunction::Xor);
+
+        let val = bytes!("ef");
+
+        // Content at the node id is within any radius of the node.
+        let near_key = IdentityContentKey::new(node_id.raw());
+        let _ = store.put(near_key.clone(), val.clone());
+        let content = store
+            .content_within_radius_of(&node_id, Distance::ZERO, 10)
+            .unwrap();
+        assert_eq!(content, vec![(near_key, val)]);
+
+        // Nothing is returned when the limit is zero.
+        let content = store
+            .content_within_radius_of(&node_id, Distance::MAX, 0)
+            .unwrap();
+        assert!(content.is_empty());
+    }
+
     #[test]
     fn content_id_from_blob() {
         let content_id = ContentId(B256::random());
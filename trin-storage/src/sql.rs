@@ -77,3 +77,14 @@ pub const HISTORICAL_SUMMARIES_EPOCH_LOOKUP_QUERY: &str =
 
 // todo: remove this in the future
 pub const DROP_USAGE_STATS_DB: &str = "DROP TABLE IF EXISTS usage_stats;";
+
+/// Query to get the size of the database file, and the size of its free pages, which are reused
+/// before the file grows.
+pub const DATABASE_SIZE_QUERY: &str = "SELECT
+    page_count * page_size AS size,
+    freelist_count * page_size AS free_size
+    FROM pragma_page_count(), pragma_page_size(), pragma_freelist_count()";
+
+/// Flushes the write-ahead log, if there is one, into the database file, and truncates it. It is a
+/// no-op in rollback journal mode, where writes are durable once their transaction commits.
+pub const WAL_CHECKPOINT: &str = "PRAGMA wal_checkpoint(TRUNCATE)";
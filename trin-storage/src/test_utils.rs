@@ -16,6 +16,7 @@ pub fn create_test_portal_storage_config_with_capacity(
         },
         NodeId::random(),
         temp_dir.path().to_path_buf(),
+        false,
     )
     .unwrap()
     .create(&Subnetwork::History)
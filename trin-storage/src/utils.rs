@@ -2,7 +2,7 @@ use std::{fs, path::Path};
 
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
     error::ContentStoreError,
@@ -15,13 +15,37 @@ use crate::{
 };
 
 /// Helper function for opening a SQLite connection.
-pub fn setup_sql(node_data_dir: &Path) -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
+///
+/// If `enable_wal` is set, the database uses a write-ahead log: writes are appended to the log
+/// before being applied to the database file, and the log is replayed when the database is opened
+/// after a crash. The log is truncated whenever it's checkpointed into the database file.
+/// Otherwise, the database uses the default rollback journal.
+pub fn setup_sql(
+    node_data_dir: &Path,
+    enable_wal: bool,
+) -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
     let sql_path = node_data_dir.join(DATABASE_NAME);
-    info!(path = %sql_path.display(), "Setting up SqliteDB");
+    info!(path = %sql_path.display(), enable_wal, "Setting up SqliteDB");
 
-    let manager = SqliteConnectionManager::file(sql_path);
+    // The size limit of the log is set per connection, while the journal mode is persisted in the
+    // database file.
+    let manager = SqliteConnectionManager::file(sql_path).with_init(move |conn| {
+        if enable_wal {
+            conn.pragma_update_and_check(None, "journal_size_limit", 0, |_| Ok(()))?;
+        }
+        Ok(())
+    });
     let pool = Pool::new(manager)?;
     let conn = pool.get()?;
+    let journal_mode = if enable_wal { "wal" } else { "delete" };
+    let applied_journal_mode: String =
+        conn.pragma_update_and_check(None, "journal_mode", journal_mode, |row| row.get(0))?;
+    if !applied_journal_mode.eq_ignore_ascii_case(journal_mode) {
+        warn!(
+            journal_mode,
+            applied_journal_mode, "Unable to set the journal mode of SqliteDB"
+        );
+    }
     conn.execute_batch(LC_BOOTSTRAP_CREATE_TABLE)?;
     conn.execute_batch(LC_UPDATE_CREATE_TABLE)?;
     conn.execute_batch(HISTORICAL_SUMMARIES_CREATE_TABLE)?;
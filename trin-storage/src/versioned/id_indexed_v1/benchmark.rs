@@ -0,0 +1,149 @@
+use std::time::{Duration, Instant};
+
+use alloy::primitives::{keccak256, B256};
+use ethportal_api::types::portal::{StoreBenchmarkInfo, StoreThroughputInfo};
+use rusqlite::Connection;
+
+use super::{
+    sql,
+    store::{
+        create_table_and_indexes, decode_stored_value, query_stored_value, write_content,
+        ContentRow,
+    },
+    IdIndexedV1StoreConfig,
+};
+use crate::{error::ContentStoreError, ContentId};
+
+/// The prefix of the synthetic content keys written by a store benchmark.
+const BENCHMARK_CONTENT_KEY_PREFIX: &[u8] = b"trin-store-benchmark:";
+
+/// The max number of entries written by a store benchmark.
+pub const MAX_BENCHMARK_ENTRY_COUNT: u64 = 100_000;
+
+/// The max total size of the values written by a store benchmark.
+pub const MAX_BENCHMARK_TOTAL_SIZE_BYTES: u64 = 1_000_000_000;
+
+/// Measures the write and read throughput of the database of a store.
+///
+/// Each run writes synthetic entries to its own tables, that have the same schema as the content
+/// tables, with the same statements that the store uses to write and read content, and drops the
+/// tables afterwards. Stored content is left untouched, runs don't interfere with each other and
+/// the store doesn't need to be held while they run.
+#[derive(Clone, Debug)]
+pub struct StoreBenchmark {
+    config: IdIndexedV1StoreConfig,
+}
+
+impl StoreBenchmark {
+    pub fn new(config: IdIndexedV1StoreConfig) -> Self {
+        Self { config }
+    }
+
+    /// Writes `entry_count` synthetic entries with distinct values of `value_size` bytes and reads
+    /// them back.
+    pub fn run(
+        &self,
+        entry_count: u64,
+        value_size: u64,
+    ) -> Result<StoreBenchmarkInfo, ContentStoreError> {
+        if entry_count == 0 || entry_count > MAX_BENCHMARK_ENTRY_COUNT {
+            return Err(ContentStoreError::InvalidData {
+                message: format!(
+                    "benchmark entry count must be between 1 and {MAX_BENCHMARK_ENTRY_COUNT}"
+                ),
+            });
+        }
+        let total_size = entry_count
+            .checked_mul(value_size)
+            .filter(|total_size| *total_size <= MAX_BENCHMARK_TOTAL_SIZE_BYTES)
+            .ok_or_else(|| ContentStoreError::InvalidData {
+                message: format!(
+                    "benchmark total value size must be at most {MAX_BENCHMARK_TOTAL_SIZE_BYTES} bytes"
+                ),
+            })?;
+
+        let content_type = &self.config.content_type;
+        let run_id = rand::random();
+        let rewrite = |statement: String| sql::for_benchmark(&statement, content_type, run_id);
+
+        let mut conn = self.config.sql_connection_pool.get()?;
+        let durations = create_table_and_indexes(&conn, content_type, &rewrite)
+            .and_then(|()| self.run_on_tables(&mut conn, &rewrite, entry_count, value_size));
+        // The tables are dropped even if the benchmark failed.
+        conn.execute_batch(&sql::drop_benchmark_tables(content_type, run_id))?;
+        let (write_duration, read_duration) = durations?;
+
+        Ok(StoreBenchmarkInfo {
+            entry_count,
+            value_size,
+            write: StoreThroughputInfo::new(entry_count, total_size, write_duration),
+            read: StoreThroughputInfo::new(entry_count, total_size, read_duration),
+        })
+    }
+
+    /// Writes and reads back the synthetic entries on the tables that `rewrite` renames the
+    /// content tables to, and returns how long writing and reading took.
+    fn run_on_tables(
+        &self,
+        conn: &mut Connection,
+        rewrite: &dyn Fn(String) -> String,
+        entry_count: u64,
+        value_size: u64,
+    ) -> Result<(Duration, Duration), ContentStoreError> {
+        let content_type = &self.config.content_type;
+        let entries: Vec<(B256, Vec<u8>)> = (0..entry_count)
+            .map(|index| {
+                let content_key = [BENCHMARK_CONTENT_KEY_PREFIX, &index.to_be_bytes()].concat();
+                (keccak256(&content_key), content_key)
+            })
+            .collect();
+        let mut content_value: Vec<u8> = (0..value_size).map(|_| rand::random::<u8>()).collect();
+
+        let write_start = Instant::now();
+        for (index, (content_id, content_key)) in entries.iter().enumerate() {
+            // Each value is made distinct, so that values aren't deduplicated.
+            let index = (index as u64).to_be_bytes();
+            let prefix_len = index.len().min(content_value.len());
+            content_value[..prefix_len].copy_from_slice(&index[..prefix_len]);
+
+            write_content(
+                conn,
+                content_type,
+                rewrite,
+                &ContentRow {
+                    content_id: content_id.as_slice(),
+                    content_key,
+                    distance: self
+                        .config
+                        .distance_fn
+                        .distance(&self.config.node_id, &content_id.0),
+                    content_size: content_id.len() + content_key.len() + content_value.len(),
+                    content_value: &content_value,
+                    block_number: None,
+                },
+            )?;
+        }
+        let write_duration = write_start.elapsed();
+
+        let lookup_value = rewrite(sql::lookup_value(content_type));
+        let read_start = Instant::now();
+        for (content_id, _) in &entries {
+            let content_id = ContentId::from(*content_id);
+            let stored_value =
+                query_stored_value(conn, &lookup_value, &content_id)?.ok_or_else(|| {
+                    ContentStoreError::Database("benchmark entry wasn't written".to_string())
+                })?;
+            let value =
+                decode_stored_value(stored_value, &content_id, self.config.verify_checksums)?;
+            if value.len() as u64 != value_size {
+                return Err(ContentStoreError::Database(format!(
+                    "benchmark read {} bytes instead of {value_size}",
+                    value.len()
+                )));
+            }
+        }
+        let read_duration = read_start.elapsed();
+
+        Ok((write_duration, read_duration))
+    }
+}
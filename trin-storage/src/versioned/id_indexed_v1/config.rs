@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 use discv5::enr::NodeId;
 use ethportal_api::types::network::Subnetwork;
@@ -19,6 +19,23 @@ pub struct IdIndexedV1StoreConfig {
     pub sql_connection_pool: Pool<SqliteConnectionManager>,
     pub distance_fn: DistanceFunction,
     pub pruning_config: PruningConfig,
+    /// The time after which inserted content expires, if any.
+    pub content_ttl: Option<Duration>,
+    /// The number of most recent blocks whose content is never pruned, if any.
+    pub recency_floor_blocks: Option<u64>,
+    /// Whether content values are verified against their checksum when they are read.
+    pub verify_checksums: bool,
+    /// The databases of the read-only stores that are consulted when content isn't stored.
+    pub readonly_store_paths: Vec<PathBuf>,
+    /// Whether stored content is kept instead of being pruned, and new content refused once the
+    /// store is full.
+    pub keep_content: bool,
+    /// The number of threads that scan the store in parallel on startup.
+    pub warmup_threads: usize,
+    /// The percentage of the capacity reserved for each content key type.
+    pub key_type_quotas: BTreeMap<u8, u8>,
+    /// The window during which content that was served to a peer isn't pruned, if any.
+    pub serve_eviction_protection: Option<Duration>,
 }
 
 impl IdIndexedV1StoreConfig {
@@ -37,6 +54,14 @@ impl IdIndexedV1StoreConfig {
             distance_fn: config.distance_fn,
             // consider making this a parameter if we start using non-default value
             pruning_config: PruningConfig::default(),
+            content_ttl: config.content_ttl,
+            recency_floor_blocks: config.recency_floor_blocks,
+            verify_checksums: config.verify_checksums,
+            readonly_store_paths: config.readonly_store_paths,
+            keep_content: config.keep_content,
+            warmup_threads: config.warmup_threads,
+            key_type_quotas: config.key_type_quotas,
+            serve_eviction_protection: config.serve_eviction_protection,
         }
     }
 }
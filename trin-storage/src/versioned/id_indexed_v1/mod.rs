@@ -1,8 +1,12 @@
+mod benchmark;
 mod config;
 mod migration;
 mod pruning_strategy;
+mod readonly_store;
 pub(super) mod sql;
 mod store;
+mod value_encoding;
 
+pub use benchmark::StoreBenchmark;
 pub use config::IdIndexedV1StoreConfig;
 pub use store::IdIndexedV1Store;
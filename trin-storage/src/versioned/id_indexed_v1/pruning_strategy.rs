@@ -193,7 +193,7 @@ impl Debug for PruningStrategy {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{collections::BTreeMap, path::PathBuf};
 
     use discv5::enr::NodeId;
     use ethportal_api::types::network::Subnetwork;
@@ -220,6 +220,14 @@ mod tests {
             sql_connection_pool: Pool::new(SqliteConnectionManager::memory()).unwrap(),
             distance_fn: DistanceFunction::Xor,
             pruning_config: PruningConfig::default(),
+            content_ttl: None,
+            recency_floor_blocks: None,
+            verify_checksums: false,
+            readonly_store_paths: vec![],
+            keep_content: false,
+            warmup_threads: 1,
+            key_type_quotas: BTreeMap::new(),
+            serve_eviction_protection: None,
         };
         PruningStrategy::new(config)
     }
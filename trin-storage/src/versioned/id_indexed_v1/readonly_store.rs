@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use ethportal_api::{types::portal::RawStoredBytesInfo, RawContentValue};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::OpenFlags;
+use tracing::info;
+
+use super::{
+    sql,
+    store::{read_content_value, read_stored_value},
+};
+use crate::{error::ContentStoreError, versioned::ContentType, ContentId};
+
+/// A read-only store, backed by the database of another node, e.g. a large immutable archive.
+///
+/// It's only consulted when content isn't found in the writable store, and is never written to.
+/// The database must have been created by the same kind of store.
+#[derive(Debug)]
+pub struct ReadOnlyStore {
+    content_type: ContentType,
+    sql_connection_pool: Pool<SqliteConnectionManager>,
+}
+
+impl ReadOnlyStore {
+    /// Opens the database at `path` in read-only mode.
+    ///
+    /// It returns an error if the database doesn't exist, or doesn't hold a table for the
+    /// `content_type`.
+    pub fn open(path: &Path, content_type: ContentType) -> Result<Self, ContentStoreError> {
+        if !path.is_file() {
+            return Err(ContentStoreError::Database(format!(
+                "Read-only store {} doesn't exist",
+                path.display()
+            )));
+        }
+        info!(path = %path.display(), %content_type, "Opening read-only store");
+
+        let manager = SqliteConnectionManager::file(path)
+            .with_flags(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX);
+        let sql_connection_pool = Pool::new(manager)?;
+        // Fails early if the table doesn't exist or has an unexpected schema.
+        sql_connection_pool
+            .get()?
+            .prepare(&sql::lookup_value(&content_type))?;
+
+        Ok(Self {
+            content_type,
+            sql_connection_pool,
+        })
+    }
+
+    /// Returns the content value of the `content_id`, if stored.
+    pub fn lookup_content_value(
+        &self,
+        content_id: &ContentId,
+        verify_checksums: bool,
+    ) -> Result<Option<RawContentValue>, ContentStoreError> {
+        read_content_value(
+            &self.sql_connection_pool.get()?,
+            &self.content_type,
+            content_id,
+            verify_checksums,
+        )
+    }
+
+    /// Returns the content value of the `content_id` as it's stored, without decoding it, if
+    /// stored.
+    pub fn lookup_stored_value(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<RawStoredBytesInfo>, ContentStoreError> {
+        read_stored_value(
+            &self.sql_connection_pool.get()?,
+            &self.content_type,
+            content_id,
+        )
+    }
+}
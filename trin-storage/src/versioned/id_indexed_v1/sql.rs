@@ -5,6 +5,28 @@ pub fn table_name(content_type: &ContentType) -> String {
     format!("ii1_{content_type}")
 }
 
+/// The name of the sql table that holds content values, shared between all content keys that
+/// have the same value.
+pub fn blob_table_name(content_type: &ContentType) -> String {
+    format!("ii1_{content_type}_blob")
+}
+
+/// The name of the sql table that holds the synthetic entries of the store benchmark run
+/// `run_id`. Each run has its own tables, so concurrent runs don't interfere with each other.
+pub fn benchmark_table_name(content_type: &ContentType, run_id: u64) -> String {
+    format!("ii1_{content_type}_benchmark_{run_id:016x}")
+}
+
+/// Rewrites the `statement` to use the tables of the store benchmark run `run_id` instead of the
+/// content tables. The names of all tables, indexes and triggers start with the name of the
+/// content table, so they are all renamed.
+pub fn for_benchmark(statement: &str, content_type: &ContentType, run_id: u64) -> String {
+    statement.replace(
+        &table_name(content_type),
+        &benchmark_table_name(content_type, run_id),
+    )
+}
+
 pub fn create_table(content_type: &ContentType) -> String {
     format!(
         "
@@ -22,6 +44,192 @@ pub fn create_table(content_type: &ContentType) -> String {
     )
 }
 
+/// Checks whether the content table has the `blob_hash` column.
+pub fn has_blob_hash_column(content_type: &ContentType) -> String {
+    format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'blob_hash'",
+        table_name(content_type)
+    )
+}
+
+/// Adds the `blob_hash` column to the content table.
+///
+/// Rows that were inserted before the column existed keep their value inline and have `NULL`
+/// blob hash.
+pub fn add_blob_hash_column(content_type: &ContentType) -> String {
+    format!(
+        "ALTER TABLE {} ADD COLUMN blob_hash BLOB",
+        table_name(content_type)
+    )
+}
+
+/// Checks whether the content table has the `inserted_at` column.
+pub fn has_inserted_at_column(content_type: &ContentType) -> String {
+    format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'inserted_at'",
+        table_name(content_type)
+    )
+}
+
+/// Adds the `inserted_at` column, holding the unix timestamp (in seconds) at which the content
+/// was inserted, to the content table.
+///
+/// Rows that were inserted before the column existed are considered inserted now.
+pub fn add_inserted_at_column(content_type: &ContentType) -> String {
+    format!(
+        "
+        ALTER TABLE {0} ADD COLUMN inserted_at INTEGER;
+        UPDATE {0} SET inserted_at = CAST(strftime('%s', 'now') AS INTEGER);
+        CREATE INDEX IF NOT EXISTS {0}_inserted_at_idx ON {0} (inserted_at);
+        ",
+        table_name(content_type)
+    )
+}
+
+/// Checks whether the content table has the `block_number` column.
+pub fn has_block_number_column(content_type: &ContentType) -> String {
+    format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'block_number'",
+        table_name(content_type)
+    )
+}
+
+/// Adds the `block_number` column, holding the number of the block that the content belongs to if
+/// its content key encodes it, to the content table.
+///
+/// Rows that were inserted before the column existed have `NULL` block number.
+pub fn add_block_number_column(content_type: &ContentType) -> String {
+    format!(
+        "
+        ALTER TABLE {0} ADD COLUMN block_number INTEGER;
+        CREATE INDEX IF NOT EXISTS {0}_block_number_idx ON {0} (block_number);
+        ",
+        table_name(content_type)
+    )
+}
+
+/// Checks whether the content table has the `served_at` column.
+pub fn has_served_at_column(content_type: &ContentType) -> String {
+    format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'served_at'",
+        table_name(content_type)
+    )
+}
+
+/// Adds the `served_at` column, holding the unix timestamp (in seconds) at which the content was
+/// last served to a peer, to the content table.
+///
+/// Content that was never served, or only while serve eviction protection was disabled, has
+/// `NULL` serve time.
+pub fn add_served_at_column(content_type: &ContentType) -> String {
+    format!(
+        "ALTER TABLE {} ADD COLUMN served_at INTEGER",
+        table_name(content_type)
+    )
+}
+
+/// Indexes the content by the first byte of its content key, which identifies its type, and its
+/// distance, so that [delete_farthest_of_key_type] doesn't scan the whole table.
+pub fn create_key_type_distance_short_index(content_type: &ContentType) -> String {
+    format!(
+        "CREATE INDEX IF NOT EXISTS {0}_key_type_distance_short_idx
+        ON {0} (substr(content_key, 1, 1), distance_short)",
+        table_name(content_type)
+    )
+}
+
+/// Checks whether the blob table has the `value_encoding` column.
+pub fn has_value_encoding_column(content_type: &ContentType) -> String {
+    format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'value_encoding'",
+        blob_table_name(content_type)
+    )
+}
+
+/// Adds the `value_encoding` column, holding the version of the encoding of the content value, to
+/// the blob table.
+///
+/// Blobs that were inserted before the column existed are stored raw, which is version 0.
+pub fn add_value_encoding_column(content_type: &ContentType) -> String {
+    format!(
+        "ALTER TABLE {} ADD COLUMN value_encoding INTEGER NOT NULL DEFAULT 0",
+        blob_table_name(content_type)
+    )
+}
+
+/// Creates the blob table and the triggers that keep its reference counts in sync with the
+/// content table.
+///
+/// A blob is deleted as soon as no content row references it anymore, regardless of how the
+/// content row was deleted.
+pub fn create_blob_table(content_type: &ContentType) -> String {
+    format!(
+        "
+        CREATE TABLE IF NOT EXISTS {1} (
+            blob_hash BLOB PRIMARY KEY,
+            content_value BLOB NOT NULL,
+            ref_count INTEGER NOT NULL
+        );
+        CREATE TRIGGER IF NOT EXISTS {0}_blob_acquire_trigger
+        AFTER INSERT ON {0}
+        WHEN NEW.blob_hash IS NOT NULL
+        BEGIN
+            UPDATE {1} SET ref_count = ref_count + 1 WHERE blob_hash = NEW.blob_hash;
+        END;
+        CREATE TRIGGER IF NOT EXISTS {0}_blob_release_trigger
+        AFTER DELETE ON {0}
+        WHEN OLD.blob_hash IS NOT NULL
+        BEGIN
+            UPDATE {1} SET ref_count = ref_count - 1 WHERE blob_hash = OLD.blob_hash;
+            DELETE FROM {1} WHERE blob_hash = OLD.blob_hash AND ref_count <= 0;
+        END;
+        ",
+        table_name(content_type),
+        blob_table_name(content_type),
+    )
+}
+
+/// Inserts the blob without any references, unless it already exists.
+pub fn insert_blob(content_type: &ContentType) -> String {
+    format!(
+        "INSERT OR IGNORE INTO {} (blob_hash, content_value, value_encoding, ref_count)
+        VALUES (:blob_hash, :content_value, :value_encoding, 0)",
+        blob_table_name(content_type)
+    )
+}
+
+/// Deletes the blob if it isn't referenced by any content row.
+pub fn delete_unreferenced_blob(content_type: &ContentType) -> String {
+    format!(
+        "DELETE FROM {} WHERE blob_hash = :blob_hash AND ref_count <= 0",
+        blob_table_name(content_type)
+    )
+}
+
+/// The content value of the row, either stored inline or in the blob table.
+fn content_value_column(content_type: &ContentType) -> String {
+    format!(
+        "CASE
+            WHEN {0}.blob_hash IS NULL THEN {0}.content_value
+            ELSE (SELECT content_value FROM {1} WHERE {1}.blob_hash = {0}.blob_hash)
+        END AS content_value",
+        table_name(content_type),
+        blob_table_name(content_type),
+    )
+}
+
+/// The version of the encoding of the content value of the row. Values stored inline are raw.
+fn value_encoding_column(content_type: &ContentType) -> String {
+    format!(
+        "CASE
+            WHEN {0}.blob_hash IS NULL THEN 0
+            ELSE (SELECT value_encoding FROM {1} WHERE {1}.blob_hash = {0}.blob_hash)
+        END AS value_encoding",
+        table_name(content_type),
+        blob_table_name(content_type),
+    )
+}
+
 pub fn insert(content_type: &ContentType) -> String {
     format!(
         "
@@ -30,14 +238,20 @@ pub fn insert(content_type: &ContentType) -> String {
             content_key,
             content_value,
             distance_short,
-            content_size
+            content_size,
+            blob_hash,
+            inserted_at,
+            block_number
         )
         VALUES (
             :content_id,
             :content_key,
-            :content_value,
+            x'',
             :distance_short,
-            :content_size
+            :content_size,
+            :blob_hash,
+            :inserted_at,
+            :block_number
         )",
         table_name(content_type)
     )
@@ -47,7 +261,23 @@ pub fn delete(content_type: &ContentType) -> String {
     format!(
         "DELETE FROM {}
         WHERE content_id = :content_id
-        RETURNING content_size",
+        RETURNING content_key, content_size",
+        table_name(content_type)
+    )
+}
+
+pub fn update_served_at(content_type: &ContentType) -> String {
+    format!(
+        "UPDATE {} SET served_at = :served_at WHERE content_id = :content_id",
+        table_name(content_type)
+    )
+}
+
+pub fn delete_inserted_before(content_type: &ContentType) -> String {
+    format!(
+        "DELETE FROM {}
+        WHERE inserted_at < :inserted_before
+        RETURNING content_key, content_size",
         table_name(content_type)
     )
 }
@@ -59,24 +289,102 @@ pub fn lookup_key(content_type: &ContentType) -> String {
     )
 }
 
+/// Looks up the content value, along with the version of its encoding and its blob hash, which is
+/// `NULL` if the value is stored inline.
 pub fn lookup_value(content_type: &ContentType) -> String {
     format!(
-        "SELECT content_value FROM {} WHERE content_id = :content_id LIMIT 1",
-        table_name(content_type)
+        "SELECT {1}, {2}, {0}.blob_hash AS blob_hash
+        FROM {0}
+        WHERE content_id = :content_id
+        LIMIT 1",
+        table_name(content_type),
+        content_value_column(content_type),
+        value_encoding_column(content_type),
+    )
+}
+
+/// Returns whether any content can be pruned, i.e. isn't of a block numbered
+/// `:exempt_from_block_number` or higher. Nothing is exempt if that parameter is `NULL`.
+pub fn has_prunable_content(content_type: &ContentType) -> String {
+    format!(
+        "SELECT EXISTS(
+            SELECT 1
+            FROM {}
+            WHERE :exempt_from_block_number IS NULL
+                OR block_number IS NULL
+                OR block_number < :exempt_from_block_number
+        ) AS has_prunable_content",
+        table_name(content_type),
     )
 }
 
+/// Deletes the farthest content, except content of blocks numbered `:exempt_from_block_number` or
+/// higher, and content served at `:protected_served_since` or later. Nothing is exempt if the
+/// respective parameter is `NULL`.
 pub fn delete_farthest(content_type: &ContentType) -> String {
     format!(
         "DELETE FROM {0}
         WHERE rowid IN (
             SELECT rowid
             FROM {0}
+            WHERE (:exempt_from_block_number IS NULL
+                    OR block_number IS NULL
+                    OR block_number < :exempt_from_block_number)
+                AND (:protected_served_since IS NULL
+                    OR served_at IS NULL
+                    OR served_at < :protected_served_since)
             ORDER BY distance_short DESC
             LIMIT :limit
         )
-        RETURNING content_key, content_value, content_size",
-        table_name(content_type)
+        RETURNING content_key, {1}, {2}, content_size",
+        table_name(content_type),
+        content_value_column(content_type),
+        value_encoding_column(content_type),
+    )
+}
+
+/// Same as [delete_farthest], but only deletes content whose content key starts with the
+/// `:key_type` byte. The key type is matched with the same expression as the one indexed by
+/// [create_key_type_distance_short_index], so that the index is used.
+pub fn delete_farthest_of_key_type(content_type: &ContentType) -> String {
+    format!(
+        "DELETE FROM {0}
+        WHERE rowid IN (
+            SELECT rowid
+            FROM {0}
+            WHERE substr(content_key, 1, 1) = :key_type
+                AND (:exempt_from_block_number IS NULL
+                    OR block_number IS NULL
+                    OR block_number < :exempt_from_block_number)
+                AND (:protected_served_since IS NULL
+                    OR served_at IS NULL
+                    OR served_at < :protected_served_since)
+            ORDER BY distance_short DESC
+            LIMIT :limit
+        )
+        RETURNING content_key, {1}, {2}, content_size",
+        table_name(content_type),
+        content_value_column(content_type),
+        value_encoding_column(content_type),
+    )
+}
+
+/// Deletes up to `:limit` of the farthest content, whose distance is greater than
+/// `:distance_short` in its 32 most significant bits.
+pub fn delete_farther_than(content_type: &ContentType) -> String {
+    format!(
+        "DELETE FROM {0}
+        WHERE rowid IN (
+            SELECT rowid
+            FROM {0}
+            WHERE distance_short > :distance_short
+            ORDER BY distance_short DESC
+            LIMIT :limit
+        )
+        RETURNING content_key, {1}, {2}, content_size",
+        table_name(content_type),
+        content_value_column(content_type),
+        value_encoding_column(content_type),
     )
 }
 
@@ -89,6 +397,38 @@ pub fn lookup_farthest(content_type: &ContentType) -> String {
     )
 }
 
+pub fn lookup_max_block_number(content_type: &ContentType) -> String {
+    format!(
+        "SELECT MAX(block_number) AS max_block_number FROM {}",
+        table_name(content_type)
+    )
+}
+
+pub fn lookup_closest(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_id, content_key, {}, {} FROM {}
+        ORDER BY distance_short
+        LIMIT :limit",
+        content_value_column(content_type),
+        value_encoding_column(content_type),
+        table_name(content_type)
+    )
+}
+
+/// Returns up to `:limit` entries whose content ids are greater than `:after_content_id`, in
+/// content id order. An empty `:after_content_id` returns entries from the first content id.
+pub fn export_page(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_id, content_key, {}, {} FROM {}
+        WHERE content_id > :after_content_id
+        ORDER BY content_id
+        LIMIT :limit",
+        content_value_column(content_type),
+        value_encoding_column(content_type),
+        table_name(content_type)
+    )
+}
+
 pub fn paginate(content_type: &ContentType) -> String {
     format!(
         "SELECT content_key FROM {}
@@ -99,9 +439,75 @@ pub fn paginate(content_type: &ContentType) -> String {
     )
 }
 
-pub fn entry_count_and_size(content_type: &ContentType) -> String {
+pub fn lookup_keys_with_prefix(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_key FROM {}
+        WHERE substr(content_key, 1, length(:prefix)) = :prefix",
+        table_name(content_type)
+    )
+}
+
+pub fn lookup_ids_and_keys(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_id, content_key FROM {}",
+        table_name(content_type)
+    )
+}
+
+pub fn lookup_any(content_type: &ContentType) -> String {
+    format!(
+        "SELECT content_id FROM {} LIMIT 1",
+        table_name(content_type)
+    )
+}
+
+/// Groups the entries by the 32 most significant bits of their distance.
+pub fn entry_count_per_distance_short(content_type: &ContentType) -> String {
+    format!(
+        "SELECT distance_short, COUNT(*) as count FROM {} GROUP BY distance_short",
+        table_name(content_type)
+    )
+}
+
+/// Groups the entries whose content id is in the range from `:start_content_id` (inclusive) to
+/// `:end_content_id` (exclusive) by the first byte of the content key, which identifies its type.
+pub fn entry_count_and_size_per_key_type(content_type: &ContentType) -> String {
     format!(
-        "SELECT COUNT(*) as count, TOTAL(content_size) as used_capacity FROM {}",
+        "SELECT substr(content_key, 1, 1) as key_type, COUNT(*) as count, TOTAL(content_size) as used_capacity
+        FROM {}
+        WHERE content_id >= :start_content_id AND content_id < :end_content_id
+        GROUP BY key_type",
         table_name(content_type)
     )
 }
+
+/// Returns the total size of content values as referenced by content keys, and the total size of
+/// content values actually stored. Values stored inline count towards both.
+pub fn value_size_referenced_and_stored(content_type: &ContentType) -> String {
+    format!(
+        "SELECT
+            inline.size + blobs.referenced_size AS referenced_size,
+            inline.size + blobs.stored_size AS stored_size
+        FROM
+            (SELECT TOTAL(length(content_value)) AS size FROM {0}) AS inline,
+            (
+                SELECT
+                    TOTAL(length(content_value) * ref_count) AS referenced_size,
+                    TOTAL(length(content_value)) AS stored_size
+                FROM {1}
+            ) AS blobs",
+        table_name(content_type),
+        blob_table_name(content_type),
+    )
+}
+
+/// Drops the tables of the store benchmark run `run_id`, along with their indexes and triggers.
+pub fn drop_benchmark_tables(content_type: &ContentType, run_id: u64) -> String {
+    format!(
+        "
+        DROP TABLE IF EXISTS {0};
+        DROP TABLE IF EXISTS {0}_blob;
+        ",
+        benchmark_table_name(content_type, run_id)
+    )
+}
@@ -1,23 +1,57 @@
-use std::marker::PhantomData;
+use std::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+    marker::PhantomData,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
-use ethportal_api::{types::distance::Distance, OverlayContentKey, RawContentValue};
+use alloy::primitives::keccak256;
+use discv5::enr::NodeId;
+use ethportal_api::{
+    types::{
+        distance::Distance,
+        portal::{RadiusChangeInfo, RawStoredBytesInfo},
+    },
+    OverlayContentKey, RawContentValue,
+};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{named_params, types::Type, OptionalExtension};
-use tracing::{debug, error, warn};
+use rusqlite::{
+    named_params, types::Type, Connection, OptionalExtension, Row, TransactionBehavior,
+};
+use tracing::{debug, error, info, warn};
 use trin_metrics::storage::StorageMetricsReporter;
 
 use super::{
-    migration::migrate_legacy_history_store, pruning_strategy::PruningStrategy, sql,
-    IdIndexedV1StoreConfig,
+    benchmark::StoreBenchmark, migration::migrate_legacy_history_store,
+    pruning_strategy::PruningStrategy, readonly_store::ReadOnlyStore, sql,
+    value_encoding::ValueEncoding, IdIndexedV1StoreConfig,
 };
 use crate::{
     error::ContentStoreError,
+    sql::{DATABASE_SIZE_QUERY, WAL_CHECKPOINT},
     utils::get_total_size_of_directory_in_bytes,
     versioned::{usage_stats::UsageStats, ContentType, StoreVersion, VersionedContentStore},
     ContentId,
 };
 
+/// The max number of radius changes that are retained. Once it's reached, the oldest change is
+/// dropped.
+pub const RADIUS_HISTORY_CAPACITY: usize = 1000;
+
+/// The smallest log2 distance that can be told apart from smaller ones, given that only the 32
+/// most significant bits of distances are stored.
+pub const MIN_DISTINGUISHABLE_LOG2_DISTANCE: u16 = 256 - 32;
+
+/// A content row to write, along with its value.
+pub(super) struct ContentRow<'a> {
+    pub content_id: &'a [u8],
+    pub content_key: &'a [u8],
+    pub distance: Distance,
+    pub content_size: usize,
+    pub content_value: &'a [u8],
+    pub block_number: Option<u64>,
+}
+
 /// The result of looking for the farthest content.
 struct FarthestQueryResult {
     content_id: ContentId,
@@ -38,6 +72,16 @@ pub struct PaginateResult<TContentKey> {
 /// Different SQL table is created for each `ContentType`, with content-id as a primary key.
 /// It has a configurable capacity and it will prune data that is farthest from the `NodeId` once
 /// it uses more than storage capacity.
+///
+/// If a recency floor is configured, content of the most recent blocks is never pruned. Once only
+/// such content remains and the capacity is exhausted, new content is refused instead.
+///
+/// Content values are stored in a separate blob table, indexed by the hash of the value, so
+/// content keys with identical values share a single blob. Blobs are reference counted and
+/// deleted once the last content key that points to them is deleted.
+///
+/// Every blob is tagged with the version of the encoding it's stored with, and values are decoded
+/// according to it, so the encoding can change without re-importing existing content.
 #[derive(Debug)]
 pub struct IdIndexedV1Store<TContentKey: OverlayContentKey> {
     /// The configuration.
@@ -45,12 +89,23 @@ pub struct IdIndexedV1Store<TContentKey: OverlayContentKey> {
     /// The maximum distance between `NodeId` and content id that store should keep. Updated
     /// dynamically after pruning to the farthest distance still stored.
     radius: Distance,
+    /// The latest changes of the radius, the oldest first.
+    radius_history: VecDeque<RadiusChangeInfo>,
+    /// The radius set with `override_radius`, which the radius doesn't exceed until it's cleared.
+    radius_override: Option<Distance>,
     /// The strategy for deciding when and how much to prune.
     pruning_strategy: PruningStrategy,
     /// The usage stats tracked manually.
     usage_stats: UsageStats,
+    /// The usage stats tracked manually, per content key type (the first byte of the content key).
+    key_type_usage_stats: BTreeMap<u8, UsageStats>,
+    /// The highest block number of any stored content, used as the reference for the recency
+    /// floor.
+    latest_block_number: Option<u64>,
     /// The Metrics for tracking performance.
     metrics: StorageMetricsReporter,
+    /// The read-only stores that are consulted when content isn't stored, in order.
+    readonly_stores: Vec<ReadOnlyStore>,
     /// Phantom Content Key
     _phantom_content_key: PhantomData<TContentKey>,
 }
@@ -82,13 +137,23 @@ impl<TContentKey: OverlayContentKey> VersionedContentStore for IdIndexedV1Store<
         let subnetwork = config.subnetwork;
 
         let pruning_strategy = PruningStrategy::new(config.clone());
+        let readonly_stores = config
+            .readonly_store_paths
+            .iter()
+            .map(|path| ReadOnlyStore::open(path, content_type.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
 
         let mut store = Self {
             config,
             radius: Distance::MAX,
+            radius_history: VecDeque::new(),
+            radius_override: None,
             pruning_strategy,
             usage_stats: UsageStats::default(),
+            key_type_usage_stats: BTreeMap::new(),
+            latest_block_number: None,
             metrics: StorageMetricsReporter::new(subnetwork),
+            readonly_stores,
             _phantom_content_key: PhantomData,
         };
         store.init()?;
@@ -103,8 +168,9 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
             .report_storage_capacity_bytes(self.config.storage_capacity_bytes as f64);
 
         self.init_usage_stats()?;
+        self.latest_block_number = self.lookup_max_block_number()?;
 
-        if self.pruning_strategy.should_prune(&self.usage_stats) {
+        if self.should_prune() {
             debug!(
                 Db = %self.config.content_type,
                 "High storage usage ({}) -> Pruning",
@@ -112,7 +178,29 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
             );
             // ignore dropped content...
             self.prune()?;
-        } else if self
+        } else {
+            self.derive_radius()?;
+        }
+
+        // Check that distance to the farthest content is what is stored. This is a simple check
+        // that the NodeId didn't change.
+        let farthest = self.lookup_farthest()?;
+        if let Some(farthest) = farthest {
+            let distance = self.distance_to_content_id(&farthest.content_id);
+            if farthest.distance_u32 != distance.big_endian_u32() {
+                return Err(ContentStoreError::Database(format!(
+                    "Distance to the farthest (short: 0x{:08X}) didn't match expected distance ({distance})!",
+                    farthest.distance_u32
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the radius from the usage of the store, when it doesn't need pruning.
+    fn derive_radius(&mut self) -> Result<(), ContentStoreError> {
+        if self
             .pruning_strategy
             .is_usage_above_target_capacity(&self.usage_stats)
         {
@@ -128,8 +216,7 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
                 Db = %self.config.content_type,
                 "Storage capacity is 0 -> Using ZERO radius",
             );
-            self.radius = Distance::ZERO;
-            self.metrics.report_radius(self.radius);
+            self.set_radius(Distance::ZERO);
         } else {
             debug!(
                 Db = %self.config.content_type,
@@ -137,23 +224,8 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
                 self.usage_stats.total_entry_size_bytes,
                 self.pruning_strategy.target_capacity_bytes()
             );
-            self.radius = Distance::MAX;
-            self.metrics.report_radius(self.radius);
-        }
-
-        // Check that distance to the farthest content is what is stored. This is a simple check
-        // that the NodeId didn't change.
-        let farthest = self.lookup_farthest()?;
-        if let Some(farthest) = farthest {
-            let distance = self.distance_to_content_id(&farthest.content_id);
-            if farthest.distance_u32 != distance.big_endian_u32() {
-                return Err(ContentStoreError::Database(format!(
-                    "Distance to the farthest (short: 0x{:08X}) didn't match expected distance ({distance})!",
-                    farthest.distance_u32
-                )));
-            }
+            self.set_radius(Distance::MAX);
         }
-
         Ok(())
     }
 
@@ -164,6 +236,31 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
         self.radius
     }
 
+    /// Sets the radius, overriding the radius derived from the stored content, e.g. to test or
+    /// recover a node, or clears the override if `radius` is `None`, deriving the radius from the
+    /// stored content again. Returns whether content farther than the radius is stored, which can
+    /// be deleted with `evict_outside_radius`.
+    ///
+    /// Until the override is cleared, the radius doesn't exceed it. Pruning may still shrink the
+    /// radius below it. The override isn't persisted, so it's cleared when the node restarts.
+    pub fn override_radius(&mut self, radius: Option<Distance>) -> Result<bool, ContentStoreError> {
+        self.radius_override = radius;
+        match radius {
+            Some(radius) => self.set_radius(radius),
+            None => self.derive_radius()?,
+        }
+        let farthest = self.lookup_farthest()?;
+        Ok(farthest.is_some_and(|farthest| {
+            self.distance_to_content_id(&farthest.content_id) > self.radius
+        }))
+    }
+
+    /// Returns the latest changes of the radius, including the radius the store started with, the
+    /// oldest first.
+    pub fn radius_history(&self) -> Vec<RadiusChangeInfo> {
+        self.radius_history.iter().cloned().collect()
+    }
+
     /// Returns distance to the content id.
     pub fn distance_to_content_id(&self, content_id: &ContentId) -> Distance {
         self.config
@@ -214,30 +311,70 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
     }
 
     /// Returns content value data is stored.
+    ///
+    /// It returns `UnknownValueEncoding` error if the value is stored with an unknown encoding.
+    ///
+    /// If checksums are verified, it returns `Corrupted` error if the value doesn't match the hash
+    /// it was stored under. Values stored inline, before blobs were introduced, aren't verified.
+    ///
+    /// Content that isn't stored is looked up in the read-only stores, if any.
     pub fn lookup_content_value(
         &self,
         content_id: &ContentId,
     ) -> Result<Option<RawContentValue>, ContentStoreError> {
         let timer = self.metrics.start_process_timer("lookup_content_value");
+        let value = read_content_value(
+            &self.config.sql_connection_pool.get()?,
+            &self.config.content_type,
+            content_id,
+            self.config.verify_checksums,
+        );
+        self.metrics.stop_process_timer(timer);
+        if let Some(value) = value? {
+            return Ok(Some(value));
+        }
 
-        let value = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::lookup_value(&self.config.content_type),
-                named_params! { ":content_id": content_id.to_vec() },
-                |row| row.get::<&str, Vec<u8>>("content_value"),
-            )
-            .optional()?;
+        // Content that isn't stored is looked up in the read-only stores, in order.
+        for readonly_store in &self.readonly_stores {
+            if let Some(value) =
+                readonly_store.lookup_content_value(content_id, self.config.verify_checksums)?
+            {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
 
-        self.metrics.stop_process_timer(timer);
-        Ok(value.map(RawContentValue::from))
+    /// Returns the content value of the `content_id` as it's stored, without decoding it or
+    /// verifying its checksum, if stored.
+    ///
+    /// Like `lookup_content_value`, content that isn't stored is looked up in the read-only
+    /// stores, in order.
+    pub fn lookup_stored_value(
+        &self,
+        content_id: &ContentId,
+    ) -> Result<Option<RawStoredBytesInfo>, ContentStoreError> {
+        let stored_value = read_stored_value(
+            &self.config.sql_connection_pool.get()?,
+            &self.config.content_type,
+            content_id,
+        )?;
+        if stored_value.is_some() {
+            return Ok(stored_value);
+        }
+        for readonly_store in &self.readonly_stores {
+            if let Some(stored_value) = readonly_store.lookup_stored_value(content_id)? {
+                return Ok(Some(stored_value));
+            }
+        }
+        Ok(None)
     }
 
     /// Inserts content key/value pair into storage and prunes the db if necessary.
     /// Returns any content items that were pruned.
-    /// It returns `InsufficientRadius` error if content is outside radius.
+    /// It returns `InsufficientRadius` error if content is outside radius,
+    /// `RecentContentCapacityExhausted` error if the store is full of content exempt from pruning,
+    /// and `CapacityExhausted` error if the store is full and stored content is kept.
     pub fn insert(
         &mut self,
         content_key: &TContentKey,
@@ -245,6 +382,23 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
     ) -> Result<Vec<(TContentKey, RawContentValue)>, ContentStoreError> {
         let insert_with_pruning_timer = self.metrics.start_process_timer("insert_with_pruning");
 
+        let block_number = content_key.block_number();
+        let mut dropped_content = vec![];
+        if let Some(recency_floor_blocks) = self.config.recency_floor_blocks {
+            // The store is still full after the last pruning, because only content exempt from
+            // pruning remains. Newer content moves the recency floor, which may allow pruning.
+            if self.should_prune() {
+                self.update_latest_block_number(block_number);
+                dropped_content = self.prune()?;
+                if self.should_prune() {
+                    self.metrics.stop_process_timer(insert_with_pruning_timer);
+                    return Err(ContentStoreError::RecentContentCapacityExhausted {
+                        recency_floor_blocks,
+                    });
+                }
+            }
+        }
+
         let content_id = content_key.content_id();
 
         let distance = self.distance_to_content_id(&content_id.into());
@@ -254,58 +408,122 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
                 distance,
             });
         }
+        if self.config.keep_content && self.is_full() {
+            self.metrics.stop_process_timer(insert_with_pruning_timer);
+            return Err(ContentStoreError::CapacityExhausted);
+        }
 
         let content_id = content_id.to_vec();
         let content_key = content_key.to_bytes().to_vec();
         let content_size = content_id.len() + content_key.len() + content_value.len();
+        let key_type = content_key.first().copied();
 
         let insert_timer = self.metrics.start_process_timer("insert");
-        self.config.sql_connection_pool.get()?.execute(
-            &sql::insert(&self.config.content_type),
-            named_params! {
-                ":content_id": content_id,
-                ":content_key": content_key,
-                ":content_value": content_value.as_ref(),
-                ":distance_short": distance.big_endian_u32(),
-                ":content_size": content_size,
+        let mut conn = self.config.sql_connection_pool.get()?;
+        write_content(
+            &mut conn,
+            &self.config.content_type,
+            &|statement| statement,
+            &ContentRow {
+                content_id: &content_id,
+                content_key: &content_key,
+                distance,
+                content_size,
+                content_value: &content_value,
+                block_number,
             },
         )?;
+        drop(conn);
         self.metrics.stop_process_timer(insert_timer);
 
         self.usage_stats.entry_count += 1;
         self.usage_stats.total_entry_size_bytes += content_size as u64;
         self.usage_stats.report_metrics(&self.metrics);
+        if let Some(key_type) = key_type {
+            self.add_key_type_usage(key_type, content_size as u64);
+        }
+        self.update_latest_block_number(block_number);
 
-        let dropped_content = if self.pruning_strategy.should_prune(&self.usage_stats) {
-            self.prune()?
-        } else {
-            vec![]
-        };
+        if self.should_prune() {
+            dropped_content.extend(self.prune()?);
+        } else if self.config.keep_content && self.is_full() {
+            // Nothing is pruned, so only content closer than the stored content is accepted.
+            self.set_radius_to_farthest()?;
+        }
 
         self.metrics.stop_process_timer(insert_with_pruning_timer);
         Ok(dropped_content)
     }
 
+    /// Returns whether inserting content of the `block_number` would be refused with
+    /// `RecentContentCapacityExhausted` or `CapacityExhausted` error, because the store is full
+    /// and pruning can't free any space. Nothing is inserted or pruned.
+    pub fn is_capacity_exhausted(
+        &self,
+        block_number: Option<u64>,
+    ) -> Result<bool, ContentStoreError> {
+        if self.config.keep_content {
+            return Ok(self.is_full());
+        }
+        if self.config.recency_floor_blocks.is_none() || !self.should_prune() {
+            return Ok(false);
+        }
+        // Content of a newer block moves the recency floor, which may allow pruning.
+        let latest_block_number = self.latest_block_number.max(block_number);
+        let has_prunable_content = self.config.sql_connection_pool.get()?.query_row(
+            &sql::has_prunable_content(&self.config.content_type),
+            named_params! {
+                ":exempt_from_block_number": self.exempt_from_block_number_given(latest_block_number),
+            },
+            |row| row.get::<&str, bool>("has_prunable_content"),
+        )?;
+        Ok(!has_prunable_content)
+    }
+
+    /// Records that the content with the given content id was served to a peer, which protects it
+    /// from pruning for the serve eviction protection window. Nothing is recorded if serve
+    /// eviction protection is disabled.
+    pub fn record_served(&self, content_id: &ContentId) -> Result<(), ContentStoreError> {
+        if self.protected_served_since().is_none() {
+            return Ok(());
+        }
+        self.config.sql_connection_pool.get()?.execute(
+            &sql::update_served_at(&self.config.content_type),
+            named_params! {
+                ":content_id": content_id.to_vec(),
+                ":served_at": unix_timestamp_secs(),
+            },
+        )?;
+        Ok(())
+    }
+
     /// Deletes content with the given content id.
     pub fn delete(&mut self, content_id: &ContentId) -> Result<(), ContentStoreError> {
         let timer = self.metrics.start_process_timer("delete");
 
-        let content_size = self
+        let deleted = self
             .config
             .sql_connection_pool
             .get()?
             .query_row(
                 &sql::delete(&self.config.content_type),
                 named_params! { ":content_id": content_id.to_vec() },
-                |row| row.get::<_, u64>("content_size"),
+                |row| {
+                    let content_key: Vec<u8> = row.get("content_key")?;
+                    let content_size: u64 = row.get("content_size")?;
+                    Ok((content_key, content_size))
+                },
             )
             .optional()?;
 
-        match content_size {
-            Some(content_size) => {
+        match deleted {
+            Some((content_key, content_size)) => {
                 self.usage_stats.entry_count -= 1;
                 self.usage_stats.total_entry_size_bytes -= content_size;
                 self.usage_stats.report_metrics(&self.metrics);
+                if let Some(key_type) = content_key.first() {
+                    self.remove_key_type_usage(*key_type, content_size);
+                }
             }
             None => {
                 debug!(Db = %self.config.content_type,
@@ -318,6 +536,53 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
         Ok(())
     }
 
+    /// Deletes content that was inserted longer than the configured time-to-live ago, and returns
+    /// the number of deleted entries.
+    ///
+    /// Nothing is deleted if no time-to-live is configured.
+    pub fn delete_expired(&mut self) -> Result<u64, ContentStoreError> {
+        let Some(content_ttl) = self.config.content_ttl else {
+            return Ok(0);
+        };
+        let inserted_before = unix_timestamp_secs().saturating_sub(content_ttl.as_secs());
+        self.delete_inserted_before(inserted_before)
+    }
+
+    /// Deletes content that was inserted before the given unix timestamp (in seconds), and
+    /// returns the number of deleted entries.
+    fn delete_inserted_before(&mut self, inserted_before: u64) -> Result<u64, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("delete_expired");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let deleted = conn
+            .prepare(&sql::delete_inserted_before(&self.config.content_type))?
+            .query_map(
+                named_params! { ":inserted_before": inserted_before },
+                |row| {
+                    let content_key: Vec<u8> = row.get("content_key")?;
+                    let content_size: u64 = row.get("content_size")?;
+                    Ok((content_key, content_size))
+                },
+            )?
+            .collect::<Result<Vec<(Vec<u8>, u64)>, rusqlite::Error>>()?;
+        drop(conn);
+
+        for (content_key, content_size) in &deleted {
+            self.usage_stats.entry_count -= 1;
+            self.usage_stats.total_entry_size_bytes -= content_size;
+            if let Some(key_type) = content_key.first() {
+                self.remove_key_type_usage(*key_type, *content_size);
+            }
+        }
+        self.usage_stats.report_metrics(&self.metrics);
+        if !deleted.is_empty() {
+            debug!(Db = %self.config.content_type, "Deleted {} expired entries", deleted.len());
+        }
+
+        self.metrics.stop_process_timer(timer);
+        Ok(deleted.len() as u64)
+    }
+
     /// Returns a paginated list of all locally available content keys, according to the provided
     /// offset and limit.
     pub fn paginate(
@@ -351,10 +616,284 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
         })
     }
 
+    /// Returns up to `limit` stored content items whose content ids are greater than `after`, or
+    /// from the first content id if `after` isn't set, in content id order.
+    ///
+    /// Passing the content id of the last item of a page as `after` returns the next page, so all
+    /// stored content is traversed in the same order on any node that stores it.
+    pub fn export_page(
+        &self,
+        after: Option<&ContentId>,
+        limit: u64,
+    ) -> Result<Vec<(ContentId, TContentKey, RawContentValue)>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("export_page");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let mut query = conn.prepare(&sql::export_page(&self.config.content_type))?;
+        let mut rows = query.query(named_params! {
+            ":after_content_id": after.map(|content_id| content_id.to_vec()).unwrap_or_default(),
+            ":limit": limit,
+        })?;
+
+        let mut content = vec![];
+        while let Some(row) = rows.next()? {
+            let content_id: ContentId = row.get("content_id")?;
+            let key_bytes: Vec<u8> = row.get("content_key")?;
+            let value_bytes: Vec<u8> = row.get("content_value")?;
+            let value_encoding: u8 = row.get("value_encoding")?;
+            let key = TContentKey::try_from_bytes(key_bytes).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, err.into())
+            })?;
+            let value = ValueEncoding::from_version(value_encoding)?.decode(value_bytes)?;
+            content.push((content_id, key, value));
+        }
+
+        self.metrics.stop_process_timer(timer);
+        Ok(content)
+    }
+
+    /// Returns up to `limit` content items whose content ids are within `radius` of `node_id`.
+    ///
+    /// Content is scanned starting with the content closest to the local node, and at most
+    /// `scan_limit` entries are inspected.
+    pub fn lookup_within_radius_of(
+        &self,
+        node_id: &NodeId,
+        radius: Distance,
+        limit: usize,
+        scan_limit: u64,
+    ) -> Result<Vec<(TContentKey, RawContentValue)>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("lookup_within_radius_of");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let mut query = conn.prepare(&sql::lookup_closest(&self.config.content_type))?;
+        let mut rows = query.query(named_params! { ":limit": scan_limit })?;
+
+        let mut content = vec![];
+        while content.len() < limit {
+            let Some(row) = rows.next()? else {
+                break;
+            };
+            let content_id: ContentId = row.get("content_id")?;
+            if self.config.distance_fn.distance(node_id, &content_id.0) > radius {
+                continue;
+            }
+            let key_bytes: Vec<u8> = row.get("content_key")?;
+            let value_bytes: Vec<u8> = row.get("content_value")?;
+            let value_encoding: u8 = row.get("value_encoding")?;
+            let key = TContentKey::try_from_bytes(key_bytes).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, err.into())
+            })?;
+            let value = ValueEncoding::from_version(value_encoding)?.decode(value_bytes)?;
+            content.push((key, value));
+        }
+
+        self.metrics.stop_process_timer(timer);
+        Ok(content)
+    }
+
+    /// Returns the keys of up to `count` stored content items whose content ids are closest to
+    /// `node_id`, sorted by distance.
+    ///
+    /// All stored content ids are scanned, but only the closest `count` keys are kept in memory.
+    pub fn lookup_closest_to(
+        &self,
+        node_id: &NodeId,
+        count: usize,
+    ) -> Result<Vec<TContentKey>, ContentStoreError> {
+        if count == 0 {
+            return Ok(vec![]);
+        }
+        let timer = self.metrics.start_process_timer("lookup_closest_to");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let mut query = conn.prepare(&sql::lookup_ids_and_keys(&self.config.content_type))?;
+        let mut rows = query.query([])?;
+
+        // A max-heap, so that the farthest of the closest keys found so far is the one replaced.
+        let mut closest: BinaryHeap<(Distance, Vec<u8>)> = BinaryHeap::new();
+        while let Some(row) = rows.next()? {
+            let content_id: ContentId = row.get("content_id")?;
+            let distance = self.config.distance_fn.distance(node_id, &content_id.0);
+            if closest.len() == count
+                && closest
+                    .peek()
+                    .is_some_and(|(farthest, _)| distance >= *farthest)
+            {
+                continue;
+            }
+            closest.push((distance, row.get("content_key")?));
+            if closest.len() > count {
+                closest.pop();
+            }
+        }
+        let content_keys = closest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, key_bytes)| {
+                TContentKey::try_from_bytes(key_bytes).map_err(|err| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, err.into())
+                })
+            })
+            .collect::<Result<Vec<TContentKey>, rusqlite::Error>>()?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(content_keys)
+    }
+
+    /// Returns the number of stored entries per log2 distance from the node id, keyed by the log2
+    /// distance.
+    ///
+    /// Only the 32 most significant bits of distances are stored, so entries whose log2 distance
+    /// is at most `MIN_DISTINGUISHABLE_LOG2_DISTANCE` are all counted under it.
+    pub fn distance_distribution(&self) -> Result<BTreeMap<u16, u64>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("distance_distribution");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let mut query = conn.prepare(&sql::entry_count_per_distance_short(
+            &self.config.content_type,
+        ))?;
+        let mut rows = query.query([])?;
+        let mut distribution = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let distance_short: u32 = row.get("distance_short")?;
+            let count: u64 = row.get("count")?;
+            *distribution
+                .entry(log2_distance_short(distance_short))
+                .or_default() += count;
+        }
+
+        self.metrics.stop_process_timer(timer);
+        Ok(distribution)
+    }
+
+    /// Calls `f` for every stored content key whose encoding starts with `prefix`.
+    ///
+    /// Keys are streamed from the database, so this can be used to scan all keys of a given type
+    /// without loading them into memory at once.
+    pub fn for_each_content_key_with_prefix(
+        &self,
+        prefix: &[u8],
+        mut f: impl FnMut(TContentKey),
+    ) -> Result<(), ContentStoreError> {
+        let timer = self
+            .metrics
+            .start_process_timer("for_each_content_key_with_prefix");
+
+        let conn = self.config.sql_connection_pool.get()?;
+        let mut query = conn.prepare(&sql::lookup_keys_with_prefix(&self.config.content_type))?;
+        let mut rows = query.query(named_params! { ":prefix": prefix })?;
+        while let Some(row) = rows.next()? {
+            let bytes: Vec<u8> = row.get("content_key")?;
+            let key = TContentKey::try_from_bytes(bytes).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, err.into())
+            })?;
+            f(key);
+        }
+
+        self.metrics.stop_process_timer(timer);
+        Ok(())
+    }
+
+    /// Returns a benchmark of the database of the store, that can run without holding on to the
+    /// store.
+    pub fn benchmark(&self) -> StoreBenchmark {
+        StoreBenchmark::new(self.config.clone())
+    }
+
+    /// Makes all previously inserted content durable on disk, and returns once that is done.
+    ///
+    /// Writes are flushed when their transaction commits, unless the database uses a
+    /// write-ahead log, in which case the log is checkpointed into the database file and
+    /// truncated.
+    pub fn sync(&self) -> Result<(), ContentStoreError> {
+        let timer = self.metrics.start_process_timer("sync");
+
+        // The checkpoint returns a single row with its status, which is not needed.
+        self.config
+            .sql_connection_pool
+            .get()?
+            .query_row(WAL_CHECKPOINT, [], |_| Ok(()))?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(())
+    }
+
+    /// Checks that the database can be read from and written to.
+    ///
+    /// The write lock is acquired and released without making any changes, so this fails if the
+    /// database is locked, read-only or corrupted.
+    pub fn check_health(&self) -> Result<(), ContentStoreError> {
+        let timer = self.metrics.start_process_timer("check_health");
+
+        let mut conn = self.config.sql_connection_pool.get()?;
+        let transaction = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        transaction
+            .query_row(&sql::lookup_any(&self.config.content_type), [], |_| Ok(()))
+            .optional()?;
+        transaction.rollback()?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok(())
+    }
+
+    /// Returns the ratio between the size of content values as referenced by content keys and the
+    /// size of content values actually stored, after deduplication.
+    ///
+    /// The ratio is `1.0` if nothing is deduplicated (or nothing is stored).
+    pub fn dedup_ratio(&self) -> Result<f64, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("dedup_ratio");
+
+        let (referenced_size, stored_size) = self.config.sql_connection_pool.get()?.query_row(
+            &sql::value_size_referenced_and_stored(&self.config.content_type),
+            [],
+            |row| {
+                let referenced_size: f64 = row.get("referenced_size")?;
+                let stored_size: f64 = row.get("stored_size")?;
+                Ok((referenced_size, stored_size))
+            },
+        )?;
+
+        self.metrics.stop_process_timer(timer);
+        if stored_size == 0.0 {
+            return Ok(1.0);
+        }
+        Ok(referenced_size / stored_size)
+    }
+
+    /// Returns the size of the database file on disk and the size of its free pages, in bytes.
+    ///
+    /// The database file is shared by the stores of all content types, and holds indexes and
+    /// other metadata besides content.
+    pub fn database_size(&self) -> Result<(u64, u64), ContentStoreError> {
+        let timer = self.metrics.start_process_timer("database_size");
+
+        let (size, free_size) =
+            self.config
+                .sql_connection_pool
+                .get()?
+                .query_row(DATABASE_SIZE_QUERY, [], |row| {
+                    Ok((row.get("size")?, row.get("free_size")?))
+                })?;
+
+        self.metrics.stop_process_timer(timer);
+        Ok((size, free_size))
+    }
+
+    /// Returns the configured storage capacity, in bytes.
+    pub fn storage_capacity_bytes(&self) -> u64 {
+        self.config.storage_capacity_bytes
+    }
+
     pub fn usage_stats(&self) -> UsageStats {
         self.usage_stats.clone()
     }
 
+    /// Returns the usage stats per content key type, keyed by the first byte of the content key.
+    pub fn key_type_usage_stats(&self) -> BTreeMap<u8, UsageStats> {
+        self.key_type_usage_stats.clone()
+    }
+
     /// Returns metrics summary.
     pub fn get_summary_info(&self) -> String {
         let timer = self.metrics.start_process_timer("get_summary_info");
@@ -379,40 +918,166 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
     /// `usage_stats` should be updated manually when entries are inserted/deleted.
     fn init_usage_stats(&mut self) -> Result<(), ContentStoreError> {
         let timer = self.metrics.start_process_timer("init_usage_stats");
+        let start = Instant::now();
+        let shard_count = self.config.warmup_threads.max(1);
+        info!(
+            Db = %self.config.content_type,
+            shards = shard_count,
+            "Warming up store: scanning content in parallel shards"
+        );
 
-        self.usage_stats = self.config.sql_connection_pool.get()?.query_row(
-            &sql::entry_count_and_size(&self.config.content_type),
-            [],
-            |row| {
-                let entry_count = row.get("count")?;
-                let used_capacity: f64 = row.get("used_capacity")?;
-                Ok(UsageStats::new(entry_count, used_capacity.round() as u64))
-            },
-        )?;
+        // Each shard is scanned by its own thread, over its own connection.
+        let config = &self.config;
+        let shard_rows = std::thread::scope(|scope| {
+            let handles = (0..shard_count)
+                .map(|shard| scope.spawn(move || scan_usage_shard(config, shard, shard_count)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .enumerate()
+                .map(|(shard, handle)| {
+                    let rows = handle.join().map_err(|_| {
+                        ContentStoreError::Database(format!(
+                            "Store warmup thread of shard {shard} panicked"
+                        ))
+                    })??;
+                    debug!(
+                        Db = %config.content_type,
+                        "Store warmup progress: {}/{shard_count} shards scanned",
+                        shard + 1,
+                    );
+                    Ok(rows)
+                })
+                .collect::<Result<Vec<_>, ContentStoreError>>()
+        })?;
+
+        // The sizes are summed as floats, like SQLite's TOTAL, and rounded once merged.
+        let mut total = (0, 0.0);
+        let mut per_key_type: BTreeMap<u8, (u64, f64)> = BTreeMap::new();
+        for (key_type, entry_count, used_capacity) in shard_rows.into_iter().flatten() {
+            total.0 += entry_count;
+            total.1 += used_capacity;
+            // Entries with an empty content key have no key type.
+            if let Some(key_type) = key_type {
+                let key_type_total = per_key_type.entry(key_type).or_default();
+                key_type_total.0 += entry_count;
+                key_type_total.1 += used_capacity;
+            }
+        }
+        self.usage_stats = UsageStats::new(total.0, total.1.round() as u64);
         self.usage_stats.report_metrics(&self.metrics);
+        self.key_type_usage_stats = per_key_type
+            .into_iter()
+            .map(|(key_type, (entry_count, used_capacity))| {
+                (
+                    key_type,
+                    UsageStats::new(entry_count, used_capacity.round() as u64),
+                )
+            })
+            .collect();
+        info!(
+            Db = %self.config.content_type,
+            entries = self.usage_stats.entry_count,
+            shards = shard_count,
+            duration = ?start.elapsed(),
+            "Store warmup completed"
+        );
+        for (key_type, usage_stats) in &self.key_type_usage_stats {
+            self.report_key_type_usage(*key_type, usage_stats);
+        }
 
         self.metrics.stop_process_timer(timer);
         Ok(())
     }
 
-    /// Returns the farthest content in the table.
-    fn lookup_farthest(&self) -> Result<Option<FarthestQueryResult>, ContentStoreError> {
-        let timer = self.metrics.start_process_timer("lookup_farthest");
-        let farthest = self
-            .config
-            .sql_connection_pool
-            .get()?
-            .query_row(
-                &sql::lookup_farthest(&self.config.content_type),
-                named_params! { ":limit": 1 },
-                |row| {
-                    Ok(FarthestQueryResult {
-                        content_id: row.get("content_id")?,
-                        distance_u32: row.get("distance_short")?,
-                    })
-                },
-            )
-            .optional()?;
+    /// Adds an entry of the given size to the usage stats of its content key type.
+    fn add_key_type_usage(&mut self, key_type: u8, content_size: u64) {
+        let usage_stats = self.key_type_usage_stats.entry(key_type).or_default();
+        usage_stats.entry_count += 1;
+        usage_stats.total_entry_size_bytes += content_size;
+        let usage_stats = usage_stats.clone();
+        self.report_key_type_usage(key_type, &usage_stats);
+    }
+
+    /// Removes an entry of the given size from the usage stats of its content key type.
+    fn remove_key_type_usage(&mut self, key_type: u8, content_size: u64) {
+        let usage_stats = self.key_type_usage_stats.entry(key_type).or_default();
+        usage_stats.entry_count = usage_stats.entry_count.saturating_sub(1);
+        usage_stats.total_entry_size_bytes = usage_stats
+            .total_entry_size_bytes
+            .saturating_sub(content_size);
+        let usage_stats = usage_stats.clone();
+        self.report_key_type_usage(key_type, &usage_stats);
+    }
+
+    fn report_key_type_usage(&self, key_type: u8, usage_stats: &UsageStats) {
+        self.metrics.report_content_type_usage(
+            &self.config.content_type.key_type_name(key_type),
+            usage_stats.entry_count,
+            usage_stats.total_entry_size_bytes as f64,
+        );
+    }
+
+    /// Returns the highest block number of any stored content.
+    fn lookup_max_block_number(&self) -> Result<Option<u64>, ContentStoreError> {
+        let max_block_number = self.config.sql_connection_pool.get()?.query_row(
+            &sql::lookup_max_block_number(&self.config.content_type),
+            [],
+            |row| row.get("max_block_number"),
+        )?;
+        Ok(max_block_number)
+    }
+
+    fn update_latest_block_number(&mut self, block_number: Option<u64>) {
+        if block_number > self.latest_block_number {
+            self.latest_block_number = block_number;
+        }
+    }
+
+    /// Returns the lowest block number whose content is exempt from pruning, if any.
+    fn exempt_from_block_number(&self) -> Option<u64> {
+        self.exempt_from_block_number_given(self.latest_block_number)
+    }
+
+    /// Returns the lowest block number whose content is exempt from pruning, if any, given the
+    /// highest block number of any stored content.
+    fn exempt_from_block_number_given(&self, latest_block_number: Option<u64>) -> Option<u64> {
+        let recency_floor_blocks = self
+            .config
+            .recency_floor_blocks
+            .filter(|blocks| *blocks > 0)?;
+        let latest_block_number = latest_block_number?;
+        Some((latest_block_number + 1).saturating_sub(recency_floor_blocks))
+    }
+
+    /// Returns the unix timestamp (in seconds) from which served content is exempt from pruning, if
+    /// any.
+    fn protected_served_since(&self) -> Option<u64> {
+        let serve_eviction_protection = self
+            .config
+            .serve_eviction_protection
+            .filter(|window| !window.is_zero())?;
+        Some(unix_timestamp_secs().saturating_sub(serve_eviction_protection.as_secs()))
+    }
+
+    /// Returns the farthest content in the table.
+    fn lookup_farthest(&self) -> Result<Option<FarthestQueryResult>, ContentStoreError> {
+        let timer = self.metrics.start_process_timer("lookup_farthest");
+        let farthest = self
+            .config
+            .sql_connection_pool
+            .get()?
+            .query_row(
+                &sql::lookup_farthest(&self.config.content_type),
+                named_params! { ":limit": 1 },
+                |row| {
+                    Ok(FarthestQueryResult {
+                        content_id: row.get("content_id")?,
+                        distance_u32: row.get("distance_short")?,
+                    })
+                },
+            )
+            .optional()?;
 
         self.metrics.stop_process_timer(timer);
         Ok(farthest)
@@ -422,25 +1087,173 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
     ///
     /// If no content is found, it sets radius to `Distance::MAX`.
     fn set_radius_to_farthest(&mut self) -> Result<(), ContentStoreError> {
-        match self.lookup_farthest()? {
+        let radius = match self.lookup_farthest()? {
             None => {
                 if self.config.storage_capacity_bytes == 0 {
                     debug!(
                         Db = %self.config.content_type,
                         "Farthest not found and storage capacity is 0",
                     );
-                    self.radius = Distance::ZERO;
+                    Distance::ZERO
                 } else {
                     error!(Db = %self.config.content_type, "Farthest not found!");
-                    self.radius = Distance::MAX;
+                    Distance::MAX
                 }
             }
-            Some(farthest) => {
-                self.radius = self.distance_to_content_id(&farthest.content_id);
+            Some(farthest) => self.distance_to_content_id(&farthest.content_id),
+        };
+        self.set_radius(radius);
+        Ok(())
+    }
+
+    /// Sets `self.radius`, but not beyond the radius override, and records it in the radius
+    /// history if it changed.
+    fn set_radius(&mut self, radius: Distance) {
+        let radius = self
+            .radius_override
+            .map_or(radius, |radius_override| radius.min(radius_override));
+        let changed = self
+            .radius_history
+            .back()
+            .map_or(true, |change| change.radius != *radius);
+        if changed {
+            if self.radius_history.len() >= RADIUS_HISTORY_CAPACITY {
+                self.radius_history.pop_front();
             }
+            self.radius_history.push_back(RadiusChangeInfo {
+                timestamp_ms: unix_timestamp_millis(),
+                radius: *radius,
+            });
         }
+        self.radius = radius;
         self.metrics.report_radius(self.radius);
-        Ok(())
+    }
+
+    /// Returns the capacity reserved for each key type with a quota.
+    pub fn key_type_quota_bytes(&self) -> BTreeMap<u8, u64> {
+        self.config
+            .key_type_quotas
+            .iter()
+            .map(|(key_type, percent)| {
+                let quota_bytes = self.config.storage_capacity_bytes * u64::from(*percent) / 100;
+                (*key_type, quota_bytes)
+            })
+            .collect()
+    }
+
+    /// Returns the key type whose content is pruned next, or `None` if the farthest content of
+    /// any key type is pruned, which is the case if there are no quotas.
+    ///
+    /// That is the key type furthest over its quota, otherwise the largest key type without a
+    /// quota, as those share the capacity that isn't reserved. Key types in `exhausted` are
+    /// skipped.
+    fn key_type_to_prune(&self, exhausted: &BTreeSet<u8>) -> Option<u8> {
+        if self.config.key_type_quotas.is_empty() {
+            return None;
+        }
+        let quota_bytes = self.key_type_quota_bytes();
+        let usage_stats = self
+            .key_type_usage_stats
+            .iter()
+            .filter(|(key_type, usage_stats)| {
+                !exhausted.contains(key_type) && usage_stats.entry_count > 0
+            });
+        let over_quota = usage_stats
+            .clone()
+            .filter_map(|(key_type, usage_stats)| {
+                let quota_bytes = quota_bytes.get(key_type)?;
+                let excess_bytes = usage_stats
+                    .total_entry_size_bytes
+                    .checked_sub(*quota_bytes)?;
+                (excess_bytes > 0).then_some((excess_bytes, *key_type))
+            })
+            .max()
+            .map(|(_, key_type)| key_type);
+        over_quota.or_else(|| {
+            usage_stats
+                .filter(|(key_type, _)| !quota_bytes.contains_key(key_type))
+                .max_by_key(|(_, usage_stats)| usage_stats.total_entry_size_bytes)
+                .map(|(key_type, _)| *key_type)
+        })
+    }
+
+    /// Returns whether the store is above its capacity.
+    fn is_full(&self) -> bool {
+        self.pruning_strategy.should_prune(&self.usage_stats)
+    }
+
+    /// Returns whether the store is above its capacity and should be pruned, unless stored
+    /// content is kept.
+    fn should_prune(&self) -> bool {
+        !self.config.keep_content && self.is_full()
+    }
+
+    /// Deletes up to `limit` of the content farther than the radius, the farthest first, e.g.
+    /// after the radius was overridden with a smaller one. Returns the deleted content. Nothing is
+    /// deleted if stored content is kept.
+    ///
+    /// Distances are compared by their 32 most significant bits, like the pruning queries do, so
+    /// content that is only slightly farther than the radius may be kept.
+    pub fn evict_outside_radius(
+        &mut self,
+        limit: u64,
+    ) -> Result<Vec<(TContentKey, RawContentValue)>, ContentStoreError> {
+        if self.config.keep_content {
+            return Ok(vec![]);
+        }
+        let timer = self.metrics.start_process_timer("evict_outside_radius");
+        let deleted_rows = self
+            .config
+            .sql_connection_pool
+            .get()?
+            .prepare(&sql::delete_farther_than(&self.config.content_type))?
+            .query_map(
+                named_params! {
+                    ":distance_short": self.radius.big_endian_u32(),
+                    ":limit": limit,
+                },
+                read_pruned_row::<TContentKey>,
+            )?
+            .collect::<Result<Vec<(TContentKey, Vec<u8>, u8, u64)>, rusqlite::Error>>()?;
+        let deleted_content = self.remove_deleted_rows(&deleted_rows);
+        self.metrics.stop_process_timer(timer);
+        Ok(deleted_content)
+    }
+
+    /// Updates the usage stats for the rows deleted by a query that returns them, and returns
+    /// their content.
+    ///
+    /// The content is deleted already, so values that can't be decoded are only left out of the
+    /// returned content.
+    fn remove_deleted_rows(
+        &mut self,
+        deleted_rows: &[(TContentKey, Vec<u8>, u8, u64)],
+    ) -> Vec<(TContentKey, RawContentValue)> {
+        let deleted_content =
+            deleted_rows
+                .iter()
+                .filter_map(|(key, value_bytes, value_encoding, _)| {
+                    match ValueEncoding::from_version(*value_encoding)
+                        .and_then(|encoding| encoding.decode(value_bytes.clone()))
+                    {
+                        Ok(value) => Some((key.clone(), value)),
+                        Err(err) => {
+                            warn!(%err, content.key = %key, "Failed to decode deleted content");
+                            None
+                        }
+                    }
+                })
+                .collect();
+        self.usage_stats.entry_count -= deleted_rows.len() as u64;
+        self.usage_stats.total_entry_size_bytes -=
+            deleted_rows.iter().map(|(_, _, _, size)| size).sum::<u64>();
+        self.usage_stats.report_metrics(&self.metrics);
+        for (key, _, _, size) in deleted_rows {
+            if let Some(key_type) = key.to_bytes().first() {
+                self.remove_key_type_usage(*key_type, *size);
+            }
+        }
+        deleted_content
     }
 
     /// Prunes database and updates `radius`.
@@ -460,11 +1273,18 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
             self.usage_stats.total_entry_size_bytes,
         );
 
+        let exempt_from_block_number = self.exempt_from_block_number();
+        let protected_served_since = self.protected_served_since();
         let conn = self.config.sql_connection_pool.get()?;
         let mut delete_query = conn.prepare(&sql::delete_farthest(&self.config.content_type))?;
+        let mut delete_key_type_query =
+            conn.prepare(&sql::delete_farthest_of_key_type(&self.config.content_type))?;
+        // The key types of which no more content can be pruned.
+        let mut exhausted_key_types = BTreeSet::new();
 
         while self.pruning_strategy.should_prune(&self.usage_stats) {
             let to_delete = self.pruning_strategy.get_pruning_count(&self.usage_stats);
+            let key_type = self.key_type_to_prune(&exhausted_key_types);
 
             if to_delete == 0 {
                 error!(
@@ -475,45 +1295,59 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
             }
 
             let delete_timer = self.metrics.start_process_timer("prune_delete");
-            let deleted_content_result = delete_query
-                .query_map(named_params! { ":limit": to_delete }, |row| {
-                    let key_bytes: Vec<u8> = row.get("content_key")?;
-                    let value_bytes: Vec<u8> = row.get("content_value")?;
-                    let value = RawContentValue::from(value_bytes);
-                    let size: u64 = row.get("content_size")?;
-                    TContentKey::try_from_bytes(key_bytes)
-                        .map(|key| (key, value, size))
-                        .map_err(|e| {
-                            rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, e.into())
-                        })
-                })?
-                .collect::<Result<Vec<(TContentKey, RawContentValue, u64)>, rusqlite::Error>>()?;
+            let deleted_content_result = match key_type {
+                Some(key_type) => delete_key_type_query.query_map(
+                    named_params! {
+                        ":key_type": vec![key_type],
+                        ":limit": to_delete,
+                        ":exempt_from_block_number": exempt_from_block_number,
+                        ":protected_served_since": protected_served_since,
+                    },
+                    read_pruned_row::<TContentKey>,
+                )?,
+                None => delete_query.query_map(
+                    named_params! {
+                        ":limit": to_delete,
+                        ":exempt_from_block_number": exempt_from_block_number,
+                        ":protected_served_since": protected_served_since,
+                    },
+                    read_pruned_row::<TContentKey>,
+                )?,
+            }
+            .collect::<Result<Vec<(TContentKey, Vec<u8>, u8, u64)>, rusqlite::Error>>()?;
             let pruning_duration = self.metrics.stop_process_timer(delete_timer);
             self.pruning_strategy
                 .observe_pruning_duration(pruning_duration);
 
             let deleted_content_count = deleted_content_result.len() as u64;
-            if to_delete != deleted_content_count {
+            // Fewer entries of a key type are deleted than requested once it runs out of
+            // prunable content, in which case other key types are pruned next.
+            if let Some(key_type) = key_type {
+                if deleted_content_count < to_delete {
+                    exhausted_key_types.insert(key_type);
+                }
+            }
+            // Fewer entries are deleted than requested once only content exempt from pruning
+            // remains.
+            let only_exempt_remains = key_type.is_none()
+                && (exempt_from_block_number.is_some() || protected_served_since.is_some())
+                && deleted_content_count < to_delete;
+            if key_type.is_none() && to_delete != deleted_content_count && !only_exempt_remains {
                 error!(Db = %self.config.content_type,
                     "Attempted to delete {to_delete} but deleted {deleted_content_count}");
                 self.init_usage_stats()?;
                 break;
             }
 
-            let deleted_content_values = deleted_content_result
-                .iter()
-                .map(|(key, value, _)| (key.clone(), value.clone()))
-                .collect::<Vec<(TContentKey, RawContentValue)>>();
-            let deleted_content_size = deleted_content_result
-                .iter()
-                .map(|(_, _, size)| size)
-                .sum::<u64>();
-            self.usage_stats.entry_count -= to_delete;
-            self.usage_stats.total_entry_size_bytes -= deleted_content_size;
-            self.usage_stats.report_metrics(&self.metrics);
-            deleted_content.extend(deleted_content_values);
+            deleted_content.extend(self.remove_deleted_rows(&deleted_content_result));
+            if only_exempt_remains {
+                debug!(Db = %self.config.content_type,
+                    "Only content exempt from pruning remains. Stopping");
+                break;
+            }
         }
         // Free connection.
+        drop(delete_key_type_query);
         drop(delete_query);
         drop(conn);
 
@@ -530,28 +1364,280 @@ impl<TContentKey: OverlayContentKey> IdIndexedV1Store<TContentKey> {
     }
 }
 
-/// Creates table and indexes if they don't already exist.
+/// Reads a row returned by the queries that delete the farthest content.
+fn read_pruned_row<TContentKey: OverlayContentKey>(
+    row: &Row,
+) -> rusqlite::Result<(TContentKey, Vec<u8>, u8, u64)> {
+    let key_bytes: Vec<u8> = row.get("content_key")?;
+    let value_bytes: Vec<u8> = row.get("content_value")?;
+    let value_encoding: u8 = row.get("value_encoding")?;
+    let size: u64 = row.get("content_size")?;
+    TContentKey::try_from_bytes(key_bytes)
+        .map(|key| (key, value_bytes, value_encoding, size))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, e.into()))
+}
+
+/// Returns the entry count and total size of each content key type stored in the `shard` of the
+/// `shard_count` equally sized ranges of content ids.
+///
+/// Shards are delimited by the two most significant bytes of the content ids.
+fn scan_usage_shard(
+    config: &IdIndexedV1StoreConfig,
+    shard: usize,
+    shard_count: usize,
+) -> Result<Vec<(Option<u8>, u64, f64)>, ContentStoreError> {
+    let prefix = |shard: usize| ((shard << 16) / shard_count) as u16;
+    let start_content_id = prefix(shard).to_be_bytes().to_vec();
+    // Longer blobs with the same prefix compare greater, so the end of the last shard is
+    // greater than any content id.
+    let end_content_id = if shard + 1 == shard_count {
+        vec![u8::MAX; 33]
+    } else {
+        prefix(shard + 1).to_be_bytes().to_vec()
+    };
+    let conn = config.sql_connection_pool.get()?;
+    let rows = conn
+        .prepare(&sql::entry_count_and_size_per_key_type(
+            &config.content_type,
+        ))?
+        .query_map(
+            named_params! {
+                ":start_content_id": start_content_id,
+                ":end_content_id": end_content_id,
+            },
+            |row| {
+                let key_type: Vec<u8> = row.get("key_type")?;
+                let entry_count = row.get("count")?;
+                let used_capacity: f64 = row.get("used_capacity")?;
+                Ok((key_type.first().copied(), entry_count, used_capacity))
+            },
+        )?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    Ok(rows)
+}
+
+/// Creates tables, indexes and triggers if they don't already exist.
 fn maybe_create_table_and_indexes(
     content_type: &ContentType,
     pool: &Pool<SqliteConnectionManager>,
 ) -> Result<(), ContentStoreError> {
     let conn = pool.get()?;
-    conn.execute_batch(&sql::create_table(content_type))?;
+    create_table_and_indexes(&conn, content_type, &|statement| statement)
+}
+
+/// Creates tables, indexes and triggers if they don't already exist, rewriting each statement
+/// with `rewrite` before executing it.
+pub(super) fn create_table_and_indexes(
+    conn: &Connection,
+    content_type: &ContentType,
+    rewrite: &dyn Fn(String) -> String,
+) -> Result<(), ContentStoreError> {
+    conn.execute_batch(&rewrite(sql::create_table(content_type)))?;
+    if !conn
+        .prepare(&rewrite(sql::has_blob_hash_column(content_type)))?
+        .exists([])?
+    {
+        conn.execute_batch(&rewrite(sql::add_blob_hash_column(content_type)))?;
+    }
+    conn.execute_batch(&rewrite(sql::create_blob_table(content_type)))?;
+    if !conn
+        .prepare(&rewrite(sql::has_value_encoding_column(content_type)))?
+        .exists([])?
+    {
+        conn.execute_batch(&rewrite(sql::add_value_encoding_column(content_type)))?;
+    }
+    if !conn
+        .prepare(&rewrite(sql::has_inserted_at_column(content_type)))?
+        .exists([])?
+    {
+        conn.execute_batch(&rewrite(sql::add_inserted_at_column(content_type)))?;
+    }
+    if !conn
+        .prepare(&rewrite(sql::has_block_number_column(content_type)))?
+        .exists([])?
+    {
+        conn.execute_batch(&rewrite(sql::add_block_number_column(content_type)))?;
+    }
+    if !conn
+        .prepare(&rewrite(sql::has_served_at_column(content_type)))?
+        .exists([])?
+    {
+        conn.execute_batch(&rewrite(sql::add_served_at_column(content_type)))?;
+    }
+    conn.execute_batch(&rewrite(sql::create_key_type_distance_short_index(
+        content_type,
+    )))?;
     Ok(())
 }
 
+/// Writes the content row, along with its value unless the same value is already stored, in a
+/// single transaction. Nothing is written if the content is already stored.
+///
+/// Each statement is rewritten with `rewrite` before it's executed.
+pub(super) fn write_content(
+    conn: &mut Connection,
+    content_type: &ContentType,
+    rewrite: &dyn Fn(String) -> String,
+    row: &ContentRow,
+) -> Result<(), ContentStoreError> {
+    let blob_hash = keccak256(row.content_value);
+    let value_encoding = ValueEncoding::CURRENT;
+
+    let transaction = conn.transaction()?;
+    transaction.execute(
+        &rewrite(sql::insert_blob(content_type)),
+        named_params! {
+            ":blob_hash": blob_hash.as_slice(),
+            ":content_value": value_encoding.encode(row.content_value),
+            ":value_encoding": value_encoding.version(),
+        },
+    )?;
+    transaction.execute(
+        &rewrite(sql::insert(content_type)),
+        named_params! {
+            ":content_id": row.content_id,
+            ":content_key": row.content_key,
+            ":distance_short": row.distance.big_endian_u32(),
+            ":content_size": row.content_size,
+            ":blob_hash": blob_hash.as_slice(),
+            ":inserted_at": unix_timestamp_secs(),
+            ":block_number": row.block_number,
+        },
+    )?;
+    // The blob is left unreferenced if the content was already present.
+    transaction.execute(
+        &rewrite(sql::delete_unreferenced_blob(content_type)),
+        named_params! { ":blob_hash": blob_hash.as_slice() },
+    )?;
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Returns the log2 distance of a distance whose 32 most significant bits are `distance_short`,
+/// or `MIN_DISTINGUISHABLE_LOG2_DISTANCE` if they are all zero.
+fn log2_distance_short(distance_short: u32) -> u16 {
+    MIN_DISTINGUISHABLE_LOG2_DISTANCE + (u32::BITS - distance_short.leading_zeros()) as u16
+}
+
+/// Returns the current unix timestamp, in seconds.
+fn unix_timestamp_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the current unix timestamp, in milliseconds.
+fn unix_timestamp_millis() -> u64 {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    u64::try_from(millis).unwrap_or(u64::MAX)
+}
+
+/// Reads the content value of the `content_id` as it's stored, without decoding it, if stored.
+pub(super) fn read_stored_value(
+    conn: &Connection,
+    content_type: &ContentType,
+    content_id: &ContentId,
+) -> Result<Option<RawStoredBytesInfo>, ContentStoreError> {
+    query_stored_value(conn, &sql::lookup_value(content_type), content_id)
+}
+
+/// Reads the content value of the `content_id` as it's stored, with the `lookup_value` statement
+/// or a rewrite of it, if stored.
+pub(super) fn query_stored_value(
+    conn: &Connection,
+    lookup_value: &str,
+    content_id: &ContentId,
+) -> Result<Option<RawStoredBytesInfo>, ContentStoreError> {
+    Ok(conn
+        .query_row(
+            lookup_value,
+            named_params! { ":content_id": content_id.to_vec() },
+            |row| {
+                Ok(RawStoredBytesInfo {
+                    raw_value: row.get::<&str, Vec<u8>>("content_value")?.into(),
+                    value_encoding: row.get("value_encoding")?,
+                    checksum: row
+                        .get::<&str, Option<Vec<u8>>>("blob_hash")?
+                        .map(Into::into),
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Reads and decodes the content value of the `content_id`, if stored.
+///
+/// It returns `Corrupted` error if `verify_checksums` is set and the value doesn't match its
+/// checksum.
+pub(super) fn read_content_value(
+    conn: &Connection,
+    content_type: &ContentType,
+    content_id: &ContentId,
+    verify_checksums: bool,
+) -> Result<Option<RawContentValue>, ContentStoreError> {
+    let Some(stored_value) = read_stored_value(conn, content_type, content_id)? else {
+        return Ok(None);
+    };
+    decode_stored_value(stored_value, content_id, verify_checksums).map(Some)
+}
+
+/// Decodes the content value of the `content_id`, as it's stored.
+///
+/// It returns `Corrupted` error if `verify_checksums` is set and the value doesn't match its
+/// checksum.
+pub(super) fn decode_stored_value(
+    stored_value: RawStoredBytesInfo,
+    content_id: &ContentId,
+    verify_checksums: bool,
+) -> Result<RawContentValue, ContentStoreError> {
+    let value = ValueEncoding::from_version(stored_value.value_encoding)?
+        .decode(stored_value.raw_value.to_vec())?;
+    if verify_checksums {
+        if let Some(blob_hash) = stored_value.checksum {
+            if keccak256(&value)[..] != blob_hash[..] {
+                error!(
+                    content.id = %**content_id,
+                    "Stored content is corrupted: its value doesn't match its checksum",
+                );
+                return Err(ContentStoreError::Corrupted {
+                    content_id: **content_id,
+                });
+            }
+        }
+    }
+    Ok(value)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use anyhow::Result;
     use discv5::enr::NodeId;
-    use ethportal_api::{types::network::Subnetwork, IdentityContentKey};
+    use ethportal_api::{
+        types::{
+            content_key::history::{
+                HISTORY_BLOCK_BODY_KEY_PREFIX, HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX,
+            },
+            network::Subnetwork,
+        },
+        HistoryContentKey, IdentityContentKey,
+    };
     use rand::Rng;
     use tempfile::TempDir;
 
     use super::*;
     use crate::{
-        test_utils::generate_random_bytes, utils::setup_sql,
-        versioned::id_indexed_v1::pruning_strategy::PruningConfig, DistanceFunction,
+        test_utils::generate_random_bytes,
+        utils::setup_sql,
+        versioned::id_indexed_v1::{
+            benchmark::MAX_BENCHMARK_ENTRY_COUNT, pruning_strategy::PruningConfig,
+        },
+        DistanceFunction, DATABASE_NAME,
     };
 
     const CONTENT_DEFAULT_SIZE_BYTES: u64 = 100;
@@ -569,9 +1655,17 @@ mod tests {
             node_id: NodeId::random(),
             node_data_dir: temp_dir.path().to_path_buf(),
             distance_fn: DistanceFunction::Xor,
-            sql_connection_pool: setup_sql(temp_dir.path()).unwrap(),
+            sql_connection_pool: setup_sql(temp_dir.path(), false).unwrap(),
             storage_capacity_bytes,
             pruning_config: PruningConfig::default(),
+            content_ttl: None,
+            recency_floor_blocks: None,
+            verify_checksums: false,
+            readonly_store_paths: vec![],
+            keep_content: false,
+            warmup_threads: 1,
+            key_type_quotas: BTreeMap::new(),
+            serve_eviction_protection: None,
         }
     }
 
@@ -606,16 +1700,24 @@ mod tests {
             let (key, value) = generate_key_value(config, 0x80);
             let id = key.content_id();
             let content_size = id.len() + key.to_bytes().len() + value.len();
-            config
-                .sql_connection_pool
-                .get()?
-                .execute(&sql::insert(&config.content_type), named_params! {
-                    ":content_id": id.as_slice(),
-                    ":content_key": key.to_bytes().to_vec(),
+            let blob_hash = keccak256(&value);
+            let conn = config.sql_connection_pool.get()?;
+            conn.execute(
+                &sql::insert_blob(&config.content_type),
+                named_params! {
+                    ":blob_hash": blob_hash.as_slice(),
                     ":content_value": value.to_vec(),
-                    ":distance_short": config.distance_fn.distance(&config.node_id, &id).big_endian_u32(),
-                    ":content_size": content_size,
-                })?;
+                    ":value_encoding": ValueEncoding::CURRENT.version(),
+                },
+            )?;
+            conn.execute(&sql::insert(&config.content_type), named_params! {
+                ":content_id": id.as_slice(),
+                ":content_key": key.to_bytes().to_vec(),
+                ":distance_short": config.distance_fn.distance(&config.node_id, &id).big_endian_u32(),
+                ":content_size": content_size,
+                ":blob_hash": blob_hash.as_slice(),
+                ":inserted_at": unix_timestamp_secs(),
+            })?;
         }
         Ok(())
     }
@@ -859,6 +1961,121 @@ mod tests {
         Ok(())
     }
 
+    fn blob_count(config: &IdIndexedV1StoreConfig) -> Result<u64> {
+        Ok(config.sql_connection_pool.get()?.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {}",
+                sql::blob_table_name(&config.content_type)
+            ),
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    #[test]
+    fn deduplicated_value_is_kept_until_last_reference_is_deleted() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let (key_a, value) = generate_key_value(&config, 0);
+        let (key_b, _) = generate_key_value(&config, 0);
+        let id_a = ContentId::from(key_a.content_id());
+        let id_b = ContentId::from(key_b.content_id());
+
+        store.insert(&key_a, value.clone())?;
+        store.insert(&key_b, value.clone())?;
+        // Inserting the same key again shouldn't add a reference.
+        store.insert(&key_b, value.clone())?;
+        assert_eq!(blob_count(&config)?, 1);
+        assert_eq!(store.dedup_ratio()?, 2.0);
+
+        store.delete(&id_a)?;
+        assert_eq!(blob_count(&config)?, 1);
+        assert_eq!(store.lookup_content_value(&id_b)?, Some(value));
+        assert_eq!(store.dedup_ratio()?, 1.0);
+
+        store.delete(&id_b)?;
+        assert_eq!(blob_count(&config)?, 0);
+        assert_eq!(store.dedup_ratio()?, 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pruning_deduplicated_value_returns_it() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        // Two far keys that share a value, and enough close content to require pruning
+        let (far_key_a, far_value) = generate_key_value(&config, 0xFF);
+        let (far_key_b, _) = generate_key_value(&config, 0xFE);
+        store.insert(&far_key_a, far_value.clone())?;
+        store.insert(&far_key_b, far_value.clone())?;
+
+        let mut pruned = vec![];
+        for _ in 0..100 {
+            let (key, value) = generate_key_value(&config, 0);
+            pruned.extend(store.insert(&key, value)?);
+        }
+
+        assert!(pruned.contains(&(far_key_a, far_value.clone())));
+        assert!(pruned.contains(&(far_key_b, far_value)));
+        assert_eq!(blob_count(&config)?, store.usage_stats.entry_count);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_value_stored_inline() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+
+        // Table created before values were deduplicated
+        config
+            .sql_connection_pool
+            .get()?
+            .execute_batch(&sql::create_table(&config.content_type))?;
+        let (key, value) = generate_key_value(&config, 0);
+        let id = key.content_id();
+        config.sql_connection_pool.get()?.execute(
+            &format!(
+                "INSERT INTO {} (content_id, content_key, content_value, distance_short, content_size)
+                VALUES (:content_id, :content_key, :content_value, 0, :content_size)",
+                sql::table_name(&config.content_type)
+            ),
+            named_params! {
+                ":content_id": id.as_slice(),
+                ":content_key": key.to_bytes().to_vec(),
+                ":content_value": value.to_vec(),
+                ":content_size": CONTENT_DEFAULT_SIZE_BYTES,
+            },
+        )?;
+
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let id = ContentId::from(id);
+        assert_eq!(store.lookup_content_value(&id)?, Some(value.clone()));
+        assert_eq!(store.dedup_ratio()?, 1.0);
+
+        // New content with the same value is stored in the blob table
+        let (other_key, _) = generate_key_value(&config, 0);
+        store.insert(&other_key, value.clone())?;
+        assert_eq!(
+            store.lookup_content_value(&ContentId::from(other_key.content_id()))?,
+            Some(value)
+        );
+        assert_eq!(blob_count(&config)?, 1);
+
+        store.delete(&id)?;
+        assert!(!store.has_content(&id)?);
+
+        Ok(())
+    }
+
     #[test]
     fn prune_simple() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -923,65 +2140,217 @@ mod tests {
     }
 
     #[test]
-    fn prune_different_sizes_elements() -> Result<()> {
+    fn kept_content_is_not_pruned_and_new_content_is_refused_once_full() -> Result<()> {
         let temp_dir = TempDir::new()?;
-        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
-
-        // fill 50% of storage with 50 items, 1% each
-        create_and_populate_table(&config, 50)?;
+        let config = IdIndexedV1StoreConfig {
+            keep_content: true,
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
         let mut store =
             IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
 
-        let mut rng = rand::thread_rng();
-
-        // Insert 10 keys that shouldn't be pruned (close distance)
-        // Each has a size in range 1-4%
-        let mut important_keys = vec![];
-        for _ in 0..10 {
-            let (key, value) = generate_key_value_with_content_size(
-                &config,
-                0,
-                rng.gen_range((CONTENT_DEFAULT_SIZE_BYTES)..(4 * CONTENT_DEFAULT_SIZE_BYTES)),
-            );
-            store.insert(&key, value)?;
-            important_keys.push(key);
-        }
-
-        // Insert 100 more keys (each has a size in range 1-3%) and check that:
-        // - we never got above storage capacity
-        // - radius never increases
-        let mut last_radius = store.radius();
-        for i in 0..100 {
-            // Use `0xFF-i` for distance so we are sure they will be accepted
-            // (radius will decrease over time)
-            let (key, value) = generate_key_value_with_content_size(
-                &config,
-                0xFF - i,
-                rng.gen_range((CONTENT_DEFAULT_SIZE_BYTES)..(3 * CONTENT_DEFAULT_SIZE_BYTES)),
-            );
-            store.insert(&key, value)?;
-            assert!(store.usage_stats.total_entry_size_bytes <= config.storage_capacity_bytes);
-
-            assert!(store.radius() <= last_radius);
-            last_radius = store.radius();
-        }
-        assert!(last_radius < Distance::MAX);
-
-        // Check that important keys are still present
-        for key in important_keys {
-            assert!(store.has_content(&key.content_id().into())?);
+        // The last insert exceeds the capacity, but nothing is pruned.
+        for _ in 0..=100 {
+            let (key, value) = generate_key_value(&config, 0x80);
+            assert_eq!(store.insert(&key, value)?, vec![]);
         }
+        assert_eq!(store.usage_stats.entry_count, 101);
+        assert!(store.is_capacity_exhausted(None)?);
+        assert_eq!(store.radius().big_endian_u32() >> 24, 0x80);
+        assert!(store.evict_outside_radius(100)?.is_empty());
+
+        let (key, value) = generate_key_value(&config, 0x00);
+        assert!(matches!(
+            store.insert(&key, value),
+            Err(ContentStoreError::CapacityExhausted)
+        ));
+        let (key, value) = generate_key_value(&config, 0xFF);
+        assert!(matches!(
+            store.insert(&key, value),
+            Err(ContentStoreError::InsufficientRadius { .. })
+        ));
+        assert_eq!(store.usage_stats.entry_count, 101);
 
         Ok(())
     }
 
     #[test]
-    fn prune_with_one_large_item() -> Result<()> {
+    fn content_outside_radius_is_evicted_in_batches() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
-
-        // fill 50% of storage with 50 items, 1% each
-        create_and_populate_table(&config, 50)?;
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let mut far_keys = vec![];
+        for distance in [0x00, 0x10, 0xC0, 0xD0, 0xE0] {
+            let (key, value) = generate_key_value(&config, distance);
+            store.insert(&key, value)?;
+            if distance >= 0xC0 {
+                far_keys.push(key);
+            }
+        }
+
+        store.override_radius(Some(Distance::from(alloy::primitives::U256::MAX >> 1)))?;
+        let mut evicted = store.evict_outside_radius(2)?;
+        assert_eq!(evicted.len(), 2);
+        evicted.extend(store.evict_outside_radius(2)?);
+        assert!(store.evict_outside_radius(2)?.is_empty());
+
+        let mut evicted_keys: Vec<_> = evicted.into_iter().map(|(key, _)| key).collect();
+        evicted_keys.sort_by_key(|key| key.to_bytes());
+        far_keys.sort_by_key(|key| key.to_bytes());
+        assert_eq!(evicted_keys, far_keys);
+        assert_eq!(store.usage_stats.entry_count, 2);
+        assert_eq!(
+            store.usage_stats.total_entry_size_bytes,
+            2 * CONTENT_DEFAULT_SIZE_BYTES
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn radius_history_records_changes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        // The radius the store started with is recorded.
+        let history = store.radius_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].radius, *Distance::MAX);
+
+        // Filling the store doesn't change the radius.
+        for i in 0..100 {
+            let (key, value) = generate_key_value(&config, 0xFF - i);
+            store.insert(&key, value)?;
+        }
+        assert_eq!(store.radius_history().len(), 1);
+
+        // Pruning shrinks the radius.
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value)?;
+        }
+        let history = store.radius_history();
+        assert!(history.len() > 1);
+        assert!(history.windows(2).all(|changes| {
+            changes[0].radius != changes[1].radius
+                && changes[0].timestamp_ms <= changes[1].timestamp_ms
+        }));
+        assert_eq!(history.last().unwrap().radius, *store.radius());
+
+        Ok(())
+    }
+
+    #[test]
+    fn override_radius_reports_content_outside_radius() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let (key, value) = generate_key_value(&config, 0x80);
+        store.insert(&key, value)?;
+
+        let radius = Distance::from(alloy::primitives::U256::MAX >> 8);
+        assert!(store.override_radius(Some(radius))?);
+        assert_eq!(store.radius(), radius);
+        assert_eq!(store.radius_history().last().unwrap().radius, *radius);
+
+        assert!(!store.override_radius(Some(Distance::MAX))?);
+        assert_eq!(store.radius(), Distance::MAX);
+        Ok(())
+    }
+
+    #[test]
+    fn radius_override_is_kept_through_pruning_until_cleared() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        for _ in 0..50 {
+            let (key, value) = generate_key_value(&config, 0x80);
+            store.insert(&key, value)?;
+        }
+
+        let radius = Distance::from(alloy::primitives::U256::MAX >> 1);
+        assert!(store.override_radius(Some(radius))?);
+
+        // Pruning deletes some of the content outside the radius, and would set the radius to the
+        // farthest content that remains.
+        let mut pruned = 0;
+        for _ in 0..60 {
+            let (key, value) = generate_key_value(&config, 0x00);
+            pruned += store.insert(&key, value)?.len();
+        }
+        assert!(pruned > 0);
+        assert_eq!(store.radius(), radius);
+
+        assert!(!store.override_radius(None)?);
+        assert!(store.radius() > radius);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_different_sizes_elements() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+
+        // fill 50% of storage with 50 items, 1% each
+        create_and_populate_table(&config, 50)?;
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let mut rng = rand::thread_rng();
+
+        // Insert 10 keys that shouldn't be pruned (close distance)
+        // Each has a size in range 1-4%
+        let mut important_keys = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value_with_content_size(
+                &config,
+                0,
+                rng.gen_range((CONTENT_DEFAULT_SIZE_BYTES)..(4 * CONTENT_DEFAULT_SIZE_BYTES)),
+            );
+            store.insert(&key, value)?;
+            important_keys.push(key);
+        }
+
+        // Insert 100 more keys (each has a size in range 1-3%) and check that:
+        // - we never got above storage capacity
+        // - radius never increases
+        let mut last_radius = store.radius();
+        for i in 0..100 {
+            // Use `0xFF-i` for distance so we are sure they will be accepted
+            // (radius will decrease over time)
+            let (key, value) = generate_key_value_with_content_size(
+                &config,
+                0xFF - i,
+                rng.gen_range((CONTENT_DEFAULT_SIZE_BYTES)..(3 * CONTENT_DEFAULT_SIZE_BYTES)),
+            );
+            store.insert(&key, value)?;
+            assert!(store.usage_stats.total_entry_size_bytes <= config.storage_capacity_bytes);
+
+            assert!(store.radius() <= last_radius);
+            last_radius = store.radius();
+        }
+        assert!(last_radius < Distance::MAX);
+
+        // Check that important keys are still present
+        for key in important_keys {
+            assert!(store.has_content(&key.content_id().into())?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn prune_with_one_large_item() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+
+        // fill 50% of storage with 50 items, 1% each
+        create_and_populate_table(&config, 50)?;
         let mut store =
             IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
         assert_eq!(store.usage_stats.entry_count, 50);
@@ -1110,6 +2479,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn key_type_usage_stats() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        // The first byte of the generated keys is determined by the first byte of the distance.
+        let key_type_close = config.node_id.raw()[0];
+        let key_type_far = key_type_close ^ 0x01;
+        let mut close_keys = vec![];
+        for _ in 0..3 {
+            let (key, value) = generate_key_value(&config, 0x00);
+            store.insert(&key, value)?;
+            close_keys.push(key);
+        }
+        let (key, value) = generate_key_value(&config, 0x01);
+        store.insert(&key, value)?;
+
+        store.delete(&ContentId::from(close_keys[0].content_id()))?;
+
+        let expected = BTreeMap::from([
+            (
+                key_type_close,
+                UsageStats::new(2, 2 * CONTENT_DEFAULT_SIZE_BYTES),
+            ),
+            (key_type_far, UsageStats::new(1, CONTENT_DEFAULT_SIZE_BYTES)),
+        ]);
+        assert_eq!(store.key_type_usage_stats(), expected);
+
+        // Usage stats should be the same after restart
+        drop(store);
+        let store = IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config)?;
+        assert_eq!(store.key_type_usage_stats(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_warmup_matches_serial_warmup() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        // Content ids are spread over the shards by their distance.
+        for distance in (0..=u8::MAX).step_by(5) {
+            let (key, value) = generate_key_value(&config, distance);
+            store.insert(&key, value)?;
+        }
+        let usage_stats = store.usage_stats.clone();
+        let key_type_usage_stats = store.key_type_usage_stats();
+        drop(store);
+
+        for warmup_threads in [1, 2, 7] {
+            let config = IdIndexedV1StoreConfig {
+                warmup_threads,
+                ..config.clone()
+            };
+            let store = IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config)?;
+            assert_eq!(store.usage_stats, usage_stats);
+            assert_eq!(store.key_type_usage_stats(), key_type_usage_stats);
+        }
+        Ok(())
+    }
+
     #[test]
     fn pagination_empty() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1198,4 +2632,568 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn export_pages_in_content_id_order() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let mut content = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value.clone()).unwrap();
+            content.push((key, value));
+        }
+        // The content id of an identity content key is the key itself.
+        content.sort_by_key(|(key, _)| key.to_vec());
+
+        let mut exported = vec![];
+        let mut after = None;
+        loop {
+            let page = store.export_page(after.as_ref(), /* limit= */ 4)?;
+            let Some((last_content_id, _, _)) = page.last() else {
+                break;
+            };
+            after = Some(last_content_id.clone());
+            exported.extend(page.into_iter().map(|(_, key, value)| (key, value)));
+        }
+        assert_eq!(exported, content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_closest_to() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let mut content_keys = vec![];
+        for _ in 0..20 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value)?;
+            content_keys.push(key);
+        }
+
+        let node_id = NodeId::random();
+        content_keys.sort_by_key(|key| config.distance_fn.distance(&node_id, &key.content_id()));
+
+        assert_eq!(
+            store.lookup_closest_to(&node_id, 5)?,
+            content_keys[..5].to_vec()
+        );
+        assert_eq!(store.lookup_closest_to(&node_id, 100)?, content_keys);
+        assert!(store.lookup_closest_to(&node_id, 0)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn distance_distribution() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        assert!(store.distance_distribution()?.is_empty());
+
+        // The first byte of the distance determines the log2 distance.
+        for distance in [0x80, 0x80, 0x81, 0x01] {
+            let (key, value) = generate_key_value(&config, distance);
+            store.insert(&key, value)?;
+        }
+        assert_eq!(
+            store.distance_distribution()?,
+            BTreeMap::from([(249, 1), (256, 3)])
+        );
+
+        assert_eq!(log2_distance_short(0), MIN_DISTINGUISHABLE_LOG2_DISTANCE);
+        assert_eq!(
+            log2_distance_short(1),
+            MIN_DISTINGUISHABLE_LOG2_DISTANCE + 1
+        );
+        assert_eq!(log2_distance_short(u32::MAX), 256);
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_expired() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        config.content_ttl = Some(Duration::from_secs(3600));
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let (expired_key, value) = generate_key_value(&config, 0);
+        store.insert(&expired_key, value)?;
+        let (fresh_key, value) = generate_key_value(&config, 0);
+        store.insert(&fresh_key, value)?;
+
+        // Backdate the first entry past the time-to-live.
+        config.sql_connection_pool.get()?.execute(
+            &format!(
+                "UPDATE {} SET inserted_at = inserted_at - 7200 WHERE content_id = :content_id",
+                sql::table_name(&config.content_type)
+            ),
+            named_params! { ":content_id": expired_key.content_id().to_vec() },
+        )?;
+
+        assert_eq!(store.delete_expired()?, 1);
+        assert!(!store.has_content(&expired_key.content_id().into())?);
+        assert!(store.has_content(&fresh_key.content_id().into())?);
+        assert_eq!(store.usage_stats().entry_count, 1);
+        assert_eq!(
+            store.usage_stats().total_entry_size_bytes,
+            CONTENT_DEFAULT_SIZE_BYTES
+        );
+        assert_eq!(store.delete_expired()?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_value_is_detected() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        config.verify_checksums = true;
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+        let id = ContentId::from(key.content_id());
+        assert_eq!(store.lookup_content_value(&id)?, Some(value.clone()));
+
+        // Flip the bits of the stored blob, as silent disk corruption would.
+        config.sql_connection_pool.get()?.execute(
+            &format!(
+                "UPDATE {} SET content_value = :content_value",
+                sql::blob_table_name(&config.content_type)
+            ),
+            named_params! {
+                ":content_value": value.iter().map(|byte| !byte).collect::<Vec<u8>>(),
+            },
+        )?;
+
+        assert!(matches!(
+            store.lookup_content_value(&id),
+            Err(ContentStoreError::Corrupted { content_id }) if content_id == *id
+        ));
+
+        // Without verification, the corrupted value is returned.
+        config.verify_checksums = false;
+        let store = IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config)?;
+        assert!(store.lookup_content_value(&id)?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stored_value_is_returned_without_verification() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        config.verify_checksums = true;
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+        let id = ContentId::from(key.content_id());
+
+        let corrupted_value = value.iter().map(|byte| !byte).collect::<Vec<u8>>();
+        config.sql_connection_pool.get()?.execute(
+            &format!(
+                "UPDATE {} SET content_value = :content_value",
+                sql::blob_table_name(&config.content_type)
+            ),
+            named_params! { ":content_value": corrupted_value },
+        )?;
+
+        // The corrupted bytes are returned as stored, alongside the checksum of the original value.
+        assert_eq!(
+            store.lookup_stored_value(&id)?,
+            Some(RawStoredBytesInfo {
+                raw_value: corrupted_value.into(),
+                value_encoding: ValueEncoding::CURRENT.version(),
+                checksum: Some(keccak256(&value).to_vec().into()),
+            })
+        );
+        assert_eq!(
+            store.lookup_stored_value(&ContentId::from([0u8; 32]))?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn readonly_stores_are_consulted_on_miss() -> Result<()> {
+        let archive_dir = TempDir::new()?;
+        let archive_config = create_config(&archive_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut archive = IdIndexedV1Store::<IdentityContentKey>::create(
+            ContentType::State,
+            archive_config.clone(),
+        )?;
+        let (archived_key, archived_value) = generate_key_value(&archive_config, 0);
+        archive.insert(&archived_key, archived_value.clone())?;
+
+        let temp_dir = TempDir::new()?;
+        let mut config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        config.readonly_store_paths = vec![archive_dir.path().join(DATABASE_NAME)];
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+
+        let archived_id = ContentId::from(archived_key.content_id());
+        let id = ContentId::from(key.content_id());
+        assert_eq!(
+            store.lookup_content_value(&archived_id)?,
+            Some(archived_value)
+        );
+        assert_eq!(store.lookup_content_value(&id)?, Some(value));
+        assert!(!store.has_content(&archived_id)?);
+
+        // Writes only go to the writable store.
+        assert_eq!(archive.lookup_content_value(&id)?, None);
+
+        // A read-only store that doesn't exist is an error.
+        config.readonly_store_paths = vec![archive_dir.path().join("missing.sqlite")];
+        assert!(
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_value_encoding_is_rejected() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+        let id = ContentId::from(key.content_id());
+        assert_eq!(store.lookup_content_value(&id)?, Some(value));
+
+        // Tag the stored blob with an encoding that isn't known, as a newer version might.
+        config.sql_connection_pool.get()?.execute(
+            &format!(
+                "UPDATE {} SET value_encoding = 255",
+                sql::blob_table_name(&config.content_type)
+            ),
+            [],
+        )?;
+
+        assert!(matches!(
+            store.lookup_content_value(&id),
+            Err(ContentStoreError::UnknownValueEncoding { version: 255 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn database_size() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let (empty_size, _) = store.database_size()?;
+
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value)?;
+        }
+
+        let (size, free_size) = store.database_size()?;
+        assert!(size > empty_size);
+        assert!(free_size < size);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_ahead_log_is_truncated_on_sync() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            sql_connection_pool: setup_sql(temp_dir.path(), true)?,
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+
+        let wal_path = temp_dir.path().join(format!("{DATABASE_NAME}-wal"));
+        assert!(wal_path.metadata()?.len() > 0);
+        store.sync()?;
+        assert_eq!(wal_path.metadata()?.len(), 0);
+
+        // The content is still there once the database is reopened without a write-ahead log.
+        drop((store, config));
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let store = IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config)?;
+        assert_eq!(
+            store.lookup_content_value(&key.content_id().into())?,
+            Some(value)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn benchmark() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+        let (key, value) = generate_key_value(&config, 0);
+        store.insert(&key, value.clone())?;
+
+        // The store isn't needed while the benchmark runs.
+        let benchmark = store.benchmark();
+        let info = benchmark.run(/* entry_count= */ 10, /* value_size= */ 1000)?;
+        assert_eq!((info.entry_count, info.value_size), (10, 1000));
+        assert!(info.write.ops_per_sec > 0.0 && info.read.ops_per_sec > 0.0);
+
+        // Stored content is untouched and the benchmark tables are dropped.
+        assert_eq!(
+            store.lookup_content_value(&key.content_id().into())?,
+            Some(value)
+        );
+        assert_eq!(store.usage_stats().entry_count, 1);
+        assert_eq!(blob_count(&config)?, 1);
+        let benchmark_tables_exist = config
+            .sql_connection_pool
+            .get()?
+            .prepare("SELECT 1 FROM sqlite_master WHERE name LIKE 'ii1_state_benchmark_%'")?
+            .exists([])?;
+        assert!(!benchmark_tables_exist);
+
+        assert!(benchmark.run(0, 1000).is_err());
+        assert!(benchmark.run(MAX_BENCHMARK_ENTRY_COUNT, u64::MAX).is_err());
+
+        Ok(())
+    }
+
+    /// Creates a block header by number content key/value pair with the default size.
+    fn generate_block_key_value(block_number: u64) -> (HistoryContentKey, RawContentValue) {
+        let key = HistoryContentKey::new_block_header_by_number(block_number);
+        let value =
+            generate_random_bytes(CONTENT_DEFAULT_SIZE_BYTES as usize - 32 - key.to_bytes().len());
+        (key, RawContentValue::copy_from_slice(value.as_ref()))
+    }
+
+    #[test]
+    fn recency_floor_exempts_recent_content_from_pruning() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            recency_floor_blocks: Some(50),
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store =
+            IdIndexedV1Store::<HistoryContentKey>::create(ContentType::History, config)?;
+
+        let mut inserted = vec![];
+        for block_number in 0..300 {
+            let (key, value) = generate_block_key_value(block_number);
+            match store.insert(&key, value) {
+                Ok(_) => inserted.push(key),
+                Err(ContentStoreError::InsufficientRadius { .. }) => {}
+                Err(err) => panic!("Unexpected error: {err}"),
+            }
+        }
+
+        assert!(store.usage_stats().total_entry_size_bytes <= STORAGE_CAPACITY_100_ITEMS);
+        for key in &inserted {
+            let has_content = store.has_content(&key.content_id().into())?;
+            if key.block_number() >= Some(250) {
+                assert!(has_content, "Recent content was pruned: {key}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn recently_served_content_is_not_pruned() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            serve_eviction_protection: Some(Duration::from_secs(3600)),
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store =
+            IdIndexedV1Store::<IdentityContentKey>::create(ContentType::State, config.clone())?;
+
+        // Insert 10 far keys that are served, so they would be pruned first if not protected
+        let mut served_keys = vec![];
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0xFF);
+            store.insert(&key, value)?;
+            store.record_served(&key.content_id().into())?;
+            served_keys.push(key);
+        }
+        let mut other_keys = vec![];
+        for _ in 0..90 {
+            let (key, value) = generate_key_value(&config, 0x80);
+            store.insert(&key, value)?;
+            other_keys.push(key);
+        }
+
+        // Insert 10 close keys, which makes the store prune
+        for _ in 0..10 {
+            let (key, value) = generate_key_value(&config, 0);
+            store.insert(&key, value)?;
+            assert!(store.usage_stats.total_entry_size_bytes <= config.storage_capacity_bytes);
+        }
+
+        for key in served_keys {
+            assert!(store.has_content(&key.content_id().into())?);
+        }
+        let mut pruned_count = 0;
+        for key in other_keys {
+            if !store.has_content(&key.content_id().into())? {
+                pruned_count += 1;
+            }
+        }
+        assert!(pruned_count >= 10);
+        Ok(())
+    }
+
+    #[test]
+    fn content_over_quota_is_pruned_first() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            key_type_quotas: BTreeMap::from([
+                (HISTORY_BLOCK_BODY_KEY_PREFIX, 50),
+                (HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX, 50),
+            ]),
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store =
+            IdIndexedV1Store::<HistoryContentKey>::create(ContentType::History, config)?;
+        assert_eq!(
+            store.key_type_quota_bytes(),
+            BTreeMap::from([
+                (
+                    HISTORY_BLOCK_BODY_KEY_PREFIX,
+                    STORAGE_CAPACITY_100_ITEMS / 2
+                ),
+                (
+                    HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX,
+                    STORAGE_CAPACITY_100_ITEMS / 2
+                ),
+            ])
+        );
+
+        // Block bodies can use the whole capacity while headers don't use their quota.
+        for _ in 0..100 {
+            let key = HistoryContentKey::new_block_body(rand::random::<[u8; 32]>());
+            let value = generate_random_bytes(
+                CONTENT_DEFAULT_SIZE_BYTES as usize - 32 - key.to_bytes().len(),
+            );
+            store.insert(&key, RawContentValue::copy_from_slice(value.as_ref()))?;
+        }
+
+        // Headers within their quota are never pruned, however far they are.
+        let mut inserted = vec![];
+        for block_number in 0..50 {
+            let (key, value) = generate_block_key_value(block_number);
+            match store.insert(&key, value) {
+                Ok(_) => inserted.push(key),
+                Err(ContentStoreError::InsufficientRadius { .. }) => {}
+                Err(err) => panic!("Unexpected error: {err}"),
+            }
+        }
+        assert!(!inserted.is_empty());
+        for key in &inserted {
+            assert!(store.has_content(&key.content_id().into())?);
+        }
+        let key_type_usage_stats = store.key_type_usage_stats();
+        assert_eq!(
+            key_type_usage_stats[&HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX].entry_count,
+            inserted.len() as u64
+        );
+        assert!(
+            key_type_usage_stats[&HISTORY_BLOCK_BODY_KEY_PREFIX].entry_count
+                <= 100 - inserted.len() as u64
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn farthest_content_of_key_type_is_looked_up_by_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS);
+        let sql_connection_pool = config.sql_connection_pool.clone();
+        IdIndexedV1Store::<HistoryContentKey>::create(ContentType::History, config)?;
+
+        let conn = sql_connection_pool.get()?;
+        let query_plan = conn
+            .prepare(&format!(
+                "EXPLAIN QUERY PLAN {}",
+                sql::delete_farthest_of_key_type(&ContentType::History)
+            ))?
+            .query_map(
+                named_params! {
+                    ":key_type": vec![HISTORY_BLOCK_BODY_KEY_PREFIX],
+                    ":limit": 1,
+                    ":exempt_from_block_number": None::<u64>,
+                    ":protected_served_since": None::<u64>,
+                },
+                |row| row.get::<_, String>("detail"),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(
+            query_plan
+                .iter()
+                .any(|detail| detail.contains("key_type_distance_short_idx")),
+            "{query_plan:?}"
+        );
+        // The index is ordered by distance, so the content doesn't need sorting.
+        assert!(
+            !query_plan
+                .iter()
+                .any(|detail| detail.contains("TEMP B-TREE")),
+            "{query_plan:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn recency_floor_refuses_content_once_only_recent_content_remains() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let config = IdIndexedV1StoreConfig {
+            recency_floor_blocks: Some(1000),
+            ..create_config(&temp_dir, STORAGE_CAPACITY_100_ITEMS)
+        };
+        let mut store =
+            IdIndexedV1Store::<HistoryContentKey>::create(ContentType::History, config)?;
+
+        // The last insert exceeds the capacity, but nothing can be pruned.
+        for block_number in 0..=100 {
+            let (key, value) = generate_block_key_value(block_number);
+            assert_eq!(store.insert(&key, value)?, vec![]);
+        }
+        assert!(store.usage_stats().total_entry_size_bytes > STORAGE_CAPACITY_100_ITEMS);
+
+        let (key, value) = generate_block_key_value(101);
+        assert!(matches!(
+            store.insert(&key, value),
+            Err(ContentStoreError::RecentContentCapacityExhausted {
+                recency_floor_blocks: 1000
+            })
+        ));
+        assert!(store.is_capacity_exhausted(Some(101))?);
+        // A block far enough ahead moves the recency floor past the stored content.
+        assert!(!store.is_capacity_exhausted(Some(1100))?);
+        assert!(!store.has_content(&key.content_id().into())?);
+        assert_eq!(store.usage_stats().entry_count, 101);
+        Ok(())
+    }
 }
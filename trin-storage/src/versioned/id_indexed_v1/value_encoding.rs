@@ -0,0 +1,74 @@
+use ethportal_api::RawContentValue;
+
+use crate::error::ContentStoreError;
+
+/// The encoding of a stored content value.
+///
+/// The version of the encoding is stored with every value, so that values stored with an older
+/// encoding can still be read after the encoding of new values changes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueEncoding {
+    /// The content value as is.
+    Raw,
+}
+
+impl ValueEncoding {
+    /// The encoding of newly stored values.
+    pub const CURRENT: Self = Self::Raw;
+
+    /// Returns the version under which the encoding is stored.
+    pub fn version(self) -> u8 {
+        match self {
+            Self::Raw => 0,
+        }
+    }
+
+    /// Returns the encoding stored under `version`.
+    ///
+    /// It returns `UnknownValueEncoding` error if the version isn't known, e.g. because the value
+    /// was stored by a newer version of trin.
+    pub fn from_version(version: u8) -> Result<Self, ContentStoreError> {
+        match version {
+            0 => Ok(Self::Raw),
+            version => Err(ContentStoreError::UnknownValueEncoding { version }),
+        }
+    }
+
+    /// Encodes the content value for storage.
+    pub fn encode(self, content_value: &RawContentValue) -> Vec<u8> {
+        match self {
+            Self::Raw => content_value.to_vec(),
+        }
+    }
+
+    /// Decodes the stored bytes into the content value.
+    pub fn decode(self, bytes: Vec<u8>) -> Result<RawContentValue, ContentStoreError> {
+        match self {
+            Self::Raw => Ok(RawContentValue::from(bytes)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let content_value = RawContentValue::from(vec![1, 2, 3]);
+        let encoding = ValueEncoding::from_version(ValueEncoding::CURRENT.version()).unwrap();
+        assert_eq!(
+            encoding.decode(encoding.encode(&content_value)).unwrap(),
+            content_value
+        );
+    }
+
+    #[test]
+    fn unknown_version() {
+        assert!(matches!(
+            ValueEncoding::from_version(u8::MAX),
+            Err(ContentStoreError::UnknownValueEncoding { version: u8::MAX })
+        ));
+    }
+}
@@ -4,7 +4,17 @@ pub mod store;
 mod usage_stats;
 mod utils;
 
-pub use id_indexed_v1::{IdIndexedV1Store, IdIndexedV1StoreConfig};
+use ethportal_api::types::content_key::{
+    history::{
+        HISTORY_BLOCK_BODY_KEY_PREFIX, HISTORY_BLOCK_HEADER_BY_HASH_KEY_PREFIX,
+        HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX, HISTORY_BLOCK_RECEIPTS_KEY_PREFIX,
+    },
+    state::{
+        STATE_ACCOUNT_TRIE_NODE_KEY_PREFIX, STATE_CONTRACT_BYTECODE_KEY_PREFIX,
+        STATE_STORAGE_TRIE_NODE_KEY_PREFIX,
+    },
+};
+pub use id_indexed_v1::{IdIndexedV1Store, IdIndexedV1StoreConfig, StoreBenchmark};
 use rusqlite::types::{FromSql, FromSqlError, ValueRef};
 pub use store::VersionedContentStore;
 use strum::{AsRefStr, Display, EnumString};
@@ -22,6 +32,24 @@ pub enum ContentType {
     State,
 }
 
+impl ContentType {
+    /// Returns the name of the content type whose content keys start with the `key_type` byte.
+    /// Unknown key types are named by their hex value.
+    pub fn key_type_name(&self, key_type: u8) -> String {
+        let name = match (self, key_type) {
+            (Self::History, HISTORY_BLOCK_HEADER_BY_HASH_KEY_PREFIX) => "blockHeaderByHash",
+            (Self::History, HISTORY_BLOCK_BODY_KEY_PREFIX) => "blockBody",
+            (Self::History, HISTORY_BLOCK_RECEIPTS_KEY_PREFIX) => "receipts",
+            (Self::History, HISTORY_BLOCK_HEADER_BY_NUMBER_KEY_PREFIX) => "blockHeaderByNumber",
+            (Self::State, STATE_ACCOUNT_TRIE_NODE_KEY_PREFIX) => "accountTrieNode",
+            (Self::State, STATE_STORAGE_TRIE_NODE_KEY_PREFIX) => "contractStorageTrieNode",
+            (Self::State, STATE_CONTRACT_BYTECODE_KEY_PREFIX) => "contractBytecode",
+            _ => return format!("0x{key_type:02x}"),
+        };
+        name.to_string()
+    }
+}
+
 /// The version of the store. There should be exactly one implementation of the
 /// `VersionedContentStore` for each version (referenced in the comment).
 ///
@@ -26,6 +26,14 @@ impl UsageStats {
         }
     }
 
+    /// Returns the estimated number of additional entries of average size that fit within
+    /// `capacity_bytes`, or `None` when empty.
+    pub fn estimated_remaining_entries(&self, capacity_bytes: u64) -> Option<u64> {
+        let average_entry_size_bytes = self.average_entry_size_bytes()?;
+        let remaining_bytes = capacity_bytes.saturating_sub(self.total_entry_size_bytes);
+        Some((remaining_bytes as f64 / average_entry_size_bytes) as u64)
+    }
+
     /// Returns whether total entry size is above provided value
     pub fn is_above(&self, size_bytes: u64) -> bool {
         self.total_entry_size_bytes > size_bytes
@@ -74,4 +82,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn estimated_remaining_entries() -> Result<()> {
+        assert_eq!(
+            UsageStats::default().estimated_remaining_entries(1_000),
+            None
+        );
+
+        assert_eq!(
+            UsageStats::new(/* entry_count= */ 2, /* total_entry_size= */ 300)
+                .estimated_remaining_entries(1_000),
+            Some(4)
+        );
+
+        // Above capacity, nothing fits.
+        assert_eq!(
+            UsageStats::new(/* entry_count= */ 2, /* total_entry_size= */ 300)
+                .estimated_remaining_entries(200),
+            Some(0)
+        );
+
+        Ok(())
+    }
 }
@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use alloy::primitives::{B256, U256};
 use anyhow::anyhow;
@@ -47,6 +50,14 @@ impl PreMergeAccumulator {
             .map_err(|err| anyhow!("Unable to decode pre-merge accumulator: {err:?}"))
     }
 
+    /// Load a pre-merge acc from an SSZ encoded file on disk, instead of the bundled one
+    pub fn try_from_path(path: &Path) -> anyhow::Result<PreMergeAccumulator> {
+        let raw = fs::read(path)
+            .map_err(|err| anyhow!("Unable to read pre-merge accumulator at {path:?}: {err}"))?;
+        PreMergeAccumulator::from_ssz_bytes(&raw)
+            .map_err(|err| anyhow!("Unable to decode pre-merge accumulator at {path:?}: {err:?}"))
+    }
+
     /// Number of the last block to be included in the accumulator
     pub fn height(&self) -> u64 {
         MERGE_BLOCK_NUMBER
@@ -121,3 +132,32 @@ impl PreMergeAccumulator {
         Ok(final_proof)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ssz::Encode;
+
+    use super::*;
+
+    #[test]
+    fn try_from_path() {
+        let dir = std::env::temp_dir().join(format!("trin-acc-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("macc.bin");
+        let default_acc = PreMergeAccumulator::default();
+        fs::write(&path, default_acc.as_ssz_bytes()).unwrap();
+        assert_eq!(
+            PreMergeAccumulator::try_from_path(&path).unwrap(),
+            default_acc
+        );
+
+        let invalid_path = dir.join("invalid.bin");
+        fs::write(&invalid_path, [1, 2, 3]).unwrap();
+        assert!(PreMergeAccumulator::try_from_path(&invalid_path).is_err());
+        assert!(PreMergeAccumulator::try_from_path(&dir.join("missing.bin")).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -39,6 +39,15 @@ impl HeaderValidator {
         }
     }
 
+    /// Creates a validator that checks pre-merge headers against the given accumulator, instead
+    /// of the bundled one.
+    pub fn new_with_pre_merge_acc(pre_merge_acc: PreMergeAccumulator) -> Self {
+        Self {
+            pre_merge_acc,
+            historical_roots_acc: HistoricalRootsAccumulator::default(),
+        }
+    }
+
     pub fn validate_header_with_proof(&self, hwp: &HeaderWithProof) -> anyhow::Result<()> {
         match &hwp.proof {
             BlockHeaderProof::PreMergeAccumulatorProof(proof) => {
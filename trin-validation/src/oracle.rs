@@ -56,7 +56,7 @@ impl HeaderOracle {
         block_hash: B256,
     ) -> anyhow::Result<HeaderWithProof> {
         let content_key = HistoryContentKey::new_block_header_by_hash(block_hash);
-        let endpoint = HistoryEndpoint::GetContent(content_key.clone());
+        let endpoint = HistoryEndpoint::GetContent(content_key.clone(), None);
         let (resp, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
         let request = HistoryJsonRpcRequest { endpoint, resp };
         let tx = self.history_jsonrpc_tx()?;
@@ -10,6 +10,12 @@ pub struct ValidationResult<TContentKey> {
     /// content-value corresponds to the content-key, but not that it's canonical).
     pub valid_for_storing: bool,
 
+    /// Whether the content was actually validated, rather than trusted without validation.
+    ///
+    /// Content that wasn't validated is only propagated to other peers if explicitly enabled, so
+    /// that the node doesn't amplify invalid content.
+    pub validated: bool,
+
     /// The optional content key/value pair to be propagated (together with original content
     /// key/value). This is used for Recursive Gossip in the state network (see [specs](
     /// https://github.com/ethereum/portal-network-specs/blob/04cc360179aeda179e0b1cac6fea900a74e87f2b/state-network.md#gossip
@@ -21,6 +27,16 @@ impl<TContentKey> ValidationResult<TContentKey> {
     pub fn new(valid_for_storing: bool) -> Self {
         Self {
             valid_for_storing,
+            validated: true,
+            additional_content_to_propagate: None,
+        }
+    }
+
+    /// Creates the result of content that is trusted without validation.
+    pub fn new_unvalidated() -> Self {
+        Self {
+            valid_for_storing: true,
+            validated: false,
             additional_content_to_propagate: None,
         }
     }
@@ -31,6 +47,7 @@ impl<TContentKey> ValidationResult<TContentKey> {
     ) -> Self {
         Self {
             valid_for_storing: true,
+            validated: true,
             additional_content_to_propagate: Some((
                 additional_content_key,
                 additional_content_value,